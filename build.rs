@@ -0,0 +1,144 @@
+//! With the `cdylib` feature, writes `rwc.h`, the C header of the API in
+//! `src/ffi.rs`, to `OUT_DIR`, so the two cannot drift apart. The source
+//! tree is left alone.
+//!
+//! This reads only what `src/ffi.rs` uses: `///` comments, `c_int`
+//! constants, `#[repr(C)]` structs of plain fields and `extern "C"`
+//! functions taking and returning integers and pointers.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    if env::var_os("CARGO_FEATURE_CDYLIB").is_none() {
+        return;
+    }
+    let source = fs::read_to_string("src/ffi.rs").expect("src/ffi.rs is readable");
+    let header = header(&source);
+    let out_dir = env::var_os("OUT_DIR").expect("cargo sets OUT_DIR");
+    let path = Path::new(&out_dir).join("rwc.h");
+    // Rewritten only on change, so the header's timestamp means something.
+    if fs::read_to_string(&path).ok().as_deref() != Some(header.as_str()) {
+        fs::write(&path, header).expect("OUT_DIR/rwc.h can be written");
+    }
+}
+
+/// `RwcCounts` as C names it: `rwc_counts`.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+fn c_type(rust: &str) -> String {
+    let rust = rust.trim();
+    if let Some(pointee) = rust.strip_prefix("*const ") {
+        return format!("const {} *", c_type(pointee));
+    }
+    if let Some(pointee) = rust.strip_prefix("*mut ") {
+        return format!("{} *", c_type(pointee));
+    }
+    match rust {
+        "u8" => "uint8_t".to_string(),
+        "u64" => "uint64_t".to_string(),
+        "usize" => "size_t".to_string(),
+        "c_int" => "int".to_string(),
+        "c_char" => "char".to_string(),
+        name => snake_case(name),
+    }
+}
+
+/// `type name`, without a space after a pointer's `*`.
+fn declaration(rust_type: &str, name: &str) -> String {
+    let c = c_type(rust_type);
+    if c.ends_with('*') {
+        format!("{}{}", c, name)
+    } else {
+        format!("{} {}", c, name)
+    }
+}
+
+fn header(source: &str) -> String {
+    let mut out = String::from(
+        "/* Generated by build.rs from src/ffi.rs; do not edit. See docs/ffi.md. */\n\n\
+         #ifndef RWC_H\n#define RWC_H\n\n#include <stddef.h>\n#include <stdint.h>\n\n\
+         #ifdef __cplusplus\nextern \"C\" {\n#endif\n",
+    );
+    let mut docs: Vec<&str> = Vec::new();
+    let mut lines = source.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.strip_prefix(' ').unwrap_or(doc));
+            continue;
+        }
+        if line.starts_with("#[") {
+            continue;
+        }
+        let comment: String = docs
+            .drain(..)
+            .map(|doc| format!("// {}\n", doc).replace("// \n", "//\n"))
+            .collect();
+        if let Some(constant) = line.strip_prefix("pub const ") {
+            let (name, rest) = constant.split_once(':').expect("a typed constant");
+            let value = rest.split_once('=').expect("a constant value").1;
+            out += &format!(
+                "\n{}#define {} ({})\n",
+                comment,
+                name,
+                value.trim().trim_end_matches(';')
+            );
+        } else if let Some(name) = line.strip_prefix("pub struct ") {
+            let name = snake_case(name.trim_end_matches(" {"));
+            out += &format!("\n{}typedef struct {} {{\n", comment, name);
+            for field in lines.by_ref().take_while(|line| *line != "}") {
+                if let Some(doc) = field.strip_prefix("///") {
+                    out += &format!("    //{}\n", doc);
+                } else if let Some(field) = field.strip_prefix("pub ") {
+                    let (field, rust_type) = field.split_once(':').expect("a typed field");
+                    out += &format!(
+                        "    {};\n",
+                        declaration(rust_type.trim_end_matches(','), field)
+                    );
+                }
+            }
+            out += &format!("}} {};\n", name);
+        } else if line.contains("extern \"C\" fn ") {
+            // The signature may be wrapped over several lines.
+            let mut signature = line.to_string();
+            while !signature.ends_with('{') {
+                signature += lines.next().expect("a function body");
+            }
+            let (_, rest) = signature.split_once("fn ").expect("a function");
+            let (name, rest) = rest.split_once('(').expect("parameters");
+            let (parameters, rest) = rest.rsplit_once(')').expect("parameters");
+            let returns = rest
+                .trim_end_matches('{')
+                .trim()
+                .strip_prefix("-> ")
+                .unwrap_or("void");
+            let parameters: Vec<String> = parameters
+                .split(',')
+                .filter(|parameter| !parameter.trim().is_empty())
+                .map(|parameter| {
+                    let (name, rust_type) = parameter.split_once(':').expect("a typed parameter");
+                    declaration(rust_type, name.trim())
+                })
+                .collect();
+            out += &format!(
+                "\n{}{} {}({});\n",
+                comment,
+                c_type(returns),
+                name,
+                parameters.join(", ")
+            );
+        }
+    }
+    out + "\n#ifdef __cplusplus\n}\n#endif\n\n#endif /* RWC_H */\n"
+}
@@ -0,0 +1,52 @@
+//! Scans arbitrary bytes whole and in chunks: the counts must not depend on
+//! where the chunks end, and never report fewer bytes than characters.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rwc::engine::{Metrics, Scanner};
+
+fuzz_target!(|input: &[u8]| {
+    // The first byte picks the chunk size; the rest is the text.
+    let Some((&size, data)) = input.split_first() else {
+        return;
+    };
+    let metrics = Metrics {
+        unique_lines: true,
+        unique_words: true,
+        wrap_at: Some(7),
+        lossy: true,
+        ..Metrics::default()
+    };
+    let mut whole = Scanner::new(&metrics);
+    whole.feed(data).unwrap();
+    let whole = whole.finish().unwrap();
+    let mut chunked = Scanner::new(&metrics);
+    for chunk in data.chunks(usize::from(size % 16) + 1) {
+        chunked.feed(chunk).unwrap();
+    }
+    let chunked = chunked.finish().unwrap();
+
+    assert!(whole.bytes >= whole.chars);
+    assert_eq!(whole.bytes, data.len());
+    let basic = |c: &rwc::engine::Counts| {
+        (
+            c.lines,
+            c.words,
+            c.chars,
+            c.bytes,
+            c.max_line_length,
+            c.paragraphs,
+            c.blank_lines,
+            c.sentences,
+            c.visual_lines,
+        )
+    };
+    assert_eq!(basic(&whole), basic(&chunked));
+
+    // Strict decoding fails exactly when the input is not UTF-8.
+    let strict = Metrics::default();
+    let mut scanner = Scanner::new(&strict);
+    let result = scanner.feed(data).and_then(|()| scanner.finish());
+    assert_eq!(result.is_ok(), std::str::from_utf8(data).is_ok());
+});
@@ -0,0 +1,30 @@
+//! Runs every built-in tokenizer over arbitrary text: each word must be a
+//! non-empty part of the line, found after the word before it, and the
+//! whitespace tokenizer must agree with the engine's word count.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rwc::engine::{self, Metrics};
+use rwc::tokenizer::{self, Tokenizer};
+
+fuzz_target!(|text: &str| {
+    let tokenizers: [Box<dyn Tokenizer>; 4] = [
+        tokenizer::builtin("whitespace").unwrap(),
+        tokenizer::builtin("uax29").unwrap(),
+        tokenizer::builtin("cjk").unwrap(),
+        tokenizer::builtin("regex:[a-z]+").unwrap(),
+    ];
+    for line in text.lines() {
+        for tokenizer in &tokenizers {
+            let mut rest = line;
+            tokenizer.words(line, &mut |word| {
+                assert!(!word.is_empty());
+                let at = rest.find(word).expect("a word of the line");
+                rest = &rest[at + word.len()..];
+            });
+        }
+    }
+    let words = tokenizer::count_words(text, tokenizers[0].as_ref());
+    assert_eq!(words, engine::scan(text, &Metrics::default()).words);
+});
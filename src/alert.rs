@@ -0,0 +1,56 @@
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use crate::output;
+
+/// Line-rate threshold checked by the streaming modes.
+#[derive(Clone)]
+pub struct RateAlert {
+    pub lines_per_sec: f64,
+    pub command: Option<String>,
+}
+
+impl RateAlert {
+    /// Fires the alert if `lines` observed over `elapsed` exceed the threshold.
+    pub fn check(&self, lines: usize, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return;
+        }
+        let rate = lines as f64 / secs;
+        if rate <= self.lines_per_sec {
+            return;
+        }
+
+        let Some(command) = &self.command else {
+            output::warn(format!(
+                "rwc: alert: {:.1} lines/sec exceeds threshold of {} lines/sec",
+                rate, self.lines_per_sec
+            ));
+            return;
+        };
+
+        let mut shell = if cfg!(windows) {
+            let mut c = Command::new("cmd");
+            c.arg("/C");
+            c
+        } else {
+            let mut c = Command::new("sh");
+            c.arg("-c");
+            c
+        };
+        shell
+            .arg(command)
+            .env("RWC_ALERT_RATE", format!("{:.1}", rate))
+            .env("RWC_ALERT_THRESHOLD", self.lines_per_sec.to_string())
+            .env("RWC_ALERT_LINES", lines.to_string());
+        // Run the hook without stalling the stream; a thread reaps the child.
+        match shell.spawn() {
+            Ok(mut child) => {
+                thread::spawn(move || child.wait());
+            }
+            Err(e) => output::warn(format!("rwc: alert command failed: {}", e)),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! `--archive`: counts of every file inside tar and zip archives, without
+//! extracting them.
+//!
+//! Archives may be compressed (`project.tar.gz`). Each member is counted as
+//! a file of its own would be, so compressed members are unpacked and the
+//! `--pre` steps apply; binary members are skipped. A row per member is
+//! followed by the archive's total.
+
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+
+use crate::filetype::{self, FileType};
+use crate::format::Formats;
+use crate::json::Value;
+use crate::{
+    count_stored, counts_json, formats, output, print_counts, print_json, tar, zip, Config, Counts,
+};
+
+struct Member {
+    label: String,
+    counts: Counts,
+}
+
+struct Report {
+    members: Vec<Member>,
+    /// Members left out as binary.
+    skipped: usize,
+    total: Counts,
+}
+
+/// Counts the members of each archive operand (standard input when there
+/// are none). Returns the exit code.
+pub fn run(config: &Config) -> i32 {
+    let stdin = ["-".to_string()];
+    let operands = if config.files.is_empty() {
+        &stdin[..]
+    } else {
+        &config.files[..]
+    };
+    let formats = formats::formats(config);
+    for operand in operands {
+        let label = if operand == "-" {
+            config.stdin_label()
+        } else {
+            operand.as_str()
+        };
+        match count(operand, label, config) {
+            Ok(report) => print_report(label, &report, &formats, config),
+            Err(e) => {
+                eprintln!("rwc: {}: {}", label, e);
+                return 1;
+            }
+        }
+    }
+    0
+}
+
+fn count(operand: &str, label: &str, config: &Config) -> io::Result<Report> {
+    let data = if operand == "-" {
+        let mut data = Vec::new();
+        io::stdin().lock().read_to_end(&mut data)?;
+        data
+    } else {
+        fs::read(operand)?
+    };
+    let (kind, data) = filetype::peel(Some(Path::new(operand)), data, None)?;
+    let mut report = Report {
+        members: Vec::new(),
+        skipped: 0,
+        total: Counts::default(),
+    };
+    let mut add = |name: &str, member: Vec<u8>| -> io::Result<()> {
+        let path = Path::new(name);
+        if FileType::detect(Some(path), &member) == FileType::Binary {
+            report.skipped += 1;
+            return Ok(());
+        }
+        let counts = match count_stored(Cursor::new(member), Some(path), config) {
+            Ok(counts) => counts,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                report.skipped += 1;
+                return Ok(());
+            }
+            Err(e) => return Err(io::Error::new(e.kind(), format!("{}: {}", name, e))),
+        };
+        report.total.add(&counts);
+        report.members.push(Member {
+            label: format!("{}:{}", label, name),
+            counts,
+        });
+        Ok(())
+    };
+    match kind {
+        FileType::Tar => tar::for_each_entry(&data[..], |header, reader| {
+            if header.kind != tar::EntryKind::File {
+                return Ok(());
+            }
+            let mut member = Vec::new();
+            reader.read_to_end(&mut member)?;
+            add(&header.path, member)
+        })?,
+        FileType::Zip => {
+            let archive = zip::Archive::open(&data)?;
+            for entry in archive.entries().iter().filter(|entry| !entry.is_dir()) {
+                add(&entry.name, archive.read(entry)?)?;
+            }
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a tar or zip archive",
+            ))
+        }
+    }
+    Ok(report)
+}
+
+fn print_report(label: &str, report: &Report, formats: &Formats<'_, Counts>, config: &Config) {
+    if report.skipped > 0 {
+        output::warn(format!(
+            "rwc: {}: skipped {} binary members",
+            label, report.skipped
+        ));
+    }
+    if config.show_json {
+        let files = report
+            .members
+            .iter()
+            .map(|member| counts_json(&member.counts, config, Some(&member.label)))
+            .collect();
+        let document = Value::Object(vec![
+            ("archive".to_string(), label.into()),
+            ("files".to_string(), Value::Array(files)),
+            ("skipped".to_string(), report.skipped.into()),
+            ("total".to_string(), counts_json(&report.total, config, None)),
+        ]);
+        print_json(&document, config);
+        return;
+    }
+    for member in &report.members {
+        print_counts(formats, &member.counts, config, Some(&member.label));
+    }
+    print_counts(formats, &report.total, config, Some(label));
+}
@@ -0,0 +1,67 @@
+//! `--format badge`: the totals as a shields.io endpoint badge.
+//!
+//! The output is the JSON a shields.io "endpoint" badge reads
+//! (`schemaVersion`, `label`, `message`, `color`), so CI can publish it and a
+//! README can show "docs | 12.3k words". The color comes from
+//! `--badge-colors`: the color of the highest threshold the count reaches.
+
+use crate::json::{self, Value};
+use crate::metric::Metric;
+use crate::status::compact;
+use crate::Counts;
+
+/// The color when no threshold is reached, or none are given.
+pub const DEFAULT_COLOR: &str = "blue";
+
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// `--badge-label`; the metric's name when unset.
+    pub label: Option<String>,
+    /// `--badge-metric`: the count shown.
+    pub metric: Metric,
+    /// `--badge-colors`: thresholds and their colors, in ascending order.
+    pub colors: Vec<(usize, String)>,
+}
+
+/// Parses `--badge-colors`: comma-separated `COUNT:COLOR` pairs, such as
+/// `1000:yellow,10000:green`.
+pub fn parse_colors(text: &str) -> Result<Vec<(usize, String)>, String> {
+    let mut colors = text
+        .split(',')
+        .map(|pair| {
+            let (count, color) = pair
+                .split_once(':')
+                .filter(|(_, color)| !color.trim().is_empty())
+                .ok_or_else(|| format!("invalid badge color '{}' (expected COUNT:COLOR)", pair))?;
+            let count = count
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid badge threshold '{}'", count))?;
+            Ok((count, color.trim().to_string()))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    colors.sort_by_key(|&(count, _)| count);
+    Ok(colors)
+}
+
+/// The badge document for `total`.
+pub fn render(total: &Counts, options: &Options) -> Value {
+    let count = options.metric.of(total);
+    let (label, message) = match &options.label {
+        // A custom label leaves the message to say what is counted.
+        Some(label) => (label.clone(), format!("{} {}", compact(count), options.metric.name())),
+        None => (options.metric.name().to_string(), compact(count)),
+    };
+    let color = options
+        .colors
+        .iter()
+        .rev()
+        .find(|&&(threshold, _)| count >= threshold)
+        .map_or(DEFAULT_COLOR, |(_, color)| color.as_str());
+    json::object([
+        ("schemaVersion", 1usize.into()),
+        ("label", label.as_str().into()),
+        ("message", message.as_str().into()),
+        ("color", color.into()),
+    ])
+}
@@ -0,0 +1,200 @@
+//! `rwc batch`: several reports from one manifest.
+//!
+//! The manifest is TOML with one table per job, run in the order written:
+//!
+//! ```toml
+//! options = ["-r"]            # before every job's own options
+//!
+//! [api]
+//! paths = ["docs/api"]
+//! options = ["--json"]
+//! output = "reports/api.json" # stdout when left out
+//! ```
+//!
+//! Each job is an `rwc OPTIONS... PATHS...` run of this executable, from the
+//! manifest's directory, so relative paths in it mean the same wherever
+//! `rwc batch` is run from.
+
+use std::fs::{self, File};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::cli;
+use crate::json::Value;
+use crate::toml;
+
+struct Job {
+    name: String,
+    args: Vec<String>,
+    output: Option<String>,
+}
+
+/// Entry point for `rwc batch`.
+pub fn run(args: &[String]) -> i32 {
+    let mut only = Vec::new();
+    let mut manifests = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--job" if i + 1 >= args.len() => {
+                eprintln!("rwc batch: option '--job' requires an argument");
+                return 1;
+            }
+            "--job" => {
+                i += 1;
+                only.push(args[i].clone());
+            }
+            "--help" => {
+                println!("Usage: rwc batch [--job NAME]... MANIFEST.toml");
+                println!();
+                println!("Runs every job in the manifest: a table with the 'paths' to count,");
+                println!("the rwc 'options' to count them with, and an 'output' file for the");
+                println!("report (stdout if left out). Top-level 'options' apply to every job.");
+                println!("Relative paths are taken from the manifest's directory. Exits with");
+                println!("the highest status of any job.");
+                println!();
+                println!("OPTIONS:");
+                println!("    --job NAME      Only run job NAME (repeatable)");
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option("rwc batch", a, &["--job", "--help"]);
+                return 1;
+            }
+            a => manifests.push(a.to_string()),
+        }
+        i += 1;
+    }
+    let [manifest] = manifests.as_slice() else {
+        eprintln!("rwc batch: expected exactly one manifest");
+        return 1;
+    };
+
+    let jobs = match fs::read_to_string(manifest)
+        .map_err(|e| e.to_string())
+        .and_then(|text| jobs(&text))
+    {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            eprintln!("rwc batch: {}: {}", manifest, e);
+            return 1;
+        }
+    };
+    if let Some(name) = only
+        .iter()
+        .find(|name| !jobs.iter().any(|job| &job.name == *name))
+    {
+        eprintln!("rwc batch: {}: no job named '{}'", manifest, name);
+        return 1;
+    }
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!("rwc batch: cannot find the rwc executable: {}", e);
+            return 1;
+        }
+    };
+    let directory = match Path::new(manifest).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut status = 0;
+    for job in jobs
+        .iter()
+        .filter(|job| only.is_empty() || only.contains(&job.name))
+    {
+        let mut command = Command::new(&exe);
+        command.args(&job.args).current_dir(directory);
+        match &job.output {
+            Some(output) => match File::create(directory.join(output)) {
+                Ok(file) => {
+                    command.stdout(Stdio::from(file));
+                }
+                Err(e) => {
+                    eprintln!("rwc batch: {}: {}: {}", job.name, output, e);
+                    status = status.max(1);
+                    continue;
+                }
+            },
+            None => println!("==> {} <==", job.name),
+        }
+        let code = match command.status() {
+            Ok(exit) => exit.code().unwrap_or(1),
+            Err(e) => {
+                eprintln!("rwc batch: {}: {}", job.name, e);
+                1
+            }
+        };
+        if let Some(output) = &job.output {
+            eprintln!("rwc batch: {}: wrote {}", job.name, output);
+        }
+        status = status.max(code);
+    }
+    status
+}
+
+/// The jobs in a manifest, in order.
+fn jobs(text: &str) -> Result<Vec<Job>, String> {
+    let Value::Object(members) = toml::parse(text)? else {
+        unreachable!("a TOML document is a table");
+    };
+    let mut common = Vec::new();
+    let mut jobs = Vec::new();
+    for (key, value) in &members {
+        match (key.as_str(), value) {
+            ("options", value) => common = strings(value, "options")?,
+            (name, Value::Object(keys)) => {
+                let known = ["paths", "options", "output"];
+                if let Some((unknown, _)) = keys.iter().find(|(k, _)| !known.contains(&k.as_str()))
+                {
+                    return Err(format!("[{}]: unknown key '{}'", name, unknown));
+                }
+                let get = |key: &str| value.get(key);
+                let paths = match get("paths") {
+                    Some(paths) => strings(paths, &format!("{}.paths", name))?,
+                    None => return Err(format!("[{}]: missing 'paths'", name)),
+                };
+                let options = match get("options") {
+                    Some(options) => strings(options, &format!("{}.options", name))?,
+                    None => Vec::new(),
+                };
+                let output = match get("output") {
+                    Some(Value::String(output)) => Some(output.clone()),
+                    Some(_) => return Err(format!("{}.output must be a string", name)),
+                    None => None,
+                };
+                let mut args = common.clone();
+                args.extend(options);
+                args.extend(paths);
+                jobs.push(Job {
+                    name: name.to_string(),
+                    args,
+                    output,
+                });
+            }
+            (key, _) => return Err(format!("unknown key '{}'", key)),
+        }
+    }
+    if jobs.is_empty() {
+        return Err("no jobs (a job is a [table] with 'paths')".to_string());
+    }
+    Ok(jobs)
+}
+
+/// `value` as a list of strings; a single string is a list of one.
+pub fn strings(value: &Value, key: &str) -> Result<Vec<String>, String> {
+    match value {
+        Value::String(s) => Ok(vec![s.clone()]),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| format!("{} must be a list of strings", key))
+            })
+            .collect(),
+        _ => Err(format!("{} must be a list of strings", key)),
+    }
+}
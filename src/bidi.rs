@@ -0,0 +1,44 @@
+//! Safe rendering of file names containing right-to-left text.
+//!
+//! Without isolation, an Arabic or Hebrew name can reorder the neighbouring
+//! count columns on screen, and embedded bidi controls (e.g. U+202E) can make
+//! a name display as something it is not.
+
+/// Bidi formatting characters, shown escaped rather than obeyed.
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Characters with strong right-to-left direction.
+fn is_rtl(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0590}'..='\u{08FF}'
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}'
+            | '\u{10800}'..='\u{10FFF}'
+            | '\u{1E800}'..='\u{1EFFF}'
+    )
+}
+
+/// Returns `name` ready for table output: bidi and other control characters
+/// are escaped as `\u{...}`, and names with right-to-left text are wrapped in
+/// first-strong isolates (U+2068 … U+2069) so they cannot affect the columns.
+pub fn display_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        if is_bidi_control(c) || c.is_control() {
+            out.push_str(&format!("\\u{{{:04x}}}", c as u32));
+        } else {
+            out.push(c);
+        }
+    }
+    if name.chars().any(is_rtl) {
+        format!("\u{2068}{}\u{2069}", out)
+    } else {
+        out
+    }
+}
@@ -0,0 +1,144 @@
+//! `rwc blame-stats`: line ownership per author from `git blame`.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use crate::json::{self, Value};
+use crate::{cli, git};
+
+/// Entry point for `rwc blame-stats [--json] PATH...`.
+pub fn run(args: &[String]) -> i32 {
+    let mut json = false;
+    let mut paths = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--help" => {
+                print_help();
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option("rwc blame-stats", a, &["--json", "--help"]);
+                return 1;
+            }
+            a => paths.push(a.to_string()),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(".".to_string());
+    }
+
+    match blame(&paths) {
+        Ok(ownership) => {
+            if json {
+                println!("{}", ownership.to_json());
+            } else {
+                ownership.print();
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("rwc blame-stats: {}", e);
+            1
+        }
+    }
+}
+
+fn print_help() {
+    println!("Usage: rwc blame-stats [--json] [PATH...]");
+    println!();
+    println!("Attributes every line of the tracked files under PATH (default: the");
+    println!("current directory) to its author with 'git blame', and reports each");
+    println!("author's line count and share of the total. Requires git.");
+    println!();
+    println!("OPTIONS:");
+    println!("    --json          Output in JSON format");
+}
+
+#[derive(Default)]
+struct Ownership {
+    files: usize,
+    lines: HashMap<String, usize>,
+}
+
+impl Ownership {
+    fn total(&self) -> usize {
+        self.lines.values().sum()
+    }
+
+    /// Authors by descending line count, then name.
+    fn ranked(&self) -> Vec<(&str, usize)> {
+        let mut authors: Vec<(&str, usize)> =
+            self.lines.iter().map(|(k, &v)| (k.as_str(), v)).collect();
+        authors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        authors
+    }
+
+    fn share(&self, lines: usize) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => lines as f64 * 100.0 / total as f64,
+        }
+    }
+
+    fn print(&self) {
+        println!("{:>8}{:>8} AUTHOR", "LINES", "SHARE");
+        for (author, lines) in self.ranked() {
+            println!("{:>8}{:>7.1}% {}", lines, self.share(lines), author);
+        }
+        println!(
+            "{:>8}{:>7.1}% total ({} files)",
+            self.total(),
+            if self.total() == 0 { 0.0 } else { 100.0 },
+            self.files
+        );
+    }
+
+    fn to_json(&self) -> Value {
+        let authors = self
+            .ranked()
+            .into_iter()
+            .map(|(author, lines)| {
+                json::object([
+                    ("author", author.into()),
+                    ("lines", lines.into()),
+                    ("share", Value::Number((self.share(lines) * 10.0).round() / 10.0)),
+                ])
+            })
+            .collect();
+        json::object([
+            ("files", self.files.into()),
+            ("lines", self.total().into()),
+            ("authors", Value::Array(authors)),
+        ])
+    }
+}
+
+fn blame(paths: &[String]) -> io::Result<Ownership> {
+    let here = Path::new(".");
+    let mut args = vec!["ls-files", "-z", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    let files = git::split_nul(&git::output(here, &args)?);
+    if files.is_empty() {
+        return Err(io::Error::other(format!(
+            "no tracked files under {}",
+            paths.join(", ")
+        )));
+    }
+
+    let mut ownership = Ownership::default();
+    for file in files {
+        let porcelain = git::output(here, ["blame", "--line-porcelain", "--", file.as_str()])?;
+        // --line-porcelain repeats the commit headers for every line, so each
+        // `author` header is exactly one line of the file.
+        for line in String::from_utf8_lossy(&porcelain).lines() {
+            if let Some(author) = line.strip_prefix("author ") {
+                *ownership.lines.entry(author.to_string()).or_insert(0) += 1;
+            }
+        }
+        ownership.files += 1;
+    }
+    Ok(ownership)
+}
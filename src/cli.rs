@@ -0,0 +1,57 @@
+//! Shared command-line helpers: typo suggestions for options and commands.
+
+/// Edit distance between `a` and `b` in characters, counting an adjacent
+/// transposition (`stast` for `stats`) as a single edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Returns the candidate closest to `input`, if it is close enough to be a
+/// plausible typo. A `--flag=value` input is compared by its flag part.
+pub fn suggest<'a>(input: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let input = input.split('=').next().unwrap_or(input);
+    let allowed = (input.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|&candidate| (edit_distance(input, candidate), candidate))
+        .filter(|&(distance, _)| distance <= allowed)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+        // An unambiguous prefix ("--lin") is a typo of its completion too.
+        .or_else(|| {
+            let mut matches = candidates
+                .iter()
+                .filter(|c| input.len() > 2 && c.starts_with(input));
+            match (matches.next(), matches.next()) {
+                (Some(&only), None) => Some(only),
+                _ => None,
+            }
+        })
+}
+
+/// Prints the unknown-option error for `program`, with a suggestion when
+/// `arg` looks like a typo of one of `options`.
+pub fn unknown_option(program: &str, arg: &str, options: &[&str]) {
+    eprintln!("{}: unknown option '{}'", program, arg);
+    if let Some(option) = suggest(arg, options) {
+        eprintln!("  did you mean '{}'?", option);
+    }
+}
@@ -0,0 +1,275 @@
+//! `rwc columnar`: rows, words in string cells and null ratios of Parquet and
+//! Arrow files.
+//!
+//! The files are read through the `duckdb` command-line client, as `rwc sql`
+//! reads databases through theirs: one query takes the schema, one counts
+//! the non-null values of every column, and the string columns are then
+//! streamed back so their words are counted by the usual rules. Arrow IPC
+//! files (`.arrow`, `.feather`, `.ipc`) need DuckDB's `nanoarrow` extension,
+//! which is installed on first use.
+
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::engine::{Metrics, Scanner};
+use crate::json::{self, Value};
+use crate::{cli, format_number};
+
+/// Separators of DuckDB's `-ascii` output mode.
+const FIELD_SEP: u8 = 0x1f;
+const RECORD_SEP: u8 = 0x1e;
+
+struct Column {
+    name: String,
+    kind: String,
+    nulls: usize,
+    /// Words in the column's cells, for string columns.
+    words: Option<usize>,
+}
+
+struct Report {
+    rows: usize,
+    columns: Vec<Column>,
+}
+
+impl Report {
+    fn words(&self) -> usize {
+        self.columns.iter().filter_map(|column| column.words).sum()
+    }
+
+    /// Null cells as a share of all cells.
+    fn null_ratio(&self) -> f64 {
+        let cells = self.rows * self.columns.len();
+        let nulls: usize = self.columns.iter().map(|column| column.nulls).sum();
+        ratio(nulls, cells)
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Entry point for `rwc columnar FILE...`.
+pub fn run(args: &[String]) -> i32 {
+    let mut files = Vec::new();
+    let mut json = false;
+    let mut human = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--human" => human = true,
+            "--help" => {
+                println!("Usage: rwc columnar [--json] [-h] FILE...");
+                println!();
+                println!("Counts rows, words in string cells and null ratios of Parquet");
+                println!("(.parquet) and Arrow IPC (.arrow, .feather, .ipc) files, with a");
+                println!("line per column. Requires the duckdb client on PATH.");
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option("rwc columnar", a, &["--json", "--human", "--help"]);
+                return 1;
+            }
+            file => files.push(file.to_string()),
+        }
+    }
+    if files.is_empty() {
+        eprintln!("rwc columnar: no input files");
+        eprintln!("Try 'rwc columnar --help' for more information.");
+        return 1;
+    }
+
+    let mut documents = Vec::new();
+    for file in &files {
+        let report = match source(file).and_then(|source| count(&source)) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("rwc columnar: {}: {}", file, e);
+                return 1;
+            }
+        };
+        if json {
+            documents.push(report_json(file, &report));
+        } else {
+            print_report(file, &report, human);
+        }
+    }
+    if json {
+        println!("{}", Value::Array(documents).pretty());
+    }
+    0
+}
+
+/// The DuckDB table expression reading `file`, with any statements needed
+/// before it.
+struct Source {
+    setup: &'static str,
+    table: String,
+}
+
+fn source(file: &str) -> io::Result<Source> {
+    let quoted = format!("'{}'", file.replace('\'', "''"));
+    let extension = Path::new(file)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("parquet" | "parq") => Ok(Source {
+            setup: "",
+            table: format!("read_parquet({})", quoted),
+        }),
+        Some("arrow" | "feather" | "ipc" | "arrows") => Ok(Source {
+            setup: "INSTALL nanoarrow FROM community; LOAD nanoarrow;",
+            table: format!("read_arrow({})", quoted),
+        }),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a Parquet or Arrow file (expected .parquet, .arrow, .feather or .ipc)",
+        )),
+    }
+}
+
+fn identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn count(source: &Source) -> io::Result<Report> {
+    let mut columns: Vec<Column> = Vec::new();
+    query(source, &format!("DESCRIBE SELECT * FROM {}", source.table), |fields| {
+        if let [name, kind, ..] = fields {
+            columns.push(Column {
+                name: String::from_utf8_lossy(name).into_owned(),
+                kind: String::from_utf8_lossy(kind).into_owned(),
+                nulls: 0,
+                words: None,
+            });
+        }
+    })?;
+
+    let mut totals: Vec<usize> = Vec::new();
+    let counted: Vec<String> = std::iter::once("count(*)".to_string())
+        .chain(columns.iter().map(|c| format!("count({})", identifier(&c.name))))
+        .collect();
+    let sql = format!("SELECT {} FROM {}", counted.join(", "), source.table);
+    query(source, &sql, |fields| {
+        totals = fields
+            .iter()
+            .map(|field| String::from_utf8_lossy(field).trim().parse().unwrap_or(0))
+            .collect();
+    })?;
+    let rows = totals.first().copied().unwrap_or(0);
+    for (column, &values) in columns.iter_mut().zip(totals.iter().skip(1)) {
+        column.nulls = rows.saturating_sub(values);
+    }
+
+    let strings: Vec<usize> = (0..columns.len())
+        .filter(|&i| columns[i].kind == "VARCHAR")
+        .collect();
+    if !strings.is_empty() {
+        let metrics = Metrics::default();
+        let mut scanners: Vec<Scanner> = strings.iter().map(|_| Scanner::new(&metrics)).collect();
+        let selected: Vec<String> =
+            strings.iter().map(|&i| identifier(&columns[i].name)).collect();
+        let sql = format!("SELECT {} FROM {}", selected.join(", "), source.table);
+        let mut result = Ok(());
+        query(source, &sql, |fields| {
+            for (scanner, field) in scanners.iter_mut().zip(fields) {
+                // A space keeps the last word of one cell from running into
+                // the first of the next.
+                if result.is_ok() {
+                    result = scanner.feed(field).and_then(|()| scanner.feed(b" "));
+                }
+            }
+        })?;
+        result?;
+        for (&i, scanner) in strings.iter().zip(scanners) {
+            columns[i].words = Some(scanner.finish()?.words);
+        }
+    }
+    Ok(Report { rows, columns })
+}
+
+/// Runs `sql` against `source` and passes each result row, split into
+/// fields, to `row`.
+fn query(source: &Source, sql: &str, mut row: impl FnMut(&[&[u8]])) -> io::Result<()> {
+    let mut child = Command::new("duckdb")
+        .args(["-batch", "-bail", "-noheader", "-ascii", "-nullvalue", ""])
+        .arg("-c")
+        .arg(format!("{} {};", source.setup, sql))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("duckdb: {}", e)))?;
+
+    let mut reader = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut record = Vec::new();
+    loop {
+        record.clear();
+        if reader.read_until(RECORD_SEP, &mut record)? == 0 {
+            break;
+        }
+        if record.last() == Some(&RECORD_SEP) {
+            record.pop();
+        }
+        let fields: Vec<&[u8]> = record.split(|&b| b == FIELD_SEP).collect();
+        row(&fields);
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("duckdb exited with {}", status)));
+    }
+    Ok(())
+}
+
+fn report_json(file: &str, report: &Report) -> Value {
+    let rounded = |value: f64| Value::Number((value * 1e4).round() / 1e4);
+    let columns = report
+        .columns
+        .iter()
+        .map(|column| {
+            json::object([
+                ("name", column.name.as_str().into()),
+                ("type", column.kind.as_str().into()),
+                ("nulls", column.nulls.into()),
+                ("null_ratio", rounded(ratio(column.nulls, report.rows))),
+                ("words", column.words.map_or(Value::Null, Value::from)),
+            ])
+        })
+        .collect();
+    json::object([
+        ("file", file.into()),
+        ("rows", report.rows.into()),
+        ("words", report.words().into()),
+        ("null_ratio", rounded(report.null_ratio())),
+        ("columns", Value::Array(columns)),
+    ])
+}
+
+fn print_report(file: &str, report: &Report, human: bool) {
+    println!(
+        "{:>8}{:>10}{:>7.1}% {}",
+        format_number(report.rows, human),
+        format_number(report.words(), human),
+        report.null_ratio() * 100.0,
+        file
+    );
+    let width = report.columns.iter().map(|c| c.name.chars().count()).max().unwrap_or(0);
+    for column in &report.columns {
+        let words = column.words.map_or("-".to_string(), |words| format_number(words, human));
+        println!(
+            "    | {:<width$} {:<10} {:>10} words {:>6.1}% null",
+            column.name,
+            column.kind,
+            words,
+            ratio(column.nulls, report.rows) * 100.0,
+            width = width
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! `rwc commit-msg FILE`: commit message length checks for a commit-msg hook.
+
+use std::fs;
+
+use crate::cli;
+
+const DEFAULT_SUBJECT_MAX: usize = 72;
+const DEFAULT_BODY_MAX: usize = 80;
+
+/// Git drops everything below this line when committing with `--verbose`.
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+struct Limits {
+    subject_max: usize,
+    body_max: usize,
+    min_words: Option<usize>,
+    max_words: Option<usize>,
+}
+
+/// Entry point for `rwc commit-msg`. Exits 0 when the message passes, 1 when
+/// it breaks a limit and 2 when it cannot be checked.
+pub fn run(args: &[String]) -> i32 {
+    let mut limits = Limits {
+        subject_max: DEFAULT_SUBJECT_MAX,
+        body_max: DEFAULT_BODY_MAX,
+        min_words: None,
+        max_words: None,
+    };
+    let mut file = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--subject-max" | "--body-max" | "--min-words" | "--max-words" => {
+                let Some(value) = args.get(i + 1) else {
+                    eprintln!("rwc commit-msg: option '{}' requires an argument", arg);
+                    return 2;
+                };
+                let Ok(n) = value.parse::<usize>() else {
+                    eprintln!("rwc commit-msg: invalid number '{}' for {}", value, arg);
+                    return 2;
+                };
+                match arg {
+                    "--subject-max" => limits.subject_max = n,
+                    "--body-max" => limits.body_max = n,
+                    "--min-words" => limits.min_words = Some(n),
+                    _ => limits.max_words = Some(n),
+                }
+                i += 1;
+            }
+            "--help" => {
+                print_help();
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option(
+                    "rwc commit-msg",
+                    a,
+                    &["--subject-max", "--body-max", "--min-words", "--max-words", "--help"],
+                );
+                return 2;
+            }
+            a => file = Some(a.to_string()),
+        }
+        i += 1;
+    }
+
+    let Some(file) = file else {
+        eprintln!("rwc commit-msg: missing FILE argument");
+        eprintln!("Try 'rwc commit-msg --help' for more information.");
+        return 2;
+    };
+    let text = match fs::read_to_string(&file) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("rwc commit-msg: {}: {}", file, e);
+            return 2;
+        }
+    };
+
+    let problems = check(&text, &limits);
+    for (line, problem) in &problems {
+        match line {
+            Some(line) => eprintln!("rwc commit-msg: {}:{}: {}", file, line, problem),
+            None => eprintln!("rwc commit-msg: {}: {}", file, problem),
+        }
+    }
+    if problems.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+fn print_help() {
+    println!("Usage: rwc commit-msg [OPTIONS] FILE");
+    println!();
+    println!("Checks a commit message against length limits. Comment lines and the");
+    println!("diff below a --verbose scissors line are ignored, as git does. Meant");
+    println!("for .git/hooks/commit-msg:");
+    println!();
+    println!("    #!/bin/sh");
+    println!("    exec rwc commit-msg \"$1\"");
+    println!();
+    println!("OPTIONS:");
+    println!(
+        "    --subject-max N Longest allowed subject line (default {})",
+        DEFAULT_SUBJECT_MAX
+    );
+    println!(
+        "    --body-max N    Longest allowed body line (default {}); lines without",
+        DEFAULT_BODY_MAX
+    );
+    println!("                    spaces, such as URLs, are exempt");
+    println!("    --min-words N   Require at least N words in the whole message");
+    println!("    --max-words N   Allow at most N words in the whole message");
+    println!();
+    println!("Exit status is 0 if the message passes, 1 if it breaks a limit and 2 if");
+    println!("it could not be checked.");
+}
+
+/// Returns the problems found, each with its 1-based line number if it
+/// concerns a single line.
+fn check(text: &str, limits: &Limits) -> Vec<(Option<usize>, String)> {
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .take_while(|line| *line != SCISSORS)
+        .enumerate()
+        .filter(|(_, line)| !line.starts_with('#'))
+        .map(|(index, line)| (index + 1, line.trim_end()))
+        .skip_while(|(_, line)| line.is_empty())
+        .collect();
+
+    let mut problems = Vec::new();
+    let Some(&(subject_line, subject)) = lines.first() else {
+        problems.push((None, "message is empty".to_string()));
+        return problems;
+    };
+
+    let length = subject.chars().count();
+    if length > limits.subject_max {
+        problems.push((
+            Some(subject_line),
+            format!(
+                "subject is {} characters (limit {})",
+                length, limits.subject_max
+            ),
+        ));
+    }
+    if let Some(&(line, text)) = lines.get(1) {
+        if !text.is_empty() {
+            problems.push((
+                Some(line),
+                "subject must be followed by a blank line".to_string(),
+            ));
+        }
+    }
+    for &(line, text) in lines.iter().skip(1) {
+        let length = text.chars().count();
+        if length > limits.body_max && text.trim().contains(char::is_whitespace) {
+            problems.push((
+                Some(line),
+                format!("body line is {} characters (limit {})", length, limits.body_max),
+            ));
+        }
+    }
+
+    let words: usize = lines.iter().map(|(_, line)| line.split_whitespace().count()).sum();
+    match (limits.min_words, limits.max_words) {
+        (Some(min), _) if words < min => problems.push((
+            None,
+            format!("message has {} words (minimum {})", words, min),
+        )),
+        (_, Some(max)) if words > max => problems.push((
+            None,
+            format!("message has {} words (maximum {})", words, max),
+        )),
+        _ => {}
+    }
+    problems
+}
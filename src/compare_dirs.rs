@@ -0,0 +1,313 @@
+//! `rwc compare-dirs`: a source tree and its translation compared file by
+//! file.
+//!
+//! Files are paired by their path relative to each root. A file with no
+//! counterpart in the translation is `missing` (untranslated); one found only
+//! in the translation is `extra`. For pairs, the ratio of target to source
+//! words is checked against `--min-ratio` and `--max-ratio`, since a
+//! translation much shorter or longer than its source is often incomplete or
+//! out of date. Markup is stripped by file type first, as `--auto` does, so
+//! the ratios compare text rather than tags.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+use crate::json::{self, Value};
+use crate::transform::Transform;
+use crate::{cli, process_file, walk, Config};
+
+const MIN_RATIO: f64 = 0.5;
+const MAX_RATIO: f64 = 2.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Status {
+    Ok,
+    Short,
+    Long,
+    Missing,
+    Extra,
+}
+
+impl Status {
+    fn name(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Short => "short",
+            Status::Long => "long",
+            Status::Missing => "missing",
+            Status::Extra => "extra",
+        }
+    }
+}
+
+struct Pair {
+    file: String,
+    source: Option<usize>,
+    target: Option<usize>,
+    status: Status,
+}
+
+impl Pair {
+    fn ratio(&self) -> Option<f64> {
+        match (self.source, self.target) {
+            (Some(source), Some(target)) if source > 0 => Some(target as f64 / source as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Entry point for `rwc compare-dirs`.
+pub fn run(args: &[String]) -> i32 {
+    let mut json = false;
+    let mut min_ratio = MIN_RATIO;
+    let mut max_ratio = MAX_RATIO;
+    let mut roots = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--min-ratio" | "--max-ratio" if i + 1 >= args.len() => {
+                eprintln!(
+                    "rwc compare-dirs: option '{}' requires an argument",
+                    args[i]
+                );
+                return 1;
+            }
+            option @ ("--min-ratio" | "--max-ratio") => {
+                i += 1;
+                let ratio = match args[i].parse::<f64>() {
+                    Ok(ratio) if ratio.is_finite() && ratio >= 0.0 => ratio,
+                    _ => {
+                        eprintln!("rwc compare-dirs: invalid ratio '{}'", args[i]);
+                        return 1;
+                    }
+                };
+                if option == "--min-ratio" {
+                    min_ratio = ratio;
+                } else {
+                    max_ratio = ratio;
+                }
+            }
+            "--json" => json = true,
+            "--help" => {
+                print_help();
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option(
+                    "rwc compare-dirs",
+                    a,
+                    &["--min-ratio", "--max-ratio", "--json", "--help"],
+                );
+                return 1;
+            }
+            a => roots.push(a.to_string()),
+        }
+        i += 1;
+    }
+    let [source, target] = roots.as_slice() else {
+        eprintln!("rwc compare-dirs: expected SOURCE_DIR and TARGET_DIR");
+        eprintln!("Try 'rwc compare-dirs --help' for more information.");
+        return 1;
+    };
+    if min_ratio > max_ratio {
+        eprintln!("rwc compare-dirs: --min-ratio is above --max-ratio");
+        return 1;
+    }
+
+    let (source_words, target_words) = match (words_under(source), words_under(target)) {
+        (Ok(source), Ok(target)) => (source, target),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("rwc compare-dirs: {}", e);
+            return 1;
+        }
+    };
+    let pairs = pair(&source_words, &target_words, min_ratio, max_ratio);
+    if json {
+        println!("{}", to_json(source, target, &pairs));
+    } else {
+        print(&pairs);
+    }
+    0
+}
+
+fn print_help() {
+    println!(
+        "Usage: rwc compare-dirs [--min-ratio R] [--max-ratio R] [--json] SOURCE_DIR TARGET_DIR"
+    );
+    println!();
+    println!("Pairs the files of a source tree and its translation by relative path and");
+    println!("reports the words in each, the ratio of target to source words, and files");
+    println!("missing from either side. Markup is stripped by file type, as with --auto;");
+    println!("files that are not text are left out.");
+    println!();
+    println!("Each file is marked ok, short or long (ratio outside the limits), missing");
+    println!("(not translated) or extra (only in TARGET_DIR).");
+    println!();
+    println!("OPTIONS:");
+    println!("    --min-ratio R   Mark pairs with fewer than R target words per source");
+    println!("                    word as short (default {})", MIN_RATIO);
+    println!("    --max-ratio R   Mark pairs with more than R target words per source");
+    println!("                    word as long (default {})", MAX_RATIO);
+    println!("    --json          Output in JSON format");
+}
+
+/// The words of each text file under `root`, keyed by relative path with `/`
+/// separators.
+fn words_under(root: &str) -> Result<BTreeMap<String, usize>, String> {
+    if !Path::new(root).is_dir() {
+        return Err(format!("{}: not a directory", root));
+    }
+    let files = walk::files(Path::new(root)).map_err(|e| format!("{}: {}", root, e))?;
+    let mut config = Config::default();
+    config.pipeline.push(Transform::Auto(None));
+
+    let mut words = BTreeMap::new();
+    for file in files {
+        let name = file.to_string_lossy();
+        let counts = match process_file(&name, &config) {
+            Ok(counts) => counts,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => continue,
+            // The message already names the file.
+            Err(e) => return Err(e.to_string().trim_start_matches("rwc: ").to_string()),
+        };
+        let relative = file.strip_prefix(root).unwrap_or(&file);
+        let key: Vec<String> = relative
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        words.insert(key.join("/"), counts.words);
+    }
+    Ok(words)
+}
+
+/// Every file of either tree, in path order, with its status.
+fn pair(
+    source: &BTreeMap<String, usize>,
+    target: &BTreeMap<String, usize>,
+    min_ratio: f64,
+    max_ratio: f64,
+) -> Vec<Pair> {
+    let mut files: Vec<&String> = source.keys().chain(target.keys()).collect();
+    files.sort_unstable();
+    files.dedup();
+    files
+        .into_iter()
+        .map(|file| {
+            let mut pair = Pair {
+                file: file.clone(),
+                source: source.get(file).copied(),
+                target: target.get(file).copied(),
+                status: Status::Ok,
+            };
+            pair.status = match (pair.source, pair.target) {
+                (Some(_), None) => Status::Missing,
+                (None, _) => Status::Extra,
+                (Some(0), Some(0)) => Status::Ok,
+                // Words in a translation of nothing count as long.
+                (Some(0), Some(_)) => Status::Long,
+                _ => match pair.ratio() {
+                    Some(ratio) if ratio < min_ratio => Status::Short,
+                    Some(ratio) if ratio > max_ratio => Status::Long,
+                    _ => Status::Ok,
+                },
+            };
+            pair
+        })
+        .collect()
+}
+
+fn count(pairs: &[Pair], status: Status) -> usize {
+    pairs.iter().filter(|pair| pair.status == status).count()
+}
+
+/// Words of the files present on both sides.
+fn paired_words(pairs: &[Pair]) -> (usize, usize) {
+    pairs
+        .iter()
+        .filter_map(|pair| Some((pair.source?, pair.target?)))
+        .fold((0, 0), |(source, target), pair| {
+            (source + pair.0, target + pair.1)
+        })
+}
+
+fn print(pairs: &[Pair]) {
+    let words = |words: Option<usize>| words.map_or("-".to_string(), |n| n.to_string());
+    println!(
+        "{:<8} {:>8} {:>8} {:>6}  file",
+        "status", "source", "target", "ratio"
+    );
+    for pair in pairs {
+        println!(
+            "{:<8} {:>8} {:>8} {:>6}  {}",
+            pair.status.name(),
+            words(pair.source),
+            words(pair.target),
+            pair.ratio()
+                .map_or("-".to_string(), |ratio| format!("{:.2}", ratio)),
+            pair.file
+        );
+    }
+    println!();
+    let paired = pairs
+        .iter()
+        .filter(|pair| pair.target.is_some() && pair.source.is_some());
+    let (source, target) = paired_words(pairs);
+    println!(
+        "{} paired ({} short, {} long), {} missing, {} extra",
+        paired.count(),
+        count(pairs, Status::Short),
+        count(pairs, Status::Long),
+        count(pairs, Status::Missing),
+        count(pairs, Status::Extra)
+    );
+    if source > 0 {
+        println!(
+            "paired words: {} → {} ({:.2})",
+            source,
+            target,
+            target as f64 / source as f64
+        );
+    }
+}
+
+fn to_json(source: &str, target: &str, pairs: &[Pair]) -> Value {
+    let optional = |words: Option<usize>| words.map_or(Value::Null, Value::from);
+    let files = pairs
+        .iter()
+        .map(|pair| {
+            json::object([
+                ("file", pair.file.as_str().into()),
+                ("status", pair.status.name().into()),
+                ("source_words", optional(pair.source)),
+                ("target_words", optional(pair.target)),
+                ("ratio", pair.ratio().map_or(Value::Null, rounded)),
+            ])
+        })
+        .collect();
+    let (source_words, target_words) = paired_words(pairs);
+    let ratio = (source_words > 0).then(|| target_words as f64 / source_words as f64);
+    json::object([
+        ("source", source.into()),
+        ("target", target.into()),
+        ("files", Value::Array(files)),
+        (
+            "summary",
+            json::object([
+                ("ok", count(pairs, Status::Ok).into()),
+                ("short", count(pairs, Status::Short).into()),
+                ("long", count(pairs, Status::Long).into()),
+                ("missing", count(pairs, Status::Missing).into()),
+                ("extra", count(pairs, Status::Extra).into()),
+                ("source_words", source_words.into()),
+                ("target_words", target_words.into()),
+                ("ratio", ratio.map_or(Value::Null, rounded)),
+            ]),
+        ),
+    ])
+}
+
+fn rounded(ratio: f64) -> Value {
+    Value::Number((ratio * 100.0).round() / 100.0)
+}
@@ -0,0 +1,285 @@
+//! `rwc corpus`: corpus-level statistics for dataset sizing.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::json::{self, Value};
+use crate::{cli, parse_size, walk};
+
+/// Entry point for `rwc corpus`.
+pub fn run(args: &[String]) -> i32 {
+    let mut json = false;
+    let mut zipf = None;
+    let mut paths = Vec::new();
+    let mut limits = walk::Limits::default();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--zipf" if i + 1 >= args.len() => {
+                eprintln!("rwc corpus: option '--zipf' requires an argument");
+                return 1;
+            }
+            "--zipf" => {
+                i += 1;
+                zipf = Some(args[i].clone());
+            }
+            "--max-files" | "--max-total-bytes" if i + 1 >= args.len() => {
+                eprintln!("rwc corpus: option '{}' requires an argument", args[i]);
+                return 1;
+            }
+            "--max-files" => {
+                i += 1;
+                match args[i].parse::<usize>() {
+                    Ok(max) if max > 0 => limits.max_files = Some(max),
+                    _ => {
+                        eprintln!("rwc corpus: invalid file limit '{}'", args[i]);
+                        return 1;
+                    }
+                }
+            }
+            "--max-total-bytes" => {
+                i += 1;
+                match parse_size(&args[i]) {
+                    Some(max) if max > 0 => limits.max_total_bytes = Some(max),
+                    _ => {
+                        eprintln!("rwc corpus: invalid size '{}'", args[i]);
+                        return 1;
+                    }
+                }
+            }
+            "--json" => json = true,
+            "--help" => {
+                print_help();
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option("rwc corpus", a, &["--zipf", "--max-files", "--max-total-bytes", "--json", "--help"]);
+                return 1;
+            }
+            a => paths.push(a.to_string()),
+        }
+        i += 1;
+    }
+    if paths.is_empty() {
+        eprintln!("rwc corpus: no PATH given");
+        eprintln!("Try 'rwc corpus --help' for more information.");
+        return 1;
+    }
+
+    let mut corpus = Corpus::default();
+    let mut budget = walk::Budget::new(limits);
+    for path in &paths {
+        if budget.is_exhausted() {
+            break;
+        }
+        let files = match walk::files_within(Path::new(path), &mut budget) {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("rwc corpus: {}: {}", path, e);
+                return 1;
+            }
+        };
+        for file in files {
+            match fs::read_to_string(&file) {
+                Ok(text) => corpus.add_document(&text),
+                // Binary and non-UTF-8 files are not part of a text corpus.
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => corpus.skipped += 1,
+                Err(e) => {
+                    eprintln!("rwc corpus: {}: {}", file.display(), e);
+                    return 1;
+                }
+            }
+        }
+    }
+
+    budget.warn_if_exhausted();
+
+    let ranked = corpus.ranked();
+    if let Some(target) = zipf {
+        if let Err(e) = write_zipf(&target, &ranked) {
+            eprintln!("rwc corpus: {}: {}", target, e);
+            return 1;
+        }
+    }
+    let report = corpus.report(&ranked);
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        report.print();
+    }
+    0
+}
+
+fn print_help() {
+    println!("Usage: rwc corpus [--zipf FILE] [--max-files N] [--max-total-bytes SIZE] [--json] PATH...");
+    println!();
+    println!("Reports corpus statistics over every text file under PATH: documents,");
+    println!("tokens, vocabulary size, hapax legomena and document length percentiles.");
+    println!();
+    println!("Tokens are whitespace-separated words, lowercased, with surrounding");
+    println!("punctuation removed.");
+    println!();
+    println!("OPTIONS:");
+    println!("    --zipf FILE     Write the rank/frequency table (Zipf curve) as CSV");
+    println!("    --max-files N   Stop after N files and report what was read");
+    println!("    --max-total-bytes SIZE");
+    println!("                    Stop before reading more than SIZE bytes (e.g. 500M)");
+    println!("    --json          Output in JSON format");
+}
+
+#[derive(Default)]
+struct Corpus {
+    frequencies: HashMap<String, usize>,
+    /// Token count of each document.
+    lengths: Vec<usize>,
+    skipped: usize,
+}
+
+impl Corpus {
+    fn add_document(&mut self, text: &str) {
+        let mut length = 0;
+        for word in text.split_whitespace() {
+            let token = word.trim_matches(|c: char| !c.is_alphanumeric());
+            if token.is_empty() {
+                continue;
+            }
+            *self.frequencies.entry(token.to_lowercase()).or_insert(0) += 1;
+            length += 1;
+        }
+        self.lengths.push(length);
+    }
+
+    /// Vocabulary by descending frequency, ties broken alphabetically.
+    fn ranked(&self) -> Vec<(&str, usize)> {
+        let mut ranked: Vec<(&str, usize)> = self
+            .frequencies
+            .iter()
+            .map(|(word, &count)| (word.as_str(), count))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        ranked
+    }
+
+    fn report(&self, ranked: &[(&str, usize)]) -> Report {
+        let mut lengths = self.lengths.clone();
+        lengths.sort_unstable();
+        let tokens: usize = lengths.iter().sum();
+        let percentile = |p: f64| -> usize {
+            if lengths.is_empty() {
+                return 0;
+            }
+            // Nearest-rank percentile.
+            let rank = ((p / 100.0) * lengths.len() as f64).ceil().max(1.0) as usize;
+            lengths[rank - 1]
+        };
+        Report {
+            documents: lengths.len(),
+            skipped: self.skipped,
+            tokens,
+            vocabulary: ranked.len(),
+            hapax: ranked.iter().filter(|&&(_, count)| count == 1).count(),
+            mean: if lengths.is_empty() {
+                0.0
+            } else {
+                tokens as f64 / lengths.len() as f64
+            },
+            percentiles: [
+                ("min", lengths.first().copied().unwrap_or(0)),
+                ("p10", percentile(10.0)),
+                ("p25", percentile(25.0)),
+                ("median", percentile(50.0)),
+                ("p75", percentile(75.0)),
+                ("p90", percentile(90.0)),
+                ("max", lengths.last().copied().unwrap_or(0)),
+            ],
+        }
+    }
+}
+
+struct Report {
+    documents: usize,
+    skipped: usize,
+    tokens: usize,
+    vocabulary: usize,
+    hapax: usize,
+    mean: f64,
+    percentiles: [(&'static str, usize); 7],
+}
+
+impl Report {
+    fn ratio(part: usize, whole: usize) -> f64 {
+        if whole == 0 {
+            0.0
+        } else {
+            part as f64 / whole as f64
+        }
+    }
+
+    fn print(&self) {
+        println!("documents         {}", self.documents);
+        if self.skipped > 0 {
+            println!("skipped           {} (binary or not UTF-8)", self.skipped);
+        }
+        println!("tokens            {}", self.tokens);
+        println!("vocabulary        {}", self.vocabulary);
+        println!(
+            "hapax legomena    {} ({:.1}% of vocabulary)",
+            self.hapax,
+            Self::ratio(self.hapax, self.vocabulary) * 100.0
+        );
+        println!(
+            "type/token ratio  {:.4}",
+            Self::ratio(self.vocabulary, self.tokens)
+        );
+        println!();
+        println!("document length (tokens)");
+        println!("    mean          {:.1}", self.mean);
+        for (name, value) in self.percentiles {
+            println!("    {:<14}{}", name, value);
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let lengths = Value::Object(
+            [("mean".to_string(), Value::Number((self.mean * 10.0).round() / 10.0))]
+                .into_iter()
+                .chain(
+                    self.percentiles
+                        .iter()
+                        .map(|&(name, value)| (name.to_string(), value.into())),
+                )
+                .collect(),
+        );
+        json::object([
+            ("documents", self.documents.into()),
+            ("skipped", self.skipped.into()),
+            ("tokens", self.tokens.into()),
+            ("vocabulary", self.vocabulary.into()),
+            ("hapax_legomena", self.hapax.into()),
+            (
+                "type_token_ratio",
+                Value::Number(Self::ratio(self.vocabulary, self.tokens)),
+            ),
+            ("document_length", lengths),
+        ])
+    }
+}
+
+/// Writes `rank,token,frequency` rows; plotting log(rank) against
+/// log(frequency) gives the Zipf curve.
+fn write_zipf(path: &str, ranked: &[(&str, usize)]) -> io::Result<()> {
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    writeln!(out, "rank,token,frequency")?;
+    for (rank, (token, count)) in ranked.iter().enumerate() {
+        let token = if token.contains([',', '"']) {
+            format!("\"{}\"", token.replace('"', "\"\""))
+        } else {
+            token.to_string()
+        };
+        writeln!(out, "{},{},{}", rank + 1, token, count)?;
+    }
+    out.flush()
+}
@@ -0,0 +1,587 @@
+//! `rwc daemon` keeps per-file counts for a tree in memory and answers
+//! queries over a local socket (a named pipe on Windows).
+//!
+//! The wire protocol is newline-delimited JSON and is described in
+//! `docs/daemon-protocol.md`.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::json::{self, Value};
+use crate::{cli, count_text, format_number, time, walk, Counts};
+
+/// Version of the wire protocol, reported by `ping`.
+const PROTOCOL_VERSION: usize = 1;
+
+struct Entry {
+    modified: SystemTime,
+    len: u64,
+    counts: Counts,
+}
+
+struct Tree {
+    root: PathBuf,
+    entries: HashMap<PathBuf, Entry>,
+    last_scan: SystemTime,
+}
+
+type State = Arc<Mutex<Tree>>;
+
+/// Default endpoint, shared by the daemon and the query client.
+fn default_endpoint() -> PathBuf {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "user".to_string());
+    if cfg!(windows) {
+        return PathBuf::from(format!(r"\\.\pipe\rwc-{}", user));
+    }
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("rwc.sock"),
+        None => std::env::temp_dir().join(format!("rwc-{}.sock", user)),
+    }
+}
+
+/// Recounts files whose size or modification time changed since the last
+/// scan and forgets files that disappeared. Returns the number of files tracked.
+fn refresh(state: &State) -> io::Result<usize> {
+    let root = state.lock().unwrap().root.clone();
+    let files = walk::files(&root)?;
+    let mut seen = HashSet::with_capacity(files.len());
+
+    for path in files {
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        let len = metadata.len();
+        let fresh = state
+            .lock()
+            .unwrap()
+            .entries
+            .get(&path)
+            .is_some_and(|e| e.modified == modified && e.len == len);
+        if !fresh {
+            // Unreadable, binary and non-UTF-8 files are not counted.
+            let counts = fs::read(&path)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .map(|text| count_text(&text));
+            let mut tree = state.lock().unwrap();
+            match counts {
+                Some(counts) => {
+                    tree.entries
+                        .insert(path.clone(), Entry { modified, len, counts });
+                }
+                None => {
+                    tree.entries.remove(&path);
+                }
+            }
+        }
+        seen.insert(path);
+    }
+
+    let mut tree = state.lock().unwrap();
+    tree.entries.retain(|path, _| seen.contains(path));
+    tree.last_scan = SystemTime::now();
+    Ok(tree.entries.len())
+}
+
+fn counts_object(counts: &Counts) -> Vec<(String, Value)> {
+    [
+        ("lines", counts.lines),
+        ("words", counts.words),
+        ("chars", counts.chars),
+        ("bytes", counts.bytes),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), Value::from(v)))
+    .collect()
+}
+
+/// Resolves a request path against the watched root.
+fn resolve(tree: &Tree, request: &Value) -> PathBuf {
+    match request.get("path").and_then(Value::as_str) {
+        Some(path) if Path::new(path).is_absolute() => PathBuf::from(path),
+        Some(path) => tree.root.join(path),
+        None => tree.root.clone(),
+    }
+}
+
+/// Executes one request and returns its `result` member.
+fn handle(state: &State, request: &Value) -> Result<Value, String> {
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .ok_or("missing 'method'")?;
+    let tree = state.lock().unwrap();
+
+    match method {
+        "ping" => Ok(json::object([("protocol", PROTOCOL_VERSION.into())])),
+        "status" => Ok(json::object([
+            ("root", tree.root.to_string_lossy().into_owned().into()),
+            ("files", tree.entries.len().into()),
+            ("last_scan", time::format_rfc3339(tree.last_scan).into()),
+        ])),
+        "counts" => {
+            let prefix = resolve(&tree, request);
+            let mut total = Counts::default();
+            let mut files = 0;
+            for (path, entry) in &tree.entries {
+                if path.starts_with(&prefix) {
+                    total.add(&entry.counts);
+                    files += 1;
+                }
+            }
+            let mut members = vec![
+                ("path".to_string(), prefix.to_string_lossy().into_owned().into()),
+                ("files".to_string(), files.into()),
+            ];
+            members.extend(counts_object(&total));
+            Ok(Value::Object(members))
+        }
+        "files" => {
+            let prefix = resolve(&tree, request);
+            let mut paths: Vec<&PathBuf> = tree
+                .entries
+                .keys()
+                .filter(|path| path.starts_with(&prefix))
+                .collect();
+            paths.sort();
+            let files = paths
+                .into_iter()
+                .map(|path| {
+                    let mut members =
+                        vec![("path".to_string(), path.to_string_lossy().into_owned().into())];
+                    members.extend(counts_object(&tree.entries[path].counts));
+                    Value::Object(members)
+                })
+                .collect();
+            Ok(json::object([("files", Value::Array(files))]))
+        }
+        other => Err(format!("unknown method '{}'", other)),
+    }
+}
+
+/// Serves newline-delimited JSON requests on one connection until EOF.
+fn serve_connection<S: Read + Write>(state: &State, stream: S) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match json::parse(line.trim()) {
+            Ok(request) => {
+                let id = request.get("id").cloned().unwrap_or(Value::Null);
+                match handle(state, &request) {
+                    Ok(result) => json::object([("id", id), ("result", result)]),
+                    Err(message) => json::object([("id", id), ("error", message.into())]),
+                }
+            }
+            Err(e) => json::object([
+                ("id", Value::Null),
+                ("error", format!("invalid request: {}", e).into()),
+            ]),
+        };
+        let stream = reader.get_mut();
+        writeln!(stream, "{}", response)?;
+        stream.flush()?;
+    }
+}
+
+/// Entry point for `rwc daemon [--socket PATH] [--interval DUR] PATH`.
+pub fn run(args: &[String]) -> i32 {
+    let mut endpoint = None;
+    let mut interval = Duration::from_secs(2);
+    let mut root = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--socket" | "--interval" if i + 1 >= args.len() => {
+                eprintln!("rwc daemon: option '{}' requires an argument", args[i]);
+                return 1;
+            }
+            "--socket" => {
+                i += 1;
+                endpoint = Some(PathBuf::from(&args[i]));
+            }
+            "--interval" => {
+                i += 1;
+                match time::parse_duration(&args[i]) {
+                    Some(d) => interval = d,
+                    None => {
+                        eprintln!("rwc daemon: invalid interval '{}'", args[i]);
+                        return 1;
+                    }
+                }
+            }
+            "--help" => {
+                println!("Usage: rwc daemon [--socket PATH] [--interval DUR] PATH");
+                println!();
+                println!("Watches PATH, keeping counts in memory, and serves `rwc query`.");
+                println!("Changed files are recounted every DUR (default 2s).");
+                println!("Default endpoint: {}", default_endpoint().display());
+                println!("See docs/daemon-protocol.md for the JSON protocol.");
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option("rwc daemon", a, &["--socket", "--interval", "--help"]);
+                return 1;
+            }
+            a => root = Some(PathBuf::from(a)),
+        }
+        i += 1;
+    }
+
+    let Some(root) = root else {
+        eprintln!("rwc daemon: missing PATH argument");
+        return 1;
+    };
+    let root = match fs::canonicalize(&root) {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("rwc daemon: {}: {}", root.display(), e);
+            return 1;
+        }
+    };
+    let endpoint = endpoint.unwrap_or_else(default_endpoint);
+
+    let state: State = Arc::new(Mutex::new(Tree {
+        root,
+        entries: HashMap::new(),
+        last_scan: SystemTime::now(),
+    }));
+    let result = refresh(&state).and_then(|files| {
+        eprintln!(
+            "rwc daemon: watching {} ({} files) on {}",
+            state.lock().unwrap().root.display(),
+            files,
+            endpoint.display()
+        );
+        let watcher = Arc::clone(&state);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            if let Err(e) = refresh(&watcher) {
+                eprintln!("rwc daemon: {}", e);
+            }
+        });
+        listen(&state, &endpoint)
+    });
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("rwc daemon: {}", e);
+            1
+        }
+    }
+}
+
+#[cfg(unix)]
+fn listen(state: &State, endpoint: &Path) -> io::Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    remove_stale_socket(endpoint)?;
+    let listener = UnixListener::bind(endpoint)?;
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let state = Arc::clone(state);
+        thread::spawn(move || serve_connection(&state, stream));
+    }
+    Ok(())
+}
+
+/// Removes a socket at `endpoint` that no daemon listens on any more. Any
+/// other file there is left alone, and is an error.
+#[cfg(unix)]
+fn remove_stale_socket(endpoint: &Path) -> io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixStream;
+
+    let Ok(metadata) = fs::symlink_metadata(endpoint) else {
+        return Ok(());
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", endpoint.display()),
+        ));
+    }
+    if UnixStream::connect(endpoint).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("a daemon is already listening on {}", endpoint.display()),
+        ));
+    }
+    fs::remove_file(endpoint)
+}
+
+#[cfg(windows)]
+fn listen(state: &State, endpoint: &Path) -> io::Result<()> {
+    loop {
+        let pipe = pipe::accept(endpoint)?;
+        let state = Arc::clone(state);
+        thread::spawn(move || serve_connection(&state, pipe));
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn listen(_state: &State, _endpoint: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the daemon is not supported on this platform",
+    ))
+}
+
+/// Server side of Windows named pipes.
+#[cfg(windows)]
+mod pipe {
+    use std::ffi::c_void;
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::FromRawHandle;
+    use std::path::Path;
+    use std::ptr;
+
+    type Handle = *mut c_void;
+
+    const PIPE_ACCESS_DUPLEX: u32 = 0x0000_0003;
+    const PIPE_TYPE_BYTE: u32 = 0x0000_0000;
+    const PIPE_WAIT: u32 = 0x0000_0000;
+    const PIPE_UNLIMITED_INSTANCES: u32 = 255;
+    const ERROR_PIPE_CONNECTED: i32 = 535;
+    const BUFFER_SIZE: u32 = 64 * 1024;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateNamedPipeW(
+            name: *const u16,
+            open_mode: u32,
+            pipe_mode: u32,
+            max_instances: u32,
+            out_buffer_size: u32,
+            in_buffer_size: u32,
+            default_timeout: u32,
+            security_attributes: *mut c_void,
+        ) -> Handle;
+        fn ConnectNamedPipe(pipe: Handle, overlapped: *mut c_void) -> i32;
+    }
+
+    /// Creates a pipe instance and blocks until a client connects to it.
+    pub fn accept(name: &Path) -> io::Result<File> {
+        let wide: Vec<u16> = name.as_os_str().encode_wide().chain(Some(0)).collect();
+        let handle = unsafe {
+            CreateNamedPipeW(
+                wide.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_WAIT,
+                PIPE_UNLIMITED_INSTANCES,
+                BUFFER_SIZE,
+                BUFFER_SIZE,
+                0,
+                ptr::null_mut(),
+            )
+        };
+        if handle as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // The File owns the handle from here on and closes it on drop.
+        let pipe = unsafe { File::from_raw_handle(handle) };
+        if unsafe { ConnectNamedPipe(handle, ptr::null_mut()) } == 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(ERROR_PIPE_CONNECTED) {
+                return Err(err);
+            }
+        }
+        Ok(pipe)
+    }
+}
+
+/// Sends one request to the daemon and returns its `result`.
+fn request(endpoint: &Path, request: &Value) -> io::Result<Value> {
+    let reply = exchange(endpoint, &request.to_string())?;
+    let reply = json::parse(reply.trim())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(error) = reply.get("error").and_then(Value::as_str) {
+        return Err(io::Error::other(error.to_string()));
+    }
+    reply
+        .get("result")
+        .cloned()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "reply has no result"))
+}
+
+#[cfg(unix)]
+fn exchange(endpoint: &Path, line: &str) -> io::Result<String> {
+    let stream = std::os::unix::net::UnixStream::connect(endpoint)?;
+    write_and_read(stream, line)
+}
+
+#[cfg(windows)]
+fn exchange(endpoint: &Path, line: &str) -> io::Result<String> {
+    let pipe = fs::OpenOptions::new().read(true).write(true).open(endpoint)?;
+    write_and_read(pipe, line)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn exchange(_endpoint: &Path, _line: &str) -> io::Result<String> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the daemon is not supported on this platform",
+    ))
+}
+
+#[cfg(any(unix, windows))]
+fn write_and_read<S: Read + Write>(mut stream: S, line: &str) -> io::Result<String> {
+    writeln!(stream, "{}", line)?;
+    stream.flush()?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(reply)
+}
+
+/// Entry point for `rwc query [--socket PATH] [--status|--files] [--json] [-h] [PATH]`.
+pub fn query(args: &[String]) -> i32 {
+    let mut endpoint = None;
+    let mut path = None;
+    let mut method = "counts";
+    let mut json_output = false;
+    let mut human = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--socket" if i + 1 >= args.len() => {
+                eprintln!("rwc query: option '--socket' requires an argument");
+                return 1;
+            }
+            "--socket" => {
+                i += 1;
+                endpoint = Some(PathBuf::from(&args[i]));
+            }
+            "--status" => method = "status",
+            "--files" => method = "files",
+            "--json" => json_output = true,
+            "-h" | "--human" => human = true,
+            "--help" => {
+                println!("Usage: rwc query [--socket PATH] [--status|--files] [--json] [-h] [PATH]");
+                println!();
+                println!("Asks a running `rwc daemon` for the counts under PATH");
+                println!("(default: the whole watched tree).");
+                println!("    --status        Show the watched root and scan state");
+                println!("    --files         List per-file counts instead of a total");
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option(
+                    "rwc query",
+                    a,
+                    &["--socket", "--status", "--files", "--json", "--human", "--help"],
+                );
+                return 1;
+            }
+            a => path = Some(a.to_string()),
+        }
+        i += 1;
+    }
+
+    let mut members = vec![
+        ("id".to_string(), Value::from(1)),
+        ("method".to_string(), Value::from(method)),
+    ];
+    if let Some(p) = &path {
+        match fs::canonicalize(p) {
+            Ok(p) => members.push(("path".to_string(), p.to_string_lossy().into_owned().into())),
+            Err(e) => {
+                eprintln!("rwc query: {}: {}", p, e);
+                return 1;
+            }
+        }
+    }
+    let endpoint = endpoint.unwrap_or_else(default_endpoint);
+
+    let result = match request(&endpoint, &Value::Object(members)) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("rwc query: {}: {}", endpoint.display(), e);
+            return 1;
+        }
+    };
+
+    if json_output {
+        println!("{}", result);
+        return 0;
+    }
+
+    let number = |v: &Value, key: &str| {
+        format_number(
+            v.get(key).and_then(Value::as_f64).unwrap_or(0.0) as usize,
+            human,
+        )
+    };
+    let row = |v: &Value, label: &str| {
+        println!(
+            "{:>8}{:>8}{:>8} {}",
+            number(v, "lines"),
+            number(v, "words"),
+            number(v, "bytes"),
+            label
+        );
+    };
+    match method {
+        "status" => {
+            let text = |key| result.get(key).and_then(Value::as_str).unwrap_or("");
+            println!("root:      {}", text("root"));
+            println!("files:     {}", number(&result, "files"));
+            println!("last scan: {}", text("last_scan"));
+        }
+        "files" => {
+            for file in result.get("files").and_then(Value::as_array).unwrap_or(&[]) {
+                row(file, file.get("path").and_then(Value::as_str).unwrap_or(""));
+            }
+        }
+        _ => row(&result, path.as_deref().unwrap_or("(watched tree)")),
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn only_stale_sockets_are_removed() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = std::env::temp_dir().join(format!("rwc-daemon-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let notes = dir.join("notes.txt");
+        fs::write(&notes, "keep me").unwrap();
+        let err = remove_stale_socket(&notes).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&notes).unwrap(), "keep me");
+        assert!(remove_stale_socket(&dir).is_err());
+
+        let socket = dir.join("rwc.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let err = remove_stale_socket(&socket).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        drop(listener);
+        remove_stale_socket(&socket).unwrap();
+        assert!(!socket.exists());
+        remove_stale_socket(&socket).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
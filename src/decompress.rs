@@ -0,0 +1,170 @@
+//! Decompression of gzip, bzip2, xz and zstd input, detected by its magic
+//! bytes.
+//!
+//! gzip is decoded by `inflate`. For the others, like `--remote` and `rwc
+//! sql`, this shells out instead of linking codecs: the matching tool must
+//! be on `PATH`.
+
+use std::io::{self, BufRead, Read};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::{external, inflate, output};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Format {
+    /// Recognizes a compressed stream from its first bytes.
+    pub fn detect(data: &[u8]) -> Option<Format> {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Some(Format::Gzip)
+        } else if is_bzip2(data) {
+            Some(Format::Bzip2)
+        } else if data.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Format::Xz)
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Format::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// The command-line tool for this format, which is also its name.
+    pub fn tool(self) -> &'static str {
+        match self {
+            Format::Gzip => "gzip",
+            Format::Bzip2 => "bzip2",
+            Format::Xz => "xz",
+            Format::Zstd => "zstd",
+        }
+    }
+}
+
+/// Whether `data` starts a bzip2 stream: "BZh", a block size digit, then
+/// the magic of a first block or of the end of an empty stream, so text
+/// that happens to start with "BZh" is not taken for one.
+fn is_bzip2(data: &[u8]) -> bool {
+    const BLOCK: [u8; 6] = [0x31, 0x41, 0x59, 0x26, 0x53, 0x59];
+    const END: [u8; 6] = [0x17, 0x72, 0x45, 0x38, 0x50, 0x90];
+    data.len() >= 10
+        && data.starts_with(b"BZh")
+        && (b'1'..=b'9').contains(&data[3])
+        && (data[4..10] == BLOCK || data[4..10] == END)
+}
+
+/// Decompresses `data` held in memory.
+pub fn decompress(format: Format, data: Vec<u8>) -> io::Result<Vec<u8>> {
+    match format {
+        Format::Gzip => inflate::gunzip(&data),
+        _ => external::filter(format.tool(), &["-d", "-c"], data),
+    }
+}
+
+/// Repeatedly decompresses while the data still looks compressed, so
+/// `file.gz.gz` and the like are fully unpacked. Plain data is returned as-is.
+pub fn decompress_all(mut data: Vec<u8>) -> io::Result<Vec<u8>> {
+    while let Some(format) = Format::detect(&data) {
+        data = decompress(format, data)?;
+    }
+    Ok(data)
+}
+
+/// Calls `read` with the decompressed content of `input`, which is streamed
+/// through: neither is ever held whole.
+///
+/// A tool is fed from this thread while `read` runs on another, so it
+/// must not rely on thread-local state; the warnings it raises are replayed
+/// here.
+pub fn with_stream<R, T>(
+    format: Format,
+    mut input: R,
+    read: impl FnOnce(&mut dyn Read) -> io::Result<T> + Send,
+) -> io::Result<T>
+where
+    R: BufRead,
+    T: Send,
+{
+    if format == Format::Gzip {
+        return read(&mut inflate::Gunzip::new(input)?);
+    }
+    let tool = format.tool();
+    let mut child = Command::new(tool)
+        .args(["-d", "-c"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("failed to run {}: {}", tool, e)))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let (result, warnings, written) = thread::scope(|scope| {
+        let reader = scope.spawn(move || output::capture(|| read(&mut stdout)));
+        // When `read` stops early the tool fails to write, exits and
+        // breaks this pipe; its result says why.
+        let written = match io::copy(&mut input, &mut stdin) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            other => other.map(drop),
+        };
+        drop(stdin);
+        let (result, warnings) = reader.join().expect("the reader does not panic");
+        (result, warnings, written)
+    });
+    for warning in warnings {
+        output::warn(warning);
+    }
+    let status = child.wait()?;
+    let value = result?;
+    written?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} failed ({})", tool, status)));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats_by_magic() {
+        assert_eq!(Format::detect(&[0x1f, 0x8b, 8]), Some(Format::Gzip));
+        let bzip2 = b"BZh91AY&SY\x00\x00";
+        assert_eq!(Format::detect(bzip2), Some(Format::Bzip2));
+        let empty_bzip2 = b"BZh9\x17\x72\x45\x38\x50\x90\x00\x00\x00\x00";
+        assert_eq!(Format::detect(empty_bzip2), Some(Format::Bzip2));
+        assert_eq!(Format::detect(b"\xfd7zXZ\x00\x00"), Some(Format::Xz));
+        assert_eq!(
+            Format::detect(&[0x28, 0xb5, 0x2f, 0xfd]),
+            Some(Format::Zstd)
+        );
+        for text in [&b"BZh is how this starts"[..], b"BZh9", b"", b"\x1f"] {
+            assert_eq!(Format::detect(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn gzip_is_decoded_in_process() {
+        let hello = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0x48, 0xcd, 0xc9,
+            0xc9, 0x57, 0xc8, 0x40, 0x27, 0xb9, 0x00, 0x00, 0x88, 0x59, 0x0b, 0x18, 0x00, 0x00,
+            0x00,
+        ];
+        let twice = [hello, hello].concat();
+        assert_eq!(
+            decompress_all(twice.clone()).unwrap(),
+            b"hello hello hello hello\n".repeat(2)
+        );
+        let text = with_stream(Format::Gzip, &twice[..], |stream| {
+            let mut text = String::new();
+            stream.read_to_string(&mut text)?;
+            Ok(text)
+        });
+        assert_eq!(text.unwrap(), "hello hello hello hello\n".repeat(2));
+    }
+}
@@ -0,0 +1,322 @@
+//! Distinct line/word tracking, exact or via HyperLogLog.
+//!
+//! Exact sets charge their strings to the `--max-memory` budget and turn
+//! into HyperLogLog estimates when it runs out.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use crate::memory;
+
+/// Estimated heap use of a set entry besides its text: the `String` and
+/// the hash table slot.
+const ENTRY_OVERHEAD: usize = 48;
+
+/// Register index bits; 2^14 registers give ~0.81% standard error in 16 KiB.
+const PRECISION: u32 = 14;
+const REGISTERS: usize = 1 << PRECISION;
+
+#[derive(Debug, Clone)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HyperLogLog {
+    pub fn new() -> Self {
+        HyperLogLog {
+            registers: vec![0; REGISTERS],
+        }
+    }
+
+    pub fn insert(&mut self, item: &str) {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let hash = hasher.finish();
+        let index = (hash >> (64 - PRECISION)) as usize;
+        let rank = ((hash << PRECISION) | (1 << (PRECISION - 1))).leading_zeros() as u8 + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for (mine, theirs) in self.registers.iter_mut().zip(&other.registers) {
+            *mine = (*mine).max(*theirs);
+        }
+    }
+
+    pub fn estimate(&self) -> usize {
+        let m = REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-i32::from(r))).sum();
+        let raw = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for small cardinalities.
+            (m * (m / zeros as f64).ln()).round() as usize
+        } else {
+            raw.round() as usize
+        }
+    }
+
+    /// Relative standard error of `estimate`.
+    pub fn error() -> f64 {
+        1.04 / (REGISTERS as f64).sqrt()
+    }
+}
+
+/// A set of distinct strings, charged to the memory budget while it lives.
+#[derive(Debug, Default)]
+pub struct ExactSet {
+    items: HashSet<String>,
+    charged: usize,
+}
+
+impl ExactSet {
+    /// Adds `item`, unless it does not fit in the memory budget.
+    fn insert(&mut self, item: &str) -> bool {
+        if self.items.contains(item) {
+            return true;
+        }
+        let cost = item.len() + ENTRY_OVERHEAD;
+        if !memory::reserve(cost) {
+            return false;
+        }
+        self.charged += cost;
+        self.items.insert(item.to_string());
+        true
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &String> {
+        self.items.iter()
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn to_estimate(&self) -> HyperLogLog {
+        let mut hll = HyperLogLog::new();
+        self.iter().for_each(|s| hll.insert(s));
+        hll
+    }
+}
+
+impl Clone for ExactSet {
+    fn clone(&self) -> Self {
+        memory::charge(self.charged);
+        ExactSet {
+            items: self.items.clone(),
+            charged: self.charged,
+        }
+    }
+}
+
+impl Drop for ExactSet {
+    fn drop(&mut self) {
+        memory::release(self.charged);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Tracker {
+    Exact(ExactSet),
+    Approx(HyperLogLog),
+}
+
+impl Tracker {
+    pub fn new(approx: bool) -> Self {
+        if approx {
+            Tracker::Approx(HyperLogLog::new())
+        } else {
+            Tracker::Exact(ExactSet::default())
+        }
+    }
+
+    pub fn insert(&mut self, item: &str) {
+        let estimate = match self {
+            Tracker::Exact(set) => {
+                if set.insert(item) {
+                    return;
+                }
+                let mut hll = set.to_estimate();
+                hll.insert(item);
+                hll
+            }
+            Tracker::Approx(hll) => return hll.insert(item),
+        };
+        memory::degraded("distinct counts");
+        *self = Tracker::Approx(estimate);
+    }
+
+    pub fn merge(&mut self, other: &Tracker) {
+        match (&mut *self, other) {
+            (Tracker::Exact(mine), Tracker::Exact(theirs)) => {
+                if theirs.iter().all(|item| mine.insert(item)) {
+                    return;
+                }
+                memory::degraded("distinct counts");
+            }
+            (Tracker::Approx(mine), Tracker::Approx(theirs)) => {
+                mine.merge(theirs);
+                return;
+            }
+            _ => {}
+        }
+        // One side is an estimate, or the union no longer fits in the memory
+        // budget: so is the result.
+        let mut hll = match &*self {
+            Tracker::Approx(hll) => hll.clone(),
+            Tracker::Exact(set) => set.to_estimate(),
+        };
+        match other {
+            Tracker::Approx(theirs) => hll.merge(theirs),
+            Tracker::Exact(set) => hll.merge(&set.to_estimate()),
+        }
+        *self = Tracker::Approx(hll);
+    }
+
+    pub fn count(&self) -> usize {
+        match self {
+            Tracker::Exact(set) => set.len(),
+            Tracker::Approx(hll) => hll.estimate(),
+        }
+    }
+
+    /// Relative standard error, or `None` for exact counts.
+    pub fn error(&self) -> Option<f64> {
+        match self {
+            Tracker::Exact(_) => None,
+            Tracker::Approx(_) => Some(HyperLogLog::error()),
+        }
+    }
+}
+
+/// Distinct lines and words seen in an input.
+#[derive(Debug, Clone)]
+pub struct Uniques {
+    pub lines: Option<Tracker>,
+    pub words: Option<Tracker>,
+}
+
+impl Uniques {
+    pub fn merge(&mut self, other: &Uniques) {
+        for (mine, theirs) in [(&mut self.lines, &other.lines), (&mut self.words, &other.words)] {
+            match (mine.as_mut(), theirs) {
+                (Some(m), Some(t)) => m.merge(t),
+                (None, Some(t)) => *mine = Some(t.clone()),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn within(estimate: usize, exact: usize, errors: f64) -> bool {
+        let off = (estimate as f64 - exact as f64).abs() / exact as f64;
+        off <= errors * HyperLogLog::error()
+    }
+
+    #[test]
+    fn estimates_small_and_large_cardinalities() {
+        assert_eq!(HyperLogLog::new().estimate(), 0);
+        let mut hll = HyperLogLog::new();
+        for _ in 0..3 {
+            ["a", "b", "c"].iter().for_each(|item| hll.insert(item));
+        }
+        assert_eq!(hll.estimate(), 3);
+        // Past 2.5 registers per item the raw estimate takes over from
+        // linear counting.
+        for exact in [1_000, 100_000] {
+            let mut hll = HyperLogLog::new();
+            (0..exact).for_each(|n| hll.insert(&n.to_string()));
+            assert!(
+                within(hll.estimate(), exact, 3.0),
+                "{} for {}",
+                hll.estimate(),
+                exact
+            );
+        }
+    }
+
+    #[test]
+    fn merge_is_the_union() {
+        let (mut a, mut b, mut both) = (HyperLogLog::new(), HyperLogLog::new(), HyperLogLog::new());
+        for n in 0..20_000 {
+            let item = n.to_string();
+            if n < 15_000 {
+                a.insert(&item);
+            }
+            if n >= 5_000 {
+                b.insert(&item);
+            }
+            both.insert(&item);
+        }
+        a.merge(&b);
+        assert_eq!(a.registers, both.registers);
+        assert!(within(a.estimate(), 20_000, 3.0), "{}", a.estimate());
+    }
+
+    // The memory budget is process-wide, so everything that charges it is
+    // in this one test.
+    #[test]
+    fn exact_sets_turn_into_estimates_out_of_budget() {
+        let mut exact = Tracker::new(false);
+        ["x", "y", "x"].iter().for_each(|item| exact.insert(item));
+        assert_eq!((exact.count(), exact.error()), (2, None));
+        let mut other = Tracker::new(false);
+        other.insert("z");
+        exact.merge(&other);
+        assert_eq!(exact.count(), 3);
+
+        let mut approx = Tracker::new(true);
+        approx.insert("y");
+        approx.merge(&exact);
+        assert!(matches!(approx, Tracker::Approx(_)));
+        assert_eq!(
+            (approx.count(), approx.error()),
+            (3, Some(HyperLogLog::error()))
+        );
+
+        let mut uniques = Uniques {
+            lines: None,
+            words: Some(Tracker::new(false)),
+        };
+        uniques.merge(&Uniques {
+            lines: Some(exact.clone()),
+            words: None,
+        });
+        assert_eq!(uniques.lines.as_ref().map(Tracker::count), Some(3));
+        assert_eq!(uniques.words.as_ref().map(Tracker::count), Some(0));
+
+        // Room for two more entries, then an estimate. What the sets above
+        // still hold is charged too.
+        memory::set_limit(usize::MAX - 1);
+        let used = usize::MAX - 1 - memory::remaining().unwrap();
+        memory::set_limit(used + 2 * (1 + ENTRY_OVERHEAD));
+        let mut tracker = Tracker::new(false);
+        for item in ["a", "b", "c", "d"] {
+            tracker.insert(item);
+        }
+        assert!(matches!(tracker, Tracker::Approx(_)));
+        assert_eq!(tracker.count(), 4);
+        let mut exact = Tracker::new(false);
+        exact.insert("e");
+        let mut more = Tracker::new(false);
+        more.insert("f");
+        exact.merge(&more);
+        assert!(matches!(exact, Tracker::Approx(_)));
+        assert_eq!(exact.count(), 2);
+        memory::set_limit(usize::MAX);
+    }
+}
@@ -0,0 +1,86 @@
+use std::io::{self, Read};
+
+use crate::{count_text, formats, output, print_counts, Config, Counts};
+
+/// A 1-based cursor position; columns count characters.
+#[derive(Clone, Copy)]
+pub struct Cursor {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Cursor {
+    /// Parses `LINE:COL` (or just `LINE`, meaning its first column).
+    pub fn parse(text: &str) -> Option<Cursor> {
+        let (line, col) = text.split_once(':').unwrap_or((text, "1"));
+        let cursor = Cursor {
+            line: line.parse().ok()?,
+            col: col.parse().ok()?,
+        };
+        (cursor.line >= 1 && cursor.col >= 1).then_some(cursor)
+    }
+
+    /// Byte offset of the cursor in `text`, clamped to the document.
+    fn offset(&self, text: &str) -> usize {
+        let mut offset = 0;
+        for (index, line) in text.split_inclusive('\n').enumerate() {
+            if index + 1 == self.line {
+                let content = line.trim_end_matches(['\n', '\r']);
+                let within: usize = content
+                    .chars()
+                    .take(self.col - 1)
+                    .map(char::len_utf8)
+                    .sum();
+                return offset + within;
+            }
+            offset += line.len();
+        }
+        text.len()
+    }
+}
+
+/// Reads a document from stdin and reports counts for the whole document and
+/// for the text before the cursor.
+pub fn run(config: &Config) -> io::Result<()> {
+    let mut text = String::new();
+    io::stdin().lock().read_to_string(&mut text)?;
+    let document = count_text(&text);
+    let (cursor, before) = match config.cursor {
+        Some(cursor) => (Some(cursor), count_text(&text[..cursor.offset(&text)])),
+        None => (None, count_text(&text)),
+    };
+
+    if config.show_json {
+        let fields = |counts: &Counts| {
+            let mut fields = Vec::new();
+            if config.show_lines {
+                fields.push(format!("\"lines\": {}", counts.lines));
+            }
+            if config.show_words {
+                fields.push(format!("\"words\": {}", counts.words));
+            }
+            if config.show_chars {
+                fields.push(format!("\"chars\": {}", counts.chars));
+            }
+            if config.show_bytes {
+                fields.push(format!("\"bytes\": {}", counts.bytes));
+            }
+            format!("{{{}}}", fields.join(", "))
+        };
+        let mut members = Vec::new();
+        if let Some(cursor) = cursor {
+            members.push(format!(
+                "  \"cursor\": {{\"line\": {}, \"col\": {}}}",
+                cursor.line, cursor.col
+            ));
+        }
+        members.push(format!("  \"document\": {}", fields(&document)));
+        members.push(format!("  \"before_cursor\": {}", fields(&before)));
+        output::print(&format!("{{\n{}\n}}", members.join(",\n")));
+    } else {
+        let formats = formats::formats(config);
+        print_counts(&formats, &document, config, Some("document"));
+        print_counts(&formats, &before, config, Some("before cursor"));
+    }
+    Ok(())
+}
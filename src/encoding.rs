@@ -0,0 +1,214 @@
+//! `--encoding`: transcoding UTF-16 and Latin-1 input to UTF-8 before it is
+//! counted.
+//!
+//! The counting engine only reads UTF-8, so other encodings are converted
+//! on the way in by a `Transcoder`. Byte counts stay
+//! those of the input as stored; characters, words and lines are counted on
+//! the decoded text. With `auto` (the default) a UTF-16 byte order mark, or
+//! the pattern of zero bytes that ASCII text has in UTF-16, selects UTF-16
+//! and everything else is read as UTF-8, as without the option.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Encoding {
+    #[default]
+    Auto,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+/// Names accepted by `--encoding`.
+pub const NAMES: &[(&str, Encoding)] = &[
+    ("auto", Encoding::Auto),
+    ("utf8", Encoding::Utf8),
+    ("utf16le", Encoding::Utf16Le),
+    ("utf16be", Encoding::Utf16Be),
+    ("latin1", Encoding::Latin1),
+];
+
+/// How many leading bytes `auto` looks at.
+pub const SNIFF_LEN: usize = 4096;
+
+impl Encoding {
+    pub fn parse(name: &str) -> Option<Encoding> {
+        let name = name.to_ascii_lowercase().replace(['-', '_'], "");
+        let name = match name.as_str() {
+            "utf16" => "utf16le",
+            "iso88591" => "latin1",
+            other => other,
+        };
+        NAMES.iter().find(|(n, _)| *n == name).map(|&(_, e)| e)
+    }
+
+    pub fn name(self) -> &'static str {
+        NAMES.iter().find(|&&(_, e)| e == self).map_or("auto", |&(n, _)| n)
+    }
+
+    /// The encoding of input starting with `head`, and the length of its
+    /// byte order mark.
+    fn detect(head: &[u8]) -> (Encoding, usize) {
+        if head.starts_with(&[0xff, 0xfe]) {
+            return (Encoding::Utf16Le, 2);
+        }
+        if head.starts_with(&[0xfe, 0xff]) {
+            return (Encoding::Utf16Be, 2);
+        }
+        // Mostly-ASCII UTF-16 has a zero in every other byte.
+        let pairs = head.len() / 2;
+        if pairs >= 2 {
+            let zeros = |offset: usize| {
+                head.iter().skip(offset).step_by(2).filter(|&&b| b == 0).count()
+            };
+            let (even, odd) = (zeros(0), zeros(1));
+            if odd * 10 >= pairs * 4 && even * 20 < pairs {
+                return (Encoding::Utf16Le, 0);
+            }
+            if even * 10 >= pairs * 4 && odd * 20 < pairs {
+                return (Encoding::Utf16Be, 0);
+            }
+        }
+        (Encoding::Utf8, 0)
+    }
+}
+
+/// Decodes input pushed in chunks of any size to UTF-8. Ill-formed UTF-16
+/// becomes U+FFFD, as in other decoders; UTF-8 is passed through untouched,
+/// to be validated by the engine.
+pub struct Transcoder {
+    encoding: Encoding,
+    /// `auto` has been settled and any byte order mark dropped.
+    resolved: bool,
+    /// Input not yet decoded: the head being sniffed, or an odd byte of
+    /// UTF-16.
+    pending: Vec<u8>,
+    /// A high surrogate waiting for its pair.
+    high: Option<u16>,
+}
+
+impl Transcoder {
+    pub fn new(encoding: Encoding) -> Transcoder {
+        Transcoder {
+            encoding,
+            resolved: false,
+            pending: Vec::new(),
+            high: None,
+        }
+    }
+
+    /// Whether input is passed through as it is, so callers can skip the
+    /// copy.
+    pub fn is_identity(&self) -> bool {
+        self.resolved && self.encoding == Encoding::Utf8 && self.pending.is_empty()
+    }
+
+    /// Decodes the next chunk, appending the text to `out`.
+    pub fn push(&mut self, chunk: &[u8], out: &mut Vec<u8>) {
+        self.pending.extend_from_slice(chunk);
+        if !self.resolved {
+            if self.pending.len() < SNIFF_LEN {
+                return;
+            }
+            self.resolve();
+        }
+        self.decode(out);
+    }
+
+    /// Decodes what is left at the end of the input.
+    pub fn finish(&mut self, out: &mut Vec<u8>) {
+        if !self.resolved {
+            self.resolve();
+        }
+        self.decode(out);
+        let odd = !self.pending.is_empty();
+        if self.high.take().is_some() || odd {
+            out.extend_from_slice(char::REPLACEMENT_CHARACTER.to_string().as_bytes());
+        }
+        self.pending.clear();
+    }
+
+    /// Settles `auto` from the first bytes and drops a UTF-16 byte order
+    /// mark.
+    fn resolve(&mut self) {
+        let (encoding, bom) = Encoding::detect(&self.pending);
+        if self.encoding == Encoding::Auto {
+            self.encoding = encoding;
+        }
+        // An explicit UTF-16 encoding still drops the matching mark.
+        if encoding == self.encoding {
+            self.pending.drain(..bom);
+        }
+        self.resolved = true;
+    }
+
+    fn decode(&mut self, out: &mut Vec<u8>) {
+        match self.encoding {
+            Encoding::Auto | Encoding::Utf8 => out.append(&mut self.pending),
+            Encoding::Latin1 => {
+                let text: String = self.pending.drain(..).map(char::from).collect();
+                out.extend_from_slice(text.as_bytes());
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let whole = self.pending.len() / 2 * 2;
+                let mut text = String::with_capacity(whole);
+                for pair in self.pending[..whole].chunks_exact(2) {
+                    let unit = if self.encoding == Encoding::Utf16Le {
+                        u16::from_le_bytes([pair[0], pair[1]])
+                    } else {
+                        u16::from_be_bytes([pair[0], pair[1]])
+                    };
+                    push_utf16(&mut text, &mut self.high, unit);
+                }
+                self.pending.drain(..whole);
+                out.extend_from_slice(text.as_bytes());
+            }
+        }
+    }
+}
+
+fn push_utf16(text: &mut String, high: &mut Option<u16>, unit: u16) {
+    match unit {
+        0xd800..=0xdbff => {
+            if high.replace(unit).is_some() {
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+        }
+        0xdc00..=0xdfff => match high.take() {
+            Some(first) => {
+                let c = 0x10000
+                    + ((u32::from(first) - 0xd800) << 10)
+                    + (u32::from(unit) - 0xdc00);
+                text.push(char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            None => text.push(char::REPLACEMENT_CHARACTER),
+        },
+        _ => {
+            if high.take().is_some() {
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+            text.push(char::from_u32(u32::from(unit)).unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+    }
+}
+
+/// Whether input starting with `head`, its first `SNIFF_LEN` bytes, is read
+/// as UTF-8 under `encoding`, and so passed to the engine as it is.
+pub fn is_utf8(encoding: Encoding, head: &[u8]) -> bool {
+    match encoding {
+        Encoding::Utf8 => true,
+        Encoding::Auto => Encoding::detect(head).0 == Encoding::Utf8,
+        _ => false,
+    }
+}
+
+/// Decodes the whole of `data`.
+pub fn decode(data: Vec<u8>, encoding: Encoding) -> Vec<u8> {
+    if encoding == Encoding::Utf8 {
+        return data;
+    }
+    let mut transcoder = Transcoder::new(encoding);
+    let mut out = Vec::with_capacity(data.len());
+    transcoder.push(&data, &mut out);
+    transcoder.finish(&mut out);
+    out
+}
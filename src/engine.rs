@@ -0,0 +1,642 @@
+//! The counting core: every enabled metric in a single streaming pass.
+//!
+//! A `Scanner` is fed the input in chunks of any size and keeps only a little
+//! state between them: the word and line in progress, a UTF-8 sequence cut
+//! off at the end of a chunk, and the running totals. Memory use does not grow
+//! with the input, except for the current line when a line-level metric
+//! (distinct lines, weighted matches, indentation, minification) or a
+//! tokenizer needs whole lines, and for the distinct-word set. ASCII text
+//! takes a fast path that skips UTF-8 decoding.
+
+use std::io;
+
+use crate::distinct::{Tracker, Uniques};
+use crate::generated::MinifiedScan;
+use crate::indent::{IndentScan, IndentStats};
+#[cfg(feature = "language")]
+use crate::language::LanguageScan;
+use crate::levels::LevelCounts;
+use crate::regex::Regex;
+use crate::tokenizer::{Tokenizer, Whitespace};
+use crate::weights::Weights;
+use crate::width;
+
+/// What a `Scanner` counts. The optional metrics are present when the
+/// `Metrics` it was given ask for them.
+#[derive(Default, Debug)]
+pub struct Counts {
+    pub bytes: usize,
+    pub chars: usize,
+    pub words: usize,
+    pub lines: usize,
+    /// Display width of the longest line, as in `wc -L`: tabs stop every
+    /// `width::TAB_WIDTH` columns.
+    pub max_line_length: usize,
+    /// Display width of the longest line with tabs stopping every
+    /// `Metrics::tab_size` columns, with `Metrics::display_width`.
+    pub max_display_width: Option<usize>,
+    /// Blocks of lines separated by blank lines.
+    pub paragraphs: usize,
+    /// Lines holding nothing but whitespace.
+    pub blank_lines: usize,
+    pub sentences: usize,
+    pub uniques: Option<Uniques>,
+    /// Weighted pattern score, with `Metrics::weights`.
+    pub score: Option<f64>,
+    /// Minified-content score, with `Metrics::minified`.
+    pub minified: Option<f64>,
+    pub indent: Option<IndentStats>,
+    /// Lines when soft-wrapped at `Metrics::wrap_at` columns.
+    pub visual_lines: Option<usize>,
+    /// Logical records started by `Metrics::record_start`.
+    pub records: Option<usize>,
+    /// Lines per severity, with `Metrics::log_levels`.
+    pub levels: Option<LevelCounts>,
+    /// ISO 639-1 code of the natural language, with `Metrics::language`.
+    pub language: Option<&'static str>,
+}
+
+/// The error for input that is not valid UTF-8.
+pub fn invalid_utf8() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8")
+}
+
+/// The optional metrics to compute alongside the basic counts.
+#[derive(Default)]
+pub struct Metrics<'a> {
+    pub unique_lines: bool,
+    pub unique_words: bool,
+    /// Use HyperLogLog rather than exact sets for distinct counts.
+    pub approx: bool,
+    pub weights: Option<&'a Weights>,
+    pub indent: bool,
+    pub minified: bool,
+    /// Count the lines the text takes when soft-wrapped at this width.
+    pub wrap_at: Option<usize>,
+    /// Measure the widest line with tabs stopping at `tab_size`.
+    pub display_width: bool,
+    /// Tab stops for `display_width` and wrapping; every `width::TAB_WIDTH`
+    /// columns when unset. `max_line_length` keeps `wc`'s stops.
+    pub tab_size: Option<usize>,
+    pub whitespace: Whitespace,
+    /// Skip soft hyphens, joiners and variation selectors entirely.
+    pub ignore_format: bool,
+    /// Find words with this rather than by splitting on `whitespace`. It
+    /// sees whole lines, so they are kept.
+    pub tokenizer: Option<&'a dyn Tokenizer>,
+    /// Count logical records: a line matching this starts a new one.
+    pub record_start: Option<&'a Regex>,
+    /// Count lines per log severity.
+    pub log_levels: bool,
+    /// Decode invalid UTF-8 as U+FFFD instead of failing.
+    pub lossy: bool,
+    /// Detect the natural language of the text; ignored without the
+    /// `language` feature.
+    pub language: bool,
+}
+
+impl Metrics<'_> {
+    /// Whether any enabled metric looks at whole lines.
+    fn needs_lines(&self) -> bool {
+        self.unique_lines
+            || self.weights.is_some()
+            || self.indent
+            || self.minified
+            || self.record_start.is_some()
+            || self.log_levels
+            || self.tokenizer.is_some()
+    }
+
+    /// Whether an input can be counted in ranges cut just after newlines and
+    /// the ranges' counts joined (`RangeCounts::join`): every metric enabled
+    /// is kept per line and summed, and no word runs on past a newline.
+    pub fn joinable(&self) -> bool {
+        !self.unique_lines
+            && !self.unique_words
+            && !self.indent
+            && !self.minified
+            && self.record_start.is_none()
+            && !self.log_levels
+            && !self.language
+            && self.whitespace.separates('\n')
+    }
+}
+
+/// The counts of one range of an input, cut just after a newline, with how
+/// the range begins and ends.
+#[derive(Debug)]
+pub struct RangeCounts {
+    pub counts: Counts,
+    /// The first line holds more than whitespace.
+    opens_with_text: bool,
+    /// The last line holds more than whitespace.
+    closes_with_text: bool,
+}
+
+impl RangeCounts {
+    /// Appends the counts of the range that follows this one. Lines, words
+    /// and sentences all end at the newline the ranges are cut after; only
+    /// a paragraph can carry on into the next range.
+    pub fn join(&mut self, next: RangeCounts) {
+        let (counts, next_counts) = (&mut self.counts, next.counts);
+        if next_counts.bytes == 0 {
+            return;
+        }
+        if counts.bytes == 0 {
+            self.opens_with_text = next.opens_with_text;
+        }
+        counts.bytes += next_counts.bytes;
+        counts.chars += next_counts.chars;
+        counts.words += next_counts.words;
+        counts.lines += next_counts.lines;
+        counts.max_line_length = counts.max_line_length.max(next_counts.max_line_length);
+        counts.paragraphs += next_counts.paragraphs;
+        if self.closes_with_text && next.opens_with_text {
+            counts.paragraphs -= 1;
+        }
+        counts.blank_lines += next_counts.blank_lines;
+        counts.sentences += next_counts.sentences;
+        if let Some(score) = next_counts.score {
+            *counts.score.get_or_insert(0.0) += score;
+        }
+        if let Some(width) = next_counts.max_display_width {
+            let widest = counts.max_display_width.get_or_insert(0);
+            *widest = (*widest).max(width);
+        }
+        if let Some(visual) = next_counts.visual_lines {
+            *counts.visual_lines.get_or_insert(0) += visual;
+        }
+        self.closes_with_text = next.closes_with_text;
+    }
+}
+
+/// Invisible formatting characters that word processors leave out of their
+/// counts: the soft hyphen, ZWNJ/ZWJ and variation selectors.
+fn is_ignorable_format(c: char) -> bool {
+    matches!(c, '\u{ad}' | '\u{200c}' | '\u{200d}' | '\u{fe00}'..='\u{fe0f}' | '\u{e0100}'..='\u{e01ef}')
+}
+
+/// Characters that end a sentence when they follow a letter or digit.
+fn is_terminal(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…' | '。' | '！' | '？')
+}
+
+/// Closing quotes and brackets allowed between a sentence end and the space
+/// after it, as in `"Yes."` or `(see above.)`.
+fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '”' | '’' | '»')
+}
+
+/// Where sentence-end detection is within a possible sentence end.
+#[derive(Default, PartialEq)]
+enum Pending {
+    #[default]
+    None,
+    /// In the run of terminators that followed a word.
+    Terminators,
+    /// In the closing quotes or brackets after the terminators.
+    Closings,
+}
+
+/// Sentence-end detection. A run of terminators after a letter or digit,
+/// optionally followed by closing marks, ends a sentence if whitespace or the
+/// end of the text comes next, so `3.14` and `e.g.x` are not split.
+#[derive(Default)]
+struct Sentences {
+    count: usize,
+    /// The last character was a letter or digit.
+    after_word: bool,
+    pending: Pending,
+}
+
+impl Sentences {
+    fn push(&mut self, c: char) {
+        match self.pending {
+            Pending::Terminators if is_terminal(c) => return,
+            Pending::Terminators | Pending::Closings if is_closing(c) => {
+                self.pending = Pending::Closings;
+                return;
+            }
+            Pending::None => {}
+            _ => {
+                self.pending = Pending::None;
+                if c.is_whitespace() {
+                    self.count += 1;
+                }
+                // The character after a candidate end never starts another.
+                self.after_word = c.is_alphanumeric();
+                return;
+            }
+        }
+        if is_terminal(c) && self.after_word {
+            // CJK terminators need no following space.
+            if matches!(c, '。' | '！' | '？') {
+                self.count += 1;
+            } else {
+                self.pending = Pending::Terminators;
+            }
+        }
+        self.after_word = c.is_alphanumeric();
+    }
+
+    fn finish(mut self) -> usize {
+        if self.pending != Pending::None {
+            self.count += 1;
+        }
+        self.count
+    }
+}
+
+/// Incremental counter; see the module documentation.
+pub struct Scanner<'a> {
+    metrics: &'a Metrics<'a>,
+    needs_lines: bool,
+    counts: Counts,
+    sentences: Sentences,
+    in_word: bool,
+    /// The word in progress, kept only for distinct-word counting.
+    word: String,
+    /// The line in progress, kept only for line-level metrics.
+    line: String,
+    /// Display column reached on the current line, for the longest line.
+    line_width: usize,
+    /// The same with tabs at `tab_size`, and the widest line so far, for
+    /// `display_width`.
+    display_column: usize,
+    max_display_width: usize,
+    /// Columns between tab stops, from `Metrics::tab_size`.
+    tab_size: usize,
+    /// Text follows the last newline, so there is an unterminated line.
+    line_open: bool,
+    /// The current line has held only whitespace so far.
+    line_blank: bool,
+    /// The last line was part of a paragraph (not blank).
+    in_paragraph: bool,
+    /// The first line held more than whitespace, for `finish_range`.
+    opened_with_text: bool,
+    /// Display column on the current visual line, and the visual lines the
+    /// current line has taken so far, for `wrap_at`.
+    wrap_column: usize,
+    wrap_rows: usize,
+    visual_lines: usize,
+    /// Logical records started so far, for `record_start`.
+    records: usize,
+    /// The start of a UTF-8 sequence cut off at the end of the last chunk.
+    partial: Vec<u8>,
+    unique_lines: Option<Tracker>,
+    unique_words: Option<Tracker>,
+    score: f64,
+    indent: Option<IndentScan>,
+    minified: Option<MinifiedScan>,
+    #[cfg(feature = "language")]
+    language: Option<LanguageScan>,
+    levels: Option<LevelCounts>,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(metrics: &'a Metrics<'a>) -> Self {
+        Scanner {
+            metrics,
+            needs_lines: metrics.needs_lines(),
+            counts: Counts::default(),
+            sentences: Sentences::default(),
+            in_word: false,
+            word: String::new(),
+            line: String::new(),
+            line_width: 0,
+            display_column: 0,
+            max_display_width: 0,
+            tab_size: metrics.tab_size.unwrap_or(width::TAB_WIDTH).max(1),
+            line_open: false,
+            line_blank: true,
+            in_paragraph: false,
+            opened_with_text: false,
+            wrap_column: 0,
+            wrap_rows: 1,
+            visual_lines: 0,
+            records: 0,
+            partial: Vec::new(),
+            unique_lines: metrics.unique_lines.then(|| Tracker::new(metrics.approx)),
+            unique_words: metrics.unique_words.then(|| Tracker::new(metrics.approx)),
+            score: 0.0,
+            indent: metrics.indent.then(IndentScan::default),
+            minified: metrics.minified.then(MinifiedScan::default),
+            #[cfg(feature = "language")]
+            language: metrics.language.then(LanguageScan::default),
+            levels: metrics.log_levels.then(LevelCounts::default),
+        }
+    }
+
+    /// Feeds the next chunk of raw input. Fails on invalid UTF-8 unless
+    /// `lossy` is set, in which case each bad sequence reads as U+FFFD; a
+    /// sequence split between chunks is completed by the next call.
+    pub fn feed(&mut self, data: &[u8]) -> io::Result<()> {
+        self.counts.bytes += data.len();
+        self.decode(data)
+    }
+
+    fn decode(&mut self, mut data: &[u8]) -> io::Result<()> {
+        if let Some(&lead) = self.partial.first() {
+            let width = match lead {
+                0xF0.. => 4,
+                0xE0.. => 3,
+                _ => 2,
+            };
+            let take = (width - self.partial.len()).min(data.len());
+            self.partial.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            // A byte that cannot continue the sequence ends it early.
+            let broken = std::str::from_utf8(&self.partial).is_err_and(|e| e.error_len().is_some());
+            if self.partial.len() < width && !broken {
+                return Ok(());
+            }
+            let sequence = std::mem::take(&mut self.partial);
+            match std::str::from_utf8(&sequence) {
+                Ok(text) => self.push_str(text),
+                Err(_) if !self.metrics.lossy => return Err(invalid_utf8()),
+                Err(e) => {
+                    // Only the lead byte's sequence is bad; what followed it
+                    // is read again.
+                    let bad = e.error_len().unwrap_or(sequence.len());
+                    self.push_str("\u{fffd}");
+                    let mut rest = sequence[bad..].to_vec();
+                    rest.extend_from_slice(data);
+                    return self.decode(&rest);
+                }
+            }
+        }
+        loop {
+            match std::str::from_utf8(data) {
+                Ok(text) => {
+                    self.push_str(text);
+                    return Ok(());
+                }
+                Err(e) => {
+                    let (valid, rest) = data.split_at(e.valid_up_to());
+                    self.push_str(std::str::from_utf8(valid).map_err(|_| invalid_utf8())?);
+                    match e.error_len() {
+                        Some(bad) if self.metrics.lossy => {
+                            self.push_str("\u{fffd}");
+                            data = &rest[bad..];
+                        }
+                        Some(_) => return Err(invalid_utf8()),
+                        None => {
+                            self.partial = rest.to_vec();
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Completes the scan. Fails if the input ended inside a UTF-8 sequence,
+    /// unless `lossy` is set.
+    pub fn finish(mut self) -> io::Result<Counts> {
+        if !self.partial.is_empty() {
+            if !self.metrics.lossy {
+                return Err(invalid_utf8());
+            }
+            self.partial.clear();
+            self.push_str("\u{fffd}");
+        }
+        Ok(self.end())
+    }
+
+    /// Like `finish`, for a range of an input that was cut just after a
+    /// newline, so that its counts can be joined with those of the ranges
+    /// around it.
+    pub fn finish_range(self) -> io::Result<RangeCounts> {
+        let open_with_text = self.line_open && !self.line_blank;
+        let opens_with_text = if self.counts.lines == 0 {
+            open_with_text
+        } else {
+            self.opened_with_text
+        };
+        let closes_with_text = if self.line_open {
+            open_with_text
+        } else {
+            self.in_paragraph
+        };
+        Ok(RangeCounts {
+            counts: self.finish()?,
+            opens_with_text,
+            closes_with_text,
+        })
+    }
+
+    /// The basic counts so far, as `finish` would report them if the input
+    /// ended here; the optional metrics are left out.
+    pub fn running_counts(&self) -> Counts {
+        Counts {
+            bytes: self.counts.bytes,
+            chars: self.counts.chars,
+            words: self.counts.words,
+            lines: self.counts.lines + usize::from(self.line_open),
+            max_line_length: self.counts.max_line_length.max(self.line_width),
+            paragraphs: self.counts.paragraphs
+                + usize::from(self.line_open && !self.line_blank && !self.in_paragraph),
+            blank_lines: self.counts.blank_lines + usize::from(self.line_open && self.line_blank),
+            ..Counts::default()
+        }
+    }
+
+    fn push_str(&mut self, text: &str) {
+        #[cfg(feature = "language")]
+        if let Some(language) = &mut self.language {
+            language.push_str(text);
+        }
+        if text.is_ascii() {
+            // Fast path: one byte per character.
+            self.counts.chars += text.len();
+            for b in text.bytes() {
+                self.step(b as char);
+            }
+        } else {
+            for c in text.chars() {
+                if self.metrics.ignore_format && is_ignorable_format(c) {
+                    // Dropped before anything sees it, so it cannot split or
+                    // distinguish words either.
+                    continue;
+                }
+                self.counts.chars += 1;
+                self.step(c);
+            }
+        }
+    }
+
+    fn step(&mut self, c: char) {
+        if self.metrics.tokenizer.is_some() {
+            // Words are found when the line ends.
+        } else if self.metrics.whitespace.separates(c) {
+            self.end_word();
+        } else {
+            if !self.in_word {
+                self.start_word();
+            }
+            self.push_word(c);
+        }
+        self.sentences.push(c);
+
+        if c == '\n' {
+            self.end_line();
+        } else {
+            self.line_open = true;
+            if !c.is_whitespace() {
+                self.line_blank = false;
+            }
+            if matches!(c, '\r' | '\x0c') {
+                // As in `wc -L`, these return to the start of the line.
+                self.counts.max_line_length = self.counts.max_line_length.max(self.line_width);
+                self.max_display_width = self.max_display_width.max(self.display_column);
+                self.line_width = 0;
+                self.display_column = 0;
+            } else {
+                self.line_width = width::advance(self.line_width, c);
+                if self.metrics.display_width {
+                    self.display_column =
+                        width::advance_tabbed(self.display_column, c, self.tab_size);
+                }
+            }
+            if self.needs_lines {
+                self.line.push(c);
+            }
+            if let Some(wrap_at) = self.metrics.wrap_at {
+                // A character that does not fit starts the next visual line.
+                let next = width::advance_tabbed(self.wrap_column, c, self.tab_size);
+                if next > wrap_at && self.wrap_column > 0 {
+                    self.wrap_rows += 1;
+                    self.wrap_column = width::advance_tabbed(0, c, self.tab_size);
+                } else {
+                    self.wrap_column = next;
+                }
+            }
+        }
+    }
+
+    fn start_word(&mut self) {
+        self.in_word = true;
+        self.counts.words += 1;
+        self.word.clear();
+    }
+
+    fn push_word(&mut self, c: char) {
+        if self.unique_words.is_some() {
+            self.word.push(c);
+        }
+    }
+
+    fn end_word(&mut self) {
+        if !self.in_word {
+            return;
+        }
+        self.in_word = false;
+        if let Some(tracker) = &mut self.unique_words {
+            tracker.insert(&self.word);
+        }
+    }
+
+    /// Closes the current line; the `\r` of a CRLF ending is not part of it.
+    fn end_line(&mut self) {
+        self.counts.lines += 1;
+        self.counts.max_line_length = self.counts.max_line_length.max(self.line_width);
+        self.max_display_width = self.max_display_width.max(self.display_column);
+        // A paragraph is a run of lines with text, ended by a blank line.
+        if self.line_blank {
+            self.counts.blank_lines += 1;
+            self.in_paragraph = false;
+        } else if !self.in_paragraph {
+            self.counts.paragraphs += 1;
+            self.in_paragraph = true;
+        }
+        if self.counts.lines == 1 {
+            self.opened_with_text = !self.line_blank;
+        }
+        self.line_blank = true;
+        if self.needs_lines {
+            let line = self.line.strip_suffix('\r').unwrap_or(&self.line);
+            if let Some(tokenizer) = self.metrics.tokenizer {
+                let (counts, unique_words) = (&mut self.counts, &mut self.unique_words);
+                tokenizer.words(line, &mut |word| {
+                    counts.words += 1;
+                    if let Some(tracker) = unique_words {
+                        tracker.insert(word);
+                    }
+                });
+            }
+            if let Some(tracker) = &mut self.unique_lines {
+                tracker.insert(line);
+            }
+            if let Some(weights) = self.metrics.weights {
+                self.score += weights.score_line(line);
+            }
+            if let Some(indent) = &mut self.indent {
+                indent.push(line);
+            }
+            if let Some(minified) = &mut self.minified {
+                minified.push(line);
+            }
+            if let Some(levels) = &mut self.levels {
+                levels.push(line);
+            }
+            if let Some(pattern) = self.metrics.record_start {
+                // Lines before the first match make up a record of their own.
+                if self.records == 0 || pattern.is_match(line) {
+                    self.records += 1;
+                }
+            }
+            self.line.clear();
+        }
+        self.visual_lines += self.wrap_rows;
+        self.line_width = 0;
+        self.display_column = 0;
+        self.line_open = false;
+        self.wrap_column = 0;
+        self.wrap_rows = 1;
+    }
+
+    fn end(mut self) -> Counts {
+        self.end_word();
+        if self.line_open {
+            self.end_line();
+        }
+
+        let mut counts = self.counts;
+        counts.sentences = self.sentences.finish();
+        if self.metrics.unique_lines || self.metrics.unique_words {
+            counts.uniques = Some(Uniques {
+                lines: self.unique_lines,
+                words: self.unique_words,
+            });
+        }
+        if self.metrics.weights.is_some() {
+            counts.score = Some(self.score);
+        }
+        counts.indent = self.indent.map(IndentScan::finish);
+        counts.minified = self.minified.map(|scan| scan.score());
+        #[cfg(feature = "language")]
+        {
+            counts.language = self.language.map(LanguageScan::finish);
+        }
+        counts.levels = self.levels;
+        if self.metrics.display_width {
+            counts.max_display_width = Some(self.max_display_width);
+        }
+        if self.metrics.wrap_at.is_some() {
+            counts.visual_lines = Some(self.visual_lines);
+        }
+        if self.metrics.record_start.is_some() {
+            counts.records = Some(self.records);
+        }
+        counts
+    }
+}
+
+/// Computes the basic counts and every metric enabled in `metrics` for text
+/// already in memory.
+pub fn scan(text: &str, metrics: &Metrics) -> Counts {
+    let mut scanner = Scanner::new(metrics);
+    scanner.counts.bytes = text.len();
+    scanner.push_str(text);
+    scanner.end()
+}
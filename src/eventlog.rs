@@ -0,0 +1,87 @@
+use std::io;
+use std::process::{Command, Stdio};
+
+use crate::time::format_rfc3339;
+use crate::{count_text, print_entry_counts, Config, Counts};
+
+/// Counts events and their message text in a Windows Event Log channel.
+///
+/// Events are queried with `wevtutil qe` in text format, restricted to the
+/// `--since`/`--until` range when given.
+pub fn run(channel: &str, config: &Config) -> io::Result<()> {
+    if !cfg!(windows) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the Windows Event Log is only available on Windows",
+        ));
+    }
+
+    let mut command = Command::new("wevtutil");
+    command.arg("qe").arg(channel).arg("/f:text");
+    if let Some(query) = time_query(config) {
+        command.arg(format!("/q:{}", query));
+    }
+    let output = command.stderr(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "wevtutil exited with {}",
+            output.status
+        )));
+    }
+
+    let (entries, counts) = count_events(&String::from_utf8_lossy(&output.stdout));
+    print_entry_counts(&format!("eventlog:{}", channel), entries, &counts, config);
+    Ok(())
+}
+
+/// Builds an XPath filter on `TimeCreated` for the configured time range.
+fn time_query(config: &Config) -> Option<String> {
+    let mut conditions = Vec::new();
+    if let Some(since) = config.since {
+        conditions.push(format!("@SystemTime>='{}'", format_rfc3339(since)));
+    }
+    if let Some(until) = config.until {
+        conditions.push(format!("@SystemTime<='{}'", format_rfc3339(until)));
+    }
+    if conditions.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "*[System[TimeCreated[{}]]]",
+        conditions.join(" and ")
+    ))
+}
+
+/// Splits `wevtutil` text output into events and counts their descriptions.
+fn count_events(output: &str) -> (usize, Counts) {
+    let mut entries = 0;
+    let mut counts = Counts::default();
+    let mut message = String::new();
+    let mut in_description = false;
+
+    for line in output.lines() {
+        if line.starts_with("Event[") {
+            if entries > 0 {
+                counts.add(&count_text(message.trim_end()));
+            }
+            entries += 1;
+            message.clear();
+            in_description = false;
+        } else if in_description {
+            message.push_str(line);
+            message.push('\n');
+        } else if let Some(rest) = line.trim_start().strip_prefix("Description:") {
+            in_description = true;
+            let rest = rest.trim();
+            if !rest.is_empty() {
+                message.push_str(rest);
+                message.push('\n');
+            }
+        }
+    }
+    if entries > 0 {
+        counts.add(&count_text(message.trim_end()));
+    }
+
+    (entries, counts)
+}
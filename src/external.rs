@@ -0,0 +1,44 @@
+//! Running external filter programs over in-memory data.
+
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Pipes `input` through `program args...` and returns its standard output.
+///
+/// A non-zero exit status is an error carrying the program's stderr.
+pub fn filter(program: &str, args: &[&str], input: Vec<u8>) -> io::Result<Vec<u8>> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("failed to run {}: {}", program, e)))?;
+
+    // Feed stdin from a thread so a full stdout pipe can't deadlock us.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let mut output = Vec::new();
+    child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_end(&mut output)?;
+    let mut errors = String::new();
+    if let Some(mut stderr) = child.stderr.take() {
+        stderr.read_to_string(&mut errors)?;
+    }
+    let status = child.wait()?;
+    // A broken pipe only means the program stopped reading early; its exit
+    // status says whether that was an error.
+    let _ = writer.join();
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} failed: {}",
+            program,
+            errors.trim()
+        )));
+    }
+    Ok(output)
+}
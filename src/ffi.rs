@@ -0,0 +1,126 @@
+//! A C API for counting from other languages, built with the `cdylib`
+//! feature. `build.rs` writes the matching header, `rwc.h`, from this file
+//! to its `OUT_DIR`; see docs/ffi.md.
+//!
+//! Text is counted as UTF-8 with the default word rules, as
+//! `rwc --encoding utf8 --no-decompress` would count it.
+
+use std::ffi::{c_char, c_int, CStr};
+use std::fs::File;
+use std::io::{self, Read};
+use std::slice;
+
+use crate::engine::{Counts, Metrics, Scanner};
+
+/// Success.
+pub const RWC_OK: c_int = 0;
+/// The input is not valid UTF-8.
+pub const RWC_INVALID_UTF8: c_int = -1;
+/// A pointer argument was null.
+pub const RWC_NULL_ARGUMENT: c_int = -2;
+/// Reading failed in a way the system gave no error number for. Other read
+/// errors are returned as their positive `errno` value.
+pub const RWC_IO_ERROR: c_int = -3;
+
+/// The counts of one input.
+#[repr(C)]
+pub struct RwcCounts {
+    pub lines: u64,
+    pub words: u64,
+    pub chars: u64,
+    pub bytes: u64,
+    /// Display width of the longest line, as in `wc -L`.
+    pub max_line_length: u64,
+    /// Blocks of lines separated by blank lines.
+    pub paragraphs: u64,
+    /// Lines holding nothing but whitespace.
+    pub blank_lines: u64,
+    pub sentences: u64,
+}
+
+impl From<&Counts> for RwcCounts {
+    fn from(counts: &Counts) -> Self {
+        RwcCounts {
+            lines: counts.lines as u64,
+            words: counts.words as u64,
+            chars: counts.chars as u64,
+            bytes: counts.bytes as u64,
+            max_line_length: counts.max_line_length as u64,
+            paragraphs: counts.paragraphs as u64,
+            blank_lines: counts.blank_lines as u64,
+            sentences: counts.sentences as u64,
+        }
+    }
+}
+
+fn status(result: io::Result<Counts>, out: &mut RwcCounts) -> c_int {
+    match result {
+        Ok(counts) => {
+            *out = RwcCounts::from(&counts);
+            RWC_OK
+        }
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => RWC_INVALID_UTF8,
+        Err(e) => e.raw_os_error().unwrap_or(RWC_IO_ERROR),
+    }
+}
+
+fn count(mut reader: impl Read) -> io::Result<Counts> {
+    let metrics = Metrics::default();
+    let mut scanner = Scanner::new(&metrics);
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return scanner.finish(),
+            Ok(n) => scanner.feed(&buffer[..n])?,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Counts the `len` bytes at `data` into `out`. Returns `RWC_OK` or an
+/// error code; `out` is only written on success.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes (or be null with `len` 0) and
+/// `out` to writable memory for the counts.
+#[no_mangle]
+pub unsafe extern "C" fn rwc_count_buffer(
+    data: *const u8,
+    len: usize,
+    out: *mut RwcCounts,
+) -> c_int {
+    let Some(out) = out.as_mut() else {
+        return RWC_NULL_ARGUMENT;
+    };
+    let data = match (data.is_null(), len) {
+        (true, 0) => &[][..],
+        (true, _) => return RWC_NULL_ARGUMENT,
+        (false, _) => slice::from_raw_parts(data, len),
+    };
+    status(count(data), out)
+}
+
+/// Counts the file at `path`, a NUL-terminated string, into `out`, reading
+/// it in chunks however large it is. Returns `RWC_OK` or an error code;
+/// `out` is only written on success.
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string and `out` must point to writable
+/// memory for the counts.
+#[no_mangle]
+pub unsafe extern "C" fn rwc_count_file(path: *const c_char, out: *mut RwcCounts) -> c_int {
+    let Some(out) = out.as_mut() else {
+        return RWC_NULL_ARGUMENT;
+    };
+    if path.is_null() {
+        return RWC_NULL_ARGUMENT;
+    }
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => path,
+        Err(_) => return RWC_INVALID_UTF8,
+    };
+    status(File::open(path).and_then(count), out)
+}
@@ -0,0 +1,209 @@
+//! File-type detection and `--auto` routing.
+//!
+//! Inputs are identified by magic bytes first, then by a `#!` line, then by
+//! extension, then by sniffing the content. `--auto` uses the detected type to
+//! pick an extraction step; `--type` overrides detection for the outermost
+//! layer (a decompressed payload is detected again).
+
+use std::io;
+use std::path::Path;
+
+use crate::decompress::{self, Format};
+use crate::json::{self, Value};
+use crate::{external, tar, transform, xlsx, zip};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileType {
+    Text,
+    /// Text with a `#!` interpreter line.
+    Script,
+    Html,
+    Notebook,
+    Pdf,
+    Tar,
+    Zip,
+    /// An Excel workbook, which is a zip archive.
+    Xlsx,
+    Compressed(Format),
+    Binary,
+}
+
+/// Names accepted by `--type`.
+pub const NAMES: &[(&str, FileType)] = &[
+    ("text", FileType::Text),
+    ("script", FileType::Script),
+    ("html", FileType::Html),
+    ("notebook", FileType::Notebook),
+    ("pdf", FileType::Pdf),
+    ("tar", FileType::Tar),
+    ("zip", FileType::Zip),
+    ("xlsx", FileType::Xlsx),
+    ("gzip", FileType::Compressed(Format::Gzip)),
+    ("bzip2", FileType::Compressed(Format::Bzip2)),
+    ("xz", FileType::Compressed(Format::Xz)),
+    ("zstd", FileType::Compressed(Format::Zstd)),
+    ("binary", FileType::Binary),
+];
+
+/// How many leading bytes are examined for NUL bytes and markup.
+const SNIFF_LEN: usize = 8192;
+
+impl FileType {
+    pub fn parse(name: &str) -> Option<FileType> {
+        NAMES.iter().find(|(n, _)| *n == name).map(|&(_, t)| t)
+    }
+
+    /// Detects the type of `data`, read from `path` if known.
+    pub fn detect(path: Option<&Path>, data: &[u8]) -> FileType {
+        if let Some(format) = Format::detect(data) {
+            return FileType::Compressed(format);
+        }
+        if data.starts_with(b"%PDF-") {
+            return FileType::Pdf;
+        }
+        let extension = path
+            .and_then(Path::extension)
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+            return match extension.as_deref() {
+                Some("xlsx" | "xlsm") => FileType::Xlsx,
+                _ => FileType::Zip,
+            };
+        }
+        if tar::is_tar_header(data) {
+            return FileType::Tar;
+        }
+        if data.starts_with(b"#!") {
+            return FileType::Script;
+        }
+
+        match extension.as_deref() {
+            Some("html" | "htm" | "xhtml") => return FileType::Html,
+            Some("ipynb") => return FileType::Notebook,
+            _ => {}
+        }
+
+        let head = &data[..data.len().min(SNIFF_LEN)];
+        if head.contains(&0) {
+            return FileType::Binary;
+        }
+        let start = String::from_utf8_lossy(&head[..head.len().min(512)]).to_ascii_lowercase();
+        let start = start.trim_start_matches('\u{feff}').trim_start();
+        if start.starts_with("<!doctype html") || start.starts_with("<html") {
+            return FileType::Html;
+        }
+        if start.starts_with('{') && start.contains("\"cells\"") {
+            return FileType::Notebook;
+        }
+        FileType::Text
+    }
+}
+
+/// Extracts the countable text from `data` according to its type, peeling
+/// compression layers first. `forced` replaces detection of the outer layer.
+pub fn extract(path: Option<&Path>, data: Vec<u8>, forced: Option<FileType>) -> io::Result<Vec<u8>> {
+    let (kind, data) = peel(path, data, forced)?;
+    extract_as(kind, data)
+}
+
+/// Unpacks compression layers, returning the type of the innermost payload
+/// and the payload itself.
+pub fn peel(
+    path: Option<&Path>,
+    mut data: Vec<u8>,
+    forced: Option<FileType>,
+) -> io::Result<(FileType, Vec<u8>)> {
+    let mut kind = forced.unwrap_or_else(|| FileType::detect(path, &data));
+    // The name of a decompressed payload is the path minus its extension.
+    let mut inner_path = path.map(Path::to_path_buf);
+    while let FileType::Compressed(format) = kind {
+        data = decompress::decompress(format, data)?;
+        inner_path = inner_path.and_then(|p| p.file_stem().map(Into::into));
+        kind = FileType::detect(inner_path.as_deref(), &data);
+    }
+    Ok((kind, data))
+}
+
+/// Extracts the countable text from an uncompressed payload of type `kind`.
+pub fn extract_as(kind: FileType, data: Vec<u8>) -> io::Result<Vec<u8>> {
+    match kind {
+        FileType::Html => {
+            let text = String::from_utf8_lossy(&data);
+            Ok(transform::html_text(&text).into_bytes())
+        }
+        FileType::Notebook => notebook_text(&data),
+        FileType::Pdf => external::filter("pdftotext", &["-q", "-", "-"], data),
+        FileType::Tar => tar_text(&data),
+        FileType::Zip => zip_text(&data),
+        FileType::Xlsx => xlsx::text(&data),
+        FileType::Compressed(_) => extract(None, data, Some(kind)),
+        FileType::Text | FileType::Script | FileType::Binary => Ok(data),
+    }
+}
+
+/// Joins the source of every notebook cell; outputs are not counted.
+fn notebook_text(data: &[u8]) -> io::Result<Vec<u8>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let text = std::str::from_utf8(data).map_err(|_| invalid("notebook is not UTF-8".into()))?;
+    let notebook = json::parse(text).map_err(|e| invalid(format!("invalid notebook: {}", e)))?;
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("notebook has no cells".into()))?;
+
+    let mut out = String::new();
+    for cell in cells {
+        match cell.get("source") {
+            Some(Value::String(source)) => out.push_str(source),
+            Some(Value::Array(lines)) => lines
+                .iter()
+                .filter_map(Value::as_str)
+                .for_each(|line| out.push_str(line)),
+            _ => {}
+        }
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    Ok(out.into_bytes())
+}
+
+/// Concatenates the text members of a tar archive, each detected and
+/// extracted in turn; binary members are left out.
+fn tar_text(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    tar::for_each_entry(data, |header, reader| {
+        if header.kind != tar::EntryKind::File {
+            return Ok(());
+        }
+        let mut member = Vec::new();
+        reader.read_to_end(&mut member)?;
+        let path = Path::new(&header.path);
+        let text = extract(Some(path), member, None)?;
+        if std::str::from_utf8(&text).is_ok() {
+            out.extend_from_slice(&text);
+            if !out.ends_with(b"\n") {
+                out.push(b'\n');
+            }
+        }
+        Ok(())
+    })?;
+    Ok(out)
+}
+
+/// Like `tar_text`, for the members of a zip archive.
+fn zip_text(data: &[u8]) -> io::Result<Vec<u8>> {
+    let archive = zip::Archive::open(data)?;
+    let mut out = Vec::new();
+    for entry in archive.entries().iter().filter(|entry| !entry.is_dir()) {
+        let text = extract(Some(Path::new(&entry.name)), archive.read(entry)?, None)?;
+        if std::str::from_utf8(&text).is_ok() {
+            out.extend_from_slice(&text);
+            if !out.ends_with(b"\n") {
+                out.push(b'\n');
+            }
+        }
+    }
+    Ok(out)
+}
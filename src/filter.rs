@@ -0,0 +1,159 @@
+//! Content filters applied before counting.
+
+use crate::regex::Regex;
+
+/// Closing mark for each supported opening quotation mark.
+fn closing_quote(open: char) -> Option<&'static [char]> {
+    Some(match open {
+        '"' => &['"'],
+        '“' => &['”'],
+        '„' => &['“', '”'],
+        '«' => &['»'],
+        '「' => &['」'],
+        '『' => &['』'],
+        _ => return None,
+    })
+}
+
+/// Removes quoted material: lines starting with `>` (email and Markdown
+/// quotes) and text between double quotation marks, including quotes that
+/// span lines. Single quotes are left alone since they double as apostrophes,
+/// and an opening mark with no closing partner is kept as ordinary text.
+pub fn strip_quotes(text: &str) -> String {
+    let unquoted: String = text
+        .split_inclusive('\n')
+        .filter(|line| !line.trim_start().starts_with('>'))
+        .collect();
+
+    let chars: Vec<char> = unquoted.chars().collect();
+    let mut out = String::with_capacity(unquoted.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let close = closing_quote(chars[i])
+            .and_then(|marks| chars[i + 1..].iter().position(|c| marks.contains(c)));
+        match close {
+            Some(offset) => {
+                // Keep line breaks so the quote doesn't merge surrounding lines.
+                out.extend(chars[i + 1..i + 1 + offset].iter().filter(|&&c| c == '\n'));
+                out.push(' ');
+                i += offset + 2;
+            }
+            None => {
+                out.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Headings that start a reference section in `--academic` mode.
+const REFERENCE_HEADINGS: [&str; 6] = [
+    "references",
+    "bibliography",
+    "works cited",
+    "literature cited",
+    "sources",
+    "endnotes",
+];
+
+/// Inline citation and footnote patterns removed by `--academic`.
+const CITATION_PATTERNS: [&str; 5] = [
+    // Markdown footnote markers: [^1], [^note]
+    r"\[\^[^\]]+\]",
+    // Numeric citations: [1], [2, 5], [3-7]
+    r"\[\d+(\s*[-–,;]\s*\d+)*\]",
+    // Bracketed author-year: [Smith 2020], [Doe et al., 2019a]
+    r"\[[^\[\]]*[A-Z][^\[\]]*\b\d{4}[a-z]?\b[^\[\]]*\]",
+    // Parenthetical author-year: (Smith, 2020), (see Doe et al. 2019; Roe 2021)
+    r"\(((see|e\.g\.,?|cf\.)\s+)?[A-Z][^()]*\b\d{4}[a-z]?\b[^()]*\)",
+    // Superscript footnote numbers
+    r"[¹²³⁴⁵⁶⁷⁸⁹⁰]+",
+];
+
+/// Markdown heading level and title, or `None` for other lines.
+pub fn markdown_heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let title = &trimmed[level..];
+    (title.is_empty() || title.starts_with(' ')).then(|| (level, title.trim().trim_end_matches('#').trim()))
+}
+
+fn is_reference_title(title: &str) -> bool {
+    let title = title.trim().trim_end_matches(':').to_lowercase();
+    REFERENCE_HEADINGS.contains(&title.as_str())
+}
+
+/// Removes what journals leave out of manuscript word counts: inline
+/// citations, footnote markers and definitions, and reference sections.
+///
+/// A reference section starts at a heading such as "References" or
+/// "Bibliography" (a Markdown heading or a line on its own) and runs to the
+/// next Markdown heading of the same or a higher level, or to the end.
+pub fn strip_academic(text: &str) -> String {
+    let citations: Vec<Regex> = CITATION_PATTERNS
+        .iter()
+        .filter_map(|pattern| Regex::new(pattern).ok())
+        .collect();
+
+    let mut out = String::with_capacity(text.len());
+    // Level of the reference heading being skipped; 0 skips to the end.
+    let mut skipping: Option<usize> = None;
+    let mut in_footnote = false;
+    for line in text.split_inclusive('\n') {
+        let heading = markdown_heading(line);
+        if let (Some(level), Some((next, _))) = (skipping, heading) {
+            if level != 0 && next <= level {
+                skipping = None;
+            }
+        }
+        if skipping.is_none() {
+            match heading {
+                Some((level, title)) if is_reference_title(title) => skipping = Some(level),
+                None if is_reference_title(line) => skipping = Some(0),
+                _ => {}
+            }
+        }
+        if skipping.is_some() {
+            continue;
+        }
+
+        // Footnote definitions, including indented continuation lines.
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("[^") && trimmed.contains("]:") {
+            in_footnote = true;
+            continue;
+        }
+        if in_footnote {
+            if line.starts_with([' ', '\t']) && !trimmed.is_empty() {
+                continue;
+            }
+            in_footnote = false;
+        }
+
+        let mut line = line.to_string();
+        for regex in &citations {
+            line = remove_matches(regex, &line);
+        }
+        out.push_str(&line);
+    }
+    out
+}
+
+/// Deletes every match of `regex`, along with the whitespace before it so
+/// that `word [1].` becomes `word.` rather than leaving a stray token.
+fn remove_matches(regex: &Regex, line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut last = 0;
+    for (start, end) in regex.find_iter(line) {
+        out.push_str(&line[last..start]);
+        let kept = out.trim_end_matches([' ', '\t']).len();
+        out.truncate(kept);
+        last = end;
+    }
+    out.push_str(&line[last..]);
+    out
+}
@@ -0,0 +1,135 @@
+//! `--follow`: running counts of files that are still being written, like
+//! `tail -f`.
+//!
+//! Each file is read to its end once and then kept open; every
+//! `POLL_INTERVAL` whatever was appended since is fed to the same `Scanner`,
+//! so nothing is read twice. A file that shrinks was truncated in place (as
+//! by `copytruncate` log rotation) and is counted again from its start.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::engine::{Metrics, Scanner};
+use crate::format::Formats;
+use crate::json::Value;
+use crate::{counts_json, formats, output, print_counts, print_json, Config, Counts};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+struct Followed<'a> {
+    name: &'a str,
+    file: File,
+    /// Bytes read so far.
+    position: u64,
+    scanner: Scanner<'a>,
+}
+
+impl<'a> Followed<'a> {
+    /// Feeds everything appended since the last call. Returns whether
+    /// anything was read or the file was found truncated.
+    fn catch_up(&mut self, metrics: &'a Metrics<'a>) -> io::Result<bool> {
+        let mut changed = false;
+        if self.file.metadata()?.len() < self.position {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            self.scanner = Scanner::new(metrics);
+            changed = true;
+        }
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let n = match self.file.read(&mut buffer) {
+                Ok(0) => return Ok(changed),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.scanner.feed(&buffer[..n])?;
+            self.position += n as u64;
+            changed = true;
+        }
+    }
+}
+
+/// Follows the configured files until interrupted. Returns the exit code.
+pub fn run(config: &Config) -> i32 {
+    if config.files.is_empty() || config.files.iter().any(|file| file == "-") {
+        eprintln!("rwc: --follow needs FILE operands; use --window to stream standard input");
+        return 1;
+    }
+    let metrics = config.metrics();
+    let mut followed = Vec::new();
+    for name in &config.files {
+        match File::open(name) {
+            Ok(file) => followed.push(Followed {
+                name,
+                file,
+                position: 0,
+                scanner: Scanner::new(&metrics),
+            }),
+            Err(e) => {
+                eprintln!("rwc: {}: {}", name, e);
+                return 1;
+            }
+        }
+    }
+
+    let formats = formats::formats(config);
+    let mut first = true;
+    let mut checked = Instant::now();
+    loop {
+        let mut changed = false;
+        let mut appended_lines = 0;
+        for entry in &mut followed {
+            let before = entry.scanner.running_counts().lines;
+            match entry.catch_up(&metrics) {
+                Ok(updated) => changed |= updated,
+                Err(e) => {
+                    eprintln!("rwc: {}: {}", entry.name, e);
+                    return 1;
+                }
+            }
+            // After a truncation the new count starts again from zero.
+            appended_lines += entry.scanner.running_counts().lines.saturating_sub(before);
+        }
+        if changed || first {
+            if !first && !config.show_json && followed.len() > 1 {
+                output::print("");
+            }
+            report(&followed, &formats, config);
+        }
+        // The first read is the backlog, not the rate the file grows at.
+        if let Some(alert) = config.alert.as_ref().filter(|_| !first) {
+            alert.check(appended_lines, checked.elapsed());
+        }
+        checked = Instant::now();
+        first = false;
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Prints the running counts of every file, with a total row when there are
+/// several.
+fn report(followed: &[Followed], formats: &Formats<'_, Counts>, config: &Config) {
+    let mut total = Counts::default();
+    let mut json_files = Vec::new();
+    for entry in followed {
+        let counts = Counts::from(entry.scanner.running_counts());
+        if config.show_json {
+            json_files.push(counts_json(&counts, config, Some(entry.name)));
+        } else {
+            print_counts(formats, &counts, config, Some(entry.name));
+        }
+        total.add(&counts);
+    }
+    if config.show_json {
+        let document = Value::Object(vec![
+            ("files".to_string(), Value::Array(json_files)),
+            ("total".to_string(), counts_json(&total, config, None)),
+        ]);
+        print_json(&document, config);
+    } else if followed.len() > 1 {
+        print_counts(formats, &total, config, Some(config.total_label()));
+    }
+}
@@ -0,0 +1,235 @@
+//! Output formats: how one record of counts is written out.
+//!
+//! A format is an `OutputFormatter` over some record type, and `Formats`
+//! holds them by the name `--format` would give them. The `rwc` binary
+//! keeps its table, CSV and plain formats this way over its own records;
+//! a program using the library can register its own formats next to the
+//! ones here, which write the basic `engine::Counts`, without touching
+//! either.
+
+use crate::engine::Counts;
+use crate::json::Value;
+
+/// Writes records of type `R`.
+pub trait OutputFormatter<R: ?Sized> {
+    /// What comes before the first record, such as a CSV header.
+    fn header(&self) -> Option<String> {
+        None
+    }
+
+    /// One record, named `name` (a file, or a totals row) if it has one,
+    /// without a final newline.
+    fn format(&self, record: &R, name: Option<&str>) -> String;
+}
+
+/// Formats by name, in the order they were registered.
+pub struct Formats<'a, R: ?Sized> {
+    formats: Vec<(String, Box<dyn OutputFormatter<R> + 'a>)>,
+}
+
+impl<R: ?Sized> Default for Formats<'_, R> {
+    fn default() -> Self {
+        Formats {
+            formats: Vec::new(),
+        }
+    }
+}
+
+impl<'a, R: ?Sized> Formats<'a, R> {
+    /// Adds `formatter` as `name`, in place of any format of that name.
+    pub fn register(&mut self, name: &str, formatter: impl OutputFormatter<R> + 'a) {
+        let formatter: Box<dyn OutputFormatter<R> + 'a> = Box::new(formatter);
+        match self.formats.iter_mut().find(|(known, _)| known == name) {
+            Some((_, known)) => *known = formatter,
+            None => self.formats.push((name.to_string(), formatter)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&(dyn OutputFormatter<R> + 'a)> {
+        self.formats
+            .iter()
+            .find(|(known, _)| known == name)
+            .map(|(_, formatter)| formatter.as_ref())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats.iter().map(|(name, _)| name.as_str())
+    }
+}
+
+/// The basic counts, in the order `rwc` writes them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Lines,
+    Words,
+    Chars,
+    Bytes,
+    MaxLineLength,
+    Paragraphs,
+    BlankLines,
+    Sentences,
+}
+
+impl Column {
+    pub const ALL: [Column; 8] = [
+        Column::Lines,
+        Column::Words,
+        Column::Chars,
+        Column::Bytes,
+        Column::MaxLineLength,
+        Column::Paragraphs,
+        Column::BlankLines,
+        Column::Sentences,
+    ];
+
+    /// The name `rwc --json` gives the count.
+    pub fn key(self) -> &'static str {
+        match self {
+            Column::Lines => "lines",
+            Column::Words => "words",
+            Column::Chars => "chars",
+            Column::Bytes => "bytes",
+            Column::MaxLineLength => "max_line_length",
+            Column::Paragraphs => "paragraphs",
+            Column::BlankLines => "blank_lines",
+            Column::Sentences => "sentences",
+        }
+    }
+
+    pub fn of(self, counts: &Counts) -> usize {
+        match self {
+            Column::Lines => counts.lines,
+            Column::Words => counts.words,
+            Column::Chars => counts.chars,
+            Column::Bytes => counts.bytes,
+            Column::MaxLineLength => counts.max_line_length,
+            Column::Paragraphs => counts.paragraphs,
+            Column::BlankLines => counts.blank_lines,
+            Column::Sentences => counts.sentences,
+        }
+    }
+}
+
+/// Cells of a table row as `rwc` writes them: 8 spaces apart, the row
+/// right-aligned in 8 columns. Color escapes in a cell take no room.
+pub fn table_row(cells: &[String]) -> String {
+    let separator = " ".repeat(8);
+    let row = cells.join(&separator);
+    let width = visible_width(&row);
+    " ".repeat(8usize.saturating_sub(width)) + &row
+}
+
+/// Characters of `text` outside `ESC [ ... m` color sequences.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// Quotes a CSV field if it contains a comma, quote or line break.
+pub fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// The counts in a `table_row`, then the name.
+pub struct Table {
+    pub columns: Vec<Column>,
+}
+
+impl OutputFormatter<Counts> for Table {
+    fn format(&self, counts: &Counts, name: Option<&str>) -> String {
+        let cells: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| column.of(counts).to_string())
+            .collect();
+        let mut record = table_row(&cells);
+        if let Some(name) = name {
+            record.push(' ');
+            record.push_str(name);
+        }
+        record
+    }
+}
+
+/// A CSV row per record, under a header naming the columns.
+pub struct Csv {
+    pub columns: Vec<Column>,
+}
+
+impl OutputFormatter<Counts> for Csv {
+    fn header(&self) -> Option<String> {
+        let keys = self.columns.iter().map(|column| column.key());
+        Some(
+            std::iter::once("file")
+                .chain(keys)
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    }
+
+    fn format(&self, counts: &Counts, name: Option<&str>) -> String {
+        let name = csv_field(name.unwrap_or(""));
+        let values = self
+            .columns
+            .iter()
+            .map(|column| column.of(counts).to_string());
+        std::iter::once(name)
+            .chain(values)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// A JSON object per record, one to a line.
+pub struct Ndjson {
+    pub columns: Vec<Column>,
+}
+
+impl OutputFormatter<Counts> for Ndjson {
+    fn format(&self, counts: &Counts, name: Option<&str>) -> String {
+        let mut members = Vec::new();
+        if let Some(name) = name {
+            members.push(("file".to_string(), Value::from(name)));
+        }
+        for column in &self.columns {
+            members.push((column.key().to_string(), column.of(counts).into()));
+        }
+        Value::Object(members).to_string()
+    }
+}
+
+/// `table`, `csv` and `ndjson`, writing `columns`.
+pub fn builtin(columns: &[Column]) -> Formats<'static, Counts> {
+    let mut formats = Formats::default();
+    formats.register(
+        "table",
+        Table {
+            columns: columns.to_vec(),
+        },
+    );
+    formats.register(
+        "csv",
+        Csv {
+            columns: columns.to_vec(),
+        },
+    );
+    formats.register(
+        "ndjson",
+        Ndjson {
+            columns: columns.to_vec(),
+        },
+    );
+    formats
+}
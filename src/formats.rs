@@ -0,0 +1,295 @@
+//! The formats `print_counts` writes a file's counts in: the default
+//! table, `--format csv`, `--format ndjson` and `--format plain`. `--json`
+//! documents are put together from `counts_json` instead, as they span
+//! every file.
+
+use rwc::format::{self, csv_field, Formats, OutputFormatter};
+
+use crate::{
+    bidi, counts_json, format_score, format_unique, levels, metric, plain_label, plain_record,
+    readtime, shown_counts, thresholds, Config, Counts,
+};
+
+/// The formats for `config`, by `--format` name. Build them once and hand
+/// them to `print_counts` for every record.
+pub fn formats(config: &Config) -> Formats<'_, Counts> {
+    let mut formats = Formats::default();
+    formats.register("table", Table { config });
+    formats.register("csv", Csv { config });
+    formats.register("ndjson", Ndjson { config });
+    formats.register("plain", Plain { config });
+    formats
+}
+
+/// A `format::table_row`, then the name; budgets color the counts they
+/// check.
+pub struct Table<'a> {
+    pub config: &'a Config,
+}
+
+impl OutputFormatter<Counts> for Table<'_> {
+    fn format(&self, counts: &Counts, filename: Option<&str>) -> String {
+        let config = self.config;
+        let uniques = counts.uniques.as_ref();
+        let unique_lines = uniques.and_then(|u| u.lines.as_ref());
+        let unique_words = uniques.and_then(|u| u.words.as_ref());
+
+        let mut output = Vec::new();
+        // Cells to color for --warn-above/--error-above: index, metric, value.
+        let mut checked = Vec::new();
+        for (shown, metric) in [
+            (config.show_lines, metric::Metric::Lines),
+            (config.show_words, metric::Metric::Words),
+            (config.show_chars, metric::Metric::Chars),
+            (config.show_bytes, metric::Metric::Bytes),
+        ] {
+            if shown {
+                let value = metric.of(counts);
+                if counts.status.is_some() {
+                    checked.push((output.len(), metric, value));
+                }
+                output.push(config.format_count(value));
+            }
+        }
+        if config.show_max_line_length {
+            output.push(config.format_count(counts.max_line_length));
+        }
+        if config.show_max_display_width {
+            output.push(config.format_count(counts.max_display_width));
+        }
+        if config.show_paragraphs {
+            output.push(config.format_count(counts.paragraphs));
+        }
+        if config.show_blank_lines {
+            output.push(config.format_count(counts.blank_lines));
+        }
+        if config.show_all {
+            output.push(config.format_count(counts.sentences));
+        }
+        for tracker in [unique_lines, unique_words].into_iter().flatten() {
+            output.push(format_unique(tracker, config));
+        }
+        if let Some(score) = counts.score {
+            output.push(format_score(score));
+        }
+        if config.minified {
+            output.push(
+                counts
+                    .minified
+                    .map_or("-".to_string(), |score| format!("{:.2}", score)),
+            );
+        }
+        if config.detect_language {
+            output.push(counts.language.unwrap_or("-").to_string());
+        }
+        if let Some(indent) = &counts.indent {
+            output.push(indent.max_depth.to_string());
+            output.push(format!("{:.1}", indent.average_depth()));
+            output.push(format!("{:.0}%", indent.tab_ratio() * 100.0));
+        }
+        if let Some(visual) = counts.visual_lines {
+            output.push(config.format_count(visual));
+        }
+        if let Some(records) = counts.records {
+            output.push(config.format_count(records));
+        }
+        for (_, lines) in counts.levels.iter().flat_map(levels::LevelCounts::iter) {
+            output.push(config.format_count(lines));
+        }
+        if config.reading_wpm.is_some() {
+            output.push(config.reading_time(counts.words));
+        }
+        if config.speaking_wpm.is_some() {
+            output.push(readtime::format(
+                config.speaking_minutes(counts.words),
+                config.time_format,
+            ));
+        }
+        if config.words_per_page.is_some() {
+            output.push(config.format_count(config.pages(counts.words)));
+        }
+        if !config.thresholds.is_empty() {
+            // The totals are not checked.
+            output.push(
+                counts
+                    .status
+                    .map_or("-", thresholds::Severity::name)
+                    .to_string(),
+            );
+        }
+
+        // The row and its preview lines are printed as one record.
+        for (index, metric, value) in checked {
+            output[index] = config.thresholds.paint(&output[index], metric, value);
+        }
+        let mut record = format::table_row(&output);
+        if let Some(name) = filename {
+            record.push(' ');
+            record.push_str(&bidi::display_name(name));
+        }
+        if let Some(structure) = &counts.structure {
+            record.push_str("\n    | ");
+            record.push_str(&structure.summary());
+        }
+        for line in counts.preview.iter().flatten() {
+            record.push_str("\n    | ");
+            record.push_str(line);
+        }
+        record
+    }
+}
+
+/// A row of values under a header naming the columns.
+pub struct Csv<'a> {
+    pub config: &'a Config,
+}
+
+impl OutputFormatter<Counts> for Csv<'_> {
+    /// The `--format csv` header: the file, the counts shown, and the status
+    /// when there are budgets.
+    fn header(&self) -> Option<String> {
+        let config = self.config;
+        let mut columns = vec!["file"];
+        columns.extend(
+            shown_counts(&Counts::default(), config)
+                .into_iter()
+                .map(|(key, _)| key),
+        );
+        if config.reading_wpm.is_some() {
+            columns.push("reading_time");
+        }
+        if config.speaking_wpm.is_some() {
+            columns.push("speaking_time");
+        }
+        if config.words_per_page.is_some() {
+            columns.push("pages");
+        }
+        if !config.thresholds.is_empty() {
+            columns.push("status");
+        }
+        Some(columns.join(","))
+    }
+
+    fn format(&self, counts: &Counts, filename: Option<&str>) -> String {
+        let config = self.config;
+        let mut row = vec![csv_field(filename.unwrap_or(""))];
+        row.extend(
+            shown_counts(counts, config)
+                .into_iter()
+                .map(|(_, value)| value.to_string()),
+        );
+        if config.reading_wpm.is_some() {
+            row.push(csv_field(&config.reading_time(counts.words)));
+        }
+        if config.speaking_wpm.is_some() {
+            let minutes = config.speaking_minutes(counts.words);
+            row.push(csv_field(&readtime::format(minutes, config.time_format)));
+        }
+        if config.words_per_page.is_some() {
+            row.push(config.pages(counts.words).to_string());
+        }
+        if !config.thresholds.is_empty() {
+            row.push(
+                counts
+                    .status
+                    .map_or("", thresholds::Severity::name)
+                    .to_string(),
+            );
+        }
+        row.join(",")
+    }
+}
+
+/// The `--json` object of each record, one to a line.
+pub struct Ndjson<'a> {
+    pub config: &'a Config,
+}
+
+impl OutputFormatter<Counts> for Ndjson<'_> {
+    fn format(&self, counts: &Counts, filename: Option<&str>) -> String {
+        counts_json(counts, self.config, filename).to_string()
+    }
+}
+
+/// `label: value` lines, one per count, and a blank line.
+pub struct Plain<'a> {
+    pub config: &'a Config,
+}
+
+impl OutputFormatter<Counts> for Plain<'_> {
+    fn format(&self, counts: &Counts, filename: Option<&str>) -> String {
+        let config = self.config;
+        let uniques = counts.uniques.as_ref();
+        let unique_lines = uniques.and_then(|u| u.lines.as_ref());
+        let unique_words = uniques.and_then(|u| u.words.as_ref());
+
+        let mut fields = Vec::new();
+        if let Some(name) = filename {
+            fields.push(("file", name.to_string()));
+        }
+        for (key, value) in shown_counts(counts, config) {
+            fields.push((plain_label(key), config.format_count(value)));
+        }
+        for (label, tracker) in [
+            ("unique lines", unique_lines),
+            ("unique words", unique_words),
+        ] {
+            if let Some(tracker) = tracker {
+                let mut value = config.format_count(tracker.count());
+                if let Some(error) = tracker.error() {
+                    value.push_str(&format!(", error {:.1} percent", error * 100.0));
+                }
+                fields.push((label, value));
+            }
+        }
+        if let Some(score) = counts.score {
+            fields.push(("score", format_score(score)));
+        }
+        if let Some(score) = counts.minified {
+            fields.push(("minified score", format!("{:.2}", score)));
+        }
+        if let Some(language) = counts.language {
+            fields.push(("language", language.to_string()));
+        }
+        if let Some(indent) = &counts.indent {
+            fields.push(("deepest indent", indent.max_depth.to_string()));
+            fields.push(("average indent", format!("{:.1}", indent.average_depth())));
+            fields.push((
+                "tab indented",
+                format!("{:.0} percent", indent.tab_ratio() * 100.0),
+            ));
+        }
+        if let Some(visual) = counts.visual_lines {
+            fields.push(("wrapped lines", config.format_count(visual)));
+        }
+        if let Some(records) = counts.records {
+            fields.push(("records", config.format_count(records)));
+        }
+        for (level, lines) in counts.levels.iter().flat_map(levels::LevelCounts::iter) {
+            fields.push((level, config.format_count(lines)));
+        }
+        if let Some(structure) = &counts.structure {
+            fields.extend(structure.fields());
+        }
+        if config.reading_wpm.is_some() {
+            fields.push(("reading time", config.reading_time(counts.words)));
+        }
+        if config.speaking_wpm.is_some() {
+            let minutes = config.speaking_minutes(counts.words);
+            fields.push((
+                "speaking time",
+                readtime::format(minutes, config.time_format),
+            ));
+        }
+        if config.words_per_page.is_some() {
+            fields.push(("pages", config.format_count(config.pages(counts.words))));
+        }
+        if let Some(status) = counts.status {
+            fields.push(("status", status.name().to_string()));
+        }
+        for line in counts.preview.iter().flatten() {
+            fields.push(("preview", line.clone()));
+        }
+        plain_record(&fields)
+    }
+}
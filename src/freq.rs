@@ -0,0 +1,267 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use crate::{cli, json, parse_size};
+
+/// Rough per-entry overhead of the frequency map beyond the word bytes.
+const ENTRY_OVERHEAD: usize = 64;
+
+/// Entry point for `rwc freq [--top N] [--max-memory SIZE] [FILES...]`.
+pub fn run(args: &[String]) -> i32 {
+    let mut top = 10;
+    let mut max_memory = None;
+    let mut json = false;
+    let mut files = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--top" | "--max-memory" if i + 1 >= args.len() => {
+                eprintln!("rwc freq: option '{}' requires an argument", args[i]);
+                return 1;
+            }
+            "--top" => {
+                i += 1;
+                match args[i].parse() {
+                    Ok(n) => top = n,
+                    Err(_) => {
+                        eprintln!("rwc freq: invalid count '{}'", args[i]);
+                        return 1;
+                    }
+                }
+            }
+            "--max-memory" => {
+                i += 1;
+                match parse_size(&args[i]) {
+                    Some(size) => max_memory = Some(size as usize),
+                    None => {
+                        eprintln!("rwc freq: invalid size '{}'", args[i]);
+                        return 1;
+                    }
+                }
+            }
+            "--json" => json = true,
+            "--help" => {
+                println!("Usage: rwc freq [--top N] [--max-memory SIZE] [--json] [FILES...]");
+                println!();
+                println!("Prints the N most frequent words (default 10).");
+                println!("With --max-memory (e.g. 512M), partial counts are spilled to temporary");
+                println!("files and merged, so arbitrarily large inputs fit the budget.");
+                return 0;
+            }
+            a if a.starts_with('-') && a != "-" => {
+                cli::unknown_option("rwc freq", a, &["--top", "--max-memory", "--json", "--help"]);
+                return 1;
+            }
+            a => files.push(a.to_string()),
+        }
+        i += 1;
+    }
+
+    let mut counter = FrequencyCounter::new(max_memory);
+    let result = if files.is_empty() {
+        counter.feed(io::stdin().lock())
+    } else {
+        files.iter().try_for_each(|name| {
+            if name == "-" {
+                return counter.feed(io::stdin().lock());
+            }
+            let file = File::open(name)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", name, e)))?;
+            counter.feed(file)
+        })
+    };
+
+    match result.and_then(|_| counter.top(top)) {
+        Ok(words) => {
+            print_top(&words, json);
+            0
+        }
+        Err(e) => {
+            eprintln!("rwc freq: {}", e);
+            1
+        }
+    }
+}
+
+fn print_top(words: &[(String, u64)], json: bool) {
+    if json {
+        let items: Vec<String> = words
+            .iter()
+            .map(|(word, count)| format!("  {{\"word\": {}, \"count\": {}}}", json::quote(word), count))
+            .collect();
+        println!("[\n{}\n]", items.join(",\n"));
+    } else {
+        for (word, count) in words {
+            println!("{:>8} {}", count, word);
+        }
+    }
+}
+
+/// Word frequency table that spills sorted runs to disk when it would
+/// exceed its memory budget.
+struct FrequencyCounter {
+    counts: HashMap<String, u64>,
+    memory: usize,
+    budget: Option<usize>,
+    runs: Vec<PathBuf>,
+}
+
+impl FrequencyCounter {
+    fn new(budget: Option<usize>) -> Self {
+        FrequencyCounter {
+            counts: HashMap::new(),
+            memory: 0,
+            budget,
+            runs: Vec::new(),
+        }
+    }
+
+    fn feed<R: Read>(&mut self, reader: R) -> io::Result<()> {
+        let mut reader = BufReader::new(reader);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                return Ok(());
+            }
+            let line = String::from_utf8_lossy(&buf);
+            for word in line.split_whitespace() {
+                self.add(word, 1)?;
+            }
+        }
+    }
+
+    fn add(&mut self, word: &str, n: u64) -> io::Result<()> {
+        if let Some(count) = self.counts.get_mut(word) {
+            *count += n;
+            return Ok(());
+        }
+        self.counts.insert(word.to_string(), n);
+        self.memory += word.len() + ENTRY_OVERHEAD;
+        if self.budget.is_some_and(|budget| self.memory > budget) {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the current table to a sorted run file and clears it.
+    fn spill(&mut self) -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "rwc-freq-{}-{}.run",
+            std::process::id(),
+            self.runs.len()
+        ));
+        let mut entries: Vec<(String, u64)> = self.counts.drain().collect();
+        entries.sort_unstable();
+        let mut out = BufWriter::new(File::create(&path)?);
+        for (word, count) in entries {
+            writeln!(out, "{}\t{}", word, count)?;
+        }
+        out.flush()?;
+        self.runs.push(path);
+        self.memory = 0;
+        Ok(())
+    }
+
+    /// Returns the `n` most frequent words, most frequent first.
+    fn top(&mut self, n: usize) -> io::Result<Vec<(String, u64)>> {
+        let mut heap = TopN::new(n);
+        if self.runs.is_empty() {
+            for (word, count) in self.counts.drain() {
+                heap.push(word, count);
+            }
+            return Ok(heap.into_sorted());
+        }
+
+        self.spill()?;
+        let mut readers = self
+            .runs
+            .iter()
+            .map(|path| File::open(path).map(|f| BufReader::new(f).lines()))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        // k-way merge of the sorted runs, summing counts of equal words.
+        let mut merge = BinaryHeap::new();
+        for (index, reader) in readers.iter_mut().enumerate() {
+            if let Some(entry) = next_entry(reader)? {
+                merge.push(Reverse((entry, index)));
+            }
+        }
+        let mut current: Option<(String, u64)> = None;
+        while let Some(Reverse(((word, count), index))) = merge.pop() {
+            if let Some(entry) = next_entry(&mut readers[index])? {
+                merge.push(Reverse((entry, index)));
+            }
+            match &mut current {
+                Some((w, c)) if *w == word => *c += count,
+                _ => {
+                    if let Some((w, c)) = current.replace((word, count)) {
+                        heap.push(w, c);
+                    }
+                }
+            }
+        }
+        if let Some((w, c)) = current {
+            heap.push(w, c);
+        }
+        Ok(heap.into_sorted())
+    }
+}
+
+impl Drop for FrequencyCounter {
+    fn drop(&mut self) {
+        for path in &self.runs {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn next_entry<B: BufRead>(lines: &mut io::Lines<B>) -> io::Result<Option<(String, u64)>> {
+    let Some(line) = lines.next().transpose()? else {
+        return Ok(None);
+    };
+    let (word, count) = line
+        .rsplit_once('\t')
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "corrupt spill file"))?;
+    let count = count
+        .parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "corrupt spill file"))?;
+    Ok(Some((word.to_string(), count)))
+}
+
+/// Keeps the `n` highest counts, breaking ties alphabetically.
+struct TopN {
+    n: usize,
+    heap: BinaryHeap<Reverse<(u64, Reverse<String>)>>,
+}
+
+impl TopN {
+    fn new(n: usize) -> Self {
+        TopN {
+            n,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    fn push(&mut self, word: String, count: u64) {
+        if self.n == 0 {
+            return;
+        }
+        self.heap.push(Reverse((count, Reverse(word))));
+        if self.heap.len() > self.n {
+            self.heap.pop();
+        }
+    }
+
+    fn into_sorted(self) -> Vec<(String, u64)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((count, Reverse(word)))| (word, count))
+            .collect()
+    }
+}
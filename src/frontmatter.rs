@@ -0,0 +1,244 @@
+//! `rwc annotate-frontmatter`: keeps `words` and `reading_time` in the front
+//! matter of Markdown files up to date.
+//!
+//! YAML (`---`) and TOML (`+++`) front matter are both understood. Only the
+//! two keys are touched, at the top level of the block: an existing line is
+//! rewritten in place and a missing key is added at the end, so the rest of
+//! the block keeps its order, comments and formatting. A file with no front
+//! matter gets a YAML block. The words are those of the body, after the
+//! front matter.
+
+use std::fs;
+
+use crate::readtime::{self, TimeFormat};
+use crate::{cli, count_text, Rounding, READING_WPM};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Syntax {
+    Yaml,
+    Toml,
+}
+
+/// Entry point for `rwc annotate-frontmatter`.
+pub fn run(args: &[String]) -> i32 {
+    let mut check = false;
+    let mut time_format = TimeFormat::Minutes;
+    let mut files = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--check" => check = true,
+            "--time-format" if i + 1 >= args.len() => {
+                eprintln!("rwc annotate-frontmatter: option '--time-format' requires an argument");
+                return 1;
+            }
+            "--time-format" => {
+                i += 1;
+                match TimeFormat::parse(&args[i]) {
+                    Some(format) => time_format = format,
+                    None => {
+                        eprintln!(
+                            "rwc annotate-frontmatter: invalid time format '{}' \
+                             (expected text, minutes, hms or iso8601)",
+                            args[i]
+                        );
+                        return 1;
+                    }
+                }
+            }
+            "--help" => {
+                println!("Usage: rwc annotate-frontmatter [--check] [--time-format F] FILE.md...");
+                println!();
+                println!("Writes the body's word count and reading time into each file's front");
+                println!("matter as 'words' and 'reading_time', adding a YAML block if there is");
+                println!("none. Other keys are left as they are.");
+                println!();
+                println!("OPTIONS:");
+                println!("    --check         Change nothing; list files whose values are out of");
+                println!("                    date and exit with status 1 if there are any");
+                println!("    --time-format F Reading time as minutes (default), text, hms or");
+                println!("                    iso8601");
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option(
+                    "rwc annotate-frontmatter",
+                    a,
+                    &["--check", "--time-format", "--help"],
+                );
+                return 1;
+            }
+            a => files.push(a.to_string()),
+        }
+        i += 1;
+    }
+    if files.is_empty() {
+        eprintln!("rwc annotate-frontmatter: no input files");
+        eprintln!("Try 'rwc annotate-frontmatter --help' for more information.");
+        return 1;
+    }
+
+    let mut stale = 0;
+    for file in &files {
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("rwc annotate-frontmatter: {}: {}", file, e);
+                return 1;
+            }
+        };
+        let annotated = annotate(&text, time_format);
+        if annotated == text {
+            continue;
+        }
+        if check {
+            println!("{}: front matter is out of date", file);
+            stale += 1;
+            continue;
+        }
+        if let Err(e) = fs::write(file, annotated) {
+            eprintln!("rwc annotate-frontmatter: {}: {}", file, e);
+            return 1;
+        }
+        println!("{}: updated", file);
+    }
+    i32::from(stale > 0)
+}
+
+/// Splits `text` into its front matter block, without the fences, and the
+/// body after it.
+fn split(text: &str) -> Option<(Syntax, &str, &str)> {
+    let (syntax, fence) = if text.starts_with("---") {
+        (Syntax::Yaml, "---")
+    } else if text.starts_with("+++") {
+        (Syntax::Toml, "+++")
+    } else {
+        return None;
+    };
+    let first = text.find('\n')?;
+    if text[..first].trim_end() != fence {
+        return None;
+    }
+    let mut at = first + 1;
+    while at < text.len() {
+        let end = text[at..].find('\n').map_or(text.len(), |n| at + n + 1);
+        let line = text[at..end].trim_end();
+        // YAML also allows `...` to end the document.
+        if line == fence || (syntax == Syntax::Yaml && line == "...") {
+            return Some((syntax, &text[first + 1..at], &text[end..]));
+        }
+        at = end;
+    }
+    None
+}
+
+/// The value of the top-level `key` in the front matter of `text`, without
+/// quotes.
+pub fn value(text: &str, key: &str) -> Option<String> {
+    let (syntax, block, _) = split(text)?;
+    let separator = match syntax {
+        Syntax::Yaml => ':',
+        Syntax::Toml => '=',
+    };
+    for line in block.lines() {
+        if syntax == Syntax::Toml && line.trim_start().starts_with('[') {
+            return None;
+        }
+        if key_of(line, syntax) == Some(key) {
+            let (_, value) = line.split_once(separator)?;
+            return Some(value.trim().trim_matches(['"', '\'']).to_string());
+        }
+    }
+    None
+}
+
+/// `text` with `words` and `reading_time` set in its front matter.
+fn annotate(text: &str, time_format: TimeFormat) -> String {
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let Some((syntax, block, body)) = split(text) else {
+        let words = count_text(text).words;
+        let values = values(words, time_format);
+        let mut out = format!("---{}", newline);
+        for (key, value) in values {
+            out.push_str(&format!("{}: {}{}", key, value, newline));
+        }
+        out.push_str(&format!("---{}", newline));
+        out.push_str(text);
+        return out;
+    };
+
+    let values = values(count_text(body).words, time_format);
+    let mut set = [false; 2];
+    let mut out = String::with_capacity(text.len() + 64);
+    let open = &text[..text.find('\n').map_or(0, |n| n + 1)];
+    out.push_str(open);
+    // Missing keys go at the end of the top level: in TOML, before the first
+    // table header, since keys after it belong to that table.
+    let add_missing = |out: &mut String, set: &mut [bool; 2]| {
+        for (index, (key, value)) in values.iter().enumerate() {
+            if !set[index] {
+                set[index] = true;
+                out.push_str(&entry(key, value, syntax));
+                out.push_str(newline);
+            }
+        }
+    };
+    let mut top_level = true;
+    for line in block.split_inclusive('\n') {
+        if syntax == Syntax::Toml && top_level && line.trim_start().starts_with('[') {
+            add_missing(&mut out, &mut set);
+            top_level = false;
+        }
+        let key = if top_level { key_of(line, syntax) } else { None };
+        match values.iter().position(|(name, _)| key == Some(*name)) {
+            Some(index) if !set[index] => {
+                set[index] = true;
+                let (key, value) = &values[index];
+                let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+                out.push_str(&entry(key, value, syntax));
+                out.push_str(ending);
+            }
+            _ => out.push_str(line),
+        }
+    }
+    add_missing(&mut out, &mut set);
+    let closing = &text[open.len() + block.len()..text.len() - body.len()];
+    out.push_str(closing);
+    out.push_str(body);
+    out
+}
+
+/// The two keys and their values, as written in either syntax.
+fn values(words: usize, time_format: TimeFormat) -> [(&'static str, String); 2] {
+    let minutes = readtime::minutes(words, READING_WPM, Rounding::Up);
+    let time = readtime::format(minutes, time_format);
+    // Only a bare number of minutes can go unquoted: `0:04:00` is a number in
+    // YAML 1.1, and TOML needs strings quoted.
+    let time = if time_format == TimeFormat::Minutes {
+        time
+    } else {
+        format!("\"{}\"", time)
+    };
+    [("words", words.to_string()), ("reading_time", time)]
+}
+
+/// The top-level key set on `line`, if any.
+fn key_of(line: &str, syntax: Syntax) -> Option<&str> {
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let separator = match syntax {
+        Syntax::Yaml => ':',
+        Syntax::Toml => '=',
+    };
+    let (key, _) = line.split_once(separator)?;
+    Some(key.trim().trim_matches(['"', '\'']))
+}
+
+fn entry(key: &str, value: &str, syntax: Syntax) -> String {
+    match syntax {
+        Syntax::Yaml => format!("{}: {}", key, value),
+        Syntax::Toml => format!("{} = {}", key, value),
+    }
+}
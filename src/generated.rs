@@ -0,0 +1,137 @@
+//! `--no-generated` and `--minified`: heuristics for generated, vendored
+//! and minified files.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Component, Path};
+
+/// Lockfiles written by package managers rather than people.
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "Gemfile.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "composer.lock",
+    "go.sum",
+    "flake.lock",
+];
+
+/// Directories holding third-party code checked into the tree.
+const VENDOR_DIRS: &[&str] = &["vendor", "node_modules", "third_party"];
+
+/// Header markers that code generators leave near the top of a file.
+const MARKERS: &[&str] = &[
+    "@generated",
+    "DO NOT EDIT",
+    "Code generated by",
+    "autogenerated",
+    "auto-generated",
+    "This file is automatically generated",
+];
+
+/// How much of the file is inspected for markers and minification.
+const HEAD_BYTES: u64 = 8192;
+
+/// Average line length at which the line-length half of the minified score
+/// saturates.
+const MINIFIED_LINE_LENGTH: f64 = 300.0;
+
+/// Whitespace ratio at or above which text gets no minified score from
+/// whitespace; hand-written code usually sits well above it.
+const MINIFIED_WHITESPACE_RATIO: f64 = 0.15;
+
+/// Scores at or above this count as minified.
+pub const MINIFIED_THRESHOLD: f64 = 0.5;
+
+/// Returns the reason `path` looks generated or vendored, if it does.
+pub fn reason(path: &Path) -> io::Result<Option<&'static str>> {
+    let mut head = Vec::new();
+    File::open(path)?.take(HEAD_BYTES).read_to_end(&mut head)?;
+    Ok(reason_for(path, &head))
+}
+
+/// Like `reason`, for a file whose content is already at hand (`data` may be
+/// the whole file or just its start).
+pub fn reason_for(path: &Path, data: &[u8]) -> Option<&'static str> {
+    if path
+        .components()
+        .any(|c| matches!(c, Component::Normal(name) if VENDOR_DIRS.iter().any(|d| name == *d)))
+    {
+        return Some("vendored");
+    }
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if LOCKFILES.contains(&name) {
+        return Some("lockfile");
+    }
+    if name.ends_with(".min.js") || name.ends_with(".min.css") {
+        return Some("minified");
+    }
+
+    let head = String::from_utf8_lossy(&data[..data.len().min(HEAD_BYTES as usize)]);
+    // Markers only count in the leading comment block, not in code that
+    // happens to mention them further down.
+    if head
+        .lines()
+        .take(10)
+        .any(|line| MARKERS.iter().any(|marker| line.contains(marker)))
+    {
+        return Some("generated");
+    }
+    if is_minified(name, &head) {
+        return Some("minified");
+    }
+    None
+}
+
+fn is_minified(name: &str, head: &str) -> bool {
+    let extension = name.rsplit_once('.').map_or("", |(_, ext)| ext);
+    matches!(extension, "js" | "mjs" | "cjs" | "css") && minified_score(head) >= MINIFIED_THRESHOLD
+}
+
+/// Scores how minified `text` looks, from 0.0 (ordinary source) to 1.0.
+pub fn minified_score(text: &str) -> f64 {
+    let mut scan = MinifiedScan::default();
+    text.lines().for_each(|line| scan.push(line));
+    scan.score()
+}
+
+/// Accumulates the minified score line by line.
+///
+/// Half of the score comes from the average length of non-blank lines and
+/// half from how little whitespace the text contains.
+#[derive(Default)]
+pub struct MinifiedScan {
+    lines: usize,
+    chars: usize,
+    whitespace: usize,
+}
+
+impl MinifiedScan {
+    pub fn push(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        self.lines += 1;
+        for c in line.chars() {
+            self.chars += 1;
+            if c.is_whitespace() {
+                self.whitespace += 1;
+            }
+        }
+    }
+
+    pub fn score(&self) -> f64 {
+        if self.chars == 0 {
+            return 0.0;
+        }
+        let average = self.chars as f64 / self.lines as f64;
+        let length = (average / MINIFIED_LINE_LENGTH).min(1.0);
+        let ratio = self.whitespace as f64 / self.chars as f64;
+        let sparse = ((MINIFIED_WHITESPACE_RATIO - ratio) / MINIFIED_WHITESPACE_RATIO).max(0.0);
+        (length + sparse) / 2.0
+    }
+}
@@ -0,0 +1,91 @@
+//! Running `git` for the repository-aware commands.
+
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Runs `git ARGS` in `dir` and returns its standard output. A non-zero exit
+/// becomes an error carrying git's own message.
+pub fn output<I, S>(dir: &Path, args: I) -> io::Result<Vec<u8>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => io::Error::new(e.kind(), "git is not installed"),
+            _ => e,
+        })?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(io::Error::other(if message.is_empty() {
+            format!("git exited with {}", output.status)
+        } else {
+            message
+        }));
+    }
+    Ok(output.stdout)
+}
+
+/// Splits NUL-separated output such as `git ls-files -z`.
+pub fn split_nul(output: &[u8]) -> Vec<String> {
+    output
+        .split(|&b| b == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| String::from_utf8_lossy(entry).into_owned())
+        .collect()
+}
+
+/// Paths (relative to the repository root) of files added, copied, modified
+/// or renamed in the index, i.e. what the next commit will contain.
+pub fn staged_files(dir: &Path) -> io::Result<Vec<String>> {
+    let output = output(
+        dir,
+        ["diff", "--cached", "--name-only", "-z", "--diff-filter=ACMR"],
+    )?;
+    Ok(split_nul(&output))
+}
+
+/// The staged content of `path`, read from the index rather than the
+/// working tree.
+pub fn staged_content(dir: &Path, path: &str) -> io::Result<Vec<u8>> {
+    output(dir, ["cat-file", "blob", format!(":{}", path).as_str()])
+}
+
+/// The top-level directory of the working tree containing `dir`.
+pub fn toplevel(dir: &Path) -> io::Result<std::path::PathBuf> {
+    let output = output(dir, ["rev-parse", "--show-toplevel"])?;
+    Ok(String::from_utf8_lossy(&output).trim_end().into())
+}
+
+/// Like `staged_files`, but with paths relative to the current directory
+/// (`../` where needed), the way `git status` shows them.
+pub fn staged_files_here() -> io::Result<Vec<String>> {
+    let here = Path::new(".");
+    let prefix = String::from_utf8_lossy(&output(here, ["rev-parse", "--show-prefix"])?)
+        .trim_end()
+        .to_string();
+    let up = "../".repeat(prefix.split('/').filter(|c| !c.is_empty()).count());
+    Ok(staged_files(here)?
+        .into_iter()
+        .map(|path| match path.strip_prefix(&prefix) {
+            Some(rest) if !prefix.is_empty() => rest.to_string(),
+            _ => format!("{}{}", up, path),
+        })
+        .collect())
+}
+
+/// Like `staged_content` for a path relative to the current directory.
+pub fn staged_content_here(path: &str) -> io::Result<Vec<u8>> {
+    let path = path.strip_prefix("./").unwrap_or(path);
+    let relative = if path.starts_with("../") {
+        path.to_string()
+    } else {
+        format!("./{}", path)
+    };
+    staged_content(Path::new("."), &relative)
+}
@@ -0,0 +1,126 @@
+//! Shell-style glob patterns, as used by `.gitignore` and `--exclude`.
+//!
+//! `*` matches any run of characters except `/`, `?` one character except
+//! `/`, `[abc]`/`[a-z]`/`[!x]` one character from a class, and `**` any run
+//! including `/`: `**/x` matches `x` in any directory and `a/**/b` matches
+//! `a/b`, `a/x/b` and so on. `\` escapes the next character.
+
+#[derive(Debug, Clone)]
+enum Token {
+    Char(char),
+    /// `?`
+    One,
+    /// `*`
+    Star,
+    /// `**`, crossing `/`.
+    Any,
+    /// `**/`: zero or more whole directories.
+    Dirs,
+    Class { negated: bool, ranges: Vec<(char, char)> },
+}
+
+#[derive(Debug, Clone)]
+pub struct Glob {
+    tokens: Vec<Token>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Glob {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '\\' if i + 1 < chars.len() => {
+                    tokens.push(Token::Char(chars[i + 1]));
+                    i += 1;
+                }
+                '?' => tokens.push(Token::One),
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    let at_start = i == 0 || chars[i - 1] == '/';
+                    if at_start && chars.get(i + 2) == Some(&'/') {
+                        tokens.push(Token::Dirs);
+                        i += 2;
+                    } else {
+                        tokens.push(Token::Any);
+                        i += 1;
+                    }
+                }
+                '*' => tokens.push(Token::Star),
+                '[' => match parse_class(&chars[i + 1..]) {
+                    Some((token, used)) => {
+                        tokens.push(token);
+                        i += used;
+                    }
+                    // An unclosed `[` is literal.
+                    None => tokens.push(Token::Char('[')),
+                },
+                c => tokens.push(Token::Char(c)),
+            }
+            i += 1;
+        }
+        Glob { tokens }
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        match_tokens(&self.tokens, &text)
+    }
+}
+
+/// Parses a class after its `[`, returning it and the characters used
+/// (including the closing `]`).
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let mut i = 0;
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    // A `]` right after the opening bracket is a member, not the end.
+    let mut first = true;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == ']' && !first {
+            return Some((Token::Class { negated, ranges }, i + 1));
+        }
+        first = false;
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&end| end != ']') {
+            ranges.push((c, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((c, c));
+            i += 1;
+        }
+    }
+    None
+}
+
+fn match_tokens(tokens: &[Token], text: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return text.is_empty();
+    };
+    match token {
+        Token::Char(c) => text.first() == Some(c) && match_tokens(rest, &text[1..]),
+        Token::One => text.first().is_some_and(|&c| c != '/') && match_tokens(rest, &text[1..]),
+        Token::Class { negated, ranges } => text.first().is_some_and(|&c| {
+            c != '/'
+                && ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+                && match_tokens(rest, &text[1..])
+        }),
+        Token::Star => {
+            let run = text.iter().take_while(|&&c| c != '/').count();
+            (0..=run).any(|n| match_tokens(rest, &text[n..]))
+        }
+        Token::Any => (0..=text.len()).any(|n| match_tokens(rest, &text[n..])),
+        Token::Dirs => {
+            // Try after each `/`, including not skipping anything.
+            match_tokens(rest, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &c)| c == '/')
+                    .any(|(n, _)| match_tokens(rest, &text[n + 1..]))
+        }
+    }
+}
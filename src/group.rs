@@ -0,0 +1,107 @@
+//! `--group-by`: counts summed per group of files instead of per file.
+//!
+//! Both groupings are for localization work. By language, files are grouped
+//! by the language `--detect-language` finds in their text. By locale, the
+//! locale of a file is read from its path, the segment nearest the file winning, so
+//! `locales/de/app.json`, `po/pt_BR.po`, `Base.lproj/../fr.lproj/x.strings`
+//! and Android's `res/values-es/strings.xml` are all recognised. A segment
+//! counts when it starts with an ISO 639-1 language code, optionally
+//! followed by a script or region (`zh-Hans`, `en_GB`, `es-419`).
+
+use std::path::Path;
+
+use crate::Counts;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GroupBy {
+    Locale,
+    Language,
+}
+
+/// The group of files whose locale could not be told.
+pub const NO_LOCALE: &str = "(none)";
+
+impl GroupBy {
+    pub fn parse(value: &str) -> Result<GroupBy, String> {
+        match value {
+            "locale" => Ok(GroupBy::Locale),
+            "language" => Ok(GroupBy::Language),
+            other => Err(format!(
+                "invalid --group-by '{}' (expected locale or language)",
+                other
+            )),
+        }
+    }
+
+    /// The name of the group `path`, counted as `counts`, falls in.
+    pub fn key(self, path: &str, counts: &Counts) -> String {
+        match self {
+            GroupBy::Locale => locale(Path::new(path)).unwrap_or_else(|| NO_LOCALE.to_string()),
+            GroupBy::Language => counts.language.unwrap_or("und").to_string(),
+        }
+    }
+}
+
+/// ISO 639-1 language codes.
+const LANGUAGES: &[&str] = &[
+    "aa", "ab", "ae", "af", "ak", "am", "an", "ar", "as", "av", "ay", "az", "ba", "be", "bg", "bh",
+    "bi", "bm", "bn", "bo", "br", "bs", "ca", "ce", "ch", "co", "cr", "cs", "cu", "cv", "cy", "da",
+    "de", "dv", "dz", "ee", "el", "en", "eo", "es", "et", "eu", "fa", "ff", "fi", "fj", "fo", "fr",
+    "fy", "ga", "gd", "gl", "gn", "gu", "gv", "ha", "he", "hi", "ho", "hr", "ht", "hu", "hy", "hz",
+    "ia", "id", "ie", "ig", "ii", "ik", "io", "is", "it", "iu", "ja", "jv", "ka", "kg", "ki", "kj",
+    "kk", "kl", "km", "kn", "ko", "kr", "ks", "ku", "kv", "kw", "ky", "la", "lb", "lg", "li", "ln",
+    "lo", "lt", "lu", "lv", "mg", "mh", "mi", "mk", "ml", "mn", "mr", "ms", "mt", "my", "na", "nb",
+    "nd", "ne", "ng", "nl", "nn", "no", "nr", "nv", "ny", "oc", "oj", "om", "or", "os", "pa", "pi",
+    "pl", "ps", "pt", "qu", "rm", "rn", "ro", "ru", "rw", "sa", "sc", "sd", "se", "sg", "si", "sk",
+    "sl", "sm", "sn", "so", "sq", "sr", "ss", "st", "su", "sv", "sw", "ta", "te", "tg", "th", "ti",
+    "tk", "tl", "tn", "to", "tr", "ts", "tt", "tw", "ty", "ug", "uk", "ur", "uz", "ve", "vi", "vo",
+    "wa", "wo", "xh", "yi", "yo", "za", "zh", "zu",
+];
+
+/// The locale named by `path`, normalised to `ll` or `ll-Xxxx-RR` form.
+pub fn locale(path: &Path) -> Option<String> {
+    let mut segments: Vec<&str> = path
+        .parent()
+        .into_iter()
+        .flat_map(|parent| parent.iter())
+        .filter_map(|segment| segment.to_str())
+        .collect();
+    // The file's own name counts without its extensions: `de.po`,
+    // `messages.fr.xlf`.
+    if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+        segments.extend(name.split('.').rev().skip(1));
+    }
+    segments.iter().rev().find_map(|segment| parse_locale(segment))
+}
+
+fn parse_locale(segment: &str) -> Option<String> {
+    let segment = segment.strip_suffix(".lproj").unwrap_or(segment);
+    let segment = segment.strip_prefix("values-").unwrap_or(segment);
+    let mut parts = segment.split(['-', '_']);
+    let language = parts.next()?;
+    if !LANGUAGES.contains(&language) {
+        return None;
+    }
+    let mut locale = language.to_string();
+    for part in parts {
+        // Android writes regions as `rGB`.
+        let part = match part.strip_prefix('r') {
+            Some(region) if region.len() == 2 && region.bytes().all(|b| b.is_ascii_uppercase()) => {
+                region
+            }
+            _ => part,
+        };
+        let tag = match part.len() {
+            2 if part.bytes().all(|b| b.is_ascii_alphabetic()) => part.to_ascii_uppercase(),
+            3 if part.bytes().all(|b| b.is_ascii_digit()) => part.to_string(),
+            4 if part.bytes().all(|b| b.is_ascii_alphabetic()) => {
+                let (first, rest) = part.split_at(1);
+                first.to_ascii_uppercase() + &rest.to_ascii_lowercase()
+            }
+            _ => return None,
+        };
+        locale.push('-');
+        locale.push_str(&tag);
+    }
+    Some(locale)
+}
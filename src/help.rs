@@ -0,0 +1,524 @@
+//! `--help`, `rwc help TOPIC` and paging of long help on terminals.
+
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::{cli, i18n, status};
+
+const TOPICS: &[(&str, &str)] = &[
+    ("formats", "Output formats: tables, JSON, human-readable numbers"),
+    ("recursion", "How directories are walked"),
+    ("unicode", "Characters, words and file names beyond ASCII"),
+    ("thresholds", "Rate alerts and weighted scores"),
+];
+
+const FORMATS: &str = "\
+OUTPUT FORMATS
+
+The default output is a table with one row per input: the selected counts in
+the order lines, words, chars, bytes, longest line (-L), paragraphs,
+blank lines, followed by any distinct counts and the --weights score, then
+the file name. With more than
+one input a totals row is added; --total-label renames it.
+
+--json prints a single JSON document: {\"files\": [...], \"total\": {...}},
+with one object per input in \"files\" and the totals in \"total\". Keys are
+\"file\", \"lines\", \"words\", \"chars\", \"bytes\", \"max_line_length\",
+\"max_display_width\", \"paragraphs\", \"blank_lines\", plus
+\"unique_lines\", \"unique_words\" (and \"*_error\" with --approx) and \"score\"
+when requested. Fields always appear in this order. The document is indented
+by default (--json-pretty); --json-compact writes it on a single line, so
+reports can be diffed byte for byte between runs. Saved JSON reports can be
+compared with 'rwc report-diff'. For reports kept as build artifacts,
+--metadata adds a \"metadata\" member first, recording the rwc version, a UTC
+timestamp, the host name, the working directory, the command line and the
+effective options. Other modes (--sample, --journal, ...) print one JSON
+object per record, and --metadata precedes them with a record of its own.
+
+-h/--human abbreviates large numbers (1.2K, 3.4M, 5.6G). --precision sets the
+number of decimals and --rounding the rounding mode; --human=both shows the
+exact figure too: 1,234,567 (1.2M). Totals are always summed from the exact
+counts, never from rounded figures.
+
+--status-line prints a single templated line for shell prompts, and
+--sidecar writes FILE.rwc.json next to each input for build systems.
+";
+
+const RECURSION: &str = "\
+RECURSION
+
+Plain 'rwc FILE...' counts exactly the files it is given; with -r, each
+directory operand is replaced by the regular files under it, each listed on
+its own row and included in the total. Commands that take a PATH walk
+directories themselves: 'rwc corpus', 'rwc daemon', 'rwc todos -r',
+'--status-line', '--license-header', '--validate-utf8' and
+'--lint-line-length'.
+
+When a directory is walked, hidden entries (names starting with '.') are
+skipped, symbolic links are not followed, and files are visited in sorted
+order so results are reproducible. With -r and the checking modes above,
+--hidden includes hidden entries and --follow-symlinks follows links to files
+and directories; a directory reached twice through links is walked once.
+
+These walks honour .gitignore and .ignore files found in the walked tree (not
+in directories above it) and never enter .git; --no-ignore turns this off.
+--exclude GLOB skips matching files and directories and --include GLOB keeps
+only matching files; both may be repeated. A GLOB without '/' is matched
+against the file name, one with '/' against the path below the operand.
+'*' and '?' stay within a path segment, '**' crosses them, and [a-z] is a
+character class: --include '*.rs' --exclude 'tests/**'. Files that are not valid UTF-8 are skipped
+by 'rwc corpus', 'rwc daemon' and '--status-line'.
+
+--max-files N and --max-total-bytes SIZE cap how much a scan may read. Once a
+limit is reached the walk stops, the files admitted so far are reported, and a
+warning saying the results are partial is printed to standard error. They
+apply to file operands, '--status-line' and 'rwc corpus'.
+
+--max-memory SIZE bounds what rwc holds in memory rather than what it reads.
+Exact --unique-lines/--unique-words sets that would grow past it turn into
+HyperLogLog estimates (with a warning, and the error shown as for --approx),
+and an input that --pre, --auto or --preview must hold whole is reported as
+an error if it does not fit. Plain counts are streamed and need no budget.
+
+Ctrl-C while files are counted stops the walk and the count: the file being
+read is left out, the files counted so far are reported with a total marked
+'(partial)' (\"partial\": true in JSON), and rwc exits with status 130. A
+second Ctrl-C stops rwc at once.
+";
+
+const UNICODE: &str = "\
+UNICODE
+
+-c counts bytes and -m counts Unicode scalar values (code points), so the two
+differ for any non-ASCII text. Words are runs of non-whitespace separated by
+Unicode whitespace, which includes no-break and ideographic spaces;
+--whitespace ascii or --whitespace custom:CHARS narrows the separators.
+--unicode-words segments words instead, as UAX #29 does, so text written
+without spaces gets a meaningful count: every Han ideograph or hiragana
+character is a word, a run of katakana is one word, and punctuation
+separates words unless it joins letters (\"can't\", \"3.14\"). Thai, Lao
+and Khmer need a dictionary to split and count one word per run.
+--tokenizer NAME picks the word definition by name: whitespace (the
+default), uax29 (as --unicode-words), cjk (every Chinese, Japanese or
+Korean character is a word, other text splits on whitespace and CJK
+punctuation) or regex:PATTERN (every match is a word). Programs using the
+rwc library can implement its Tokenizer trait for their own.
+--ignore-format-chars drops soft hyphens, zero-width joiners and variation
+selectors before counting, so -m matches what word processors report.
+
+Input must be valid UTF-8; otherwise the file is reported as an error.
+With --lossy each invalid sequence counts as one U+FFFD character instead,
+so a stray byte in a large log does not stop the run; -c is unaffected.
+--encoding utf16le, utf16be or latin1 decodes other text first; characters,
+words and lines are counted on the decoded text while -c still counts the
+bytes as stored. The default, auto, reads UTF-16 when the input starts with
+a byte order mark or looks like UTF-16 ASCII, and UTF-8 otherwise.
+
+File names containing right-to-left text are wrapped in Unicode isolates in
+table output so they cannot reorder the count columns, and bidi control
+characters (such as U+202E) are shown escaped as \\u{202e}.
+";
+
+const THRESHOLDS: &str = "\
+THRESHOLDS
+
+In streaming mode (--window DUR or --follow), --alert-lines-per-sec N raises
+an alert whenever the line rate over a window or update exceeds N. By default the alert is printed to
+stderr; --alert-cmd CMD runs CMD through the shell instead, with
+RWC_ALERT_RATE, RWC_ALERT_THRESHOLD and RWC_ALERT_LINES in its environment.
+
+--weights FILE adds a score column: each line of FILE is PATTERN=WEIGHT, and
+every regex match in the input adds WEIGHT to the file's score. Scores are
+summed into the totals row and included in --json output.
+
+--warn-above METRIC=N and --error-above METRIC=N set per-file budgets for
+lines, words, chars or bytes, and can be repeated. A status column (JSON:
+status) reports ok, warn or error for each file, and on a terminal the cells
+over a limit are colored yellow or red (NO_COLOR turns this off). Budgets
+only report: the exit status is unchanged.
+";
+
+/// The main `--help` text.
+fn main_help() -> String {
+    let mut text = String::new();
+    let mut line = |s: &str| {
+        text.push_str(i18n::tr(s));
+        text.push('\n');
+    };
+    line("rwc - A modern word counter");
+    line("");
+    line("USAGE:");
+    line("    rwc [OPTIONS] [FILES...]");
+    line("    rwc image [--json] IMAGE[:TAG]");
+    line("    rwc sql --dsn DSN --query SQL   (requires the 'sql' feature)");
+    line("    rwc xlsx [--json] FILE.xlsx...  (cells and words per sheet)");
+    line("    rwc sqlite [--json] FILE.db     (requires the 'sql' feature)");
+    line("    rwc columnar [--json] FILE...  (Parquet/Arrow; requires the 'arrow' feature)");
+    line("    rwc stats --overlap FILE_A FILE_B");
+    line("    rwc stats --length-distribution [--format csv] [FILE...]");
+    line("    rwc stats --readability [--format csv] [FILE...]");
+    line("    rwc freq [--top N] [--max-memory SIZE] [FILES...]");
+    line("    rwc daemon [--socket PATH] PATH");
+    line("    rwc query [--socket PATH] [PATH]");
+    line("    rwc corpus [--zipf FILE] [--json] PATH...");
+    line("    rwc toc [--depth N] [--json] FILE.md");
+    line("    rwc annotate-frontmatter [--check] FILE.md...");
+    line("    rwc batch [--job NAME]... MANIFEST.toml");
+    line("    rwc todos [-r] [--json] PATH...");
+    line("    rwc blame-stats [--json] [PATH...]  (requires git)");
+    line("    rwc commit-msg [--subject-max N] [--body-max N] FILE");
+    line("    rwc hook install [--force] CHECKS...   (git pre-commit hook)");
+    line("    rwc report-diff [--json] OLD.json NEW.json");
+    line("    rwc compare-dirs [--json] SOURCE_DIR TARGET_DIR  (translation coverage)");
+    line("    rwc lsp                         (language server over stdio)");
+    line("    rwc help [TOPIC | COMMAND]");
+    line("");
+    line("COUNTS:");
+    line("    -l, --lines     Show line count");
+    line("    -w, --words     Show word count");
+    line("    -c, --bytes     Show byte count");
+    line("    -m, --chars     Show character count");
+    line("    -L, --max-line-length");
+    line("                    Show the display width of the longest line (tabs to 8,");
+    line("                    wide characters 2); totals show the maximum, not the sum");
+    line("    --max-display-width");
+    line("                    Show the width of the longest line in terminal columns,");
+    line("                    as -L but with tabs to --tab-size");
+    line("    --paragraphs    Show the number of paragraphs (blocks of lines separated by");
+    line("                    blank lines)");
+    line("    --blank-lines   Show the number of blank (whitespace-only) lines");
+    line("    --show-all      Show every metric: lines, words, chars, bytes, longest line,");
+    line("                    paragraphs, blank lines and sentences; totals always cover");
+    line("                    all of them");
+    line("    --unique-lines  Show the number of distinct lines");
+    line("    --unique-words  Show the number of distinct words");
+    line("    --approx        Estimate distinct counts in constant memory (HyperLogLog)");
+    line("");
+    line("FILTERS:");
+    line("    --pre STEPS     Transform input before counting, in order (comma-separated):");
+    line("                    strip-ansi, decompress, html-text, exclude-quotes, academic");
+    line("    --auto          Detect each input's type (compressed, HTML, notebook, PDF,");
+    line("                    tar, zip, xlsx) and count its extracted text; runs before");
+    line("                    --pre steps");
+    line("    --type TYPE     Like --auto, but treat inputs as TYPE: text, html, notebook,");
+    line("                    pdf, tar, zip, xlsx, gzip, bzip2, xz, zstd");
+    line("    --no-decompress Count gzip, bzip2, xz and zstd input as it is; by default it");
+    line("                    is decompressed (bzip2, xz and zstd with the tool on PATH) and");
+    line("                    the text counted");
+    line("    --structure     With --auto/--type, also report PDF pages and paragraphs, or");
+    line("                    HTML text nodes and element depth");
+    line("    --exclude-quotes");
+    line("                    Skip \"quoted text\" and lines starting with '>'");
+    line("                    (same as --pre exclude-quotes)");
+    line("    --academic      Skip citations, footnotes and reference sections");
+    line("                    (same as --pre academic)");
+    line("    --no-generated  Skip generated and vendored files: '@generated' and 'DO NOT");
+    line("                    EDIT' headers, minified JS/CSS, lockfiles, vendor/ directories");
+    line("    --minified      Add a minified score column (0-1; JSON adds 'minified')");
+    line("    --skip-minified Leave out inputs that look minified (implies --minified)");
+    line("    --only-minified Only report inputs that look minified (implies --minified)");
+    line("    --detect-language");
+    line("                    Add a column with each input's language as an ISO 639-1 code,");
+    line("                    or 'und' if it cannot be told ('language' feature)");
+    line("    --wrap-at N, --wrap-width N");
+    line("                    Add the number of lines the text takes soft-wrapped at N");
+    line("                    columns (wide characters count 2, tabs stop every 8 or");
+    line("                    --tab-size; JSON: visual_lines)");
+    line("    --tab-size N    Columns between tab stops for --max-display-width and");
+    line("                    --wrap-at (default 8); -L keeps 8, as in wc");
+    line("    --archive       Count every file inside tar and zip archives (which may be");
+    line("                    compressed), with a total row per archive");
+    line("    --group-by locale|language");
+    line("                    Sum counts per locale read from each path (locales/de/,");
+    line("                    pt_BR.po, fr.lproj, values-es) or per detected language");
+    line("                    (implies --detect-language) instead of listing files");
+    line("    --po, --xliff   Read gettext PO or XLIFF files: entries, translated, fuzzy and");
+    line("                    untranslated entries, and source and target words");
+    line("    --outline       Show the words under each Markdown heading (with its");
+    line("                    subsections) as a tree; JSON nests sections in their parents");
+    line("    --split-on REGEX");
+    line("                    Count each section of the input apart: a line matching REGEX");
+    line("                    (e.g. '^CHAPTER \\d+') starts one and names it; then the total");
+    line("    --json-logs     Read NDJSON/GELF logs: count records and the text of one field,");
+    line("                    and report how many records have each top-level field");
+    line("    --field NAME    With --json-logs, the field to count (default: message;");
+    line("                    use dots for nested fields, e.g. log.message)");
+    line("    --log-levels    Add lines per severity: error, warn, info and debug columns");
+    line("                    (fatal and critical count as error, trace as debug)");
+    line("    --record-start REGEX");
+    line("                    Add a records column: a line matching REGEX starts a record,");
+    line("                    so a stack trace counts with the log entry above it");
+    line("    --whitespace W  Characters that separate words: unicode (default), ascii,");
+    line("                    or custom:CHARS (escapes \\t \\n \\r \\s \\\\)");
+    line("    --unicode-words Segment words as Unicode UAX #29 does: each Chinese or Japanese");
+    line("                    ideograph is a word, and \"can't\" or \"3.14\" is one word");
+    line("    --tokenizer NAME");
+    line("                    Words as whitespace, uax29, cjk or regex:PATTERN defines them");
+    line("    --encoding ENC  Decode input as auto (default), utf8, utf16le, utf16be or");
+    line("                    latin1 before counting characters, words and lines");
+    line("    --lossy         Count invalid UTF-8 as U+FFFD replacement characters rather");
+    line("                    than reporting the file as an error");
+    line("    --ignore-format-chars");
+    line("                    Leave soft hyphens, ZWJ/ZWNJ and variation selectors out of");
+    line("                    character counts");
+    line("    --indent-stats  Add max and average indentation depth and the share of");
+    line("                    tab-indented lines (JSON: indent_max, indent_avg, indent_tab_ratio)");
+    line("    --license-header PATTERN");
+    line("                    List files (directories are walked) with no line matching");
+    line("                    PATTERN in their first 10 lines");
+    line("    --header-lines N");
+    line("                    With --license-header, search the first N lines");
+    line("    --require-header");
+    line("                    With --license-header, exit 1 if any file is missing it");
+    line("    --validate-utf8 Instead of counting, report whether each file (directories");
+    line("                    are walked) is valid UTF-8 and the offset of the first bad");
+    line("                    byte; exits 1 if any file is invalid, 2 on read errors");
+    line("    --lint-line-length N");
+    line("                    Instead of counting, list lines wider than N columns as");
+    line("                    FILE:LINE:LENGTH (tabs expand to 8); exits 1 if any");
+    line("    --warn-above METRIC=N, --error-above METRIC=N");
+    line("                    Add a status column: warn or error for files whose lines,");
+    line("                    words, chars or bytes exceed N (cells colored on a terminal)");
+    line("    --weights FILE  Add a score column from weighted regex matches (PATTERN=WEIGHT)");
+    line("");
+    line("OUTPUT:");
+    line("    --json          Output in JSON format");
+    line("    --json-pretty   Output indented JSON (the default for --json)");
+    line("    --json-compact  Output JSON on a single line (implies --json)");
+    line("    --print-schema  Print the JSON Schema of --json reports, which start with");
+    line("                    \"schema\": 1 (the version, raised on breaking changes)");
+    line("    --metadata      Start JSON output with a record of the rwc version, time,");
+    line("                    host, directory, command line and effective options");
+    line("    --plain         Screen-reader friendly output: one \"label: value\" line per");
+    line("                    metric, a blank line between inputs, no padding or symbols");
+    line("    --top N         List only the N files with the highest counts; the total");
+    line("                    still covers every file");
+    line("    --by METRIC     With --top, rank by lines, words (default), chars or bytes");
+    line("    --summary       After the totals, add min, max, mean and median rows for each");
+    line("                    count across files (JSON: summary; the table rounds)");
+    line("    --format F      table (default), json, csv, ndjson (a JSON object per line),");
+    line("                    plain, or badge: the totals as shields.io endpoint JSON for");
+    line("                    a README badge");
+    line("    --output FILE   Write the report to FILE instead of stdout; a .json, .csv or");
+    line("                    .ndjson name picks that format unless another is given");
+    line("    --badge-label L Badge label (default: the metric's name)");
+    line("    --badge-metric M");
+    line("                    Count shown on the badge: words (default), lines, chars, bytes");
+    line("    --badge-colors COUNT:COLOR,...");
+    line("                    Badge color by threshold, e.g. 1000:yellow,10000:green");
+    line("                    (default blue)");
+    line("    -h, --human     Human readable numbers (1.2K, 1.5M)");
+    line("    --human=both    Exact and human readable numbers: 1,234,567 (1.2M)");
+    line("    --precision N   Decimal places for --human (default 1)");
+    line("    --rounding MODE Rounding for --human: half-even (default), half-up, down, up");
+    line("    --stdin-label NAME, --label NAME");
+    line("                    Name standard input NAME in reports (default");
+    line("                    \"(standard input)\"); '-' reads it among files: rwc a - b");
+    line("    --total-label STR");
+    line("                    Label for the totals row (default \"total\")");
+    line("    --status-line   Print a compact one-line summary for prompts and status bars");
+    line("    --status-template TEMPLATE");
+    line("                    Template for --status-line using {lines}, {words}, {chars},");
+    line("                    {bytes}, {files} and {reading_time}");
+    line(&format!("                    (default \"{}\")", status::DEFAULT_TEMPLATE));
+    line("    --reading-time [WPM]");
+    line("                    Add an estimated reading time at WPM words a minute (default");
+    line("                    230; JSON: reading_minutes, reading_time)");
+    line("    --speaking-time [WPM]");
+    line("                    Add how long the text takes to say aloud at WPM words a minute");
+    line("                    (default 130; per section with --split-on)");
+    line("    --pages [WORDS|PRESET]");
+    line("                    Add an estimated page count at WORDS words a page, or a");
+    line("                    preset: manuscript (250, the default), novel (300) or");
+    line("                    academic (500, single-spaced)");
+    line("    --time-format F Write reading and speaking times as text (\"4 min\", the");
+    line("                    default), minutes (4), hms (0:04:00) or iso8601 (PT4M)");
+    line("    --time-rounding MODE");
+    line("                    Rounding of reading and speaking times to whole minutes: up");
+    line("                    (default), half-even, half-up or down");
+    line("    --preview N     Show the first N lines of each input's counted content");
+    line("    --preview-random");
+    line("                    With --preview, show N randomly sampled lines instead (--seed)");
+    line("    --sidecar       Write FILE.rwc.json with counts and SHA-256 next to each file");
+    line("    --sidecar-dir DIR");
+    line("                    Write sidecars under DIR (mirroring input paths) instead");
+    line("");
+    line("SOURCES:");
+    line("    --remote SPEC   Count a remote file over SSH ([user@]host:path)");
+    line("    --journal       Count systemd journal entries (Linux, 'journal' feature)");
+    line("    --unit NAME     With --journal, only count entries from unit NAME");
+    line("    --eventlog CHANNEL");
+    line("                    Count Windows Event Log events in CHANNEL (Windows only)");
+    line("    --since TIME    With --journal/--eventlog/--timestamp-format, start at TIME");
+    line("                    (e.g. 2024-05-01, -1h)");
+    line("    --until TIME    With --journal/--eventlog/--timestamp-format, stop at TIME");
+    line("    --timestamp-format FMT");
+    line("                    Apply --since/--until to log lines stamped as FMT: iso,");
+    line("                    syslog, epoch, or a pattern such as '%d/%b/%Y:%H:%M:%S'");
+    line("    --sorted        Lines are in time order: seek to --since, stop after --until");
+    line("    --files0-from=F Read NUL-separated file names from F (- for stdin), as");
+    line("                    written by 'find -print0'; no FILE operands are allowed");
+    line("    -r, --recursive Count the files under directory operands, each with the");
+    line("                    counting options set for it in .rwc.toml files (options =");
+    line("                    [\"--type\", \"html\"]) and Markdown front matter (rwc: ...)");
+    line("    --hidden        With -r, include hidden files and directories");
+    line("    --follow-symlinks");
+    line("                    With -r, follow symbolic links");
+    line("    --no-ignore     With -r, do not honour .gitignore and .ignore files");
+    line("    --include GLOB  With -r, only count files matching GLOB (repeatable)");
+    line("    --exclude GLOB  With -r, skip files and directories matching GLOB");
+    line("    --list-only     Print the files that would be counted, after -r, --include,");
+    line("                    --exclude, ignore files, --no-generated and limits, and stop");
+    line("    -j, --jobs N    Count N files at once (0: one per CPU); output keeps the");
+    line("                    operand order and totals are the same as with one job");
+    line("    --file-threads N");
+    line("                    Count each large file (8 MiB or more) in ranges on N threads");
+    line("                    at once (0: one per CPU), with the same counts as one pass;");
+    line("                    not with --pre, --auto, --unique-*, --indent-stats or");
+    line("                    other options that need whole files in order");
+    line("    --unordered     With --jobs, list each file as soon as it is counted;");
+    line("                    totals, groups and --top rankings are unchanged");
+    line("    --mmap          Count files over a memory map of them rather than reading");
+    line("                    them, on 64-bit Unix; a file truncated while it is counted");
+    line("                    stops rwc, so only for files that do not change");
+    line("    --no-mmap       Read every file (the default)");
+    line("    --max-files N   Stop after N files; results are reported as partial");
+    line("    --max-total-bytes SIZE");
+    line("                    Stop before reading more than SIZE bytes (e.g. 500M)");
+    line("    --max-memory SIZE");
+    line("                    Keep memory use under SIZE: distinct counts become estimates");
+    line("                    and inputs too large to hold whole are errors");
+    line("    --watch         Keep running and reprint the counts whenever a FILE changes");
+    line("    --follow        Like tail -f: keep FILEs open and print running counts as");
+    line("                    data is appended, without rereading them");
+    line("    --clear         With --watch, clear the screen before each update");
+    line("    --last SIZE     Count only the lines that start in the last SIZE bytes of each");
+    line("                    file (e.g. 500M), seeking past the rest; stdin is read whole");
+    line("    --staged        Count files as staged in the git index instead of on disk;");
+    line("                    with no FILEs, count every staged file");
+    line("    --patch         Read a unified diff (stdin or FILEs) and count the lines");
+    line("                    and words it adds and removes, per file");
+    line("");
+    line("STREAMING:");
+    line("    --window DUR    Stream stdin, printing NDJSON counts every DUR (e.g. 60s)");
+    line("    --alert-lines-per-sec N");
+    line("                    In streaming mode, alert when the line rate exceeds N");
+    line("    --alert-cmd CMD Run CMD via the shell instead of printing the alert");
+    line("                    (RWC_ALERT_RATE, RWC_ALERT_LINES are set)");
+    line("");
+    line("SAMPLING:");
+    line("    --sample PCT    Estimate counts from a random sample (e.g. 1%)");
+    line("    --sample-lines N");
+    line("                    Estimate counts from N randomly sampled lines");
+    line("    --seed N        Seed for reproducible sampling (default 0)");
+    line("");
+    line("EDITORS:");
+    line("    --editor-mode   Read a document from stdin and also count text before --cursor");
+    line("    --cursor L:C    Cursor position for --editor-mode (1-based line and column)");
+    line("");
+    line("    --help          Show this help message");
+    line("");
+    line("If no files or remotes are specified, reads from stdin.");
+    line("If no count options are specified, shows lines, words, and bytes.");
+    line("");
+    line("HELP TOPICS:");
+    for (name, summary) in TOPICS {
+        line(&format!("    {:<16}{}", name, summary));
+    }
+    line("Run 'rwc help TOPIC' for details.");
+    line("");
+    line("Set RWC_LANG (e.g. de, fr, es) to localize messages, or RWC_CATALOG to a");
+    line(".po file with your own translations.");
+    text
+}
+
+/// Prints the main help, through a pager when stdout is a terminal.
+pub fn print_main() {
+    page(&main_help());
+}
+
+/// Entry point for `rwc help [TOPIC | COMMAND]`.
+pub fn run(args: &[String]) -> i32 {
+    let Some(topic) = args.first() else {
+        print_main();
+        return 0;
+    };
+    let help = vec!["--help".to_string()];
+    match topic.as_str() {
+        "formats" => page(FORMATS),
+        "recursion" => page(RECURSION),
+        "unicode" => page(UNICODE),
+        "thresholds" => page(THRESHOLDS),
+        "image" => return crate::image::run(&help),
+        "stats" => return crate::stats::run(&help),
+        "freq" => return crate::freq::run(&help),
+        "daemon" => return crate::daemon::run(&help),
+        "query" => return crate::daemon::query(&help),
+        "corpus" => return crate::corpus::run(&help),
+        "toc" => return crate::toc::run(&help),
+        "annotate-frontmatter" => return crate::frontmatter::run(&help),
+        "batch" => return crate::batch::run(&help),
+        "todos" => return crate::todos::run(&help),
+        "blame-stats" => return crate::blame::run(&help),
+        "commit-msg" => return crate::commit_msg::run(&help),
+        "hook" => return crate::hook::run(&help),
+        "report-diff" => return crate::report_diff::run(&help),
+        "compare-dirs" => return crate::compare_dirs::run(&help),
+        "xlsx" => return crate::xlsx::run(&help),
+        #[cfg(feature = "sql")]
+        "sql" => return crate::sql::run(&help),
+        #[cfg(feature = "sql")]
+        "sqlite" => return crate::sqlite::run(&help),
+        #[cfg(feature = "arrow")]
+        "columnar" => return crate::columnar::run(&help),
+        "lsp" => {
+            println!("Usage: rwc lsp");
+            println!();
+            println!("Runs a language server on stdin/stdout that publishes word, character");
+            println!("and reading-time statistics for open documents.");
+        }
+        other => {
+            eprintln!("rwc help: unknown topic '{}'", other);
+            let names: Vec<&str> = TOPICS.iter().map(|(name, _)| *name).collect();
+            if let Some(name) = cli::suggest(other, &names) {
+                eprintln!("  did you mean '{}'?", name);
+            }
+            eprintln!("Topics: {}", names.join(", "));
+            return 1;
+        }
+    }
+    0
+}
+
+/// Writes `text` to stdout, through `$RWC_PAGER`, `$PAGER` or `less` when
+/// stdout is a terminal. An empty pager variable disables paging.
+fn page(text: &str) {
+    if io::stdout().is_terminal() {
+        let pager = std::env::var("RWC_PAGER")
+            .or_else(|_| std::env::var("PAGER"))
+            .unwrap_or_else(|_| {
+                if cfg!(windows) {
+                    "more".to_string()
+                } else {
+                    // -F exits straight away when the text fits on one screen.
+                    "less -FRX".to_string()
+                }
+            });
+        let mut parts = pager.split_whitespace();
+        if let Some(program) = parts.next() {
+            let child = Command::new(program)
+                .args(parts)
+                .stdin(Stdio::piped())
+                .spawn();
+            if let Ok(mut child) = child {
+                if let Some(mut stdin) = child.stdin.take() {
+                    // The user quitting the pager early closes the pipe.
+                    let _ = stdin.write_all(text.as_bytes());
+                }
+                let _ = child.wait();
+                return;
+            }
+        }
+    }
+    print!("{}", text);
+}
@@ -0,0 +1,295 @@
+//! `rwc hook`: a git pre-commit hook that checks staged files.
+//!
+//! `rwc hook install CHECKS` writes a pre-commit hook that runs
+//! `rwc hook run CHECKS`. The checks read each staged file from the index, so
+//! a commit is judged by exactly what it will contain, not by unstaged edits
+//! in the working tree.
+
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+
+use crate::license::{self, HeaderCheck};
+use crate::regex::Regex;
+use crate::{cli, count_text, generated, git};
+
+const CHECK_OPTIONS: &[&str] = &[
+    "--max-lines",
+    "--max-words",
+    "--max-bytes",
+    "--license-header",
+    "--header-lines",
+    "--no-generated",
+];
+
+/// Marks hooks written by `rwc hook install`, so they may be replaced.
+const MARKER: &str = "# Installed by 'rwc hook install'.";
+
+#[derive(Default)]
+struct Checks {
+    max_lines: Option<usize>,
+    max_words: Option<usize>,
+    max_bytes: Option<usize>,
+    license: Option<HeaderCheck>,
+    no_generated: bool,
+}
+
+impl Checks {
+    fn is_empty(&self) -> bool {
+        self.max_lines.is_none()
+            && self.max_words.is_none()
+            && self.max_bytes.is_none()
+            && self.license.is_none()
+    }
+
+    /// Problems with one staged file.
+    fn check(&self, data: &[u8]) -> io::Result<Vec<String>> {
+        let counts = count_text(&String::from_utf8_lossy(data));
+        let mut problems = Vec::new();
+        for (limit, value, unit) in [
+            (self.max_lines, counts.lines, "lines"),
+            (self.max_words, counts.words, "words"),
+            (self.max_bytes, counts.bytes, "bytes"),
+        ] {
+            if let Some(limit) = limit.filter(|&limit| value > limit) {
+                problems.push(format!("{} {} (limit {})", value, unit, limit));
+            }
+        }
+        if let Some(license) = &self.license {
+            if !license.has_header(Cursor::new(data))? {
+                problems.push("missing license header".to_string());
+            }
+        }
+        Ok(problems)
+    }
+}
+
+/// Entry point for `rwc hook install|run`.
+pub fn run(args: &[String]) -> i32 {
+    match args.first().map(String::as_str) {
+        Some("install") => install(&args[1..]),
+        Some("run") => match parse_checks(&args[1..]) {
+            Ok((checks, _)) => run_checks(&checks),
+            Err(code) => code,
+        },
+        Some("--help") => {
+            print_help();
+            0
+        }
+        Some(other) => {
+            eprintln!("rwc hook: unknown action '{}'", other);
+            if let Some(action) = cli::suggest(other, &["install", "run"]) {
+                eprintln!("  did you mean '{}'?", action);
+            }
+            1
+        }
+        None => {
+            eprintln!("rwc hook: missing action (install or run)");
+            eprintln!("Try 'rwc hook --help' for more information.");
+            1
+        }
+    }
+}
+
+fn print_help() {
+    println!("Usage: rwc hook install [--force] CHECKS...");
+    println!("       rwc hook run CHECKS...");
+    println!();
+    println!("'install' writes a git pre-commit hook that runs 'rwc hook run' with the");
+    println!("given checks. 'run' checks every staged file, reading its content from");
+    println!("the index, and exits 1 if any check fails.");
+    println!();
+    println!("CHECKS:");
+    println!("    --max-lines N   Fail files with more than N lines");
+    println!("    --max-words N   Fail files with more than N words");
+    println!("    --max-bytes N   Fail files larger than N bytes");
+    println!("    --license-header PATTERN");
+    println!("                    Fail files with no line matching PATTERN near the top");
+    println!("    --header-lines N");
+    println!(
+        "                    With --license-header, search the first N lines (default {})",
+        license::DEFAULT_HEADER_LINES
+    );
+    println!("    --no-generated  Skip generated and vendored files");
+    println!();
+    println!("OPTIONS:");
+    println!("    --force         Replace an existing pre-commit hook");
+}
+
+/// Parses the checks, returning them with their original arguments (which
+/// `install` writes into the hook). Errors carry the exit code.
+fn parse_checks(args: &[String]) -> Result<(Checks, Vec<String>), i32> {
+    let mut checks = Checks::default();
+    let mut pattern = None;
+    let mut header_lines = license::DEFAULT_HEADER_LINES;
+    let mut kept = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        if arg == "--no-generated" {
+            checks.no_generated = true;
+            kept.push(args[i].clone());
+            i += 1;
+            continue;
+        }
+        if !CHECK_OPTIONS.contains(&arg) {
+            cli::unknown_option("rwc hook", arg, CHECK_OPTIONS);
+            return Err(1);
+        }
+        let Some(value) = args.get(i + 1) else {
+            eprintln!("rwc hook: option '{}' requires an argument", arg);
+            return Err(1);
+        };
+        if arg == "--license-header" {
+            pattern = Some(Regex::new(value).map_err(|e| {
+                eprintln!("rwc hook: --license-header: {}", e);
+                1
+            })?);
+        } else {
+            let n = value.parse::<usize>().map_err(|_| {
+                eprintln!("rwc hook: invalid number '{}' for {}", value, arg);
+                1
+            })?;
+            match arg {
+                "--max-lines" => checks.max_lines = Some(n),
+                "--max-words" => checks.max_words = Some(n),
+                "--max-bytes" => checks.max_bytes = Some(n),
+                _ => header_lines = n,
+            }
+        }
+        kept.extend([args[i].clone(), value.clone()]);
+        i += 2;
+    }
+
+    checks.license = pattern.map(|pattern| HeaderCheck {
+        pattern,
+        lines: header_lines,
+        require: true,
+    });
+    if checks.is_empty() {
+        eprintln!("rwc hook: no checks given");
+        eprintln!("Try 'rwc hook --help' for more information.");
+        return Err(1);
+    }
+    Ok((checks, kept))
+}
+
+fn run_checks(checks: &Checks) -> i32 {
+    let root = match git::toplevel(Path::new(".")) {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("rwc hook: {}", e);
+            return 1;
+        }
+    };
+    let staged = match git::staged_files(&root) {
+        Ok(staged) => staged,
+        Err(e) => {
+            eprintln!("rwc hook: {}", e);
+            return 1;
+        }
+    };
+
+    let mut failed = 0;
+    for path in staged {
+        let data = match git::staged_content(&root, &path) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("rwc hook: {}: {}", path, e);
+                return 1;
+            }
+        };
+        if checks.no_generated && generated::reason_for(Path::new(&path), &data).is_some() {
+            continue;
+        }
+        match checks.check(&data) {
+            Ok(problems) if problems.is_empty() => {}
+            Ok(problems) => {
+                failed += 1;
+                for problem in problems {
+                    eprintln!("rwc hook: {}: {}", path, problem);
+                }
+            }
+            Err(e) => {
+                eprintln!("rwc hook: {}: {}", path, e);
+                return 1;
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!("rwc hook: {} staged file(s) failed; commit aborted", failed);
+        1
+    } else {
+        0
+    }
+}
+
+fn install(args: &[String]) -> i32 {
+    let force = args.iter().any(|a| a == "--force");
+    let args: Vec<String> = args.iter().filter(|a| *a != "--force").cloned().collect();
+    let kept = match parse_checks(&args) {
+        Ok((_, kept)) => kept,
+        Err(code) => return code,
+    };
+
+    let path = match hook_path() {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("rwc hook: {}", e);
+            return 1;
+        }
+    };
+    if let Ok(existing) = fs::read_to_string(&path) {
+        if !force && !existing.contains(MARKER) {
+            eprintln!(
+                "rwc hook: {} already exists; use --force to replace it",
+                path.display()
+            );
+            return 1;
+        }
+    }
+
+    let quoted: Vec<String> = kept.iter().map(|a| shell_quote(a)).collect();
+    let script = format!(
+        "#!/bin/sh\n{}\nexec rwc hook run {}\n",
+        MARKER,
+        quoted.join(" ")
+    );
+    if let Err(e) = write_executable(&path, &script) {
+        eprintln!("rwc hook: {}: {}", path.display(), e);
+        return 1;
+    }
+    println!("Installed pre-commit hook at {}", path.display());
+    0
+}
+
+/// Where git looks for the pre-commit hook; honours `core.hooksPath`.
+fn hook_path() -> io::Result<PathBuf> {
+    let output = git::output(Path::new("."), ["rev-parse", "--git-path", "hooks/pre-commit"])?;
+    Ok(String::from_utf8_lossy(&output).trim_end().into())
+}
+
+fn shell_quote(arg: &str) -> String {
+    if arg
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_./=:,".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+fn write_executable(path: &Path, script: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, script)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::io::{self, Cursor, Read};
+use std::process::{Command, Stdio};
+
+use crate::engine::{Metrics, Scanner};
+use crate::json::{self, Value};
+use crate::{cli, decompress, format_number, tar, Counts};
+
+#[derive(Default)]
+struct LayerStats {
+    files: usize,
+    text_files: usize,
+    /// Text files that are not UTF-8, left out of the counts without
+    /// `--lossy`.
+    not_utf8: usize,
+    size: u64,
+    counts: Counts,
+    compressed: bool,
+}
+
+impl LayerStats {
+    fn add(&mut self, other: &LayerStats) {
+        self.files += other.files;
+        self.text_files += other.text_files;
+        self.not_utf8 += other.not_utf8;
+        self.size += other.size;
+        self.counts.add(&other.counts);
+    }
+}
+
+/// Entry point for `rwc image [--json] [-h] [--lossy] IMAGE[:TAG]`.
+pub fn run(args: &[String]) -> i32 {
+    let mut json = false;
+    let mut human = false;
+    let mut lossy = false;
+    let mut image = None;
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--human" => human = true,
+            "--lossy" => lossy = true,
+            "--help" => {
+                println!("Usage: rwc image [--json] [-h] [--lossy] IMAGE[:TAG]");
+                println!();
+                println!("Counts text files inside each layer of a container image.");
+                println!("The image is pulled with docker if it is not present locally.");
+                println!("Files that are not UTF-8 are only counted with --lossy, which");
+                println!("reads each invalid sequence as U+FFFD.");
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                let options = ["--json", "--human", "--lossy", "--help"];
+                cli::unknown_option("rwc image", a, &options);
+                return 1;
+            }
+            a => image = Some(a.to_string()),
+        }
+    }
+
+    let Some(image) = image else {
+        eprintln!("rwc image: missing IMAGE argument");
+        eprintln!("Try 'rwc image --help' for more information.");
+        return 1;
+    };
+
+    match scan_image(&image, lossy) {
+        Ok(layers) => {
+            print_layers(&layers, json, human);
+            let not_utf8: usize = layers.iter().map(|(_, stats)| stats.not_utf8).sum();
+            if not_utf8 > 0 {
+                eprintln!(
+                    "rwc image: {}: {} files are not UTF-8 and were not counted \
+                     (--lossy counts them)",
+                    image, not_utf8
+                );
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("rwc image: {}: {}", image, e);
+            1
+        }
+    }
+}
+
+fn docker() -> Command {
+    Command::new("docker")
+}
+
+/// Exports the image with `docker save` and collects per-layer statistics in
+/// manifest order.
+fn scan_image(image: &str, lossy: bool) -> io::Result<Vec<(String, LayerStats)>> {
+    let present = docker()
+        .args(["image", "inspect", image])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?
+        .success();
+    if !present {
+        let status = docker()
+            .args(["pull", image])
+            .stdout(Stdio::from(io::stderr()))
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "docker pull exited with {}",
+                status
+            )));
+        }
+    }
+
+    let mut child = docker()
+        .args(["save", image])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    let mut blobs: Vec<(String, LayerStats)> = Vec::new();
+    let mut manifest = None;
+
+    tar::for_each_entry(stdout, |header, data| {
+        if header.kind != tar::EntryKind::File {
+            return Ok(());
+        }
+        if header.path == "manifest.json" {
+            let mut text = String::new();
+            data.read_to_string(&mut text)?;
+            manifest = Some(text);
+            return Ok(());
+        }
+
+        if let Some(stats) = scan_blob(data, lossy)? {
+            blobs.push((header.path.clone(), stats));
+        }
+        Ok(())
+    })?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "docker save exited with {}",
+            status
+        )));
+    }
+
+    let order = manifest.as_deref().map(layer_order).unwrap_or_default();
+    if order.is_empty() {
+        return Ok(blobs);
+    }
+    let mut by_path: HashMap<String, LayerStats> = blobs.into_iter().collect();
+    Ok(order
+        .into_iter()
+        .filter_map(|path| by_path.remove(&path).map(|stats| (path, stats)))
+        .collect())
+}
+
+/// Reads the layer paths of the first image listed in `manifest.json`.
+fn layer_order(manifest: &str) -> Vec<String> {
+    let Ok(value) = json::parse(manifest) else {
+        return Vec::new();
+    };
+    value
+        .as_array()
+        .and_then(|images| images.first())
+        .and_then(|image| image.get("Layers"))
+        .and_then(|layers| layers.as_array())
+        .map(|layers| {
+            layers
+                .iter()
+                .filter_map(|l| l.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Counts a blob of the saved image if it is a layer: a tar archive as it
+/// is, or compressed, as OCI layouts and containerd keep most layers.
+fn scan_blob(data: &mut dyn Read, lossy: bool) -> io::Result<Option<LayerStats>> {
+    let mut block = Vec::with_capacity(512);
+    data.take(512).read_to_end(&mut block)?;
+    if tar::is_tar_header(&block) {
+        return scan_layer(Cursor::new(block).chain(data), lossy).map(Some);
+    }
+    let Some(format) = decompress::Format::detect(&block) else {
+        return Ok(None);
+    };
+    let input = io::BufReader::new(Cursor::new(block).chain(data));
+    let stats = decompress::with_stream(format, input, |layer| {
+        let mut block = Vec::with_capacity(512);
+        layer.take(512).read_to_end(&mut block)?;
+        if !tar::is_tar_header(&block) {
+            // Compressed, but not a layer; read it out so a tool exits cleanly.
+            io::copy(layer, &mut io::sink())?;
+            return Ok(None);
+        }
+        scan_layer(Cursor::new(block).chain(layer), lossy).map(Some)
+    })?;
+    Ok(stats.map(|stats| LayerStats {
+        compressed: true,
+        ..stats
+    }))
+}
+
+/// Counts the text files of a layer as they stream past. A file with a NUL
+/// in its first 8 KiB is binary and skipped.
+fn scan_layer<R: Read>(reader: R, lossy: bool) -> io::Result<LayerStats> {
+    let metrics = Metrics {
+        lossy,
+        ..Metrics::default()
+    };
+    let mut stats = LayerStats::default();
+    tar::for_each_entry(reader, |header, data| {
+        if header.kind != tar::EntryKind::File {
+            return Ok(());
+        }
+        stats.files += 1;
+        stats.size += header.size;
+
+        let mut head = Vec::new();
+        data.take(8192).read_to_end(&mut head)?;
+        if head.contains(&0) {
+            return Ok(());
+        }
+        match scan_file(&head, data, &metrics)? {
+            Some(counts) => {
+                stats.text_files += 1;
+                stats.counts.add(&counts);
+            }
+            None => stats.not_utf8 += 1,
+        }
+        Ok(())
+    })?;
+    Ok(stats)
+}
+
+/// Streams a file of a layer through the engine, its `head` first: `None`
+/// if it is not UTF-8 and `metrics` is not lossy. What is left of it then
+/// is skipped by the caller.
+fn scan_file(head: &[u8], rest: &mut dyn Read, metrics: &Metrics) -> io::Result<Option<Counts>> {
+    let mut scanner = Scanner::new(metrics);
+    if scanner.feed(head).is_err() {
+        return Ok(None);
+    }
+    let mut buffer = [0; 8192];
+    loop {
+        let n = match rest.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if scanner.feed(&buffer[..n]).is_err() {
+            return Ok(None);
+        }
+    }
+    Ok(scanner.finish().ok().map(Counts::from))
+}
+
+/// Shortens `<id>/layer.tar` and `blobs/sha256/<digest>` paths to a digest.
+fn layer_name(path: &str) -> String {
+    let digest = match path.strip_prefix("blobs/") {
+        Some(rest) => rest.replacen('/', ":", 1),
+        None => format!("sha256:{}", path.trim_end_matches("/layer.tar")),
+    };
+    digest.chars().take("sha256:".len() + 12).collect()
+}
+
+fn print_layers(layers: &[(String, LayerStats)], json: bool, human: bool) {
+    let mut total = LayerStats::default();
+    for (_, stats) in layers {
+        total.add(stats);
+    }
+
+    if json {
+        println!("{}", layers_json(layers, &total).pretty());
+        return;
+    }
+
+    println!(
+        "{:>8}{:>8}{:>10}{:>10}{:>10}  LAYER",
+        "FILES", "TEXT", "LINES", "WORDS", "BYTES"
+    );
+    let row = |stats: &LayerStats, name: &str| {
+        println!(
+            "{:>8}{:>8}{:>10}{:>10}{:>10}  {}",
+            format_number(stats.files, human),
+            format_number(stats.text_files, human),
+            format_number(stats.counts.lines, human),
+            format_number(stats.counts.words, human),
+            format_number(stats.size as usize, human),
+            name
+        );
+    };
+    for (path, stats) in layers {
+        row(stats, &layer_name(path));
+    }
+    row(&total, "total");
+}
+
+fn layers_json(layers: &[(String, LayerStats)], total: &LayerStats) -> Value {
+    let layers = layers
+        .iter()
+        .map(|(path, stats)| {
+            json::object([
+                ("layer", layer_name(path).into()),
+                ("files", stats.files.into()),
+                ("text_files", stats.text_files.into()),
+                ("not_utf8", stats.not_utf8.into()),
+                ("lines", stats.counts.lines.into()),
+                ("words", stats.counts.words.into()),
+                ("bytes", Value::Number(stats.size as f64)),
+                ("compressed", Value::Bool(stats.compressed)),
+            ])
+        })
+        .collect();
+    let total = json::object([
+        ("files", total.files.into()),
+        ("text_files", total.text_files.into()),
+        ("not_utf8", total.not_utf8.into()),
+        ("lines", total.counts.lines.into()),
+        ("words", total.counts.words.into()),
+        ("bytes", Value::Number(total.size as f64)),
+    ]);
+    json::object([("layers", Value::Array(layers)), ("total", total)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tar of `etc/motd` (2 lines, 5 words) and a binary `bin/tool`,
+    /// compressed with gzip.
+    const LAYER_GZ: [u8; 160] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xed, 0xd3, 0x41, 0x0a, 0xc2,
+        0x30, 0x10, 0x85, 0xe1, 0xac, 0x7b, 0x8a, 0xdc, 0xa0, 0x53, 0x8c, 0x7a, 0x02, 0x5d, 0x79,
+        0x89, 0xda, 0x04, 0x5a, 0x88, 0x09, 0xa4, 0x11, 0x71, 0xe5, 0xd5, 0x0d, 0xd9, 0x08, 0xba,
+        0xb6, 0xa2, 0xfe, 0xdf, 0xe6, 0x0d, 0xb3, 0x99, 0xcd, 0x3c, 0x97, 0x87, 0xf6, 0x14, 0xb3,
+        0x55, 0x6f, 0x24, 0xc5, 0xc6, 0x98, 0x9a, 0xc5, 0x73, 0x8a, 0xac, 0xd6, 0x8f, 0xb9, 0xee,
+        0xb7, 0x46, 0x8c, 0xd2, 0xa2, 0x16, 0x70, 0x9e, 0x73, 0x9f, 0xca, 0x49, 0xf5, 0x9f, 0x46,
+        0xe7, 0x7d, 0xd4, 0x97, 0x98, 0xbc, 0x6d, 0x66, 0x37, 0xc4, 0x60, 0xb5, 0x9f, 0x82, 0xd3,
+        0xa3, 0x4b, 0xae, 0x51, 0xf8, 0x75, 0xc7, 0x29, 0xb4, 0x39, 0x46, 0xff, 0xd1, 0xfe, 0x77,
+        0xe6, 0xa5, 0xff, 0x9d, 0xd0, 0xff, 0x25, 0xdc, 0x76, 0x87, 0x7d, 0xfd, 0x82, 0x3e, 0x5d,
+        0x69, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0xf7,
+        0xb9, 0x03, 0x39, 0x5f, 0xf9, 0xbf, 0x00, 0x28, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn gzip_layers_are_counted() {
+        let stats = scan_blob(&mut &LAYER_GZ[..], false).unwrap().unwrap();
+        assert!(stats.compressed);
+        assert_eq!((stats.files, stats.text_files), (2, 1));
+        assert_eq!((stats.counts.lines, stats.counts.words), (2, 5));
+        assert_eq!(stats.size, 29 + 12);
+
+        let tar = crate::inflate::gunzip(&LAYER_GZ).unwrap();
+        let stats = scan_blob(&mut &tar[..], false).unwrap().unwrap();
+        assert!(!stats.compressed);
+        assert_eq!((stats.counts.lines, stats.counts.words), (2, 5));
+    }
+
+    #[test]
+    fn other_blobs_are_skipped() {
+        assert!(scan_blob(&mut &b"{\"architecture\": \"amd64\"}"[..], false)
+            .unwrap()
+            .is_none());
+        let hello_gz = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0x48, 0xcd, 0xc9,
+            0xc9, 0x57, 0xc8, 0x40, 0x27, 0xb9, 0x00, 0x00, 0x88, 0x59, 0x0b, 0x18, 0x00, 0x00,
+            0x00,
+        ];
+        assert!(scan_blob(&mut &hello_gz[..], false).unwrap().is_none());
+        let mut truncated = &LAYER_GZ[..100];
+        assert!(scan_blob(&mut truncated, false).is_err());
+    }
+
+    /// An uncompressed layer of `files`, without checksums, which are not
+    /// checked.
+    fn layer(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (name, data) in files {
+            let mut header = vec![0u8; 512];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[124..136].copy_from_slice(format!("{:011o}\0", data.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            out.extend(header);
+            out.extend_from_slice(data);
+            out.resize(out.len().next_multiple_of(512), 0);
+        }
+        out.resize(out.len() + 1024, 0);
+        out
+    }
+
+    #[test]
+    fn files_are_streamed_and_non_utf8_is_reported() {
+        // Longer than the head that is checked for NULs.
+        let long = "word ".repeat(4_000) + "\n";
+        let latin1 = b"caf\xe9 au lait\n";
+        let tar = layer(&[
+            ("long.txt", long.as_bytes()),
+            ("latin1.txt", latin1),
+            ("utf8.txt", "café\n".as_bytes()),
+        ]);
+
+        let stats = scan_blob(&mut &tar[..], false).unwrap().unwrap();
+        assert_eq!((stats.files, stats.text_files, stats.not_utf8), (3, 2, 1));
+        assert_eq!((stats.counts.lines, stats.counts.words), (2, 4_001));
+
+        let stats = scan_blob(&mut &tar[..], true).unwrap().unwrap();
+        assert_eq!((stats.text_files, stats.not_utf8), (3, 0));
+        assert_eq!((stats.counts.lines, stats.counts.words), (3, 4_004));
+    }
+
+    #[test]
+    fn json_report_escapes_names() {
+        let layers = vec![("blobs/sha256/\"quoted\\".to_string(), LayerStats::default())];
+        let report = layers_json(&layers, &LayerStats::default());
+        let parsed = json::parse(&report.pretty()).unwrap();
+        let layer = &parsed.get("layers").unwrap().as_array().unwrap()[0];
+        assert_eq!(
+            layer.get("layer").unwrap().as_str(),
+            Some("sha256:\"quoted\\")
+        );
+        assert_eq!(
+            parsed
+                .get("total")
+                .unwrap()
+                .get("not_utf8")
+                .unwrap()
+                .as_f64(),
+            Some(0.0)
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! `--indent-stats`: indentation depth and tab/space usage for code.
+
+/// Indent width assumed when a file's space indentation shows no clear unit.
+const DEFAULT_UNIT: usize = 4;
+
+#[derive(Debug, Clone, Default)]
+pub struct IndentStats {
+    /// Non-blank lines seen.
+    pub lines: usize,
+    /// Deepest indentation level of any line.
+    pub max_depth: usize,
+    /// Sum of every line's level, for the average.
+    pub depth_sum: usize,
+    /// Indented lines whose indentation starts with a tab.
+    pub tab_lines: usize,
+    /// Indented lines whose indentation starts with a space.
+    pub space_lines: usize,
+}
+
+/// Collects line indentation during a scan; depths are only known once the
+/// whole file has been seen.
+///
+/// Each leading tab is one level; leading spaces are divided by the file's
+/// indent unit, the GCD of its space-indent widths (so a 2-space file and a
+/// 4-space file report the same depths).
+#[derive(Default)]
+pub struct IndentScan {
+    /// Leading tabs, leading spaces and the first indent byte of each
+    /// non-blank line.
+    indents: Vec<(usize, usize, Option<u8>)>,
+    unit: usize,
+}
+
+impl IndentScan {
+    pub fn push(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        let leading = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        let tabs = leading.bytes().filter(|&b| b == b'\t').count();
+        let spaces = leading.len() - tabs;
+        if spaces > 0 {
+            self.unit = gcd(self.unit, spaces);
+        }
+        self.indents.push((tabs, spaces, leading.bytes().next()));
+    }
+
+    pub fn finish(self) -> IndentStats {
+        // A lone odd offset (continuation lines, doc comment stars) should not
+        // shrink the unit to 1 and inflate every depth.
+        let unit = if self.unit <= 1 { DEFAULT_UNIT } else { self.unit };
+
+        let mut stats = IndentStats::default();
+        for (tabs, spaces, first) in self.indents {
+            let depth = tabs + spaces / unit;
+            stats.lines += 1;
+            stats.depth_sum += depth;
+            stats.max_depth = stats.max_depth.max(depth);
+            match first {
+                Some(b'\t') => stats.tab_lines += 1,
+                Some(b' ') => stats.space_lines += 1,
+                _ => {}
+            }
+        }
+        stats
+    }
+}
+
+impl IndentStats {
+    pub fn merge(&mut self, other: &IndentStats) {
+        self.lines += other.lines;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.depth_sum += other.depth_sum;
+        self.tab_lines += other.tab_lines;
+        self.space_lines += other.space_lines;
+    }
+
+    pub fn average_depth(&self) -> f64 {
+        if self.lines == 0 {
+            0.0
+        } else {
+            self.depth_sum as f64 / self.lines as f64
+        }
+    }
+
+    /// Fraction of indented lines that use tabs, from 0.0 to 1.0.
+    pub fn tab_ratio(&self) -> f64 {
+        let indented = self.tab_lines + self.space_lines;
+        if indented == 0 {
+            0.0
+        } else {
+            self.tab_lines as f64 / indented as f64
+        }
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
@@ -0,0 +1,625 @@
+//! DEFLATE decompression (RFC 1951) and the gzip format around it (RFC
+//! 1952).
+//!
+//! Zip members are raw deflate streams and gzip files wrap one in a header
+//! and a checksum, so unlike the other formats in `decompress` they are
+//! decoded here rather than by a tool. The decoder follows zlib's `puff`:
+//! canonical Huffman codes are decoded a bit at a time, which is slow next
+//! to table-driven decoders but ample for the text it is used on.
+//!
+//! Decoding streams: only the 32 KiB window that matches copy from and a
+//! chunk of output not yet read are held, however large the input.
+
+use std::io::{self, BufRead, Read};
+
+const MAX_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order code-length code lengths are sent in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// How far back a match may copy from.
+const WINDOW: usize = 32 * 1024;
+
+/// Output decoded ahead of reads, at most (plus one match).
+const CHUNK: usize = 64 * 1024;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid deflate data: {}", message),
+    )
+}
+
+/// Reads the input least significant bit first.
+struct Bits<R> {
+    input: R,
+    buffer: u32,
+    count: u32,
+}
+
+/// The next byte of `input`.
+fn byte<R: BufRead>(input: &mut R) -> io::Result<u8> {
+    let byte = *input
+        .fill_buf()?
+        .first()
+        .ok_or_else(|| invalid("truncated"))?;
+    input.consume(1);
+    Ok(byte)
+}
+
+impl<R: BufRead> Bits<R> {
+    fn take(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            self.buffer |= u32::from(byte(&mut self.input)?) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1 << n) - 1);
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Drops the rest of the current byte. Whole bytes are only loaded when
+    /// needed, so fewer than eight bits are ever left over.
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+}
+
+/// A canonical Huffman code: how many codes have each length, and the
+/// symbols ordered by code.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0; MAX_BITS + 1];
+        for &length in lengths {
+            counts[usize::from(length)] += 1;
+        }
+        let mut offsets = [0; MAX_BITS + 2];
+        for length in 1..=MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                let slot = &mut offsets[usize::from(length)];
+                symbols[usize::from(*slot)] = symbol as u16;
+                *slot += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode<R: BufRead>(&self, bits: &mut Bits<R>) -> io::Result<u16> {
+        // `code` is the code read so far and `first` the first code of the
+        // current length; codes of one length are consecutive.
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..=MAX_BITS {
+            code |= bits.take(1)? as i32;
+            let count = i32::from(self.counts[length]);
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("bad Huffman code"))
+    }
+}
+
+/// Where the decoder is in the stream.
+enum Block {
+    /// At a block header.
+    Header,
+    /// In a stored block, with this many bytes left.
+    Stored(usize),
+    /// In a compressed block, with its literal/length and distance codes.
+    Codes(Huffman, Huffman),
+    Done,
+}
+
+/// Decompresses a raw deflate stream read from `input`.
+pub struct Inflate<R> {
+    bits: Bits<R>,
+    block: Block,
+    last: bool,
+    /// The last `WINDOW` bytes read, then the output not yet read.
+    out: Vec<u8>,
+    read: usize,
+}
+
+impl<R: BufRead> Inflate<R> {
+    pub fn new(input: R) -> Self {
+        Inflate {
+            bits: Bits {
+                input,
+                buffer: 0,
+                count: 0,
+            },
+            block: Block::Header,
+            last: false,
+            out: Vec::new(),
+            read: 0,
+        }
+    }
+
+    /// The input, positioned after the stream once it has all been read.
+    fn input(&mut self) -> &mut R {
+        &mut self.bits.input
+    }
+
+    /// Starts on another stream that follows in the same input.
+    fn restart(&mut self) {
+        self.bits.align();
+        self.block = Block::Header;
+        self.last = false;
+        self.out.clear();
+        self.read = 0;
+    }
+
+    /// Decodes until a chunk of output is waiting or the stream ends.
+    fn fill(&mut self) -> io::Result<()> {
+        while self.out.len() - self.read < CHUNK {
+            match &self.block {
+                Block::Done => break,
+                Block::Header => {
+                    self.last = self.bits.take(1)? == 1;
+                    self.block = match self.bits.take(2)? {
+                        0 => Block::Stored(stored_len(&mut self.bits)?),
+                        1 => {
+                            let (literals, distances) = fixed_codes();
+                            Block::Codes(literals, distances)
+                        }
+                        2 => {
+                            let (literals, distances) = dynamic_codes(&mut self.bits)?;
+                            Block::Codes(literals, distances)
+                        }
+                        _ => return Err(invalid("reserved block type")),
+                    };
+                }
+                Block::Stored(0) => self.end_block(),
+                &Block::Stored(left) => {
+                    let input = &mut self.bits.input;
+                    let available = input.fill_buf()?;
+                    if available.is_empty() {
+                        return Err(invalid("truncated"));
+                    }
+                    let n = left.min(available.len()).min(CHUNK);
+                    self.out.extend_from_slice(&available[..n]);
+                    input.consume(n);
+                    self.block = Block::Stored(left - n);
+                }
+                Block::Codes(literals, distances) => {
+                    let limit = self.read + CHUNK;
+                    if codes(&mut self.bits, &mut self.out, literals, distances, limit)? {
+                        self.end_block();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn end_block(&mut self) {
+        if self.last {
+            // The rest of the last byte is padding.
+            self.bits.align();
+            self.block = Block::Done;
+        } else {
+            self.block = Block::Header;
+        }
+    }
+}
+
+impl<R: BufRead> Read for Inflate<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.read == self.out.len() {
+            // Keep only the window before decoding more.
+            let drop = self.out.len().saturating_sub(WINDOW);
+            self.out.drain(..drop);
+            self.read -= drop;
+            self.fill()?;
+        }
+        let n = buffer.len().min(self.out.len() - self.read);
+        buffer[..n].copy_from_slice(&self.out[self.read..self.read + n]);
+        self.read += n;
+        Ok(n)
+    }
+}
+
+/// Decompresses a raw deflate stream.
+pub fn inflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 4);
+    Inflate::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// The length of a stored block, from the header after its block type.
+fn stored_len<R: BufRead>(bits: &mut Bits<R>) -> io::Result<usize> {
+    bits.align();
+    let mut header = [0; 4];
+    for slot in &mut header {
+        *slot = byte(&mut bits.input)?;
+    }
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let complement = u16::from_le_bytes([header[2], header[3]]);
+    if len != !complement {
+        return Err(invalid("stored block length check failed"));
+    }
+    Ok(usize::from(len))
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes<R: BufRead>(bits: &mut Bits<R>) -> io::Result<(Huffman, Huffman)> {
+    let literal_count = bits.take(5)? as usize + 257;
+    let distance_count = bits.take(5)? as usize + 1;
+    let code_length_count = bits.take(4)? as usize + 4;
+    if literal_count > 286 || distance_count > 30 {
+        return Err(invalid("too many codes"));
+    }
+    let mut code_lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[symbol] = bits.take(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let symbol = code_length_code.decode(bits)?;
+        let (length, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or_else(|| invalid("repeat with no length"))?;
+                (previous, 3 + bits.take(2)?)
+            }
+            17 => (0, 3 + bits.take(3)?),
+            _ => (0, 11 + bits.take(7)?),
+        };
+        if lengths.len() + repeat as usize > literal_count + distance_count {
+            return Err(invalid("too many code lengths"));
+        }
+        lengths.extend(std::iter::repeat_n(length, repeat as usize));
+    }
+    if lengths[256] == 0 {
+        return Err(invalid("no end-of-block code"));
+    }
+    let (literals, distances) = lengths.split_at(literal_count);
+    Ok((Huffman::new(literals), Huffman::new(distances)))
+}
+
+/// Decodes a compressed block until it ends, returning `true`, or until
+/// `out` reaches `limit` bytes.
+fn codes<R: BufRead>(
+    bits: &mut Bits<R>,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+    limit: usize,
+) -> io::Result<bool> {
+    while out.len() < limit {
+        let symbol = usize::from(literals.decode(bits)?);
+        if symbol < 256 {
+            out.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(true);
+        }
+        let index = symbol - 257;
+        if index >= LENGTH_BASE.len() {
+            return Err(invalid("bad length code"));
+        }
+        let length =
+            usize::from(LENGTH_BASE[index]) + bits.take(u32::from(LENGTH_EXTRA[index]))? as usize;
+        let index = usize::from(distances.decode(bits)?);
+        if index >= DIST_BASE.len() {
+            return Err(invalid("bad distance code"));
+        }
+        let distance =
+            usize::from(DIST_BASE[index]) + bits.take(u32::from(DIST_EXTRA[index]))? as usize;
+        if distance > out.len() {
+            return Err(invalid("distance too far back"));
+        }
+        // Copies may overlap the bytes they produce, so go byte by byte.
+        let start = out.len() - distance;
+        for i in 0..length {
+            out.push(out[start + i]);
+        }
+    }
+    Ok(false)
+}
+
+/// CRC-32 as gzip uses it (the IEEE polynomial, reflected).
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+    table
+};
+
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    crc = !crc;
+    for &byte in data {
+        crc = CRC_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+fn invalid_gzip(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid gzip data: {}", message),
+    )
+}
+
+/// Reads a gzip member header, leaving `input` at the deflate stream.
+fn gzip_header<R: BufRead>(input: &mut R) -> io::Result<()> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+    let mut fixed = [0; 10];
+    input
+        .read_exact(&mut fixed)
+        .map_err(|_| invalid_gzip("truncated header"))?;
+    if fixed[..2] != [0x1f, 0x8b] {
+        return Err(invalid_gzip("bad magic"));
+    }
+    if fixed[2] != 8 {
+        return Err(invalid_gzip("unknown compression method"));
+    }
+    let flags = fixed[3];
+    if flags & 0xe0 != 0 {
+        return Err(invalid_gzip("reserved flags set"));
+    }
+    if flags & FEXTRA != 0 {
+        let len = u16::from_le_bytes([byte(input)?, byte(input)?]);
+        for _ in 0..len {
+            byte(input)?;
+        }
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            while byte(input)? != 0 {}
+        }
+    }
+    if flags & FHCRC != 0 {
+        byte(input)?;
+        byte(input)?;
+    }
+    Ok(())
+}
+
+/// Decompresses gzip data: every member, as `gzip -d` does, each checked
+/// against its CRC and length.
+pub struct Gunzip<R> {
+    inflate: Inflate<R>,
+    crc: u32,
+    size: u32,
+    done: bool,
+}
+
+impl<R: BufRead> Gunzip<R> {
+    pub fn new(mut input: R) -> io::Result<Self> {
+        gzip_header(&mut input)?;
+        Ok(Gunzip {
+            inflate: Inflate::new(input),
+            crc: 0,
+            size: 0,
+            done: false,
+        })
+    }
+
+    /// Checks the trailer of the member just read, and starts on the next
+    /// one if there is one.
+    fn end_member(&mut self) -> io::Result<()> {
+        let input = self.inflate.input();
+        let mut trailer = [0; 8];
+        input
+            .read_exact(&mut trailer)
+            .map_err(|_| invalid_gzip("truncated trailer"))?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        if crc != self.crc {
+            return Err(invalid_gzip("CRC mismatch"));
+        }
+        if size != self.size {
+            return Err(invalid_gzip("length mismatch"));
+        }
+        if input.fill_buf()?.is_empty() {
+            self.done = true;
+            return Ok(());
+        }
+        gzip_header(input).map_err(|_| invalid_gzip("trailing data after the last member"))?;
+        self.inflate.restart();
+        self.crc = 0;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Gunzip<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        while !self.done && !buffer.is_empty() {
+            let n = self.inflate.read(buffer)?;
+            if n > 0 {
+                self.crc = crc32(self.crc, &buffer[..n]);
+                // The length is kept modulo 2^32, as in the trailer.
+                self.size = self.size.wrapping_add(n as u32);
+                return Ok(n);
+            }
+            self.end_member()?;
+        }
+        Ok(0)
+    }
+}
+
+/// Decompresses gzip data held in memory.
+pub fn gunzip(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 4);
+    Gunzip::new(data)?.read_to_end(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `printf 'hello hello hello hello\n' | gzip -n`
+    const HELLO: [u8; 29] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9,
+        0x57, 0xc8, 0x40, 0x27, 0xb9, 0x00, 0x00, 0x88, 0x59, 0x0b, 0x18, 0x00, 0x00, 0x00,
+    ];
+
+    /// Raw deflate of `data` in stored blocks.
+    fn stored(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let blocks: Vec<&[u8]> = data.chunks(65_535).collect();
+        for (n, block) in blocks.iter().enumerate() {
+            out.push(u8::from(n + 1 == blocks.len()));
+            let len = block.len() as u16;
+            out.extend(len.to_le_bytes());
+            out.extend((!len).to_le_bytes());
+            out.extend(*block);
+        }
+        out
+    }
+
+    /// Reads `reader` a few bytes at a time, so decoding stops and resumes
+    /// inside blocks.
+    fn read_slowly(mut reader: impl Read) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut buffer = [0; 7];
+        loop {
+            match reader.read(&mut buffer)? {
+                0 => return Ok(out),
+                n => out.extend_from_slice(&buffer[..n]),
+            }
+        }
+    }
+
+    #[test]
+    fn gzip_members() {
+        assert_eq!(gunzip(&HELLO).unwrap(), b"hello hello hello hello\n");
+        let empty = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x03, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(gunzip(&empty).unwrap(), b"");
+        // `gzip named.txt`, which stores the name.
+        let named = [
+            0x1f, 0x8b, 0x08, 0x08, 0xbc, 0x9f, 0xd0, 0x6a, 0x00, 0x03, 0x6e, 0x61, 0x6d, 0x65,
+            0x64, 0x2e, 0x74, 0x78, 0x74, 0x00, 0xcb, 0xc8, 0xe4, 0x02, 0x00, 0x7a, 0x7a, 0x6f,
+            0xed, 0x03, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(gunzip(&named).unwrap(), b"hi\n");
+        let twice = [HELLO, HELLO].concat();
+        assert_eq!(
+            gunzip(&twice).unwrap(),
+            b"hello hello hello hello\n".repeat(2)
+        );
+    }
+
+    #[test]
+    fn corrupt_gzip() {
+        let mut crc = HELLO;
+        crc[21] ^= 1;
+        let mut length = HELLO;
+        length[25] ^= 1;
+        let mut method = HELLO;
+        method[2] = 7;
+        let junk = [&HELLO[..], b"junk"].concat();
+        for data in [
+            &crc[..],
+            &length,
+            &method,
+            &HELLO[..20],
+            &HELLO[..26],
+            &junk,
+            b"",
+        ] {
+            let error = gunzip(data).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn streams_past_the_window() {
+        let text: Vec<u8> = (0..200_000u32).map(|n| (n % 251) as u8).collect();
+        let data = stored(&text);
+        assert_eq!(read_slowly(Inflate::new(&data[..])).unwrap(), text);
+
+        // 'ab' 40000 times: one dynamic block whose matches copy across
+        // every chunk boundary.
+        let mut data = vec![
+            0xed, 0xc2, 0x81, 0x0c, 0x00, 0x00, 0x00, 0x02, 0x30, 0xd6, 0xf2, 0x87, 0x08, 0x20,
+            0x85, 0xef, 0x4f, 0x03,
+        ];
+        data.resize(95, 0);
+        data.push(0x67);
+        let ab = b"ab".repeat(40_000);
+        assert_eq!(read_slowly(Inflate::new(&data[..])).unwrap(), ab);
+        assert_eq!(inflate(&data).unwrap(), ab);
+    }
+
+    #[test]
+    fn corrupt_deflate() {
+        // A last block of the reserved type 3.
+        assert!(inflate(&[0x07]).is_err());
+        // A stored block whose length check fails.
+        assert!(inflate(&[0x01, 0x02, 0x00, 0x00, 0x00, b'a', b'b']).is_err());
+        // A stored block cut short, and a stream with no final block.
+        assert!(inflate(&[0x01, 0x02, 0x00, 0xfd, 0xff, b'a']).is_err());
+        assert!(inflate(&[0x00, 0x01, 0x00, 0xfe, 0xff, b'a']).is_err());
+        assert!(inflate(&[]).is_err());
+        // A fixed-code match before any output to copy from: length 3
+        // (code 257, 0000001), distance 1 (code 0, 00000).
+        assert!(inflate(&[0x03, 0x02, 0x00]).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! Typing into rwc: standard input read from a terminal.
+//!
+//! Rather than wait silently for end of input, rwc says how to end it and
+//! keeps a running count on standard error, on a line of its own under what
+//! has been typed or pasted, removed again at end of input. The count is
+//! redrawn once input pauses, so a paste is counted as a whole. It is a
+//! quick count (words split on whitespace, a line only counted at Enter);
+//! the counts printed at the end come from the usual engine, with every
+//! option applied.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use crate::{i18n, interrupt};
+
+/// How to end input on this platform.
+#[cfg(windows)]
+const END_OF_INPUT: &str = "Ctrl-Z, Enter";
+#[cfg(not(windows))]
+const END_OF_INPUT: &str = "Ctrl-D";
+
+/// How long input must pause before the count is redrawn.
+const IDLE: Duration = Duration::from_millis(50);
+
+/// Whether standard input is a person typing: it is a terminal, and so is
+/// standard error, where the running count goes.
+pub fn wanted() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Reads standard input to its end, showing the running count.
+pub fn read() -> io::Result<Vec<u8>> {
+    let mut stderr = io::stderr();
+    let notice = i18n::tr("rwc: reading from the terminal; press {} on an empty line to finish");
+    let _ = writeln!(stderr, "{}", notice.replace("{}", END_OF_INPUT));
+
+    // Lines arrive on a channel so a pause in the input can be noticed; an
+    // empty chunk is the end of input.
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        loop {
+            let mut chunk = Vec::new();
+            let result = stdin.read_until(b'\n', &mut chunk).map(|_| chunk);
+            let last = !matches!(&result, Ok(chunk) if !chunk.is_empty());
+            if sender.send(result).is_err() || last {
+                return;
+            }
+        }
+    });
+
+    let (mut lines, mut words, mut chars) = (0, 0, 0);
+    let mut data = Vec::new();
+    // Whether the count is on screen, and the lines echoed under it since.
+    let mut shown = false;
+    let mut rows = 0;
+    let mut mid_line = false;
+    loop {
+        match receiver.recv_timeout(IDLE) {
+            Ok(Ok(chunk)) if chunk.is_empty() => break,
+            Ok(Ok(chunk)) => {
+                let text = String::from_utf8_lossy(&chunk);
+                mid_line = !text.ends_with('\n');
+                rows += usize::from(!mid_line);
+                lines += usize::from(!mid_line);
+                words += text.split_whitespace().count();
+                chars += text.chars().count();
+                data.extend_from_slice(&chunk);
+            }
+            Ok(Err(e)) => return Err(e),
+            // Ctrl-C drops the input, as it does a file being read.
+            Err(RecvTimeoutError::Timeout) if interrupt::requested() => {
+                return Err(interrupt::error())
+            }
+            Err(RecvTimeoutError::Timeout) if rows > 0 && !mid_line => {
+                if shown {
+                    // Delete the old count and come back down under the
+                    // lines typed since.
+                    let _ = write!(stderr, "\x1b[{}A\x1b[M\x1b[{}B", rows + 1, rows);
+                }
+                let _ = writeln!(
+                    stderr,
+                    "\r\x1b[K  {} lines  {} words  {} chars",
+                    lines, words, chars
+                );
+                let _ = stderr.flush();
+                shown = true;
+                rows = 0;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    if shown {
+        let _ = write!(stderr, "\x1b[{}A\x1b[M", rows + 1);
+        if rows > 0 {
+            let _ = write!(stderr, "\x1b[{}B", rows);
+        }
+        let _ = stderr.flush();
+    }
+    Ok(data)
+}
@@ -0,0 +1,134 @@
+//! Ctrl-C while counting files: stop, and report what was counted.
+//!
+//! The first Ctrl-C only raises a flag. The walk stops listing files, no new
+//! file is started and the file being read is dropped, then the files
+//! counted so far are reported as partial and rwc exits with `STATUS`. The
+//! handler is removed once it runs, so a second Ctrl-C stops rwc at once,
+//! for instance while it waits on a pipe that sends nothing.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The exit status after an interrupted count: 128 plus SIGINT, as shells
+/// report a process killed by Ctrl-C.
+pub const STATUS: i32 = 130;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether Ctrl-C has been pressed.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+/// The error a read stopped by Ctrl-C ends with.
+pub fn error() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "interrupted")
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "openbsd"
+))]
+mod platform {
+    use std::ffi::c_int;
+    use std::sync::atomic::Ordering;
+
+    const SIGINT: c_int = 2;
+
+    /// `struct sigaction` as the Linux C libraries lay it out.
+    #[cfg(target_os = "linux")]
+    #[repr(C)]
+    struct SigAction {
+        handler: usize,
+        mask: [std::ffi::c_ulong; 128 / std::mem::size_of::<std::ffi::c_ulong>()],
+        flags: c_int,
+        restorer: usize,
+    }
+
+    #[cfg(any(target_vendor = "apple", target_os = "openbsd"))]
+    #[repr(C)]
+    struct SigAction {
+        handler: usize,
+        mask: u32,
+        flags: c_int,
+    }
+
+    #[cfg(target_os = "freebsd")]
+    #[repr(C)]
+    struct SigAction {
+        handler: usize,
+        flags: c_int,
+        mask: [u32; 4],
+    }
+
+    #[cfg(target_os = "linux")]
+    const SA_RESTART: c_int = 0x1000_0000;
+    #[cfg(target_os = "linux")]
+    const SA_RESETHAND: c_int = 0x8000_0000_u32 as c_int;
+    #[cfg(not(target_os = "linux"))]
+    const SA_RESTART: c_int = 0x2;
+    #[cfg(not(target_os = "linux"))]
+    const SA_RESETHAND: c_int = 0x4;
+
+    extern "C" {
+        fn sigaction(signum: c_int, act: *const SigAction, old: *mut SigAction) -> c_int;
+    }
+
+    extern "C" fn on_interrupt(_: c_int) {
+        super::REQUESTED.store(true, Ordering::Relaxed);
+    }
+
+    /// Catches the first SIGINT; `SA_RESETHAND` puts the default action back
+    /// as it is delivered, and reads it interrupts carry on (`SA_RESTART`).
+    pub fn install() {
+        let action = SigAction {
+            handler: on_interrupt as extern "C" fn(c_int) as usize,
+            flags: SA_RESETHAND | SA_RESTART,
+            ..unsafe { std::mem::zeroed() }
+        };
+        unsafe { sigaction(SIGINT, &action, std::ptr::null_mut()) };
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::sync::atomic::Ordering;
+
+    const CTRL_C_EVENT: u32 = 0;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(
+            handler: Option<unsafe extern "system" fn(u32) -> i32>,
+            add: i32,
+        ) -> i32;
+    }
+
+    /// Handles the first Ctrl-C; after that the default handler ends rwc.
+    unsafe extern "system" fn on_control(event: u32) -> i32 {
+        let first = event == CTRL_C_EVENT && !super::REQUESTED.swap(true, Ordering::Relaxed);
+        i32::from(first)
+    }
+
+    pub fn install() {
+        unsafe { SetConsoleCtrlHandler(Some(on_control), 1) };
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    windows
+)))]
+mod platform {
+    pub fn install() {}
+}
+
+/// Catches Ctrl-C from here on.
+pub fn install() {
+    platform::install();
+}
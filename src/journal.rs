@@ -0,0 +1,122 @@
+//! Reads the systemd journal through libsystemd's sd-journal API.
+
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::io;
+use std::ptr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{count_text, print_entry_counts, Config, Counts};
+
+#[repr(C)]
+struct SdJournal {
+    _private: [u8; 0],
+}
+
+const SD_JOURNAL_LOCAL_ONLY: c_int = 1;
+/// Returned by `sd_journal_get_data` when an entry lacks the field.
+const ENOENT: c_int = 2;
+
+#[link(name = "libsystemd.so.0", kind = "dylib", modifiers = "+verbatim")]
+extern "C" {
+    fn sd_journal_open(ret: *mut *mut SdJournal, flags: c_int) -> c_int;
+    fn sd_journal_close(j: *mut SdJournal);
+    fn sd_journal_set_data_threshold(j: *mut SdJournal, sz: usize) -> c_int;
+    fn sd_journal_add_match(j: *mut SdJournal, data: *const c_void, size: usize) -> c_int;
+    fn sd_journal_seek_realtime_usec(j: *mut SdJournal, usec: u64) -> c_int;
+    fn sd_journal_next(j: *mut SdJournal) -> c_int;
+    fn sd_journal_get_realtime_usec(j: *mut SdJournal, ret: *mut u64) -> c_int;
+    fn sd_journal_get_data(
+        j: *mut SdJournal,
+        field: *const c_char,
+        data: *mut *const c_void,
+        length: *mut usize,
+    ) -> c_int;
+}
+
+/// Owned handle that closes the journal on drop.
+struct Journal(*mut SdJournal);
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        unsafe { sd_journal_close(self.0) }
+    }
+}
+
+fn check(r: c_int) -> io::Result<c_int> {
+    if r < 0 {
+        Err(io::Error::from_raw_os_error(-r))
+    } else {
+        Ok(r)
+    }
+}
+
+fn usec(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+/// Counts journal entries and the words/bytes of their `MESSAGE` fields.
+fn count_journal(
+    unit: Option<&str>,
+    since: Option<SystemTime>,
+    until: Option<SystemTime>,
+) -> io::Result<(usize, Counts)> {
+    let mut handle = ptr::null_mut();
+    check(unsafe { sd_journal_open(&mut handle, SD_JOURNAL_LOCAL_ONLY) })?;
+    let journal = Journal(handle);
+    check(unsafe { sd_journal_set_data_threshold(journal.0, 0) })?;
+
+    if let Some(unit) = unit {
+        // Match journalctl: a bare name refers to a service unit.
+        let unit = if unit.contains('.') {
+            unit.to_string()
+        } else {
+            format!("{}.service", unit)
+        };
+        let expr = format!("_SYSTEMD_UNIT={}", unit);
+        check(unsafe { sd_journal_add_match(journal.0, expr.as_ptr().cast(), expr.len()) })?;
+    }
+    if let Some(since) = since {
+        check(unsafe { sd_journal_seek_realtime_usec(journal.0, usec(since)) })?;
+    }
+    let until = until.map(usec);
+
+    let field = CString::new("MESSAGE").expect("no interior NUL");
+    let mut entries = 0;
+    let mut counts = Counts::default();
+    while check(unsafe { sd_journal_next(journal.0) })? > 0 {
+        if let Some(until) = until {
+            let mut when = 0;
+            check(unsafe { sd_journal_get_realtime_usec(journal.0, &mut when) })?;
+            if when > until {
+                break;
+            }
+        }
+        entries += 1;
+
+        let mut data = ptr::null();
+        let mut len = 0;
+        let r = unsafe { sd_journal_get_data(journal.0, field.as_ptr(), &mut data, &mut len) };
+        if r == -ENOENT {
+            continue;
+        }
+        check(r)?;
+        let bytes = unsafe { std::slice::from_raw_parts(data.cast::<u8>(), len) };
+        let message = bytes.strip_prefix(b"MESSAGE=").unwrap_or(bytes);
+        counts.add(&count_text(&String::from_utf8_lossy(message)));
+    }
+
+    Ok((entries, counts))
+}
+
+pub fn run(config: &Config) -> io::Result<()> {
+    let unit = config.unit.as_deref();
+    let (entries, counts) = count_journal(unit, config.since, config.until)?;
+    let label = match unit {
+        Some(unit) => format!("journal:{}", unit),
+        None => "journal".to_string(),
+    };
+    print_entry_counts(&label, entries, &counts, config);
+    Ok(())
+}
@@ -0,0 +1,162 @@
+//! `--json-logs`: structured logs (NDJSON, GELF) counted record by record.
+//!
+//! Every line is one JSON object. Only the text of `--field` is counted
+//! (`message` by default; dots reach into nested objects, as in
+//! `log.message`), so keys, timestamps and other metadata do not inflate the
+//! word count. Alongside the counts, each top-level field is reported with
+//! the number of records that have it. Lines that are not JSON objects are
+//! skipped and reported.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use crate::engine::Scanner;
+use crate::json::{self, Value};
+use crate::{counts_json, output, print_entry_counts, print_json, print_plain, Config, Counts};
+
+pub const DEFAULT_FIELD: &str = "message";
+
+struct Report {
+    records: usize,
+    /// Records that are not JSON objects.
+    skipped: usize,
+    counts: Counts,
+    /// Records having each top-level field.
+    presence: BTreeMap<String, usize>,
+}
+
+/// The value at a dotted `path` in `record`.
+fn lookup<'v>(record: &'v Value, path: &str) -> Option<&'v Value> {
+    path.split('.').try_fold(record, |value, key| value.get(key))
+}
+
+fn count<R: BufRead>(reader: R, config: &Config) -> io::Result<Report> {
+    let field = config.json_logs.as_deref().unwrap_or(DEFAULT_FIELD);
+    let metrics = config.metrics();
+    let mut scanner = Scanner::new(&metrics);
+    let mut records = 0;
+    let mut skipped = 0;
+    let mut presence = BTreeMap::new();
+    for line in reader.split(b'\n') {
+        let line = line?;
+        let line = String::from_utf8_lossy(&line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = match json::parse(&line) {
+            Ok(record @ Value::Object(_)) => record,
+            _ => {
+                skipped += 1;
+                continue;
+            }
+        };
+        records += 1;
+        if let Value::Object(members) = &record {
+            for (key, _) in members {
+                *presence.entry(key.clone()).or_insert(0) += 1;
+            }
+        }
+        let text = match lookup(&record, field) {
+            None | Some(Value::Null) => continue,
+            Some(Value::String(text)) => text.clone(),
+            Some(other) => other.to_string(),
+        };
+        // One line of counted text per record that has the field.
+        scanner.feed(text.as_bytes())?;
+        scanner.feed(b"\n")?;
+    }
+    Ok(Report {
+        records,
+        skipped,
+        counts: scanner.finish()?.into(),
+        presence,
+    })
+}
+
+/// Counts each operand (standard input when there are none). Returns the
+/// exit code.
+pub fn run(config: &Config) -> i32 {
+    let stdin = ["-".to_string()];
+    let operands = if config.files.is_empty() {
+        &stdin[..]
+    } else {
+        &config.files[..]
+    };
+    for operand in operands {
+        let (label, result) = if operand == "-" {
+            (config.stdin_label(), count(io::stdin().lock(), config))
+        } else {
+            let result = File::open(operand).and_then(|file| count(BufReader::new(file), config));
+            (operand.as_str(), result)
+        };
+        match result {
+            Ok(report) => print_report(label, &report, config),
+            Err(e) => {
+                eprintln!("rwc: {}: {}", label, e);
+                return 1;
+            }
+        }
+    }
+    0
+}
+
+fn print_report(label: &str, report: &Report, config: &Config) {
+    if report.skipped > 0 {
+        output::warn(format!(
+            "rwc: {}: skipped {} lines that are not JSON objects",
+            label, report.skipped
+        ));
+    }
+    let share = |records: usize| records as f64 * 100.0 / report.records.max(1) as f64;
+
+    if config.show_json {
+        let Value::Object(mut members) = counts_json(&report.counts, config, Some(label)) else {
+            unreachable!("counts are an object");
+        };
+        members.insert(1, ("records".to_string(), report.records.into()));
+        members.push(("skipped".to_string(), report.skipped.into()));
+        let fields = report
+            .presence
+            .iter()
+            .map(|(field, &records)| (field.clone(), Value::from(records)))
+            .collect();
+        members.push(("fields".to_string(), Value::Object(fields)));
+        print_json(&Value::Object(members), config);
+        return;
+    }
+
+    print_entry_counts(label, report.records, &report.counts, config);
+    if config.plain {
+        let fields: Vec<(&str, String)> = report
+            .presence
+            .iter()
+            .map(|(field, &records)| {
+                let value =
+                    format!("{} in {} records, {:.1} percent", field, records, share(records));
+                ("field", value)
+            })
+            .collect();
+        if !fields.is_empty() {
+            print_plain(&fields);
+        }
+        return;
+    }
+    let width = report.presence.keys().map(|field| field.chars().count()).max().unwrap_or(0);
+    let lines: Vec<String> = report
+        .presence
+        .iter()
+        .map(|(field, &records)| {
+            format!(
+                "    | {:<width$} {:>8} {:>6.1}%",
+                field,
+                config.format_count(records),
+                share(records),
+                width = width
+            )
+        })
+        .collect();
+    if !lines.is_empty() {
+        output::print(&lines.join("\n"));
+    }
+}
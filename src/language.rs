@@ -0,0 +1,407 @@
+//! Natural-language detection for `--detect-language`, built with the
+//! `language` feature.
+//!
+//! The script of the letters settles most languages: kana means Japanese,
+//! Hangul Korean, Greek letters Greek. Text in Latin or Cyrillic script is
+//! told apart by its commonest words, counted against a short list for each
+//! language. Only the start of the input is looked at (`SAMPLE_CHARS`),
+//! which is plenty to decide and keeps large files cheap.
+//!
+//! Languages are named by ISO 639-1 codes, as `--group-by locale` names
+//! them, or `und` (undetermined) when the text is too short or too evenly
+//! matched to tell.
+
+/// The language of text that could not be told.
+pub const UNDETERMINED: &str = "und";
+
+/// How many characters from the start of the input are looked at.
+const SAMPLE_CHARS: usize = 64 * 1024;
+
+/// Longer words are not compared; no listed word comes close.
+const MAX_WORD_CHARS: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Bengali,
+    Tamil,
+    Thai,
+    Georgian,
+    Armenian,
+    Hangul,
+    Kana,
+    Han,
+}
+
+const ALL: [Script; 14] = [
+    Script::Latin,
+    Script::Cyrillic,
+    Script::Greek,
+    Script::Arabic,
+    Script::Hebrew,
+    Script::Devanagari,
+    Script::Bengali,
+    Script::Tamil,
+    Script::Thai,
+    Script::Georgian,
+    Script::Armenian,
+    Script::Hangul,
+    Script::Kana,
+    Script::Han,
+];
+
+const SCRIPTS: usize = ALL.len();
+
+fn script(c: char) -> Option<Script> {
+    Some(match c {
+        'a'..='z' | 'A'..='Z' | '\u{c0}'..='\u{24f}' | '\u{1e00}'..='\u{1eff}' => Script::Latin,
+        '\u{370}'..='\u{3ff}' | '\u{1f00}'..='\u{1fff}' => Script::Greek,
+        '\u{400}'..='\u{52f}' => Script::Cyrillic,
+        '\u{531}'..='\u{58f}' => Script::Armenian,
+        '\u{590}'..='\u{5ff}' => Script::Hebrew,
+        '\u{600}'..='\u{6ff}' | '\u{750}'..='\u{77f}' => Script::Arabic,
+        '\u{900}'..='\u{97f}' => Script::Devanagari,
+        '\u{980}'..='\u{9ff}' => Script::Bengali,
+        '\u{b80}'..='\u{bff}' => Script::Tamil,
+        '\u{e00}'..='\u{e7f}' => Script::Thai,
+        '\u{10a0}'..='\u{10ff}' => Script::Georgian,
+        '\u{1100}'..='\u{11ff}' | '\u{3130}'..='\u{318f}' | '\u{ac00}'..='\u{d7af}' => {
+            Script::Hangul
+        }
+        '\u{3041}'..='\u{30ff}' | '\u{31f0}'..='\u{31ff}' | '\u{ff66}'..='\u{ff9f}' => Script::Kana,
+        '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}' => Script::Han,
+        _ => return None,
+    })
+}
+
+/// The languages told apart by their words, with the script they are
+/// written in and some of their commonest words, lowercase.
+const WORDS: &[(&str, Script, &[&str])] = &[
+    (
+        "en",
+        Script::Latin,
+        &[
+            "the", "and", "of", "to", "a", "is", "in", "that", "it", "for", "was", "with", "this",
+            "are", "you", "not", "be", "on", "have", "but", "they", "which", "from",
+        ],
+    ),
+    (
+        "de",
+        Script::Latin,
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "ein", "eine", "zu", "den", "mit", "sich",
+            "des", "auf", "für", "dem", "auch", "es", "wird", "sie", "ich", "wir", "von",
+        ],
+    ),
+    (
+        "fr",
+        Script::Latin,
+        &[
+            "le", "la", "les", "et", "des", "est", "une", "que", "pas", "dans", "pour", "qui",
+            "sur", "du", "au", "avec", "ce", "il", "sont", "mais", "nous", "vous", "elle",
+        ],
+    ),
+    (
+        "es",
+        Script::Latin,
+        &[
+            "el", "la", "los", "las", "que", "y", "es", "en", "una", "por", "con", "para", "del",
+            "no", "se", "su", "al", "lo", "como", "pero", "más", "está", "muy",
+        ],
+    ),
+    (
+        "it",
+        Script::Latin,
+        &[
+            "il", "che", "di", "la", "è", "e", "per", "una", "sono", "non", "del", "della", "con",
+            "gli", "le", "nel", "anche", "come", "ma", "questo", "più", "ho",
+        ],
+    ),
+    (
+        "pt",
+        Script::Latin,
+        &[
+            "o", "a", "os", "que", "e", "não", "é", "do", "da", "em", "um", "uma", "para", "com",
+            "se", "dos", "das", "mais", "como", "mas", "foi", "ao", "está",
+        ],
+    ),
+    (
+        "nl",
+        Script::Latin,
+        &[
+            "de", "het", "een", "en", "van", "is", "dat", "niet", "op", "te", "zijn", "met",
+            "voor", "ik", "je", "ook", "maar", "wat", "er", "aan", "bij", "worden",
+        ],
+    ),
+    (
+        "sv",
+        Script::Latin,
+        &[
+            "och", "att", "det", "som", "är", "en", "på", "för", "med", "inte", "av", "den",
+            "till", "har", "jag", "var", "om", "ett", "men", "de", "sig", "kan",
+        ],
+    ),
+    (
+        "da",
+        Script::Latin,
+        &[
+            "og", "at", "det", "er", "en", "til", "på", "som", "med", "for", "ikke", "af", "den",
+            "har", "jeg", "var", "de", "et", "men", "om", "vi", "sig", "hvad", "efter",
+        ],
+    ),
+    (
+        "nb",
+        Script::Latin,
+        &[
+            "og", "i", "det", "er", "som", "en", "til", "på", "at", "med", "for", "ikke", "av",
+            "den", "har", "jeg", "var", "de", "et", "men", "om", "seg", "hva", "etter",
+        ],
+    ),
+    (
+        "fi",
+        Script::Latin,
+        &[
+            "ja", "on", "ei", "se", "että", "hän", "oli", "ovat", "joka", "mutta", "kuin", "tai",
+            "ole", "niin", "myös", "tämä", "sen", "kun", "mitä", "minä", "hyvin",
+        ],
+    ),
+    (
+        "pl",
+        Script::Latin,
+        &[
+            "i", "w", "nie", "na", "się", "z", "że", "do", "to", "jest", "jak", "ale", "o", "co",
+            "od", "po", "tak", "za", "są", "jego", "przez", "dla", "który",
+        ],
+    ),
+    (
+        "cs",
+        Script::Latin,
+        &[
+            "a", "se", "na", "je", "v", "že", "to", "s", "z", "do", "o", "jako", "ale", "jsem",
+            "pro", "by", "jsou", "tak", "od", "není", "které", "také", "byl",
+        ],
+    ),
+    (
+        "tr",
+        Script::Latin,
+        &[
+            "ve", "bir", "bu", "da", "de", "için", "ile", "çok", "daha", "ne", "o", "ama", "gibi",
+            "var", "olarak", "en", "sonra", "kadar", "değil", "ben", "mi",
+        ],
+    ),
+    (
+        "id",
+        Script::Latin,
+        &[
+            "yang", "dan", "di", "itu", "dengan", "untuk", "tidak", "ini", "dari", "dalam", "akan",
+            "pada", "juga", "saya", "ke", "karena", "ada", "bisa", "mereka", "oleh",
+        ],
+    ),
+    (
+        "ro",
+        Script::Latin,
+        &[
+            "și", "de", "în", "la", "este", "că", "nu", "pe", "cu", "o", "un", "să", "care", "mai",
+            "din", "pentru", "ce", "sunt", "se", "dar", "fost",
+        ],
+    ),
+    (
+        "hu",
+        Script::Latin,
+        &[
+            "a", "az", "és", "hogy", "nem", "is", "egy", "van", "meg", "de", "csak", "ez", "el",
+            "mint", "már", "volt", "még", "azt", "ki", "vagy", "kell",
+        ],
+    ),
+    (
+        "vi",
+        Script::Latin,
+        &[
+            "và", "của", "là", "có", "không", "những", "các", "được", "một", "người", "trong",
+            "cho", "này", "với", "đã", "khi", "đến", "như", "cũng", "ở",
+        ],
+    ),
+    (
+        "ru",
+        Script::Cyrillic,
+        &[
+            "и", "в", "не", "на", "что", "я", "с", "он", "как", "это", "по", "но", "из", "у", "к",
+            "за", "от", "так", "все", "она", "они", "был", "мы",
+        ],
+    ),
+    (
+        "uk",
+        Script::Cyrillic,
+        &[
+            "і", "в", "не", "на", "що", "я", "з", "він", "як", "це", "та", "до", "у", "за", "від",
+            "але", "так", "все", "вона", "вони", "був", "ми", "й",
+        ],
+    ),
+    (
+        "bg",
+        Script::Cyrillic,
+        &[
+            "и",
+            "на",
+            "да",
+            "се",
+            "в",
+            "не",
+            "от",
+            "за",
+            "че",
+            "са",
+            "по",
+            "това",
+            "как",
+            "ще",
+            "което",
+            "които",
+            "има",
+            "но",
+            "със",
+            "към",
+            "беше",
+            "той",
+        ],
+    ),
+    (
+        "sr",
+        Script::Cyrillic,
+        &[
+            "и", "у", "је", "да", "се", "на", "не", "за", "од", "са", "што", "као", "из", "али",
+            "били", "био", "су", "то", "који", "која", "овај",
+        ],
+    ),
+];
+
+/// Letters found only in Persian and Urdu among Arabic-script languages:
+/// پ چ ژ گ ک ی for Persian, ٹ ڈ ڑ ں ے for Urdu.
+fn arabic_variant(c: char) -> Option<usize> {
+    match c {
+        'ٹ' | 'ڈ' | 'ڑ' | 'ں' | 'ے' => Some(1),
+        'پ' | 'چ' | 'ژ' | 'گ' | 'ک' | 'ی' => Some(0),
+        _ => None,
+    }
+}
+
+/// Gathers what `finish` decides on, from text pushed in pieces of any size.
+#[derive(Debug, Default)]
+pub struct LanguageScan {
+    seen: usize,
+    /// Letters per script.
+    scripts: [usize; SCRIPTS],
+    /// Letters particular to Persian and to Urdu.
+    arabic_variants: [usize; 2],
+    word: String,
+    word_chars: usize,
+    /// Words matched, per language of `WORDS`.
+    hits: [usize; WORDS.len()],
+}
+
+impl LanguageScan {
+    pub fn push_str(&mut self, text: &str) {
+        for c in text.chars() {
+            if self.seen == SAMPLE_CHARS {
+                self.end_word();
+                return;
+            }
+            self.seen += 1;
+            if !c.is_alphabetic() {
+                self.end_word();
+                continue;
+            }
+            if let Some(script) = script(c) {
+                self.scripts[script as usize] += 1;
+            }
+            if let Some(variant) = arabic_variant(c) {
+                self.arabic_variants[variant] += 1;
+            }
+            self.word_chars += 1;
+            if self.word_chars <= MAX_WORD_CHARS {
+                self.word.extend(c.to_lowercase());
+            }
+        }
+    }
+
+    fn end_word(&mut self) {
+        if self.word_chars <= MAX_WORD_CHARS && !self.word.is_empty() {
+            for (hits, (_, _, words)) in self.hits.iter_mut().zip(WORDS) {
+                if words.contains(&self.word.as_str()) {
+                    *hits += 1;
+                }
+            }
+        }
+        self.word.clear();
+        self.word_chars = 0;
+    }
+
+    /// The ISO 639-1 code of the language, or `UNDETERMINED`.
+    pub fn finish(mut self) -> &'static str {
+        self.end_word();
+        let letters: usize = self.scripts.iter().sum();
+        if letters == 0 {
+            return UNDETERMINED;
+        }
+        let count = |script: Script| self.scripts[script as usize];
+        // Japanese mixes kana with Han characters; Chinese has no kana.
+        let cjk = count(Script::Kana) + count(Script::Han);
+        if cjk * 2 >= letters {
+            return if count(Script::Kana) * 10 >= cjk {
+                "ja"
+            } else {
+                "zh"
+            };
+        }
+        let dominant = ALL
+            .into_iter()
+            .max_by_key(|&script| count(script))
+            .expect("there are scripts");
+        match dominant {
+            Script::Latin | Script::Cyrillic => self.by_words(dominant),
+            Script::Greek => "el",
+            Script::Arabic => {
+                // A letter in a hundred is well above chance.
+                let [persian, urdu] = self.arabic_variants;
+                if urdu * 100 >= letters {
+                    "ur"
+                } else if persian * 100 >= letters {
+                    "fa"
+                } else {
+                    "ar"
+                }
+            }
+            Script::Hebrew => "he",
+            Script::Devanagari => "hi",
+            Script::Bengali => "bn",
+            Script::Tamil => "ta",
+            Script::Thai => "th",
+            Script::Georgian => "ka",
+            Script::Armenian => "hy",
+            Script::Hangul => "ko",
+            Script::Kana => "ja",
+            Script::Han => "zh",
+        }
+    }
+
+    /// The language of `script` whose words were matched most, if it leads
+    /// clearly enough to be told.
+    fn by_words(&self, script: Script) -> &'static str {
+        let mut ranked: Vec<(usize, &'static str)> = WORDS
+            .iter()
+            .zip(self.hits)
+            .filter(|((_, written_in, _), _)| *written_in == script)
+            .map(|((code, _, _), hits)| (hits, *code))
+            .collect();
+        ranked.sort_by_key(|entry| std::cmp::Reverse(entry.0));
+        match ranked.as_slice() {
+            [(best, code), (second, _), ..] if *best >= 2 && *best > *second => code,
+            _ => UNDETERMINED,
+        }
+    }
+}
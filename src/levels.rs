@@ -0,0 +1,57 @@
+//! `--log-levels`: lines per severity, the `grep ERROR | wc -l` chore.
+//!
+//! A line's level is the first word in it that names one, compared without
+//! regard to case, so `ERROR`, `[error]` and `"level":"error"` all count.
+//! Less common names are folded into the four reported levels.
+
+/// Reported levels, in column order.
+pub const LEVELS: [&str; 4] = ["error", "warn", "info", "debug"];
+
+/// Words naming a level, with the index into `LEVELS` they count towards.
+const NAMES: &[(&str, usize)] = &[
+    ("fatal", 0),
+    ("critical", 0),
+    ("crit", 0),
+    ("severe", 0),
+    ("error", 0),
+    ("err", 0),
+    ("warning", 1),
+    ("warn", 1),
+    ("notice", 2),
+    ("info", 2),
+    ("debug", 3),
+    ("trace", 3),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct LevelCounts {
+    /// Lines at each of `LEVELS`.
+    pub lines: [usize; 4],
+}
+
+impl LevelCounts {
+    pub fn push(&mut self, line: &str) {
+        let level = line
+            .split(|c: char| !c.is_ascii_alphabetic())
+            .find_map(|word| {
+                NAMES
+                    .iter()
+                    .find(|(name, _)| word.eq_ignore_ascii_case(name))
+                    .map(|&(_, level)| level)
+            });
+        if let Some(level) = level {
+            self.lines[level] += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &LevelCounts) {
+        for (mine, theirs) in self.lines.iter_mut().zip(other.lines) {
+            *mine += theirs;
+        }
+    }
+
+    /// Each level's name with its line count.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        LEVELS.iter().copied().zip(self.lines.iter().copied())
+    }
+}
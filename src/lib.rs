@@ -0,0 +1,28 @@
+//! The parts of rwc that other programs can build on: the counting engine
+//! and what it is made of.
+//!
+//! The `rwc` binary counts with these same modules, so a program using this
+//! library, its WebAssembly build (the `wasm` feature) or its C API (the
+//! `cdylib` feature) gets the same counts as the command line, and a
+//! tokenizer written against it defines words exactly as `--tokenizer`
+//! would. Its output formats sit behind the same `format::OutputFormatter`
+//! trait as the binary's, so a program can add its own.
+
+pub mod distinct;
+pub mod engine;
+#[cfg(feature = "cdylib")]
+pub mod ffi;
+pub mod format;
+pub mod generated;
+pub mod indent;
+pub mod json;
+#[cfg(feature = "language")]
+pub mod language;
+pub mod levels;
+pub mod memory;
+pub mod regex;
+pub mod tokenizer;
+#[cfg(feature = "wasm")]
+mod wasm;
+pub mod weights;
+pub mod width;
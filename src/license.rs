@@ -0,0 +1,104 @@
+//! `--license-header PATTERN`: audit files for a license header.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::json::{self, Value};
+use crate::regex::Regex;
+use crate::{bidi, is_generated, output, walk, Config};
+
+/// How many leading lines are searched when `--header-lines` is not given.
+pub const DEFAULT_HEADER_LINES: usize = 10;
+
+#[derive(Clone)]
+pub struct HeaderCheck {
+    pub pattern: Regex,
+    /// Number of leading lines searched for the pattern.
+    pub lines: usize,
+    /// Exit with status 1 when any file is missing the header.
+    pub require: bool,
+}
+
+impl HeaderCheck {
+    /// Whether one of the first `self.lines` lines read from `reader` matches.
+    pub fn has_header<R: Read>(&self, reader: R) -> io::Result<bool> {
+        let reader = BufReader::new(reader);
+        for line in reader.split(b'\n').take(self.lines) {
+            let line = line?;
+            if !self.pattern.find_iter(&String::from_utf8_lossy(&line)).is_empty() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Lists the files under the configured operands that lack the header.
+/// Returns the exit code.
+pub fn run(config: &Config, check: &HeaderCheck) -> i32 {
+    if config.files.is_empty() {
+        eprintln!("rwc: --license-header needs FILE or directory operands");
+        return 1;
+    }
+
+    let mut budget = walk::Budget::new(config.limits);
+    let mut checked = 0;
+    let mut missing: Vec<PathBuf> = Vec::new();
+    for operand in &config.files {
+        if budget.is_exhausted() {
+            break;
+        }
+        let paths = match walk::files_with(Path::new(operand), &mut budget, &config.walk) {
+            Ok(paths) => paths,
+            Err(e) => {
+                eprintln!("rwc: {}: {}", operand, e);
+                return 1;
+            }
+        };
+        for path in paths {
+            if config.no_generated && is_generated(&path) {
+                continue;
+            }
+            match File::open(&path).and_then(|file| check.has_header(file)) {
+                Ok(true) => {}
+                Ok(false) => missing.push(path),
+                Err(e) => {
+                    eprintln!("rwc: {}: {}", path.display(), e);
+                    return 1;
+                }
+            }
+            checked += 1;
+        }
+    }
+    budget.warn_if_exhausted();
+
+    if config.show_json {
+        let missing = missing
+            .iter()
+            .map(|path| Value::from(path.display().to_string()))
+            .collect();
+        output::print(&format!(
+            "{}",
+            json::object([
+                ("checked", checked.into()),
+                ("missing", Value::Array(missing)),
+            ])
+        ));
+    } else {
+        for path in &missing {
+            output::print(&bidi::display_name(&path.display().to_string()));
+        }
+        eprintln!(
+            "rwc: {} of {} files missing a license header",
+            missing.len(),
+            checked
+        );
+    }
+
+    if check.require && !missing.is_empty() {
+        1
+    } else {
+        0
+    }
+}
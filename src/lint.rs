@@ -0,0 +1,115 @@
+//! `--lint-line-length N`: list lines longer than N columns.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::json::{self, Value};
+use crate::{bidi, is_generated, output, walk, width, Config};
+
+/// `(line number, width)` of every line `reader` yields that is wider than
+/// `max` columns.
+fn long_lines<R: Read>(reader: R, max: usize) -> io::Result<Vec<(usize, usize)>> {
+    let mut found = Vec::new();
+    for (index, line) in BufReader::new(reader).split(b'\n').enumerate() {
+        let line = line?;
+        let line = line.strip_suffix(b"\r").unwrap_or(&line);
+        let width = width::str_width(&String::from_utf8_lossy(line));
+        if width > max {
+            found.push((index + 1, width));
+        }
+    }
+    Ok(found)
+}
+
+/// Reports the long lines in every file under the configured operands
+/// (standard input when there are none). Returns the exit code: 1 if any
+/// line is too long, 2 if a file could not be read.
+pub fn run(config: &Config, max: usize) -> i32 {
+    let stdin = ["-".to_string()];
+    let operands = if config.files.is_empty() {
+        &stdin[..]
+    } else {
+        &config.files[..]
+    };
+
+    let mut budget = walk::Budget::new(config.limits);
+    let mut checked = 0;
+    let mut found: Vec<(String, usize, usize)> = Vec::new();
+    for operand in operands {
+        if budget.is_exhausted() {
+            break;
+        }
+        let paths = if operand == "-" {
+            vec![operand.into()]
+        } else {
+            match walk::files_with(Path::new(operand), &mut budget, &config.walk) {
+                Ok(paths) => paths,
+                Err(e) => {
+                    eprintln!("rwc: {}: {}", operand, e);
+                    return 2;
+                }
+            }
+        };
+        for path in paths {
+            if config.no_generated && is_generated(&path) {
+                continue;
+            }
+            let (name, result) = if path.as_os_str() == "-" {
+                let name = config.stdin_label().to_string();
+                (name, long_lines(io::stdin().lock(), max))
+            } else {
+                let name = path.display().to_string();
+                (name, File::open(&path).and_then(|file| long_lines(file, max)))
+            };
+            match result {
+                Ok(lines) => {
+                    found.extend(lines.into_iter().map(|(line, width)| (name.clone(), line, width)))
+                }
+                Err(e) => {
+                    eprintln!("rwc: {}: {}", name, e);
+                    return 2;
+                }
+            }
+            checked += 1;
+        }
+    }
+    budget.warn_if_exhausted();
+
+    if config.show_json {
+        let lines = found
+            .iter()
+            .map(|(file, line, width)| {
+                json::object([
+                    ("file", file.as_str().into()),
+                    ("line", (*line).into()),
+                    ("length", (*width).into()),
+                ])
+            })
+            .collect();
+        output::print(&format!(
+            "{}",
+            json::object([
+                ("max", max.into()),
+                ("checked", checked.into()),
+                ("lines", Value::Array(lines)),
+            ])
+        ));
+    } else {
+        for (file, line, width) in &found {
+            output::print(&format!("{}:{}:{}", bidi::display_name(file), line, width));
+        }
+        eprintln!(
+            "rwc: {} lines longer than {} columns in {} files",
+            found.len(),
+            max,
+            checked
+        );
+    }
+
+    if found.is_empty() {
+        0
+    } else {
+        1
+    }
+}
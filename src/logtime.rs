@@ -0,0 +1,488 @@
+//! `--since`/`--until` for timestamped log files.
+//!
+//! Each line's timestamp is read from its start (after leading spaces and an
+//! optional `[`) with the `--timestamp-format` in effect. Lines without one,
+//! such as the rest of a stack trace, go with the line before them; lines
+//! before the first timestamp are left out. With `--sorted`, the file is
+//! trusted to be in time order: the start of the range is found by bisecting
+//! the file with seeks, and reading stops at the first line past its end.
+
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::time::{days_from_civil, days_in_month, year_of};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Item {
+    /// `%Y`: four-digit year.
+    Year,
+    /// `%m`
+    Month,
+    /// `%b`: `Jan` to `Dec`.
+    MonthName,
+    /// `%d`, or `%e` which may be padded with a space.
+    Day,
+    /// `%H`
+    Hour,
+    /// `%M`
+    Minute,
+    /// `%S`, with an optional `.` or `,` fraction.
+    Second,
+    /// `%z`: `Z`, `+HH:MM` or `+HHMM`.
+    Zone,
+    /// `%s`: seconds since the Unix epoch, with an optional fraction.
+    Epoch,
+    Literal(char),
+}
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// How timestamps are written; a line matches if any pattern does.
+#[derive(Clone, Debug)]
+pub struct TimestampFormat {
+    patterns: Vec<Vec<Item>>,
+}
+
+impl TimestampFormat {
+    /// Parses `iso` (RFC 3339 and `YYYY-MM-DD HH:MM:SS`), `syslog`
+    /// (`Jan  2 15:04:05`, in the current year), `epoch`, or a pattern using
+    /// `%Y %m %d %e %b %H %M %S %z %s %%`.
+    pub fn parse(text: &str) -> Result<TimestampFormat, String> {
+        let sources: &[&str] = match text {
+            "iso" => &[
+                "%Y-%m-%dT%H:%M:%S%z",
+                "%Y-%m-%dT%H:%M:%S",
+                "%Y-%m-%d %H:%M:%S%z",
+                "%Y-%m-%d %H:%M:%S",
+            ],
+            "syslog" => &["%b %e %H:%M:%S"],
+            "epoch" => &["%s"],
+            pattern => &[pattern],
+        };
+        let patterns = sources
+            .iter()
+            .map(|source| parse_pattern(source))
+            .collect::<Result<_, _>>()?;
+        Ok(TimestampFormat { patterns })
+    }
+
+    /// The timestamp at the start of `line`, if it has one.
+    pub fn find(&self, line: &str) -> Option<SystemTime> {
+        let line = line.trim_start();
+        let line = line.strip_prefix('[').unwrap_or(line);
+        self.patterns.iter().find_map(|pattern| match_pattern(pattern, line))
+    }
+}
+
+fn parse_pattern(source: &str) -> Result<Vec<Item>, String> {
+    let mut items = Vec::new();
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            items.push(Item::Literal(c));
+            continue;
+        }
+        items.push(match chars.next() {
+            Some('Y') => Item::Year,
+            Some('m') => Item::Month,
+            Some('b') => Item::MonthName,
+            Some('d' | 'e') => Item::Day,
+            Some('H') => Item::Hour,
+            Some('M') => Item::Minute,
+            Some('S') => Item::Second,
+            Some('z') => Item::Zone,
+            Some('s') => Item::Epoch,
+            Some('%') => Item::Literal('%'),
+            other => {
+                return Err(format!(
+                    "unsupported timestamp field '%{}' (expected %Y %m %d %e %b %H %M %S %z %s)",
+                    other.map(String::from).unwrap_or_default()
+                ))
+            }
+        });
+    }
+    if !items.iter().any(|item| matches!(item, Item::Epoch | Item::Day)) {
+        return Err(format!("timestamp format '{}' has no date (%d or %s)", source));
+    }
+    Ok(items)
+}
+
+/// Takes between `min` and `max` leading ASCII digits from `text`.
+fn digits<'t>(text: &mut &'t str, min: usize, max: usize) -> Option<&'t str> {
+    let len = text.bytes().take(max).take_while(u8::is_ascii_digit).count();
+    if len < min {
+        return None;
+    }
+    let (number, rest) = text.split_at(len);
+    *text = rest;
+    Some(number)
+}
+
+/// Takes an optional `.123` or `,123` fraction of a second.
+fn fraction(text: &mut &str) -> f64 {
+    let Some(rest) = text.strip_prefix(['.', ',']) else {
+        return 0.0;
+    };
+    let mut rest = rest;
+    match digits(&mut rest, 1, 9) {
+        Some(digits) => {
+            *text = rest;
+            format!("0.{}", digits).parse().unwrap_or(0.0)
+        }
+        None => 0.0,
+    }
+}
+
+fn match_pattern(pattern: &[Item], mut text: &str) -> Option<SystemTime> {
+    let mut year = None;
+    let (mut month, mut day, mut hour, mut minute) = (1, 1, 0, 0);
+    let mut second = 0.0;
+    let mut offset = 0;
+    let mut epoch = None;
+    for &item in pattern {
+        match item {
+            Item::Year => year = Some(digits(&mut text, 4, 4)?.parse::<i64>().ok()?),
+            Item::Month => month = digits(&mut text, 1, 2)?.parse().ok()?,
+            Item::MonthName => {
+                let name = text.get(..3)?.to_ascii_lowercase();
+                month = MONTHS.iter().position(|m| *m == name)? as u32 + 1;
+                text = &text[3..];
+            }
+            Item::Day => {
+                text = text.strip_prefix(' ').unwrap_or(text);
+                day = digits(&mut text, 1, 2)?.parse().ok()?;
+            }
+            Item::Hour => hour = digits(&mut text, 1, 2)?.parse().ok()?,
+            Item::Minute => minute = digits(&mut text, 2, 2)?.parse().ok()?,
+            Item::Second => {
+                second = digits(&mut text, 2, 2)?.parse::<f64>().ok()? + fraction(&mut text);
+            }
+            Item::Zone => {
+                if let Some(rest) = text.strip_prefix(['Z', 'z']) {
+                    text = rest;
+                    continue;
+                }
+                let sign = match text.chars().next()? {
+                    '+' => 1,
+                    '-' => -1,
+                    _ => return None,
+                };
+                text = &text[1..];
+                let hours: i64 = digits(&mut text, 2, 2)?.parse().ok()?;
+                text = text.strip_prefix(':').unwrap_or(text);
+                let minutes: i64 = digits(&mut text, 2, 2).map_or(Some(0), |m| m.parse().ok())?;
+                offset = sign * (hours * 3600 + minutes * 60);
+            }
+            Item::Epoch => {
+                let seconds = digits(&mut text, 1, 12)?.parse::<f64>().ok()?;
+                epoch = Some(seconds + fraction(&mut text));
+            }
+            Item::Literal(c) => text = text.strip_prefix(c)?,
+        }
+    }
+    if let Some(seconds) = epoch {
+        return Some(UNIX_EPOCH + Duration::from_secs_f64(seconds));
+    }
+    // A second of 60 is a leap second.
+    if !(1..=12).contains(&month) || day == 0 || hour > 23 || minute > 59 || second >= 61.0 {
+        return None;
+    }
+    let at = |year: i64| {
+        if day > days_in_month(year, month) {
+            return None;
+        }
+        let days = days_from_civil(year, month, day);
+        let secs = days * 86400 + i64::from(hour) * 3600 + i64::from(minute) * 60 - offset;
+        let whole = UNIX_EPOCH.checked_add(Duration::from_secs(u64::try_from(secs).ok()?))?;
+        whole.checked_add(Duration::from_secs_f64(second))
+    };
+    match year {
+        Some(year) => at(year),
+        None => {
+            // Syslog leaves the year out: take the most recent one that does
+            // not put the line in the future.
+            let now = SystemTime::now();
+            let this_year = year_of(now);
+            at(this_year)
+                .filter(|&t| t <= now + Duration::from_secs(86400))
+                .or_else(|| at(this_year - 1))
+        }
+    }
+}
+
+/// The `--since`/`--until` window and how to find timestamps.
+#[derive(Clone, Debug)]
+pub struct TimeRange {
+    pub format: TimestampFormat,
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+    /// `--sorted`: lines are in time order.
+    pub sorted: bool,
+}
+
+impl TimeRange {
+    fn contains(&self, time: SystemTime) -> bool {
+        self.since.is_none_or(|since| time >= since) && self.until.is_none_or(|until| time <= until)
+    }
+
+    /// With `--sorted` and `--since`, moves `input` to the start of a line
+    /// at or shortly before the first line in range, by bisecting on the
+    /// timestamps found after each probe.
+    pub fn seek_to_start<R: Read + Seek>(&self, input: &mut R) -> io::Result<()> {
+        let Some(since) = self.since.filter(|_| self.sorted) else {
+            return Ok(());
+        };
+        let len = input.seek(SeekFrom::End(0))?;
+        // `low` is always the start of a line (or of the file) before the range.
+        let (mut low, mut high) = (0, len);
+        while high - low > 64 * 1024 {
+            let middle = low + (high - low) / 2;
+            match self.first_timestamp_after(input, middle)? {
+                Some((start, time)) if time < since && start < high => low = start,
+                _ => high = middle,
+            }
+        }
+        input.seek(SeekFrom::Start(low))?;
+        Ok(())
+    }
+
+    /// The start and timestamp of the first timestamped line that starts
+    /// after `offset`.
+    fn first_timestamp_after<R: Read + Seek>(
+        &self,
+        input: &mut R,
+        offset: u64,
+    ) -> io::Result<Option<(u64, SystemTime)>> {
+        input.seek(SeekFrom::Start(offset))?;
+        let mut reader = io::BufReader::new(input);
+        let mut line = Vec::new();
+        // Skip the rest of the line the probe landed in.
+        let mut position = offset + reader.read_until(b'\n', &mut line)? as u64;
+        loop {
+            line.clear();
+            let n = reader.read_until(b'\n', &mut line)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            if let Some(time) = self.format.find(&String::from_utf8_lossy(&line)) {
+                return Ok(Some((position, time)));
+            }
+            position += n as u64;
+        }
+    }
+}
+
+/// A reader yielding only the lines of `inner` within a `TimeRange`.
+pub struct Filtered<'a, R> {
+    inner: R,
+    range: &'a TimeRange,
+    line: Vec<u8>,
+    /// How much of `line` has been returned.
+    taken: usize,
+    /// Whether the current record (the last timestamped line and the lines
+    /// after it) is in range.
+    keep: bool,
+    done: bool,
+}
+
+impl<'a, R: BufRead> Filtered<'a, R> {
+    pub fn new(inner: R, range: &'a TimeRange) -> Self {
+        Filtered {
+            inner,
+            range,
+            line: Vec::new(),
+            taken: 0,
+            keep: false,
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Read for Filtered<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        while self.taken == self.line.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.line.clear();
+            self.taken = 0;
+            if self.inner.read_until(b'\n', &mut self.line)? == 0 {
+                self.done = true;
+                return Ok(0);
+            }
+            if let Some(time) = self.range.format.find(&String::from_utf8_lossy(&self.line)) {
+                if self.range.sorted && self.range.until.is_some_and(|until| time > until) {
+                    self.line.clear();
+                    self.done = true;
+                    return Ok(0);
+                }
+                self.keep = self.range.contains(time);
+            }
+            if !self.keep {
+                self.line.clear();
+            }
+        }
+        let n = buffer.len().min(self.line.len() - self.taken);
+        buffer[..n].copy_from_slice(&self.line[self.taken..self.taken + n]);
+        self.taken += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    /// 2024-03-01T00:00:00Z.
+    const MARCH: u64 = 1_709_251_200;
+
+    #[test]
+    fn formats_and_their_errors() {
+        assert!(TimestampFormat::parse("iso").is_ok());
+        assert!(TimestampFormat::parse("%d/%b/%Y:%H:%M:%S %z").is_ok());
+        assert_eq!(
+            TimestampFormat::parse("%Y-%q").unwrap_err(),
+            "unsupported timestamp field '%q' (expected %Y %m %d %e %b %H %M %S %z %s)"
+        );
+        assert_eq!(
+            TimestampFormat::parse("50%").unwrap_err(),
+            "unsupported timestamp field '%' (expected %Y %m %d %e %b %H %M %S %z %s)"
+        );
+        assert_eq!(
+            TimestampFormat::parse("%H:%M").unwrap_err(),
+            "timestamp format '%H:%M' has no date (%d or %s)"
+        );
+    }
+
+    #[test]
+    fn iso_timestamps() {
+        let iso = TimestampFormat::parse("iso").unwrap();
+        assert_eq!(iso.find("2024-03-01T00:00:00Z boot"), Some(at(MARCH)));
+        assert_eq!(iso.find("  [2024-03-01 01:30:00+01:30] x"), Some(at(MARCH)));
+        assert_eq!(iso.find("2024-02-29T23:00:00-0100"), Some(at(MARCH)));
+        assert_eq!(
+            iso.find("2024-03-01 00:00:01,250 INFO"),
+            Some(at(MARCH + 1) + Duration::from_millis(250))
+        );
+        assert_eq!(iso.find("2024-03-01T00:00:60Z"), Some(at(MARCH + 60)));
+        for line in [
+            "",
+            "INFO 2024-03-01T00:00:00Z",
+            "2024-02-30T00:00:00Z",
+            "2023-02-29 00:00:00",
+            "2024-13-01 00:00:00",
+            "2024-03-00 00:00:00",
+            "2024-03-01 24:00:00",
+            "2024-03-01 00:60:00",
+            "2024-03-01 00:00:61",
+            "2024-03-01 0:0:0",
+            "24-03-01 00:00:00",
+            "1969-12-31 23:59:59",
+            "é024-03-01 00:00:00",
+        ] {
+            assert_eq!(iso.find(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn syslog_epoch_and_custom_timestamps() {
+        let syslog = TimestampFormat::parse("syslog").unwrap();
+        let new_year = syslog.find("Jan  1 00:00:00 host kernel: up").unwrap();
+        assert_eq!(year_of(new_year), year_of(SystemTime::now()));
+        assert_eq!(syslog.find("jan 1 00:00:00"), Some(new_year));
+        assert_eq!(syslog.find("Jab  1 00:00:00"), None);
+        assert_eq!(syslog.find("Ja"), None);
+
+        let epoch = TimestampFormat::parse("epoch").unwrap();
+        assert_eq!(epoch.find("1709251200 x"), Some(at(MARCH)));
+        assert_eq!(
+            epoch.find("1709251200.5"),
+            Some(at(MARCH) + Duration::from_millis(500))
+        );
+        assert_eq!(epoch.find("x 1709251200"), None);
+
+        let apache = TimestampFormat::parse("%d/%b/%Y:%H:%M:%S %z").unwrap();
+        assert_eq!(
+            apache.find("[01/Mar/2024:02:00:00 +0200] GET /"),
+            Some(at(MARCH))
+        );
+        assert_eq!(apache.find("[01/Mar/2024:02:00:00] GET /"), None);
+        let percent = TimestampFormat::parse("%%%Y%m%d").unwrap();
+        assert_eq!(percent.find("%20240301"), Some(at(MARCH)));
+    }
+
+    fn range(since: Option<u64>, until: Option<u64>, sorted: bool) -> TimeRange {
+        TimeRange {
+            format: TimestampFormat::parse("epoch").unwrap(),
+            since: since.map(at),
+            until: until.map(at),
+            sorted,
+        }
+    }
+
+    fn filtered(text: &str, range: &TimeRange) -> String {
+        let mut out = String::new();
+        Filtered::new(text.as_bytes(), range)
+            .read_to_string(&mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn records_in_range_are_kept_whole() {
+        let text = "preamble\n10 a\n20 b\n  trace of b\n30 c\n25 d\nlast";
+        let unsorted = range(Some(20), Some(25), false);
+        assert_eq!(filtered(text, &unsorted), "20 b\n  trace of b\n25 d\nlast");
+        // Sorted input stops at the first line past the end.
+        let sorted = range(Some(20), Some(25), true);
+        assert_eq!(filtered(text, &sorted), "20 b\n  trace of b\n");
+        assert_eq!(filtered(text, &range(None, None, false)), &text[9..]);
+        assert_eq!(filtered("", &unsorted), "");
+        assert_eq!(filtered("no timestamps\nat all\n", &unsorted), "");
+    }
+
+    #[test]
+    fn sorted_input_is_bisected() {
+        let text: String = (0..100_000)
+            .map(|n| format!("{} line {}\n", 1_000 + n, n))
+            .collect();
+        let range = range(Some(80_000), None, true);
+        let mut input = Cursor::new(text.as_bytes());
+        range.seek_to_start(&mut input).unwrap();
+        let start = input.position() as usize;
+        assert!(start > 0 && (start == text.len() || text.as_bytes()[start - 1] == b'\n'));
+        let first: u64 = text[start..]
+            .split_whitespace()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(first <= 80_000 && 80_000 - first < 10_000, "{}", first);
+        assert!(text[..start]
+            .lines()
+            .all(|line| line.starts_with(|c: char| c.is_ascii_digit())));
+
+        // Unsorted, or without --since, it stays at the start.
+        for range in [
+            self::range(Some(80_000), None, false),
+            self::range(None, Some(5), true),
+        ] {
+            let mut input = Cursor::new(text.as_bytes());
+            range.seek_to_start(&mut input).unwrap();
+            assert_eq!(input.position(), 0);
+        }
+        // A range starting after everything leaves little to read.
+        let mut input = Cursor::new(text.as_bytes());
+        self::range(Some(u64::from(u32::MAX)), None, true)
+            .seek_to_start(&mut input)
+            .unwrap();
+        assert!(text.len() - input.position() as usize <= 64 * 1024);
+    }
+}
@@ -0,0 +1,229 @@
+//! `rwc lsp`: a minimal language server that publishes document statistics.
+//!
+//! Open documents are synchronised in full; after every change the server
+//! publishes an Information diagnostic on the first line with word/char counts
+//! and reading time, plus an `rwc/status` notification carrying the raw
+//! numbers for status-bar integrations.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use crate::json::{self, Value};
+use crate::{count_text, reading_minutes};
+
+/// LSP `TextDocumentSyncKind.Full`.
+const SYNC_FULL: usize = 1;
+/// LSP `DiagnosticSeverity.Information`.
+const SEVERITY_INFORMATION: usize = 3;
+/// JSON-RPC "method not found".
+const METHOD_NOT_FOUND: f64 = -32601.0;
+
+/// Reads one `Content-Length`-framed message, or `None` at end of input.
+fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    let text = String::from_utf8(body)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "message is not UTF-8"))?;
+    json::parse(&text)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+fn position(line: usize, character: usize) -> Value {
+    json::object([("line", line.into()), ("character", character.into())])
+}
+
+/// Builds the diagnostics and status notifications for a document.
+fn publish(uri: &str, text: Option<&str>) -> [Value; 2] {
+    let Some(text) = text else {
+        return [
+            notification(
+                "textDocument/publishDiagnostics",
+                json::object([("uri", uri.into()), ("diagnostics", Value::Array(Vec::new()))]),
+            ),
+            notification("rwc/status", json::object([("uri", uri.into())])),
+        ];
+    };
+
+    let counts = count_text(text);
+    let minutes = reading_minutes(counts.words);
+    let message = format!(
+        "{} words · {} chars · {} lines · {} min read",
+        counts.words, counts.chars, counts.lines, minutes
+    );
+    let first_line_len = text.lines().next().map_or(0, |l| l.encode_utf16().count());
+    let diagnostic = json::object([
+        (
+            "range",
+            json::object([("start", position(0, 0)), ("end", position(0, first_line_len))]),
+        ),
+        ("severity", SEVERITY_INFORMATION.into()),
+        ("source", "rwc".into()),
+        ("message", message.into()),
+    ]);
+
+    [
+        notification(
+            "textDocument/publishDiagnostics",
+            json::object([("uri", uri.into()), ("diagnostics", Value::Array(vec![diagnostic]))]),
+        ),
+        notification(
+            "rwc/status",
+            json::object([
+                ("uri", uri.into()),
+                ("lines", counts.lines.into()),
+                ("words", counts.words.into()),
+                ("chars", counts.chars.into()),
+                ("bytes", counts.bytes.into()),
+                ("readingMinutes", minutes.into()),
+            ]),
+        ),
+    ]
+}
+
+fn text_document_uri(params: &Value) -> Option<&str> {
+    params.get("textDocument")?.get("uri")?.as_str()
+}
+
+/// Runs the server on stdin/stdout until `exit`. Returns the process exit code.
+pub fn run() -> i32 {
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let mut writer = io::stdout().lock();
+    let mut documents: HashMap<String, String> = HashMap::new();
+    let mut shutdown = false;
+
+    loop {
+        let message = match read_message(&mut reader) {
+            Ok(Some(message)) => message,
+            Ok(None) => return 1,
+            Err(e) => {
+                eprintln!("rwc lsp: {}", e);
+                return 1;
+            }
+        };
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let id = message.get("id").cloned();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let mut outgoing = Vec::new();
+        let mut changed = None;
+        match method {
+            "initialize" => outgoing.push(json::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone().unwrap_or(Value::Null)),
+                (
+                    "result",
+                    json::object([
+                        (
+                            "capabilities",
+                            json::object([("textDocumentSync", SYNC_FULL.into())]),
+                        ),
+                        (
+                            "serverInfo",
+                            json::object([
+                                ("name", "rwc".into()),
+                                ("version", env!("CARGO_PKG_VERSION").into()),
+                            ]),
+                        ),
+                    ]),
+                ),
+            ])),
+            "shutdown" => {
+                shutdown = true;
+                outgoing.push(json::object([
+                    ("jsonrpc", "2.0".into()),
+                    ("id", id.clone().unwrap_or(Value::Null)),
+                    ("result", Value::Null),
+                ]));
+            }
+            "exit" => return if shutdown { 0 } else { 1 },
+            "textDocument/didOpen" => {
+                let document = params.get("textDocument");
+                let uri = document.and_then(|d| d.get("uri")).and_then(Value::as_str);
+                let text = document.and_then(|d| d.get("text")).and_then(Value::as_str);
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    documents.insert(uri.to_string(), text.to_string());
+                    changed = Some(uri.to_string());
+                }
+            }
+            "textDocument/didChange" => {
+                // With full sync the last change holds the complete text.
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Value::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Value::as_str);
+                if let (Some(uri), Some(text)) = (text_document_uri(&params), text) {
+                    documents.insert(uri.to_string(), text.to_string());
+                    changed = Some(uri.to_string());
+                }
+            }
+            "textDocument/didClose" => {
+                if let Some(uri) = text_document_uri(&params) {
+                    documents.remove(uri);
+                    changed = Some(uri.to_string());
+                }
+            }
+            _ => {
+                // Unknown requests get an error; unknown notifications are ignored.
+                if let Some(id) = id {
+                    outgoing.push(json::object([
+                        ("jsonrpc", "2.0".into()),
+                        ("id", id),
+                        (
+                            "error",
+                            json::object([
+                                ("code", Value::Number(METHOD_NOT_FOUND)),
+                                ("message", format!("method not found: {}", method).into()),
+                            ]),
+                        ),
+                    ]));
+                }
+            }
+        }
+
+        if let Some(uri) = changed {
+            outgoing.extend(publish(&uri, documents.get(&uri).map(String::as_str)));
+        }
+        for message in &outgoing {
+            if let Err(e) = write_message(&mut writer, message) {
+                eprintln!("rwc lsp: {}", e);
+                return 1;
+            }
+        }
+    }
+}
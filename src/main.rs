@@ -1,7 +1,99 @@
+mod alert;
+mod archive;
+mod badge;
+mod batch;
+mod bidi;
+mod blame;
+mod cli;
+#[cfg(feature = "arrow")]
+mod columnar;
+mod commit_msg;
+mod compare_dirs;
+mod corpus;
+mod daemon;
+mod decompress;
+mod editor;
+mod encoding;
+mod eventlog;
+mod external;
+mod filetype;
+mod filter;
+mod follow;
+mod formats;
+mod freq;
+mod frontmatter;
+mod git;
+mod glob;
+mod group;
+mod help;
+mod hook;
+mod i18n;
+mod inflate;
+mod interactive;
+mod interrupt;
+mod image;
+#[cfg(all(target_os = "linux", feature = "journal"))]
+mod journal;
+mod jsonlogs;
+mod license;
+mod lint;
+mod logtime;
+mod lsp;
+mod metadata;
+mod metric;
+mod mmap;
+mod output;
+mod overrides;
+mod patch;
+mod pool;
+mod preview;
+mod ranges;
+mod readtime;
+mod remote;
+mod report_diff;
+mod sample;
+mod schema;
+mod sha256;
+mod sidecar;
+mod split;
+mod stats;
+mod status;
+mod structure;
+mod summary;
+#[cfg(feature = "sql")]
+mod sql;
+#[cfg(feature = "sql")]
+mod sqlite;
+mod tar;
+mod thresholds;
+mod time;
+mod toc;
+mod todos;
+mod toml;
+mod transform;
+mod translation;
+mod validate;
+mod walk;
+mod watch;
+mod window;
+mod xlsx;
+mod xml;
+mod zip;
+
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
-use std::path::Path;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use rwc::{
+    distinct, engine, format, generated, indent, json, levels, memory, regex, tokenizer, weights,
+    width,
+};
+
+use json::Value;
 
 #[derive(Default, Debug)]
 struct Counts {
@@ -9,19 +101,461 @@ struct Counts {
     chars: usize,
     words: usize,
     lines: usize,
+    /// Display width of the longest line, as in `wc -L`; totals keep the
+    /// maximum.
+    max_line_length: usize,
+    /// The same with tabs at `--tab-size`, for `--max-display-width`.
+    max_display_width: usize,
+    /// Blocks of lines separated by blank lines.
+    paragraphs: usize,
+    /// Lines holding nothing but whitespace.
+    blank_lines: usize,
+    sentences: usize,
+    uniques: Option<distinct::Uniques>,
+    /// Weighted pattern score, present with `--weights`.
+    score: Option<f64>,
+    /// Sample lines for `--preview`; never merged into totals.
+    preview: Option<Vec<String>>,
+    /// Minified-content score for `--minified`; per file, never merged.
+    minified: Option<f64>,
+    /// Hex SHA-256 of the raw input, taken while it is read for `--sidecar`.
+    sha256: Option<String>,
+    indent: Option<indent::IndentStats>,
+    /// Lines when soft-wrapped at `--wrap-at` columns.
+    visual_lines: Option<usize>,
+    /// Logical records for `--record-start`.
+    records: Option<usize>,
+    /// Lines per severity for `--log-levels`.
+    levels: Option<levels::LevelCounts>,
+    /// Document structure for `--structure`; per file, never merged.
+    structure: Option<structure::Structure>,
+    /// The worst severity reached with `--warn-above`/`--error-above`; per
+    /// file, never merged.
+    status: Option<thresholds::Severity>,
+    /// Natural language for `--detect-language`; per file, never merged.
+    language: Option<&'static str>,
 }
 
-#[derive(Default)]
+impl Counts {
+    fn add(&mut self, other: &Counts) {
+        self.bytes += other.bytes;
+        self.chars += other.chars;
+        self.words += other.words;
+        self.lines += other.lines;
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
+        self.max_display_width = self.max_display_width.max(other.max_display_width);
+        self.paragraphs += other.paragraphs;
+        self.blank_lines += other.blank_lines;
+        self.sentences += other.sentences;
+        // Distinct counts do not sum; totals report the union.
+        match (&mut self.uniques, &other.uniques) {
+            (Some(mine), Some(theirs)) => mine.merge(theirs),
+            (None, Some(theirs)) => self.uniques = Some(theirs.clone()),
+            _ => {}
+        }
+        if let Some(score) = other.score {
+            *self.score.get_or_insert(0.0) += score;
+        }
+        if let Some(visual) = other.visual_lines {
+            *self.visual_lines.get_or_insert(0) += visual;
+        }
+        if let Some(records) = other.records {
+            *self.records.get_or_insert(0) += records;
+        }
+        if let Some(levels) = &other.levels {
+            self.levels.get_or_insert_with(Default::default).merge(levels);
+        }
+        if let Some(indent) = &other.indent {
+            self.indent.get_or_insert_with(Default::default).merge(indent);
+        }
+    }
+}
+
+impl From<engine::Counts> for Counts {
+    fn from(counts: engine::Counts) -> Self {
+        Counts {
+            bytes: counts.bytes,
+            chars: counts.chars,
+            words: counts.words,
+            lines: counts.lines,
+            max_line_length: counts.max_line_length,
+            max_display_width: counts.max_display_width.unwrap_or(0),
+            paragraphs: counts.paragraphs,
+            blank_lines: counts.blank_lines,
+            sentences: counts.sentences,
+            uniques: counts.uniques,
+            score: counts.score,
+            minified: counts.minified,
+            indent: counts.indent,
+            visual_lines: counts.visual_lines,
+            records: counts.records,
+            levels: counts.levels,
+            language: counts.language,
+            ..Counts::default()
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 struct Config {
     show_bytes: bool,
     show_chars: bool,
+    /// `--show-all`: every metric column, including the ones without a flag.
+    show_all: bool,
     show_words: bool,
     show_lines: bool,
+    /// `-L`: the display width of the longest line.
+    show_max_line_length: bool,
+    /// `--max-display-width`: the same, with tabs at `--tab-size`.
+    show_max_display_width: bool,
+    show_paragraphs: bool,
+    show_blank_lines: bool,
     show_json: bool,
+    /// `--format csv`: a header row, then one comma-separated row per input.
+    csv: bool,
+    /// `--format ndjson`: the `--json` object of each input on a line.
+    ndjson: bool,
+    json_compact: bool,
+    /// `--plain`: one labelled metric per line, for screen readers.
+    plain: bool,
+    /// `--metadata`: precede JSON output with a provenance record.
+    metadata: bool,
     show_human: bool,
+    /// `--human=both`: exact figure followed by the human-readable one.
+    human_both: bool,
     files: Vec<String>,
+    remotes: Vec<String>,
+    window: Option<Duration>,
+    alert: Option<alert::RateAlert>,
+    journal: bool,
+    unit: Option<String>,
+    eventlog: Option<String>,
+    since: Option<SystemTime>,
+    until: Option<SystemTime>,
+    /// `--since`/`--until` applied to the lines of timestamped files.
+    time_range: Option<logtime::TimeRange>,
+    sample: Option<sample::Sampling>,
+    seed: u64,
+    unique_lines: bool,
+    unique_words: bool,
+    approx: bool,
+    editor_mode: bool,
+    cursor: Option<editor::Cursor>,
+    status_line: Option<String>,
+    /// `--format badge`: print the totals as a shields.io endpoint badge.
+    badge: Option<badge::Options>,
+    /// `--warn-above`/`--error-above`: per-file count budgets.
+    thresholds: thresholds::Thresholds,
+    /// `--summary`: min, max, mean and median rows after the totals.
+    summary: bool,
+    /// `--top N --by METRIC`: list only the N files highest in METRIC.
+    top: Option<(usize, metric::Metric)>,
+    /// `--reading-time [WPM]`: add a reading-time column at WPM words a minute.
+    reading_wpm: Option<usize>,
+    /// `--speaking-time [WPM]`: add a speaking-time column at WPM words a
+    /// minute.
+    speaking_wpm: Option<usize>,
+    /// `--pages [WORDS|PRESET]`: add a page-count column at WORDS words a page.
+    words_per_page: Option<usize>,
+    /// `--time-format`: how reading times are written.
+    time_format: readtime::TimeFormat,
+    /// `--time-rounding`: rounding of reading times to whole minutes (up by
+    /// default).
+    time_rounding: Option<Rounding>,
+    sidecar: bool,
+    sidecar_dir: Option<PathBuf>,
+    weights: Option<weights::Weights>,
+    precision: Option<usize>,
+    /// Content transforms from `--pre`, `--exclude-quotes` and `--academic`,
+    /// applied in command-line order.
+    pipeline: Vec<transform::Transform>,
+    total_label: Option<String>,
+    /// `--stdin-label` (or `--label`): the name shown for standard input.
+    label: Option<String>,
+    preview: Option<preview::Preview>,
+    rounding: Rounding,
+    limits: walk::Limits,
+    no_generated: bool,
+    minified: bool,
+    minified_filter: Option<MinifiedFilter>,
+    indent_stats: bool,
+    license_header: Option<license::HeaderCheck>,
+    patch: bool,
+    staged: bool,
+    validate_utf8: bool,
+    lint_line_length: Option<usize>,
+    wrap_at: Option<usize>,
+    /// `--tab-size`: columns between tab stops for `--max-display-width`
+    /// and `--wrap-at`.
+    tab_size: Option<usize>,
+    /// `--json-logs`: the field counted in each NDJSON record.
+    json_logs: Option<String>,
+    /// `--log-levels`: lines per severity.
+    log_levels: bool,
+    /// `--archive`: count the members of tar and zip archives.
+    archive: bool,
+    /// `--group-by`: report counts per group of files rather than per file.
+    group_by: Option<group::GroupBy>,
+    /// `--po`/`--xliff`: count translation files entry by entry.
+    translation: Option<translation::Format>,
+    /// `--outline`: words per Markdown section, as a tree.
+    outline: bool,
+    /// `--split-on REGEX`: lines that start a section, counted apart.
+    split_on: Option<regex::Regex>,
+    /// `--record-start REGEX`: lines that begin a logical record.
+    record_start: Option<regex::Regex>,
+    /// `--whitespace`: which characters separate words.
+    whitespace: tokenizer::Whitespace,
+    /// `--ignore-format-chars`
+    ignore_format: bool,
+    /// `--watch`: reprint the counts whenever a file changes.
+    watch: bool,
+    /// `--follow`: count what is appended to files as they grow.
+    follow: bool,
+    /// `--clear`: clear the screen before each `--watch` update.
+    clear_screen: bool,
+    /// `--last SIZE`: count only the final SIZE bytes of each file, from the
+    /// first line that starts within them.
+    last: Option<u64>,
+    /// `--unicode-words`: UAX #29 word segmentation.
+    unicode_words: bool,
+    /// `--tokenizer`: the name given and the tokenizer it selects.
+    tokenizer: Option<(String, Arc<dyn tokenizer::Tokenizer>)>,
+    /// `--encoding`: how input is decoded before counting.
+    encoding: encoding::Encoding,
+    /// `--lossy`: count invalid UTF-8 as U+FFFD rather than failing.
+    lossy: bool,
+    /// `--structure`: report the structure of extracted documents.
+    structure: bool,
+    /// `--no-decompress`: count compressed input as it is.
+    no_decompress: bool,
+    /// `-r`: count the files under directory operands.
+    recursive: bool,
+    walk: walk::Options,
+    /// `--jobs`: number of files counted at once.
+    jobs: usize,
+    /// `--file-threads`: threads a large file is counted on, in ranges.
+    file_threads: usize,
+    /// `--mmap`/`--no-mmap`: whether files are counted over a memory map.
+    mmap: mmap::Mode,
+    /// `--unordered`: list files as they are counted rather than in order.
+    unordered: bool,
+    /// `--list-only`: print the files that would be counted instead.
+    list_only: bool,
+    /// `--detect-language`: report each input's natural language.
+    detect_language: bool,
 }
 
+/// Every long option, for typo suggestions.
+const OPTIONS: &[&str] = &[
+    "--lines",
+    "--words",
+    "--bytes",
+    "--chars",
+    "--max-line-length",
+    "--max-display-width",
+    "--paragraphs",
+    "--blank-lines",
+    "--show-all",
+    "--json",
+    "--json-compact",
+    "--json-pretty",
+    "--plain",
+    "--metadata",
+    "--unique-lines",
+    "--unique-words",
+    "--approx",
+    "--pre",
+    "--auto",
+    "--type",
+    "--structure",
+    "--no-decompress",
+    "--exclude-quotes",
+    "--no-generated",
+    "--minified",
+    "--skip-minified",
+    "--only-minified",
+    "--detect-language",
+    "--indent-stats",
+    "--wrap-at",
+    "--wrap-width",
+    "--tab-size",
+    "--whitespace",
+    "--record-start",
+    "--log-levels",
+    "--json-logs",
+    "--field",
+    "--archive",
+    "--po",
+    "--xliff",
+    "--outline",
+    "--split-on",
+    "--group-by",
+    "--ignore-format-chars",
+    "--unicode-words",
+    "--tokenizer",
+    "--encoding",
+    "--lossy",
+    "--license-header",
+    "--header-lines",
+    "--require-header",
+    "--patch",
+    "--validate-utf8",
+    "--lint-line-length",
+    "--staged",
+    "--academic",
+    "--human",
+    "--human=both",
+    "--precision",
+    "--rounding",
+    "--reading-time",
+    "--speaking-time",
+    "--pages",
+    "--time-format",
+    "--time-rounding",
+    "--warn-above",
+    "--error-above",
+    "--summary",
+    "--top",
+    "--by",
+    "--format",
+    "--output",
+    "--print-schema",
+    "--badge-label",
+    "--badge-metric",
+    "--badge-colors",
+    "--remote",
+    "--alert-lines-per-sec",
+    "--alert-cmd",
+    "--journal",
+    "--unit",
+    "--eventlog",
+    "--since",
+    "--until",
+    "--timestamp-format",
+    "--sorted",
+    "--sample",
+    "--sample-lines",
+    "--seed",
+    "--editor-mode",
+    "--sidecar",
+    "--sidecar-dir",
+    "--weights",
+    "--total-label",
+    "--label",
+    "--stdin-label",
+    "--preview",
+    "--preview-random",
+    "--jobs",
+    "--file-threads",
+    "--mmap",
+    "--no-mmap",
+    "--unordered",
+    "--recursive",
+    "--files0-from",
+    "--hidden",
+    "--follow-symlinks",
+    "--no-ignore",
+    "--include",
+    "--exclude",
+    "--list-only",
+    "--max-files",
+    "--max-total-bytes",
+    "--max-memory",
+    "--last",
+    "--watch",
+    "--clear",
+    "--follow",
+    "--status-line",
+    "--status-template",
+    "--cursor",
+    "--window",
+    "--help",
+];
+
+/// Options that each select a different way of reading input; at most one
+/// may be given.
+const MODES: &[&str] = &[
+    "--journal",
+    "--eventlog",
+    "--editor-mode",
+    "--window",
+    "--sample",
+    "--sample-lines",
+    "--status-line",
+    "--license-header",
+    "--patch",
+    "--validate-utf8",
+    "--lint-line-length",
+    "--watch",
+    "--follow",
+    "--json-logs",
+    "--archive",
+    "--po",
+    "--xliff",
+    "--outline",
+    "--split-on",
+    "--list-only",
+];
+
+/// Pairs of options that cannot be combined.
+const CONFLICTS: &[(&str, &str)] = &[
+    ("--format", "--json"),
+    ("--format", "--json-compact"),
+    ("--format", "--json-pretty"),
+    ("--format", "--plain"),
+    ("--group-by", "--summary"),
+    ("--group-by", "--top"),
+    ("--json", "--status-line"),
+    ("--human=both", "--json"),
+    ("--json-compact", "--status-line"),
+    ("--human=both", "--json-compact"),
+    ("--json-pretty", "--json-compact"),
+    ("--json-pretty", "--status-line"),
+    ("--human=both", "--json-pretty"),
+    ("--plain", "--json-pretty"),
+    ("--plain", "--json"),
+    ("--plain", "--json-compact"),
+    ("--plain", "--status-line"),
+    ("--skip-minified", "--only-minified"),
+    ("--staged", "--remote"),
+    ("--unicode-words", "--whitespace"),
+    ("--tokenizer", "--unicode-words"),
+    ("--tokenizer", "--whitespace"),
+    ("--last", "--timestamp-format"),
+    ("--sidecar", "--status-line"),
+    ("--sidecar-dir", "--status-line"),
+    ("--mmap", "--no-mmap"),
+];
+
+/// `--format` values.
+const FORMATS: &[&str] = &["table", "json", "csv", "ndjson", "plain", "badge"];
+
+/// Subcommand names, for typo suggestions.
+const COMMANDS: &[&str] = &[
+    "help",
+    "image",
+    "stats",
+    "freq",
+    "daemon",
+    "query",
+    "lsp",
+    "report-diff",
+    "compare-dirs",
+    "toc",
+    "annotate-frontmatter",
+    "batch",
+    "corpus",
+    "todos",
+    "blame-stats",
+    "commit-msg",
+    "hook",
+    "sql",
+    "xlsx",
+    "sqlite",
+    "columnar",
+];
+
 impl Config {
     fn new() -> Self {
         let mut config = Config::default();
@@ -35,21 +569,587 @@ impl Config {
             return config;
         }
 
+        let mut seen: Vec<&str> = Vec::new();
+        let mut alert_cmd = None;
+        let mut timestamp_format = None;
+        let mut json_logs = false;
+        let mut json_field = None;
+        let mut sorted = false;
+        let mut status_line = false;
+        let mut preview_random = false;
+        let mut auto = None;
+        let mut status_template = None;
+        let mut license_pattern = None;
+        let mut header_lines = None;
+        let mut require_header = false;
+        let mut no_ignore = false;
+        let mut files0_from = None;
+        let mut format = None;
+        let mut output_path = None;
+        let mut top = None;
+        let mut top_by = None;
+        let mut badge_options = badge::Options::default();
         let mut i = 1;
         while i < args.len() {
+            if args[i].starts_with('-') {
+                seen.push(args[i].as_str());
+            }
             match args[i].as_str() {
                 "-l" | "--lines" => config.show_lines = true,
                 "-w" | "--words" => config.show_words = true,
                 "-c" | "--bytes" => config.show_bytes = true,
                 "-m" | "--chars" => config.show_chars = true,
+                "-L" | "--max-line-length" => config.show_max_line_length = true,
+                "--max-display-width" => config.show_max_display_width = true,
+                "--paragraphs" => config.show_paragraphs = true,
+                "--blank-lines" => config.show_blank_lines = true,
+                "--show-all" => config.show_all = true,
                 "--json" => config.show_json = true,
+                "--json-compact" => {
+                    config.show_json = true;
+                    config.json_compact = true;
+                }
+                "--json-pretty" => config.show_json = true,
+                "--plain" => config.plain = true,
+                "--metadata" => config.metadata = true,
+                "--unique-lines" => config.unique_lines = true,
+                "--unique-words" => config.unique_words = true,
+                "--approx" => config.approx = true,
+                "--pre" => {
+                    let value = option_value(&args, &mut i);
+                    match transform::parse_list(&value) {
+                        Ok(steps) => config.pipeline.extend(steps),
+                        Err(e) => {
+                            eprintln!("rwc: --pre: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--auto" => auto = auto.or(Some(None)),
+                "--structure" => config.structure = true,
+                "--no-decompress" => config.no_decompress = true,
+                "--type" => {
+                    let value = option_value(&args, &mut i);
+                    match filetype::FileType::parse(&value) {
+                        Some(kind) => auto = Some(Some(kind)),
+                        None => {
+                            let names: Vec<&str> =
+                                filetype::NAMES.iter().map(|(name, _)| *name).collect();
+                            eprintln!(
+                                "rwc: invalid type '{}' (expected {})",
+                                value,
+                                names.join(", ")
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--exclude-quotes" => config.pipeline.push(transform::Transform::ExcludeQuotes),
+                "--no-generated" => config.no_generated = true,
+                "--minified" => config.minified = true,
+                "--detect-language" => config.detect_language = true,
+                "--indent-stats" => config.indent_stats = true,
+                "--wrap-at" | "--wrap-width" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(width) if width > 0 => config.wrap_at = Some(width),
+                        _ => {
+                            eprintln!("rwc: invalid wrap width '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--tab-size" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(size) if size > 0 => config.tab_size = Some(size),
+                        _ => {
+                            eprintln!("rwc: invalid tab size '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--ignore-format-chars" => config.ignore_format = true,
+                "--unicode-words" => config.unicode_words = true,
+                "--tokenizer" => {
+                    let value = option_value(&args, &mut i);
+                    match tokenizer::builtin(&value) {
+                        Ok(tokenizer) => config.tokenizer = Some((value, Arc::from(tokenizer))),
+                        Err(e) => {
+                            eprintln!("rwc: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--lossy" => config.lossy = true,
+                "--log-levels" => config.log_levels = true,
+                "--json-logs" => json_logs = true,
+                "--archive" => config.archive = true,
+                "--po" => config.translation = Some(translation::Format::Po),
+                "--xliff" => config.translation = Some(translation::Format::Xliff),
+                "--outline" => config.outline = true,
+                "--split-on" => {
+                    let value = option_value(&args, &mut i);
+                    match regex::Regex::new(&value) {
+                        Ok(pattern) => config.split_on = Some(pattern),
+                        Err(e) => {
+                            eprintln!("rwc: invalid --split-on pattern: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--field" => json_field = Some(option_value(&args, &mut i)),
+                "--record-start" => {
+                    let value = option_value(&args, &mut i);
+                    match regex::Regex::new(&value) {
+                        Ok(pattern) => config.record_start = Some(pattern),
+                        Err(e) => {
+                            eprintln!("rwc: invalid --record-start pattern: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--group-by" => {
+                    let value = option_value(&args, &mut i);
+                    match group::GroupBy::parse(&value) {
+                        Ok(group_by) => config.group_by = Some(group_by),
+                        Err(e) => {
+                            eprintln!("rwc: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--encoding" => {
+                    let value = option_value(&args, &mut i);
+                    match encoding::Encoding::parse(&value) {
+                        Some(encoding) => config.encoding = encoding,
+                        None => {
+                            let names: Vec<&str> =
+                                encoding::NAMES.iter().map(|&(name, _)| name).collect();
+                            eprintln!(
+                                "rwc: invalid encoding '{}' (expected {})",
+                                value,
+                                names.join(", ")
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--whitespace" => {
+                    let value = option_value(&args, &mut i);
+                    match tokenizer::Whitespace::parse(&value) {
+                        Ok(whitespace) => config.whitespace = whitespace,
+                        Err(e) => {
+                            eprintln!("rwc: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--license-header" => {
+                    let value = option_value(&args, &mut i);
+                    match regex::Regex::new(&value) {
+                        Ok(pattern) => license_pattern = Some(pattern),
+                        Err(e) => {
+                            eprintln!("rwc: --license-header: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--header-lines" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(lines) if lines > 0 => header_lines = Some(lines),
+                        _ => {
+                            eprintln!("rwc: invalid header line count '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--require-header" => require_header = true,
+                "--patch" => config.patch = true,
+                "--validate-utf8" => config.validate_utf8 = true,
+                "--lint-line-length" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(max) => config.lint_line_length = Some(max),
+                        Err(_) => {
+                            eprintln!("rwc: invalid line length '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--staged" => config.staged = true,
+                "--skip-minified" => {
+                    config.minified = true;
+                    config.minified_filter = Some(MinifiedFilter::Skip);
+                }
+                "--only-minified" => {
+                    config.minified = true;
+                    config.minified_filter = Some(MinifiedFilter::Only);
+                }
+                "--academic" => config.pipeline.push(transform::Transform::Academic),
                 "-h" | "--human" => config.show_human = true,
+                "--human=both" => {
+                    config.show_human = true;
+                    config.human_both = true;
+                }
+                "--precision" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(n) if n <= 9 => config.precision = Some(n),
+                        _ => {
+                            eprintln!("rwc: invalid precision '{}' (expected 0-9)", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--rounding" => {
+                    let value = option_value(&args, &mut i);
+                    match Rounding::parse(&value) {
+                        Some(rounding) => config.rounding = rounding,
+                        None => {
+                            eprintln!(
+                                "rwc: invalid rounding '{}' (expected half-even, half-up, down or up)",
+                                value
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--warn-above" | "--error-above" => {
+                    let severity = if args[i] == "--warn-above" {
+                        thresholds::Severity::Warn
+                    } else {
+                        thresholds::Severity::Error
+                    };
+                    let value = option_value(&args, &mut i);
+                    if let Err(e) = config.thresholds.add(severity, &value) {
+                        eprintln!("rwc: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                "--summary" => config.summary = true,
+                "--top" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(count) if count > 0 => top = Some(count),
+                        _ => {
+                            eprintln!("rwc: invalid number of files '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--by" => {
+                    let value = option_value(&args, &mut i);
+                    match metric::Metric::parse(&value) {
+                        Some(metric) => top_by = Some(metric),
+                        None => {
+                            eprintln!(
+                                "rwc: invalid metric '{}' (expected lines, words, chars or bytes)",
+                                value
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--format" => {
+                    let value = option_value(&args, &mut i);
+                    match FORMATS.iter().find(|&&name| name == value) {
+                        Some(&name) => format = Some(name),
+                        None => {
+                            eprintln!(
+                                "rwc: invalid format '{}' (expected table, json, csv, ndjson, plain or badge)",
+                                value
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--output" => output_path = Some(option_value(&args, &mut i)),
+                "--badge-label" => badge_options.label = Some(option_value(&args, &mut i)),
+                "--badge-metric" => {
+                    let value = option_value(&args, &mut i);
+                    match metric::Metric::parse(&value) {
+                        Some(metric) => badge_options.metric = metric,
+                        None => {
+                            eprintln!(
+                                "rwc: invalid badge metric '{}' (expected lines, words, chars or bytes)",
+                                value
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--badge-colors" => {
+                    let value = option_value(&args, &mut i);
+                    match badge::parse_colors(&value) {
+                        Ok(colors) => badge_options.colors = colors,
+                        Err(e) => {
+                            eprintln!("rwc: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                arg if arg == "--reading-time" || arg.starts_with("--reading-time=") => {
+                    config.reading_wpm = Some(optional_rate(&args, &mut i).unwrap_or(READING_WPM));
+                }
+                arg if arg == "--speaking-time" || arg.starts_with("--speaking-time=") => {
+                    config.speaking_wpm = Some(optional_rate(&args, &mut i).unwrap_or(SPEAKING_WPM));
+                }
+                arg if arg == "--pages" || arg.starts_with("--pages=") => {
+                    config.words_per_page = Some(words_per_page(&args, &mut i));
+                }
+                "--time-format" => {
+                    let value = option_value(&args, &mut i);
+                    match readtime::TimeFormat::parse(&value) {
+                        Some(format) => config.time_format = format,
+                        None => {
+                            eprintln!(
+                                "rwc: invalid time format '{}' (expected text, minutes, hms or iso8601)",
+                                value
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--time-rounding" => {
+                    let value = option_value(&args, &mut i);
+                    match Rounding::parse(&value) {
+                        Some(rounding) => config.time_rounding = Some(rounding),
+                        None => {
+                            eprintln!(
+                                "rwc: invalid rounding '{}' (expected half-even, half-up, down or up)",
+                                value
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--remote" => config.remotes.push(option_value(&args, &mut i)),
+                "--alert-lines-per-sec" => {
+                    let value = option_value(&args, &mut i);
+                    let lines_per_sec = match value.parse::<f64>() {
+                        Ok(rate) if rate >= 0.0 => rate,
+                        _ => {
+                            eprintln!("rwc: invalid line rate '{}'", value);
+                            std::process::exit(1);
+                        }
+                    };
+                    config.alert = Some(alert::RateAlert {
+                        lines_per_sec,
+                        command: None,
+                    });
+                }
+                "--alert-cmd" => alert_cmd = Some(option_value(&args, &mut i)),
+                "--journal" => config.journal = true,
+                "--unit" => config.unit = Some(option_value(&args, &mut i)),
+                "--eventlog" => config.eventlog = Some(option_value(&args, &mut i)),
+                "--since" | "--until" => {
+                    let option = args[i].clone();
+                    let value = option_value(&args, &mut i);
+                    let Some(time) = time::parse_timestamp(&value) else {
+                        eprintln!("rwc: invalid timestamp '{}' for {}", value, option);
+                        std::process::exit(1);
+                    };
+                    if option == "--since" {
+                        config.since = Some(time);
+                    } else {
+                        config.until = Some(time);
+                    }
+                }
+                "--timestamp-format" => {
+                    let value = option_value(&args, &mut i);
+                    match logtime::TimestampFormat::parse(&value) {
+                        Ok(format) => timestamp_format = Some(format),
+                        Err(e) => {
+                            eprintln!("rwc: --timestamp-format: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--sorted" => sorted = true,
+                "--sample" => {
+                    let value = option_value(&args, &mut i);
+                    match sample::Sampling::parse_fraction(&value) {
+                        Some(sampling) => config.sample = Some(sampling),
+                        None => {
+                            eprintln!("rwc: invalid sample size '{}' (expected e.g. 1%)", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--sample-lines" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(n) if n > 0 => config.sample = Some(sample::Sampling::Lines(n)),
+                        _ => {
+                            eprintln!("rwc: invalid line count '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--seed" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<u64>() {
+                        Ok(seed) => config.seed = seed,
+                        Err(_) => {
+                            eprintln!("rwc: invalid seed '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--editor-mode" => config.editor_mode = true,
+                "--sidecar" => config.sidecar = true,
+                "--sidecar-dir" => {
+                    config.sidecar = true;
+                    config.sidecar_dir = Some(PathBuf::from(option_value(&args, &mut i)));
+                }
+                "--weights" => {
+                    let path = option_value(&args, &mut i);
+                    match weights::Weights::load(&path) {
+                        Ok(weights) => config.weights = Some(weights),
+                        Err(e) => {
+                            eprintln!("rwc: --weights: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--total-label" => config.total_label = Some(option_value(&args, &mut i)),
+                "--stdin-label" | "--label" => config.label = Some(option_value(&args, &mut i)),
+                "--preview" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(lines) if lines > 0 => {
+                            let random = config.preview.is_some_and(|p| p.random);
+                            config.preview = Some(preview::Preview { lines, random });
+                        }
+                        _ => {
+                            eprintln!("rwc: invalid preview line count '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--preview-random" => preview_random = true,
+                "-r" | "--recursive" => config.recursive = true,
+                "--hidden" => config.walk.hidden = true,
+                "--follow-symlinks" => config.walk.follow_symlinks = true,
+                "--no-ignore" => no_ignore = true,
+                "--include" => {
+                    let value = option_value(&args, &mut i);
+                    config.walk.include.push(walk::Pattern::new(&value));
+                }
+                "--exclude" => {
+                    let value = option_value(&args, &mut i);
+                    config.walk.exclude.push(walk::Pattern::new(&value));
+                }
+                "--list-only" => config.list_only = true,
+                "-j" | "--jobs" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(0) => config.jobs = pool::default_jobs(),
+                        Ok(jobs) => config.jobs = jobs,
+                        Err(_) => {
+                            eprintln!("rwc: invalid number of jobs '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--file-threads" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(0) => config.file_threads = pool::default_jobs(),
+                        Ok(threads) => config.file_threads = threads,
+                        Err(_) => {
+                            eprintln!("rwc: invalid number of threads '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--mmap" => config.mmap = mmap::Mode::Always,
+                "--no-mmap" => config.mmap = mmap::Mode::Never,
+                "--unordered" => config.unordered = true,
+                "--max-files" => {
+                    let value = option_value(&args, &mut i);
+                    match value.parse::<usize>() {
+                        Ok(max) if max > 0 => config.limits.max_files = Some(max),
+                        _ => {
+                            eprintln!("rwc: invalid file limit '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--max-memory" => {
+                    let value = option_value(&args, &mut i);
+                    match parse_size(&value) {
+                        Some(max) if max > 0 => {
+                            memory::set_limit(max as usize);
+                            memory::warn_with(output::warn);
+                        }
+                        _ => {
+                            eprintln!("rwc: invalid size '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--max-total-bytes" => {
+                    let value = option_value(&args, &mut i);
+                    match parse_size(&value) {
+                        Some(max) if max > 0 => config.limits.max_total_bytes = Some(max),
+                        _ => {
+                            eprintln!("rwc: invalid size '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--watch" => config.watch = true,
+                "--clear" => config.clear_screen = true,
+                "--follow" => config.follow = true,
+                "--last" => {
+                    let value = option_value(&args, &mut i);
+                    match parse_size(&value) {
+                        Some(window) if window > 0 => config.last = Some(window),
+                        _ => {
+                            eprintln!("rwc: invalid size '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--status-line" => status_line = true,
+                "--status-template" => status_template = Some(option_value(&args, &mut i)),
+                "--cursor" => {
+                    let value = option_value(&args, &mut i);
+                    match editor::Cursor::parse(&value) {
+                        Some(cursor) => config.cursor = Some(cursor),
+                        None => {
+                            eprintln!("rwc: invalid cursor '{}' (expected LINE:COL)", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--window" => {
+                    let value = option_value(&args, &mut i);
+                    match time::parse_duration(&value) {
+                        Some(window) => config.window = Some(window),
+                        None => {
+                            eprintln!("rwc: invalid window duration '{}'", value);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                "--files0-from" => files0_from = Some(option_value(&args, &mut i)),
+                arg if arg.starts_with("--files0-from=") => {
+                    files0_from = Some(arg["--files0-from=".len()..].to_string());
+                }
                 "--help" => {
-                    print_help();
+                    help::print_main();
+                    std::process::exit(0);
+                }
+                "--print-schema" => {
+                    output::print(&schema::document().pretty());
                     std::process::exit(0);
                 }
-                arg if arg.starts_with('-') => {
-                    eprintln!("rwc: unknown option '{}'", arg);
+                arg if arg.starts_with('-') && arg != "-" => {
+                    cli::unknown_option("rwc", arg, OPTIONS);
                     eprintln!("Try 'rwc --help' for more information.");
                     std::process::exit(1);
                 }
@@ -58,170 +1158,1696 @@ impl Config {
             i += 1;
         }
 
+        let modes: Vec<&str> = MODES.iter().copied().filter(|m| seen.contains(m)).collect();
+        if let [first, second, ..] = modes.as_slice() {
+            eprintln!("rwc: {} cannot be combined with {}", first, second);
+            std::process::exit(1);
+        }
+        for (a, b) in CONFLICTS {
+            if seen.contains(a) && seen.contains(b) {
+                eprintln!("rwc: {} cannot be combined with {}", a, b);
+                std::process::exit(1);
+            }
+        }
+
+        match (&mut config.alert, alert_cmd) {
+            (Some(alert), command) => alert.command = command,
+            (None, Some(_)) => {
+                eprintln!("rwc: --alert-cmd requires --alert-lines-per-sec");
+                std::process::exit(1);
+            }
+            (None, None) => {}
+        }
+        if config.alert.is_some() && config.window.is_none() && !config.follow {
+            eprintln!("rwc: --alert-lines-per-sec requires --window or --follow");
+            std::process::exit(1);
+        }
+
+        // Type detection extracts the text, so it runs before any other step.
+        if let Some(forced) = auto {
+            config.pipeline.insert(0, transform::Transform::Auto(forced));
+        } else if config.structure {
+            eprintln!("rwc: --structure requires --auto or --type");
+            std::process::exit(1);
+        }
+
+        match (&mut config.preview, preview_random) {
+            (Some(preview), random) => preview.random = random,
+            (None, true) => {
+                eprintln!("rwc: --preview-random requires --preview");
+                std::process::exit(1);
+            }
+            (None, false) => {}
+        }
+
+        // --output picks the format from the file's extension, unless one is
+        // asked for.
+        let format = format.or_else(|| {
+            let explicit = ["--json", "--json-compact", "--json-pretty", "--plain"];
+            if seen.iter().any(|arg| explicit.contains(arg)) {
+                return None;
+            }
+            let path = Path::new(output_path.as_ref()?);
+            match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+                "json" => Some("json"),
+                "csv" => Some("csv"),
+                "ndjson" | "jsonl" => Some("ndjson"),
+                _ => None,
+            }
+        });
+        match format {
+            Some("json") => config.show_json = true,
+            Some("csv") => config.csv = true,
+            Some("ndjson") => config.ndjson = true,
+            Some("plain") => config.plain = true,
+            _ => {}
+        }
+        if format == Some("badge") {
+            // The badge only shows the totals.
+            for option in ["--group-by", "--summary", "--top"] {
+                if seen.contains(&option) {
+                    eprintln!("rwc: --format badge cannot be combined with {}", option);
+                    std::process::exit(1);
+                }
+            }
+            config.badge = Some(badge_options);
+        } else if let Some(option) = seen.iter().find(|arg| arg.starts_with("--badge-")) {
+            eprintln!("rwc: {} requires --format badge", option);
+            std::process::exit(1);
+        }
+
+        match (top, top_by) {
+            (Some(count), by) => config.top = Some((count, by.unwrap_or_default())),
+            (None, Some(_)) => {
+                eprintln!("rwc: --by requires --top");
+                std::process::exit(1);
+            }
+            (None, None) => {}
+        }
+
+        match (status_line, status_template) {
+            (true, template) => {
+                config.status_line =
+                    Some(template.unwrap_or_else(|| status::DEFAULT_TEMPLATE.to_string()))
+            }
+            (false, Some(_)) => {
+                eprintln!("rwc: --status-template requires --status-line");
+                std::process::exit(1);
+            }
+            (false, None) => {}
+        }
+
+        if files0_from.is_some() && !config.files.is_empty() {
+            eprintln!("rwc: file operands cannot be combined with --files0-from");
+            std::process::exit(1);
+        }
+
+        match license_pattern {
+            Some(pattern) => {
+                config.license_header = Some(license::HeaderCheck {
+                    pattern,
+                    lines: header_lines.unwrap_or(license::DEFAULT_HEADER_LINES),
+                    require: require_header,
+                })
+            }
+            None if header_lines.is_some() || require_header => {
+                eprintln!("rwc: --header-lines and --require-header require --license-header");
+                std::process::exit(1);
+            }
+            None => {}
+        }
+
+        if !config.show_human && (config.precision.is_some() || config.rounding != Rounding::default())
+        {
+            eprintln!("rwc: --precision and --rounding require --human");
+            std::process::exit(1);
+        }
+
+        let walks = config.recursive
+            || config.license_header.is_some()
+            || config.validate_utf8
+            || config.lint_line_length.is_some();
+        const WALK_OPTIONS: &[&str] =
+            &["--hidden", "--follow-symlinks", "--no-ignore", "--include", "--exclude"];
+        if let Some(option) = seen.iter().find(|o| WALK_OPTIONS.contains(o)).filter(|_| !walks) {
+            eprintln!("rwc: {} only applies when directories are walked (-r)", option);
+            std::process::exit(1);
+        }
+        config.walk.gitignore = !no_ignore;
+
+        if config.metadata && !config.show_json {
+            eprintln!("rwc: --metadata requires --json or --json-compact");
+            std::process::exit(1);
+        }
+
+        // Languages are grouped by as they are detected.
+        if config.group_by == Some(group::GroupBy::Language) {
+            config.detect_language = true;
+        }
+        if config.detect_language && !cfg!(feature = "language") {
+            eprintln!(
+                "rwc: language detection is not enabled (rebuild with --features language)"
+            );
+            std::process::exit(1);
+        }
+
+        if config.approx && !config.unique_lines && !config.unique_words {
+            eprintln!("rwc: --approx requires --unique-lines or --unique-words");
+            std::process::exit(1);
+        }
+        match (json_logs, json_field) {
+            (true, field) => {
+                config.json_logs =
+                    Some(field.unwrap_or_else(|| jsonlogs::DEFAULT_FIELD.to_string()))
+            }
+            (false, Some(_)) => {
+                eprintln!("rwc: --field requires --json-logs");
+                std::process::exit(1);
+            }
+            (false, None) => {}
+        }
+        if config.clear_screen && !config.watch {
+            eprintln!("rwc: --clear requires --watch");
+            std::process::exit(1);
+        }
+        if config.cursor.is_some() && !config.editor_mode {
+            eprintln!("rwc: --cursor requires --editor-mode");
+            std::process::exit(1);
+        }
+        if !config.journal && config.unit.is_some() {
+            eprintln!("rwc: --unit requires --journal");
+            std::process::exit(1);
+        }
+        if let Some(format) = timestamp_format {
+            if config.since.is_none() && config.until.is_none() {
+                eprintln!("rwc: --timestamp-format requires --since or --until");
+                std::process::exit(1);
+            }
+            config.time_range = Some(logtime::TimeRange {
+                format,
+                since: config.since,
+                until: config.until,
+                sorted,
+            });
+        } else if sorted {
+            eprintln!("rwc: --sorted requires --timestamp-format");
+            std::process::exit(1);
+        }
+        if !config.journal
+            && config.eventlog.is_none()
+            && config.time_range.is_none()
+            && (config.since.is_some() || config.until.is_some())
+        {
+            eprintln!(
+                "rwc: --since and --until require --journal, --eventlog or --timestamp-format"
+            );
+            std::process::exit(1);
+        }
+
+        if config.show_all {
+            config.show_lines = true;
+            config.show_words = true;
+            config.show_chars = true;
+            config.show_bytes = true;
+            config.show_max_line_length = true;
+            config.show_paragraphs = true;
+            config.show_blank_lines = true;
+        }
+
         // If no specific counts requested, show all
-        if !config.show_lines && !config.show_words && !config.show_bytes && !config.show_chars {
+        if !config.show_lines
+            && !config.show_words
+            && !config.show_bytes
+            && !config.show_chars
+            && !config.show_max_line_length
+            && !config.show_max_display_width
+            && !config.show_paragraphs
+            && !config.show_blank_lines
+        {
             config.show_lines = true;
             config.show_words = true;
             config.show_bytes = true;
         }
 
+        // Only now that the command line is known to be valid may the
+        // --output file be replaced.
+        if let Some(path) = &output_path {
+            if let Err(e) = output::redirect(path) {
+                eprintln!("rwc: {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+
+        if let Some(source) = files0_from {
+            config.files = read_files0(&source);
+            if config.files.is_empty() {
+                // An empty list means no input at all, not standard input.
+                std::process::exit(0);
+            }
+        }
+
+        // With no operands, --staged counts everything the next commit adds
+        // or changes.
+        if config.staged && config.files.is_empty() {
+            match git::staged_files_here() {
+                Ok(files) if files.is_empty() => {
+                    eprintln!("rwc: --staged: no staged files");
+                    std::process::exit(0);
+                }
+                Ok(files) => config.files = files,
+                Err(e) => {
+                    eprintln!("rwc: --staged: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
         config
     }
 }
 
-fn print_help() {
-    println!("rwc - A modern word counter");
-    println!();
-    println!("USAGE:");
-    println!("    rwc [OPTIONS] [FILES...]");
-    println!();
-    println!("OPTIONS:");
-    println!("    -l, --lines     Show line count");
-    println!("    -w, --words     Show word count");
-    println!("    -c, --bytes     Show byte count");
-    println!("    -m, --chars     Show character count");
-    println!("    --json          Output in JSON format");
-    println!("    -h, --human     Human readable numbers (1.2K, 1.5M)");
-    println!("    --help          Show this help message");
-    println!();
-    println!("If no files are specified, reads from stdin.");
-    println!("If no count options are specified, shows lines, words, and bytes.");
+impl Config {
+    /// Whether an option replaced plain file counting with another mode.
+    fn selects_mode(&self) -> bool {
+        self.journal
+            || self.eventlog.is_some()
+            || self.editor_mode
+            || self.window.is_some()
+            || self.sample.is_some()
+            || self.status_line.is_some()
+            || self.patch
+            || self.license_header.is_some()
+            || self.validate_utf8
+            || self.lint_line_length.is_some()
+            || self.watch
+            || self.follow
+            || self.json_logs.is_some()
+            || self.archive
+            || self.translation.is_some()
+            || self.outline
+            || self.split_on.is_some()
+    }
+
+    /// The counting-engine settings the options select.
+    fn metrics(&self) -> engine::Metrics<'_> {
+        engine::Metrics {
+            unique_lines: self.unique_lines,
+            unique_words: self.unique_words,
+            approx: self.approx,
+            weights: self.weights.as_ref(),
+            indent: self.indent_stats,
+            minified: self.minified,
+            wrap_at: self.wrap_at,
+            display_width: self.show_max_display_width,
+            tab_size: self.tab_size,
+            whitespace: self.whitespace.clone(),
+            ignore_format: self.ignore_format,
+            tokenizer: match &self.tokenizer {
+                Some((_, tokenizer)) => Some(tokenizer.as_ref()),
+                None if self.unicode_words => Some(&tokenizer::Uax29),
+                None => None,
+            },
+            record_start: self.record_start.as_ref(),
+            log_levels: self.log_levels,
+            lossy: self.lossy,
+            language: self.detect_language,
+        }
+    }
+
+    /// Whether the byte count is all that is asked for, so a file's size
+    /// can stand in for reading it, as in `wc -c`.
+    fn bytes_only(&self) -> bool {
+        let text_counts = self.show_lines
+            || self.show_words
+            || self.show_chars
+            || self.show_max_line_length
+            || self.show_max_display_width
+            || self.show_paragraphs
+            || self.show_blank_lines
+            || self.show_all
+            || self.badge.as_ref().is_some_and(|badge| badge.metric != metric::Metric::Bytes);
+        let extra_metrics = self.unique_lines
+            || self.unique_words
+            || self.weights.is_some()
+            || self.indent_stats
+            || self.minified
+            || self.wrap_at.is_some()
+            || self.record_start.is_some()
+            || self.log_levels;
+        self.show_bytes
+            && !text_counts
+            && !extra_metrics
+            && self.pipeline.is_empty()
+            && self.preview.is_none()
+            && !self.sidecar
+            && self.last.is_none()
+            && self.time_range.is_none()
+    }
+
+    /// Whether `--skip-minified`/`--only-minified` keep a file with `counts`.
+    fn keeps_minified(&self, counts: &Counts) -> bool {
+        let minified = counts
+            .minified
+            .is_some_and(|score| score >= generated::MINIFIED_THRESHOLD);
+        match self.minified_filter {
+            None => true,
+            Some(MinifiedFilter::Skip) => !minified,
+            Some(MinifiedFilter::Only) => minified,
+        }
+    }
+
+    /// Name for standard input in reports and errors.
+    fn stdin_label(&self) -> &str {
+        self.label.as_deref().unwrap_or_else(|| i18n::tr("(standard input)"))
+    }
+
+    /// Whole minutes to read `words` at the `--reading-time` rate.
+    fn reading_minutes(&self, words: usize) -> usize {
+        let wpm = self.reading_wpm.unwrap_or(READING_WPM);
+        readtime::minutes(words, wpm, self.time_rounding.unwrap_or(Rounding::Up))
+    }
+
+    /// The reading time of `words`, rounded and written as the options ask.
+    fn reading_time(&self, words: usize) -> String {
+        readtime::format(self.reading_minutes(words), self.time_format)
+    }
+
+    /// Whole minutes to say `words` aloud at the `--speaking-time` rate.
+    fn speaking_minutes(&self, words: usize) -> usize {
+        let wpm = self.speaking_wpm.unwrap_or(SPEAKING_WPM);
+        readtime::minutes(words, wpm, self.time_rounding.unwrap_or(Rounding::Up))
+    }
+
+    /// Pages `words` fill at the `--pages` density; a part page counts.
+    fn pages(&self, words: usize) -> usize {
+        words.div_ceil(self.words_per_page.unwrap_or(WORDS_PER_PAGE))
+    }
+
+    /// The name `print_counts` finds its format by: `csv`, `ndjson`, `plain`
+    /// or `table`.
+    fn format_name(&self) -> &'static str {
+        if self.csv {
+            "csv"
+        } else if self.ndjson {
+            "ndjson"
+        } else if self.plain {
+            "plain"
+        } else {
+            "table"
+        }
+    }
+
+    /// Label for the totals row: `--total-label`, or the localized "total".
+    fn total_label(&self) -> &str {
+        self.total_label.as_deref().unwrap_or_else(|| i18n::tr("total"))
+    }
+
+    /// Formats a count for table output, honouring `--human` and its options.
+    fn format_count(&self, num: usize) -> String {
+        if !self.show_human {
+            return num.to_string();
+        }
+        let human = format_human(num, self.precision.unwrap_or(1), self.rounding);
+        if self.human_both && num >= 1_000 {
+            format!("{} ({})", group_thousands(num), human)
+        } else {
+            human
+        }
+    }
+}
+
+/// Returns the value following the option at `args[*i]`, advancing `i` past it.
+fn option_value(args: &[String], i: &mut usize) -> String {
+    let option = &args[*i];
+    *i += 1;
+    match args.get(*i) {
+        Some(value) => value.clone(),
+        None => {
+            eprintln!("rwc: option '{}' requires an argument", option);
+            eprintln!("Try 'rwc --help' for more information.");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The optional words-per-minute rate after `args[*i]`, an option like
+/// `--reading-time [WPM]`: `--option=WPM`, or a number as the next argument
+/// unless a file has that name. Exits if the rate is not a positive number.
+fn optional_rate(args: &[String], i: &mut usize) -> Option<usize> {
+    let (option, value) = match args[*i].split_once('=') {
+        Some((option, value)) => (option, value),
+        None => match args.get(*i + 1) {
+            Some(next) if next.parse::<usize>().is_ok() && !Path::new(next).exists() => {
+                *i += 1;
+                (args[*i - 1].as_str(), next.as_str())
+            }
+            _ => return None,
+        },
+    };
+    match value.parse() {
+        Ok(wpm) if wpm > 0 => Some(wpm),
+        _ => {
+            eprintln!("rwc: invalid words per minute '{}' for {}", value, option);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Reads the NUL-separated file names of `--files0-from` from `source` (`-`
+/// for standard input), as produced by `find -print0`. Exits on errors.
+fn read_files0(source: &str) -> Vec<String> {
+    let mut data = Vec::new();
+    let result = if source == "-" {
+        io::stdin().lock().read_to_end(&mut data)
+    } else {
+        File::open(source).and_then(|mut file| file.read_to_end(&mut data))
+    };
+    if let Err(e) = result {
+        eprintln!("rwc: cannot open '{}' for reading: {}", source, e);
+        std::process::exit(1);
+    }
+
+    let mut names = Vec::new();
+    let entries = data.strip_suffix(b"\0").unwrap_or(&data);
+    if entries.is_empty() {
+        return names;
+    }
+    for (index, name) in entries.split(|&b| b == 0).enumerate() {
+        if name.is_empty() {
+            eprintln!("rwc: {}:{}: invalid zero-length file name", source, index + 1);
+            std::process::exit(1);
+        }
+        let name = String::from_utf8_lossy(name).into_owned();
+        if source == "-" && name == "-" {
+            eprintln!(
+                "rwc: when reading file names from standard input, no file name of '-' allowed"
+            );
+            std::process::exit(1);
+        }
+        names.push(name);
+    }
+    names
 }
 
+/// Counts `text` with the basic metrics only; see `engine::scan`.
 fn count_text(text: &str) -> Counts {
-    let mut counts = Counts::default();
-    
-    counts.bytes = text.len();
-    counts.chars = text.chars().count();
-    counts.lines = text.lines().count();
-    
-    // More sophisticated word counting
-    counts.words = text
-        .split_whitespace()
-        .filter(|word| !word.is_empty())
-        .count();
-    
-    counts
-}
-
-fn count_reader<R: Read>(mut reader: R) -> io::Result<Counts> {
-    let mut buffer = String::new();
-    reader.read_to_string(&mut buffer)?;
-    Ok(count_text(&buffer))
+    engine::scan(text, &engine::Metrics::default()).into()
 }
 
-fn format_number(num: usize, human: bool) -> String {
-    if !human {
-        return num.to_string();
+/// Average silent reading speed used for reading-time estimates.
+const READING_WPM: usize = 230;
+
+/// Average speaking pace of a talk or podcast script read aloud.
+const SPEAKING_WPM: usize = 130;
+
+/// Words on a standard manuscript page: 12 pt, double-spaced.
+const WORDS_PER_PAGE: usize = 250;
+
+/// `--pages` presets, in words a page.
+const PAGE_PRESETS: &[(&str, usize)] = &[
+    ("manuscript", WORDS_PER_PAGE),
+    // A printed trade paperback.
+    ("novel", 300),
+    // Single-spaced 12 pt, as papers and theses are often set.
+    ("academic", 500),
+];
+
+/// The words a page after `args[*i]`, `--pages [WORDS|PRESET]`: given as
+/// `--pages=VALUE`, or as the next argument unless a file has that name.
+/// Exits on an invalid value.
+fn words_per_page(args: &[String], i: &mut usize) -> usize {
+    let preset = |name: &str| {
+        PAGE_PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .map(|&(_, words)| words)
+    };
+    let value = match args[*i].split_once('=') {
+        Some((_, value)) => value,
+        None => match args.get(*i + 1) {
+            Some(next)
+                if (next.parse::<usize>().is_ok() || preset(next).is_some())
+                    && !Path::new(next).exists() =>
+            {
+                *i += 1;
+                next.as_str()
+            }
+            _ => return WORDS_PER_PAGE,
+        },
+    };
+    match value.parse() {
+        Ok(words) if words > 0 => words,
+        _ => preset(value).unwrap_or_else(|| {
+            let presets: Vec<&str> = PAGE_PRESETS.iter().map(|(name, _)| *name).collect();
+            eprintln!(
+                "rwc: invalid words per page '{}' for --pages (expected a number or {})",
+                value,
+                presets.join(", ")
+            );
+            std::process::exit(1);
+        }),
+    }
+}
+
+/// Estimated reading time in whole minutes, rounded up.
+fn reading_minutes(words: usize) -> usize {
+    readtime::minutes(words, READING_WPM, Rounding::Up)
+}
+
+/// Counts `reader` with the metrics `config` asks for.
+fn count_input<R: Read>(reader: R, config: &Config) -> io::Result<Counts> {
+    count_named(reader, None, config)
+}
+
+/// Like `count_input`, for input read from `path` (used to detect its type).
+///
+/// Input is streamed through the counting engine in fixed-size chunks unless
+/// a transform or `--preview` needs the whole text at once.
+fn count_named<R: Read>(reader: R, path: Option<&Path>, config: &Config) -> io::Result<Counts> {
+    let metrics = config.metrics();
+    if config.pipeline.is_empty() && config.preview.is_none() {
+        let mut hasher = config.sidecar.then(sha256::Sha256::new);
+        let transcoder = encoding::Transcoder::new(config.encoding);
+        let mut counts = stream(reader, &metrics, hasher.as_mut(), Some(transcoder))?;
+        counts.sha256 = hasher.map(sha256::Sha256::finish_hex);
+        return Ok(counts);
     }
-    
-    if num >= 1_000_000_000 {
-        format!("{:.1}G", num as f64 / 1_000_000_000.0)
-    } else if num >= 1_000_000 {
-        format!("{:.1}M", num as f64 / 1_000_000.0)
-    } else if num >= 1_000 {
-        format!("{:.1}K", num as f64 / 1_000.0)
+
+    // The input is held whole, so it must fit in what is left of the
+    // --max-memory budget.
+    let mut data = Vec::new();
+    let limit = memory::remaining().map_or(u64::MAX, |left| left as u64 + 1);
+    reader.take(limit).read_to_end(&mut data)?;
+    let _held = memory::hold(data.len()).ok_or_else(|| {
+        io::Error::other("too large to hold in --max-memory (--pre, --auto and --preview read inputs whole)")
+    })?;
+    let sha256 = if config.sidecar {
+        Some(sha256::hex_digest(data.as_slice())?)
     } else {
-        num.to_string()
+        None
+    };
+    let stored_bytes = data.len();
+    let data = encoding::decode(data, config.encoding);
+    let (buffer, structure) = if config.structure {
+        structure::apply(&config.pipeline, path, data, config.lossy)?
+    } else {
+        (transform::apply(&config.pipeline, path, data, config.lossy)?, None)
+    };
+    let mut counts = Counts::from(engine::scan(&buffer, &metrics));
+    if config.pipeline.is_empty() {
+        counts.bytes = stored_bytes;
+    }
+    counts.sha256 = sha256;
+    counts.structure = structure;
+    if let Some(preview) = config.preview {
+        counts.preview = Some(preview.pick(&buffer, config.seed));
     }
+    Ok(counts)
 }
 
-fn print_counts(counts: &Counts, config: &Config, filename: Option<&str>) {
-    if config.show_json {
-        println!("{{");
-        if let Some(name) = filename {
-            println!("  \"file\": \"{}\",", name);
+/// Feeds `reader` to a `Scanner` in 64 KiB chunks, hashing the raw bytes on
+/// the way if asked. With a `transcoder` the text is decoded before it is
+/// scanned, and bytes are still counted as read.
+fn stream<R: Read>(
+    mut reader: R,
+    metrics: &engine::Metrics,
+    mut hasher: Option<&mut sha256::Sha256>,
+    mut transcoder: Option<encoding::Transcoder>,
+) -> io::Result<Counts> {
+    let mut scanner = engine::Scanner::new(metrics);
+    let mut buffer = vec![0; 64 * 1024];
+    let mut decoded = Vec::new();
+    let mut bytes = 0;
+    loop {
+        if interrupt::requested() {
+            return Err(interrupt::error());
+        }
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        bytes += n;
+        if let Some(hasher) = hasher.as_deref_mut() {
+            hasher.update(&buffer[..n]);
         }
-        if config.show_lines {
-            println!("  \"lines\": {},", counts.lines);
+        match transcoder.as_mut() {
+            Some(transcoder) if !transcoder.is_identity() => {
+                decoded.clear();
+                transcoder.push(&buffer[..n], &mut decoded);
+                scanner.feed(&decoded)?;
+            }
+            _ => scanner.feed(&buffer[..n])?,
+        }
+    }
+    if let Some(transcoder) = transcoder.as_mut() {
+        decoded.clear();
+        transcoder.finish(&mut decoded);
+        scanner.feed(&decoded)?;
+    }
+    let mut counts = Counts::from(scanner.finish()?);
+    counts.bytes = bytes;
+    Ok(counts)
+}
+
+fn format_number(num: usize, human: bool) -> String {
+    if !human {
+        return num.to_string();
+    }
+    format_human(num, 1, Rounding::default())
+}
+
+/// Formats `num` as `1.2K`/`3.4M`/`5.6G` with `precision` decimals.
+///
+/// Rounding is done on the exact integer, so no floating-point error can
+/// tip a value across a rounding boundary; a value that rounds up to the next
+/// magnitude (`999.96K`) is shown in that unit (`1.0M`).
+fn format_human(num: usize, precision: usize, rounding: Rounding) -> String {
+    let scale = 10u128.pow(precision as u32);
+    let units = [(1_000_000_000u128, 'G'), (1_000_000, 'M'), (1_000, 'K')];
+    let mut chosen = None;
+    let mut rolled_over = false;
+    for (index, &(divisor, suffix)) in units.iter().enumerate().rev() {
+        if (num as u128) < divisor && !rolled_over {
+            break;
         }
-        if config.show_words {
-            println!("  \"words\": {},", counts.words);
+        let scaled = rounding.divide(num as u128 * scale, divisor);
+        // Rounded up to 1000 of this unit: let the next unit display it.
+        rolled_over = scaled >= 1000 * scale && index > 0;
+        if !rolled_over {
+            chosen = Some((scaled, suffix));
         }
-        if config.show_chars {
-            println!("  \"chars\": {},", counts.chars);
+    }
+    match chosen {
+        Some((scaled, suffix)) if precision == 0 => format!("{}{}", scaled, suffix),
+        Some((scaled, suffix)) => format!(
+            "{}.{:0width$}{}",
+            scaled / scale,
+            scaled % scale,
+            suffix,
+            width = precision
+        ),
+        None => num.to_string(),
+    }
+}
+
+/// Formats `num` with comma thousands separators: `1,234,567`.
+fn group_thousands(num: usize) -> String {
+    let digits = num.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
         }
-        if config.show_bytes {
-            println!("  \"bytes\": {}", counts.bytes);
+        out.push(c);
+    }
+    out
+}
+
+/// Which files `--skip-minified` / `--only-minified` keep.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MinifiedFilter {
+    Skip,
+    Only,
+}
+
+/// How `--human` rounds to the displayed precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Rounding {
+    /// Round to nearest, ties to even (the default).
+    #[default]
+    HalfEven,
+    /// Round to nearest, ties away from zero.
+    HalfUp,
+    /// Truncate.
+    Down,
+    /// Round any remainder up.
+    Up,
+}
+
+impl Rounding {
+    fn parse(text: &str) -> Option<Rounding> {
+        match text {
+            "half-even" => Some(Rounding::HalfEven),
+            "half-up" => Some(Rounding::HalfUp),
+            "down" => Some(Rounding::Down),
+            "up" => Some(Rounding::Up),
+            _ => None,
         }
-        println!("}}");
-        return;
     }
 
-    let mut output = Vec::new();
-    
-    if config.show_lines {
-        output.push(format_number(counts.lines, config.show_human));
+    /// Integer division of `value` by `divisor` using this rounding mode.
+    fn divide(self, value: u128, divisor: u128) -> u128 {
+        let quotient = value / divisor;
+        let remainder = value % divisor;
+        let round_up = match self {
+            Rounding::Down => false,
+            Rounding::Up => remainder > 0,
+            Rounding::HalfUp => remainder * 2 >= divisor,
+            Rounding::HalfEven => {
+                remainder * 2 > divisor || (remainder * 2 == divisor && quotient % 2 == 1)
+            }
+        };
+        quotient + round_up as u128
     }
-    if config.show_words {
-        output.push(format_number(counts.words, config.show_human));
+}
+
+fn format_unique(tracker: &distinct::Tracker, config: &Config) -> String {
+    let count = config.format_count(tracker.count());
+    match tracker.error() {
+        Some(error) => format!("{}±{:.1}%", count, error * 100.0),
+        None => count,
     }
-    if config.show_chars {
-        output.push(format_number(counts.chars, config.show_human));
+}
+
+/// Scores are usually whole numbers; fractional weights keep two decimals.
+fn format_score(score: f64) -> String {
+    if score.fract() == 0.0 {
+        format!("{}", score as i64)
+    } else {
+        format!("{:.2}", score)
     }
-    if config.show_bytes {
-        output.push(format_number(counts.bytes, config.show_human));
+}
+
+/// Whether `--no-generated` should leave `path` out. Files that cannot be
+/// inspected are kept so the usual error is reported when counting them.
+fn is_generated(path: &Path) -> bool {
+    path != Path::new("-") && matches!(generated::reason(path), Ok(Some(_)))
+}
+
+/// Parses sizes such as `4096`, `64K`, `512M` or `2GiB` (binary multiples).
+fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let value: f64 = number.parse().ok()?;
+    let unit = unit.trim().to_ascii_uppercase();
+    let unit = unit.trim_end_matches("IB").trim_end_matches('B');
+    let multiplier: u64 = match unit {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return None,
+    };
+    Some((value * multiplier as f64) as u64)
+}
+
+/// Prints hand-built `  "key": value` fields as a JSON object: one field per
+/// line, or all on one line with `--json-compact`. Fields keep the order they
+/// were pushed in, so reports diff cleanly between runs.
+fn print_json_fields(fields: &[String], config: &Config) {
+    if config.json_compact {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| field.trim_start().replacen("\": ", "\":", 1))
+            .collect();
+        output::print(&format!("{{{}}}", fields.join(",")));
+    } else {
+        output::print(&format!("{{\n{}\n}}", fields.join(",\n")));
     }
+}
 
-    print!("{:>8}", output.join(&format!("{:>8}", "")));
-    
+/// The JSON object for one input (or the totals, with no `filename`). Members
+/// always come in the same order, so reports diff cleanly between runs.
+fn counts_json(counts: &Counts, config: &Config, filename: Option<&str>) -> Value {
+    // Ratios and averages keep two decimals, as in the table.
+    let rounded = |value: f64| Value::Number((value * 100.0).round() / 100.0);
+    let mut members: Vec<(&str, Value)> = Vec::new();
     if let Some(name) = filename {
-        println!(" {}", name);
+        members.push(("file", name.into()));
+    }
+    for (key, value) in shown_counts(counts, config) {
+        members.push((key, value.into()));
+    }
+    let uniques = counts.uniques.as_ref();
+    for (key, error_key, tracker) in [
+        ("unique_lines", "unique_lines_error", uniques.and_then(|u| u.lines.as_ref())),
+        ("unique_words", "unique_words_error", uniques.and_then(|u| u.words.as_ref())),
+    ] {
+        if let Some(tracker) = tracker {
+            members.push((key, tracker.count().into()));
+            if let Some(error) = tracker.error() {
+                members.push((error_key, Value::Number((error * 1e4).round() / 1e4)));
+            }
+        }
+    }
+    if let Some(score) = counts.score {
+        members.push(("score", rounded(score)));
+    }
+    if let Some(score) = counts.minified {
+        members.push(("minified", Value::Bool(score >= generated::MINIFIED_THRESHOLD)));
+        members.push(("minified_score", rounded(score)));
+    }
+    if let Some(language) = counts.language {
+        members.push(("language", language.into()));
+    }
+    if let Some(indent) = &counts.indent {
+        members.push(("indent_max", indent.max_depth.into()));
+        members.push(("indent_avg", rounded(indent.average_depth())));
+        members.push(("indent_tab_ratio", rounded(indent.tab_ratio())));
+    }
+    if let Some(visual) = counts.visual_lines {
+        members.push(("visual_lines", visual.into()));
+    }
+    if let Some(records) = counts.records {
+        members.push(("records", records.into()));
+    }
+    if let Some(levels) = &counts.levels {
+        let per_level = levels
+            .iter()
+            .map(|(level, lines)| (level.to_string(), Value::from(lines)))
+            .collect();
+        members.push(("levels", Value::Object(per_level)));
+    }
+    if config.reading_wpm.is_some() {
+        members.push(("reading_minutes", config.reading_minutes(counts.words).into()));
+        members.push(("reading_time", config.reading_time(counts.words).into()));
+    }
+    if config.speaking_wpm.is_some() {
+        let minutes = config.speaking_minutes(counts.words);
+        members.push(("speaking_minutes", minutes.into()));
+        members.push(("speaking_time", readtime::format(minutes, config.time_format).into()));
+    }
+    if config.words_per_page.is_some() {
+        members.push(("pages", config.pages(counts.words).into()));
+    }
+    if let Some(status) = counts.status {
+        members.push(("status", status.name().into()));
+    }
+    if let Some(structure) = &counts.structure {
+        members.push(("structure", structure.to_json()));
+    }
+    if let Some(preview) = &counts.preview {
+        let lines = preview.iter().map(|line| Value::from(line.as_str())).collect();
+        members.push(("preview", Value::Array(lines)));
+    }
+    Value::Object(members.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+}
+
+/// Prints a JSON document: indented, or on one line with `--json-compact`.
+fn print_json(value: &Value, config: &Config) {
+    if config.json_compact {
+        output::print(&value.to_string());
     } else {
-        println!();
+        output::print(&value.pretty());
     }
 }
 
+/// Prints the files `--list-only` found, one per line or as JSON.
+fn list_files(files: &[String], partial: bool, config: &Config) {
+    if !config.show_json {
+        for file in files {
+            output::print(file);
+        }
+        return;
+    }
+    let names = files.iter().map(|file| file.as_str().into()).collect();
+    let mut members = vec![("files".to_string(), Value::Array(names))];
+    if partial {
+        members.push(("partial".to_string(), Value::Bool(true)));
+    }
+    print_json(&Value::Object(members), config);
+}
+
+/// Prints a `--plain` record: one `label: value` line per metric and a blank
+/// line after, with no padding or symbols, for screen readers.
+fn print_plain(fields: &[(&str, String)]) {
+    output::print(&plain_record(fields));
+}
+
+fn plain_record(fields: &[(&str, String)]) -> String {
+    let mut record: Vec<String> = fields
+        .iter()
+        .map(|(label, value)| format!("{}: {}", label, value))
+        .collect();
+    record.push(String::new());
+    record.join("\n")
+}
+
+/// The `--plain` label of a count `shown_counts` names.
+fn plain_label(key: &str) -> &str {
+    match key {
+        "chars" => "characters",
+        "max_line_length" => "longest line",
+        "max_display_width" => "widest line",
+        "blank_lines" => "blank lines",
+        key => key,
+    }
+}
+
+/// The basic counts `config` shows, by their JSON names.
+fn shown_counts(counts: &Counts, config: &Config) -> Vec<(&'static str, usize)> {
+    [
+        (config.show_lines, "lines", counts.lines),
+        (config.show_words, "words", counts.words),
+        (config.show_chars, "chars", counts.chars),
+        (config.show_bytes, "bytes", counts.bytes),
+        (config.show_max_line_length, "max_line_length", counts.max_line_length),
+        (config.show_max_display_width, "max_display_width", counts.max_display_width),
+        (config.show_paragraphs, "paragraphs", counts.paragraphs),
+        (config.show_blank_lines, "blank_lines", counts.blank_lines),
+        (config.show_all, "sentences", counts.sentences),
+    ]
+    .into_iter()
+    .filter(|&(shown, _, _)| shown)
+    .map(|(_, key, value)| (key, value))
+    .collect()
+}
+
+/// Prints a record in the format `config` picked from `formats`, which
+/// `formats::formats` builds once per run.
+fn print_counts(
+    formats: &format::Formats<'_, Counts>,
+    counts: &Counts,
+    config: &Config,
+    filename: Option<&str>,
+) {
+    let formatter = formats.get(config.format_name()).expect("every format is registered");
+    output::print(&formatter.format(counts, filename));
+}
+
+/// Prints counts for log sources that also report an entry count, laid out
+/// as the other records are.
+fn print_entry_counts(label: &str, entries: usize, counts: &Counts, config: &Config) {
+    let shown = shown_counts(counts, config);
+    if config.show_json {
+        let mut fields = vec![
+            format!("  \"source\": {}", json::quote(label)),
+            format!("  \"entries\": {}", entries),
+        ];
+        fields.extend(shown.iter().map(|(key, value)| format!("  \"{}\": {}", key, value)));
+        print_json_fields(&fields, config);
+        return;
+    }
+
+    if config.plain {
+        let mut fields = vec![
+            ("source", label.to_string()),
+            ("entries", config.format_count(entries)),
+        ];
+        fields.extend(shown.iter().map(|&(key, value)| (plain_label(key), config.format_count(value))));
+        print_plain(&fields);
+        return;
+    }
+
+    let mut cells = vec![config.format_count(entries)];
+    cells.extend(shown.iter().map(|&(_, value)| config.format_count(value)));
+    output::print(&format!("{} {}", format::table_row(&cells), bidi::display_name(label)));
+}
+
 fn process_file(filename: &str, config: &Config) -> io::Result<Counts> {
     if filename == "-" {
-        count_reader(io::stdin().lock())
+        let result = if interactive::wanted() {
+            interactive::read().and_then(|data| count_stored(io::Cursor::new(data), None, config))
+        } else {
+            count_stored(io::stdin().lock(), None, config)
+        };
+        let label = config.stdin_label();
+        result.map_err(|e| io::Error::new(e.kind(), format!("rwc: {}: {}", label, e)))
+    } else if config.staged {
+        let with_name = |e: io::Error| io::Error::new(e.kind(), format!("rwc: {}: {}", filename, e));
+        let data = io::Cursor::new(git::staged_content_here(filename).map_err(with_name)?);
+        count_seekable(data, Path::new(filename), config).map_err(with_name)
+    } else {
+        let with_name = |e: io::Error| io::Error::new(e.kind(), format!("rwc: {}: {}", filename, e));
+        let mut file = File::open(filename).map_err(with_name)?;
+        if let Some(counts) = count_in_ranges(&mut file, Path::new(filename), config).map_err(with_name)? {
+            return Ok(counts);
+        }
+        if let Some(counts) = count_mapped(&file, config).map_err(with_name)? {
+            return Ok(counts);
+        }
+        count_seekable(file, Path::new(filename), config).map_err(with_name)
+    }
+}
+
+/// Counts `file` over a memory map of it for `--mmap`, or `None`, with
+/// nothing consumed, when it is read instead: it is not to be mapped,
+/// cannot be, is compressed, or it is not scanned as it is stored.
+fn count_mapped(file: &File, config: &Config) -> io::Result<Option<Counts>> {
+    let len = file.metadata()?.len();
+    if !scans_as_stored(config) || !config.mmap.maps(len) {
+        return Ok(None);
+    }
+    // Special files that report a size may still refuse a map.
+    let Ok(map) = mmap::Map::new(file, len) else {
+        return Ok(None);
+    };
+    let data = map.bytes();
+    if compression(&mut &data[..], config)?.is_some() {
+        return Ok(None);
+    }
+    let metrics = config.metrics();
+    let mut scanner = engine::Scanner::new(&metrics);
+    let mut hasher = config.sidecar.then(sha256::Sha256::new);
+    let mut transcoder = encoding::Transcoder::new(config.encoding);
+    let mut decoded = Vec::new();
+    // In pieces, so that text in another encoding is decoded a piece at a
+    // time; UTF-8 is scanned where it is mapped.
+    for chunk in data.chunks(1 << 20) {
+        if interrupt::requested() {
+            return Err(interrupt::error());
+        }
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(chunk);
+        }
+        if transcoder.is_identity() {
+            scanner.feed(chunk)?;
+        } else {
+            decoded.clear();
+            transcoder.push(chunk, &mut decoded);
+            scanner.feed(&decoded)?;
+        }
+    }
+    decoded.clear();
+    transcoder.finish(&mut decoded);
+    scanner.feed(&decoded)?;
+    let mut counts = Counts::from(scanner.finish()?);
+    counts.bytes = data.len();
+    counts.sha256 = hasher.map(sha256::Sha256::finish_hex);
+    Ok(Some(counts))
+}
+
+/// Whether a file is scanned from start to end as it is stored: nothing
+/// transforms it, skips part of it, or needs it whole, and its text is
+/// counted rather than only its size.
+fn scans_as_stored(config: &Config) -> bool {
+    config.pipeline.is_empty()
+        && config.preview.is_none()
+        && config.last.is_none()
+        && config.time_range.is_none()
+        && !config.bytes_only()
+}
+
+/// Counts a file or staged blob, seeking past what `--last` or a sorted
+/// `--since` leaves out instead of reading it, and only seeking to the end
+/// when the byte count is all that is asked for.
+fn count_seekable<R: Read + Seek>(input: R, path: &Path, config: &Config) -> io::Result<Counts> {
+    let mut input = io::BufReader::new(input);
+    if compression(&mut input, config)?.is_some() {
+        // Offsets in the compressed data say nothing about the text.
+        if config.last.is_some() {
+            return Err(io::Error::other(
+                "--last cannot seek in compressed input (--no-decompress counts it as is)",
+            ));
+        }
+    } else if config.bytes_only() {
+        if let Some(len) = remaining_len(&mut input)? {
+            return Ok(Counts {
+                bytes: len as usize,
+                ..Counts::default()
+            });
+        }
     } else {
-        let file = File::open(filename)
-            .map_err(|e| io::Error::new(e.kind(), format!("rwc: {}: {}", filename, e)))?;
-        count_reader(file)
+        if let Some(window) = config.last {
+            seek_to_last(&mut input, window)?;
+        }
+        if let Some(range) = &config.time_range {
+            range.seek_to_start(&mut input)?;
+        }
+    }
+    count_stored(input, Some(path), config)
+}
+
+/// Counts `file` in ranges on `--file-threads` threads, or `None`, with
+/// nothing consumed, when it is read in one pass: it is small, compressed
+/// or not UTF-8, or the options need it whole or in order.
+fn count_in_ranges(file: &mut File, path: &Path, config: &Config) -> io::Result<Option<Counts>> {
+    let metrics = config.metrics();
+    if config.file_threads <= 1
+        || !scans_as_stored(config)
+        || config.sidecar
+        || !metrics.joinable()
+    {
+        return Ok(None);
+    }
+    let len = file.metadata()?.len();
+    if len < 2 * ranges::MIN_RANGE {
+        return Ok(None);
+    }
+    let mut head = Vec::new();
+    file.take(encoding::SNIFF_LEN as u64).read_to_end(&mut head)?;
+    file.seek(SeekFrom::Start(0))?;
+    let compressed = !config.no_decompress && decompress::Format::detect(&head).is_some();
+    if compressed || !encoding::is_utf8(config.encoding, &head) {
+        return Ok(None);
+    }
+    ranges::count(path, len, &metrics, config.file_threads).map(|counts| Some(counts.into()))
+}
+
+/// The compression `input` starts with, if it is to be undone before
+/// counting. Nothing is consumed.
+fn compression<R: BufRead>(
+    input: &mut R,
+    config: &Config,
+) -> io::Result<Option<decompress::Format>> {
+    // A forced --type says how to read the input as it is.
+    let forced = config
+        .pipeline
+        .iter()
+        .any(|step| matches!(step, transform::Transform::Auto(Some(_))));
+    if config.no_decompress || forced {
+        return Ok(None);
+    }
+    Ok(decompress::Format::detect(input.fill_buf()?))
+}
+
+/// Counts `input`, decompressing it first if it is compressed and keeping
+/// only the `--since`/`--until` range of its lines.
+fn count_stored<R: BufRead>(
+    mut input: R,
+    path: Option<&Path>,
+    config: &Config,
+) -> io::Result<Counts> {
+    if let Some(format) = compression(&mut input, config)? {
+        // The payload of `access.log.gz` is named `access.log`; it may be
+        // compressed again.
+        let inner = path.and_then(Path::file_stem).map(Path::new);
+        let count = |stream: &mut dyn Read| count_stored(io::BufReader::new(stream), inner, config);
+        if !config.sidecar {
+            return decompress::with_stream(format, input, count);
+        }
+        // The sidecar digest is of the file as stored, hashed on the way
+        // through, to its end.
+        let mut hasher = sha256::Sha256::new();
+        let mut stored = io::BufReader::new(sha256::Hashing {
+            inner: input,
+            hasher: &mut hasher,
+        });
+        let mut counts = decompress::with_stream(format, &mut stored, count)?;
+        io::copy(&mut stored, &mut io::sink())?;
+        drop(stored);
+        counts.sha256 = Some(hasher.finish_hex());
+        return Ok(counts);
+    }
+    // Compressed data left as it is with --no-decompress is binary.
+    let kept = config.no_decompress && decompress::Format::detect(input.fill_buf()?).is_some();
+    let result = match &config.time_range {
+        Some(range) => count_named(logtime::Filtered::new(input, range), path, config),
+        None => count_named(input, path, config),
+    };
+    result.map_err(|e| match e.kind() {
+        io::ErrorKind::InvalidData if kept => io::Error::new(
+            e.kind(),
+            format!("{} (--no-decompress reads compressed data as is: count its bytes with -c, or its text with --lossy)", e),
+        ),
+        _ => e,
+    })
+}
+
+/// The bytes left in `input`, found by seeking to its end. `None`, with
+/// nothing consumed, for pipes, empty files and special files that report
+/// no size, which have to be read to be measured.
+fn remaining_len<R: Seek>(input: &mut R) -> io::Result<Option<u64>> {
+    let Ok(start) = input.stream_position() else {
+        return Ok(None);
+    };
+    match input.seek(SeekFrom::End(0)) {
+        Ok(end) if end > start => Ok(Some(end - start)),
+        _ => {
+            input.seek(SeekFrom::Start(start))?;
+            Ok(None)
+        }
+    }
+}
+
+/// Positions `input` at the start of the first line that begins within its
+/// final `window` bytes, for `--last`. Input no longer than the window is
+/// left at the start; a window inside one long line leaves nothing to read.
+fn seek_to_last<R: Read + Seek>(input: &mut R, window: u64) -> io::Result<()> {
+    let len = input.seek(SeekFrom::End(0))?;
+    if len <= window {
+        input.seek(SeekFrom::Start(0))?;
+        return Ok(());
+    }
+    // Start one byte early: if that byte ends a line, the window is aligned.
+    let mut position = input.seek(SeekFrom::Start(len - window - 1))?;
+    let mut buffer = [0; 8192];
+    loop {
+        let n = match input.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if let Some(newline) = buffer[..n].iter().position(|&b| b == b'\n') {
+            input.seek(SeekFrom::Start(position + newline as u64 + 1))?;
+            return Ok(());
+        }
+        position += n as u64;
     }
 }
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("image") => std::process::exit(image::run(&args[2..])),
+        Some("stats") => std::process::exit(stats::run(&args[2..])),
+        Some("freq") => std::process::exit(freq::run(&args[2..])),
+        Some("daemon") => std::process::exit(daemon::run(&args[2..])),
+        Some("query") => std::process::exit(daemon::query(&args[2..])),
+        Some("lsp") => std::process::exit(lsp::run()),
+        Some("help") => std::process::exit(help::run(&args[2..])),
+        Some("corpus") => std::process::exit(corpus::run(&args[2..])),
+        Some("todos") => std::process::exit(todos::run(&args[2..])),
+        Some("blame-stats") => std::process::exit(blame::run(&args[2..])),
+        Some("commit-msg") => std::process::exit(commit_msg::run(&args[2..])),
+        Some("hook") => std::process::exit(hook::run(&args[2..])),
+        Some("toc") => std::process::exit(toc::run(&args[2..])),
+        Some("annotate-frontmatter") => std::process::exit(frontmatter::run(&args[2..])),
+        Some("batch") => std::process::exit(batch::run(&args[2..])),
+        Some("report-diff") => std::process::exit(report_diff::run(&args[2..])),
+        Some("compare-dirs") => std::process::exit(compare_dirs::run(&args[2..])),
+        Some("xlsx") => std::process::exit(xlsx::run(&args[2..])),
+        #[cfg(feature = "sql")]
+        Some("sql") => std::process::exit(sql::run(&args[2..])),
+        #[cfg(not(feature = "sql"))]
+        Some("sql") => {
+            eprintln!("rwc: sql support is not enabled (rebuild with --features sql)");
+            std::process::exit(1);
+        }
+        #[cfg(feature = "sql")]
+        Some("sqlite") => std::process::exit(sqlite::run(&args[2..])),
+        #[cfg(not(feature = "sql"))]
+        Some("sqlite") => {
+            eprintln!("rwc: sqlite support is not enabled (rebuild with --features sql)");
+            std::process::exit(1);
+        }
+        #[cfg(feature = "arrow")]
+        Some("columnar") => std::process::exit(columnar::run(&args[2..])),
+        #[cfg(not(feature = "arrow"))]
+        Some("columnar") => {
+            eprintln!("rwc: Parquet/Arrow support is not enabled (rebuild with --features arrow)");
+            std::process::exit(1);
+        }
+        Some(arg) if !arg.starts_with('-') && !std::path::Path::new(arg).exists() => {
+            if let Some(command) = cli::suggest(arg, COMMANDS) {
+                eprintln!("rwc: '{}' is not a file or command", arg);
+                eprintln!("  did you mean 'rwc {}'?", command);
+                std::process::exit(1);
+            }
+        }
+        _ => {}
+    }
+
     let config = Config::new();
+    // File counts carry the metadata inside their JSON document; other modes
+    // print it as a record of its own first.
+    if config.metadata && config.selects_mode() {
+        metadata::print(&config);
+    }
     let mut total_counts = Counts::default();
     let mut file_count = 0;
+    let mut json_files = Vec::new();
+    // With --group-by: files and summed counts per group, by name.
+    let mut groups: BTreeMap<String, (usize, Counts)> = BTreeMap::new();
+    let mut summary = summary::Summary::default();
+    // With --top: every file's counts, ranked once all are counted.
+    let mut ranked: Vec<(Option<String>, Counts)> = Vec::new();
+
+    if config.journal {
+        #[cfg(all(target_os = "linux", feature = "journal"))]
+        let result = journal::run(&config);
+        #[cfg(not(all(target_os = "linux", feature = "journal")))]
+        let result: io::Result<()> = Err(io::Error::other(
+            "journal support is not enabled (rebuild on Linux with --features journal)",
+        ));
+        if let Err(e) = result {
+            eprintln!("rwc: --journal: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(channel) = &config.eventlog {
+        if let Err(e) = eventlog::run(channel, &config) {
+            eprintln!("rwc: --eventlog: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if config.editor_mode {
+        if !config.files.is_empty() || !config.remotes.is_empty() {
+            eprintln!("rwc: --editor-mode reads the document from standard input");
+            std::process::exit(1);
+        }
+        if let Err(e) = editor::run(&config) {
+            eprintln!("rwc: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(window) = config.window {
+        if !config.files.is_empty() || !config.remotes.is_empty() {
+            eprintln!("rwc: --window only applies to standard input");
+            std::process::exit(1);
+        }
+        if let Err(e) = window::run(window, &config) {
+            eprintln!("rwc: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(sampling) = config.sample {
+        if !config.remotes.is_empty() {
+            eprintln!("rwc: --sample cannot be combined with --remote");
+            std::process::exit(1);
+        }
+        std::process::exit(sample::run(&config, sampling));
+    }
+
+    if let Some(template) = &config.status_line {
+        std::process::exit(status::run(&config, template));
+    }
+
+    if config.patch {
+        if let Err(e) = patch::run(&config) {
+            eprintln!("rwc: --patch: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(check) = &config.license_header {
+        std::process::exit(license::run(&config, check));
+    }
+
+    if config.validate_utf8 {
+        std::process::exit(validate::run(&config));
+    }
+
+    if let Some(max) = config.lint_line_length {
+        std::process::exit(lint::run(&config, max));
+    }
+
+    if config.watch {
+        std::process::exit(watch::run(&config));
+    }
+
+    if config.follow {
+        std::process::exit(follow::run(&config));
+    }
+
+    if config.json_logs.is_some() {
+        std::process::exit(jsonlogs::run(&config));
+    }
+
+    if config.archive {
+        std::process::exit(archive::run(&config));
+    }
+
+    if let Some(format) = config.translation {
+        std::process::exit(translation::run(&config, format));
+    }
+
+    if config.outline {
+        std::process::exit(toc::run_outline(&config));
+    }
+
+    if let Some(marker) = &config.split_on {
+        std::process::exit(split::run(&config, marker));
+    }
+
+    // With no operands standard input is read, and like any `-` operand it
+    // goes through the same listing and totals as files. It is only left
+    // unnamed, as in wc, when it is the sole input and has no --label.
+    let implicit_stdin = ["-".to_string()];
+    let operands = if config.files.is_empty() && config.remotes.is_empty() {
+        &implicit_stdin[..]
+    } else {
+        &config.files[..]
+    };
+    let unnamed_stdin = operands.len() == 1 && config.remotes.is_empty() && config.label.is_none();
+
+    let formats = formats::formats(&config);
+    if !config.list_only {
+        let header = formats.get(config.format_name()).and_then(|format| format.header());
+        if let Some(header) = header {
+            output::print(&header);
+        }
+    }
 
-    if config.files.is_empty() {
-        // Read from stdin
-        match count_reader(io::stdin().lock()) {
-            Ok(counts) => {
-                print_counts(&counts, &config, None);
+    // From here Ctrl-C stops the count and reports what was counted.
+    interrupt::install();
+
+    // With -r, directory operands are replaced by the files under them,
+    // each counted with the options set for it in `.rwc.toml` files and
+    // front matter. Files past --max-files / --max-total-bytes are left out;
+    // the files before them are still reported and totalled.
+    let mut budget = walk::Budget::new(config.limits);
+    let mut files: Vec<String> = Vec::new();
+    let mut overrides = overrides::Overrides::new(&config);
+    let mut file_configs: HashMap<String, usize> = HashMap::new();
+    for operand in operands {
+        if budget.is_exhausted() || interrupt::requested() {
+            break;
+        }
+        let root = Path::new(operand);
+        if config.recursive && operand != "-" && root.is_dir() {
+            let paths = match walk::files_with(root, &mut budget, &config.walk) {
+                Ok(paths) => paths,
+                Err(e) => {
+                    eprintln!("rwc: {}: {}", operand, e);
+                    std::process::exit(1);
+                }
+            };
+            for path in paths {
+                if config.no_generated && is_generated(&path) {
+                    continue;
+                }
+                let name = path.display().to_string();
+                match overrides.find(root, &path) {
+                    Ok(Some(index)) => {
+                        file_configs.insert(name.clone(), index);
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        eprintln!("rwc: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                files.push(name);
             }
-            Err(e) => {
-                eprintln!("rwc: {}", e);
-                std::process::exit(1);
+            continue;
+        }
+        if config.no_generated && is_generated(Path::new(operand)) {
+            continue;
+        }
+        let len = if operand == "-" {
+            0
+        } else {
+            std::fs::metadata(operand).map_or(0, |m| m.len())
+        };
+        if !budget.admit(len) {
+            break;
+        }
+        files.push(operand.clone());
+    }
+
+    if config.list_only {
+        list_files(&files, budget.is_exhausted(), &config);
+        budget.warn_if_exhausted();
+        std::process::exit(0);
+    }
+
+    // Warnings raised while counting a file are held back and printed with
+    // its result; see `output`.
+    let count_file = |filename: &String| {
+        let config = file_configs
+            .get(filename)
+            .map_or(&config, |&index| overrides.config(index));
+        output::capture(|| {
+            process_file(filename, config).and_then(|counts| {
+                if config.sidecar && filename != "-" {
+                    sidecar::write(filename, &counts, config.sidecar_dir.as_deref()).map_err(
+                        |e| io::Error::new(e.kind(), format!("rwc: {}: sidecar: {}", filename, e)),
+                    )?;
+                }
+                Ok(counts)
+            })
+        })
+    };
+
+    // With --jobs, files are counted concurrently but still listed, and the
+    // first error still reported, in operand order. A repeated `-` finds
+    // standard input at its end, as in wc; counting in order keeps the first
+    // one the one that reads it. With --unordered each file is listed as soon
+    // as it is counted, but its counts still go into the totals, groups,
+    // summary and ranking in operand order, so those come out the same
+    // whatever the order of completion.
+    let repeated_stdin = files.iter().filter(|file| *file == "-").count() > 1;
+    std::thread::scope(|scope| {
+        type Counted = (usize, (io::Result<Counts>, Vec<String>));
+        let counted: Box<dyn Iterator<Item = Counted>> = if config.jobs <= 1 || repeated_stdin {
+            Box::new(files.iter().map(count_file).enumerate())
+        } else if config.unordered {
+            Box::new(pool::unordered(scope, &files, config.jobs, &count_file))
+        } else {
+            Box::new(pool::ordered(scope, &files, config.jobs, &count_file).enumerate())
+        };
+        let remotes = config
+            .remotes
+            .iter()
+            .map(|spec| output::capture(|| remote::count_remote(spec, &config)))
+            .enumerate()
+            .map(|(index, counted)| (files.len() + index, counted));
+        let inputs: Vec<&String> = files.iter().chain(&config.remotes).collect();
+
+        // Counts not yet tallied, by input, and the next input to tally.
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        for (index, (result, warnings)) in counted.chain(remotes) {
+            let filename = inputs[index];
+            for warning in warnings {
+                output::warn(warning);
+            }
+            match result {
+                Ok(counts) if !config.keeps_minified(&counts) => {
+                    pending.insert(index, None);
+                }
+                Ok(mut counts) => {
+                    if !config.thresholds.is_empty() {
+                        counts.status = Some(config.thresholds.status(&counts));
+                    }
+                    let name = match filename.as_str() {
+                        "-" if unnamed_stdin => None,
+                        "-" => Some(config.stdin_label()),
+                        name => Some(name),
+                    };
+                    if config.group_by.is_some() || config.badge.is_some() || config.top.is_some() {
+                        // Groups, the badge and the ranking are printed once
+                        // every file is counted.
+                    } else if config.show_json {
+                        json_files.push(counts_json(&counts, &config, name));
+                    } else {
+                        print_counts(&formats, &counts, &config, name);
+                    }
+                    pending.insert(index, Some((name, counts)));
+                }
+                // A file cut short by Ctrl-C is left out.
+                Err(_) if interrupt::requested() => {
+                    pending.insert(index, None);
+                }
+                Err(e) => {
+                    output::warn(e.to_string());
+                    std::process::exit(1);
+                }
+            }
+
+            // After Ctrl-C no more files are taken, so whatever is pending is
+            // tallied, still in operand order.
+            let interrupted = interrupt::requested();
+            while let Some((index, tallied)) = if interrupted {
+                pending.pop_first()
+            } else {
+                pending.remove_entry(&next)
+            } {
+                if let Some((name, counts)) = tallied {
+                    if let Some(group_by) = config.group_by {
+                        let group = groups.entry(group_by.key(inputs[index], &counts)).or_default();
+                        group.0 += 1;
+                        group.1.add(&counts);
+                    }
+                    if config.summary {
+                        summary.push(&counts);
+                    }
+                    // Add to totals
+                    total_counts.add(&counts);
+                    file_count += 1;
+                    if config.top.is_some() {
+                        ranked.push((name.map(str::to_string), counts));
+                    }
+                }
+                next = index + 1;
+            }
+            if interrupted {
+                break;
             }
         }
-        return;
+    });
+
+    budget.warn_if_exhausted();
+    let interrupted = interrupt::requested();
+    if interrupted {
+        output::warn(format!(
+            "rwc: interrupted after {} files; results are partial",
+            file_count
+        ));
     }
 
-    for filename in &config.files {
-        match process_file(filename, &config) {
-            Ok(counts) => {
-                print_counts(&counts, &config, Some(filename));
-                
-                // Add to totals
-                total_counts.bytes += counts.bytes;
-                total_counts.chars += counts.chars;
-                total_counts.words += counts.words;
-                total_counts.lines += counts.lines;
-                file_count += 1;
-            }
-            Err(e) => {
-                eprintln!("{}", e);
-                std::process::exit(1);
+    if let Some((count, by)) = config.top {
+        // A stable sort keeps ties in the order they were given.
+        ranked.sort_by_key(|(_, counts)| std::cmp::Reverse(by.of(counts)));
+        for (name, counts) in ranked.iter().take(count) {
+            if config.show_json {
+                json_files.push(counts_json(counts, &config, name.as_deref()));
+            } else {
+                print_counts(&formats, counts, &config, name.as_deref());
+            }
+        }
+    }
+
+    if let Some(options) = &config.badge {
+        output::print(&badge::render(&total_counts, options).to_string());
+    } else if config.show_json {
+        // JSON is one document holding every input and the totals.
+        let mut members = vec![("schema".to_string(), schema::VERSION.into())];
+        if config.metadata {
+            members.push(("metadata".to_string(), metadata::record(&config)));
+        }
+        if interrupted {
+            members.push(("partial".to_string(), Value::Bool(true)));
+        }
+        if config.group_by.is_some() {
+            let groups = groups
+                .iter()
+                .map(|(name, (files, counts))| {
+                    let Value::Object(mut members) = counts_json(counts, &config, None) else {
+                        unreachable!("counts are an object");
+                    };
+                    members.insert(0, ("group".to_string(), name.as_str().into()));
+                    members.insert(1, ("files".to_string(), (*files).into()));
+                    Value::Object(members)
+                })
+                .collect();
+            members.push(("groups".to_string(), Value::Array(groups)));
+        } else {
+            members.push(("files".to_string(), Value::Array(json_files)));
+        }
+        members.push(("total".to_string(), counts_json(&total_counts, &config, None)));
+        if config.summary {
+            members.push(("summary".to_string(), summary.to_json(&config)));
+        }
+        print_json(&Value::Object(members), &config);
+    } else {
+        for (name, (_, counts)) in &groups {
+            print_counts(&formats, counts, &config, Some(name));
+        }
+
+        // Show totals if multiple files, and always after Ctrl-C, as the
+        // place to say that they are partial.
+        if interrupted {
+            let label = format!("{} ({})", config.total_label(), i18n::tr("partial"));
+            print_counts(&formats, &total_counts, &config, Some(&label));
+        } else if file_count > 1 {
+            print_counts(&formats, &total_counts, &config, Some(config.total_label()));
+        }
+        if config.summary && file_count > 0 {
+            for (statistic, counts) in summary.rows() {
+                print_counts(&formats, &counts, &config, Some(statistic));
             }
         }
     }
 
-    // Show totals if multiple files
-    if file_count > 1 {
-        print_counts(&total_counts, &config, Some("total"));
+    if interrupted {
+        std::process::exit(interrupt::STATUS);
     }
 }
\ No newline at end of file
@@ -0,0 +1,79 @@
+//! `--max-memory`: a budget for what rwc keeps in memory.
+//!
+//! Structures that grow with the input charge what they hold against one
+//! process-wide budget. When a charge would go over it they fall back to
+//! something that does not grow: exact distinct counts become HyperLogLog
+//! estimates (with the usual error reported), and an input that would have
+//! to be held whole, for `--pre`, `--auto` or `--preview`, is an error
+//! rather than a risk of running out of memory. Without `--max-memory`
+//! nothing is limited. Charges are estimates of heap use, not measurements.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+static LIMIT: AtomicUsize = AtomicUsize::new(usize::MAX);
+static USED: AtomicUsize = AtomicUsize::new(0);
+static DEGRADED: AtomicBool = AtomicBool::new(false);
+static WARN: OnceLock<fn(String)> = OnceLock::new();
+
+pub fn set_limit(bytes: usize) {
+    LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+/// What is left of the budget, or `None` with no `--max-memory`.
+pub fn remaining() -> Option<usize> {
+    let limit = LIMIT.load(Ordering::Relaxed);
+    (limit != usize::MAX).then(|| limit.saturating_sub(USED.load(Ordering::Relaxed)))
+}
+
+/// Charges `bytes` if they fit in the budget.
+pub fn reserve(bytes: usize) -> bool {
+    let limit = LIMIT.load(Ordering::Relaxed);
+    let before = USED.fetch_add(bytes, Ordering::Relaxed);
+    if before.saturating_add(bytes) > limit {
+        USED.fetch_sub(bytes, Ordering::Relaxed);
+        return false;
+    }
+    true
+}
+
+/// Charges `bytes` whether or not they fit, for copies of what is already
+/// held.
+pub fn charge(bytes: usize) {
+    USED.fetch_add(bytes, Ordering::Relaxed);
+}
+
+pub fn release(bytes: usize) {
+    USED.fetch_sub(bytes, Ordering::Relaxed);
+}
+
+/// A charge released when dropped.
+pub struct Held(usize);
+
+impl Drop for Held {
+    fn drop(&mut self) {
+        release(self.0);
+    }
+}
+
+/// Charges `bytes` for as long as the result lives, if they fit.
+pub fn hold(bytes: usize) -> Option<Held> {
+    reserve(bytes).then_some(Held(bytes))
+}
+
+/// Sends the warning from `degraded` to `warn` rather than standard error,
+/// as rwc does to print it next to the file it concerns.
+pub fn warn_with(warn: fn(String)) {
+    let _ = WARN.set(warn);
+}
+
+/// Says, once, that `what` switched to an estimate to stay in the budget.
+pub fn degraded(what: &str) {
+    if !DEGRADED.swap(true, Ordering::Relaxed) {
+        let message = format!("rwc: --max-memory reached; {} are now estimates", what);
+        match WARN.get() {
+            Some(warn) => warn(message),
+            None => eprintln!("{}", message),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! `--metadata`: a provenance record ahead of JSON reports, so an archived
+//! report says which rwc produced it, when, where and with what options.
+
+use std::env;
+use std::fs;
+use std::time::SystemTime;
+
+use crate::tokenizer::Whitespace;
+use crate::json::{self, Value};
+use crate::time::format_rfc3339;
+use crate::{print_json_fields, Config};
+
+/// Prints the metadata record as the first object of the JSON output, for
+/// modes whose output is a stream of records.
+pub fn print(config: &Config) {
+    print_json_fields(&[format!("  \"metadata\": {}", record(config))], config);
+}
+
+/// The metadata record: what produced the report, when, where and how.
+pub fn record(config: &Config) -> Value {
+    let command_line = env::args().map(Value::from).collect();
+    let cwd = env::current_dir()
+        .map(|dir| Value::from(dir.display().to_string()))
+        .unwrap_or(Value::Null);
+    json::object([
+        ("version", env!("CARGO_PKG_VERSION").into()),
+        ("timestamp", format_rfc3339(SystemTime::now()).into()),
+        ("hostname", hostname().map_or(Value::Null, Value::from)),
+        ("cwd", cwd),
+        ("command_line", Value::Array(command_line)),
+        ("options", options(config)),
+    ])
+}
+
+/// The settings in effect after defaults are applied, which the command line
+/// alone does not show (e.g. the counts selected when no flag is given).
+fn options(config: &Config) -> Value {
+    let columns = [
+        ("lines", config.show_lines),
+        ("words", config.show_words),
+        ("chars", config.show_chars),
+        ("bytes", config.show_bytes),
+        ("paragraphs", config.show_paragraphs),
+        ("blank_lines", config.show_blank_lines),
+    ]
+    .into_iter()
+    .filter(|&(_, shown)| shown)
+    .map(|(name, _)| Value::from(name))
+    .collect();
+    let optional = |value: Option<usize>| value.map_or(Value::Null, Value::from);
+    json::object([
+        ("counts", Value::Array(columns)),
+        ("show_all", Value::Bool(config.show_all)),
+        ("unique_lines", Value::Bool(config.unique_lines)),
+        ("unique_words", Value::Bool(config.unique_words)),
+        ("approx", Value::Bool(config.approx)),
+        ("weights", Value::Bool(config.weights.is_some())),
+        ("transforms", config.pipeline.len().into()),
+        ("indent_stats", Value::Bool(config.indent_stats)),
+        ("minified", Value::Bool(config.minified)),
+        ("detect_language", Value::Bool(config.detect_language)),
+        ("whitespace", whitespace(&config.whitespace)),
+        ("ignore_format_chars", Value::Bool(config.ignore_format)),
+        ("unicode_words", Value::Bool(config.unicode_words)),
+        (
+            "tokenizer",
+            config.tokenizer.as_ref().map_or(Value::Null, |(name, _)| name.as_str().into()),
+        ),
+        ("encoding", config.encoding.name().into()),
+        ("lossy", Value::Bool(config.lossy)),
+        ("structure", Value::Bool(config.structure)),
+        ("decompress", Value::Bool(!config.no_decompress)),
+        ("record_start", Value::Bool(config.record_start.is_some())),
+        ("log_levels", Value::Bool(config.log_levels)),
+        ("no_generated", Value::Bool(config.no_generated)),
+        ("staged", Value::Bool(config.staged)),
+        ("recursive", Value::Bool(config.recursive)),
+        ("hidden", Value::Bool(config.walk.hidden)),
+        ("follow_symlinks", Value::Bool(config.walk.follow_symlinks)),
+        ("max_files", optional(config.limits.max_files)),
+        (
+            "max_total_bytes",
+            optional(config.limits.max_total_bytes.map(|n| n as usize)),
+        ),
+        ("last", optional(config.last.map(|n| n as usize))),
+        ("label", config.label.as_deref().map_or(Value::Null, Value::from)),
+        ("reading_wpm", optional(config.reading_wpm)),
+        ("speaking_wpm", optional(config.speaking_wpm)),
+        ("words_per_page", optional(config.words_per_page)),
+        ("tab_size", optional(config.tab_size)),
+    ])
+}
+
+fn whitespace(whitespace: &Whitespace) -> Value {
+    match whitespace {
+        Whitespace::Unicode => "unicode".into(),
+        Whitespace::Ascii => "ascii".into(),
+        Whitespace::Custom(separators) => {
+            format!("custom:{}", separators.iter().collect::<String>()).into()
+        }
+    }
+}
+
+/// The machine's host name, if the platform exposes it without a library.
+fn hostname() -> Option<String> {
+    let name = fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .ok()
+        .or_else(|| env::var("COMPUTERNAME").ok())
+        .or_else(|| env::var("HOSTNAME").ok())?;
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
@@ -0,0 +1,43 @@
+//! The basic counts, named, for options that pick one: `--badge-metric`,
+//! `--by`, `--warn-above` and `--error-above`.
+
+use crate::Counts;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Metric {
+    Lines,
+    #[default]
+    Words,
+    Chars,
+    Bytes,
+}
+
+impl Metric {
+    pub fn parse(text: &str) -> Option<Metric> {
+        match text {
+            "lines" => Some(Metric::Lines),
+            "words" => Some(Metric::Words),
+            "chars" => Some(Metric::Chars),
+            "bytes" => Some(Metric::Bytes),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Lines => "lines",
+            Metric::Words => "words",
+            Metric::Chars => "chars",
+            Metric::Bytes => "bytes",
+        }
+    }
+
+    pub fn of(self, counts: &Counts) -> usize {
+        match self {
+            Metric::Lines => counts.lines,
+            Metric::Words => counts.words,
+            Metric::Chars => counts.chars,
+            Metric::Bytes => counts.bytes,
+        }
+    }
+}
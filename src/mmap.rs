@@ -0,0 +1,147 @@
+//! `--mmap`: counting a file over a memory map of it instead of reading it.
+//!
+//! A mapped file is scanned where the kernel pages it in, with no copy into
+//! a read buffer, which is worth it for files of many megabytes. Files are
+//! only mapped when `--mmap` is given: a file that another process truncates
+//! while it is mapped stops rwc with SIGBUS, so mapping is for files that
+//! are known not to change while they are counted. Mapping is only done on
+//! 64-bit Unix; elsewhere files are always read.
+
+use std::fs::File;
+use std::io;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Mode {
+    /// `--no-mmap`: read every file.
+    #[default]
+    Never,
+    /// `--mmap`: map every file that can be.
+    Always,
+}
+
+impl Mode {
+    /// Whether a file of `len` bytes is to be mapped. Empty files cannot be.
+    pub fn maps(self, len: u64) -> bool {
+        platform::SUPPORTED && len > 0 && self == Mode::Always
+    }
+}
+
+/// A read-only, private mapping of a whole file, unmapped when dropped.
+pub struct Map {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Map {
+    /// Maps the first `len` bytes of `file`, which must be at least that
+    /// long and not empty.
+    pub fn new(file: &File, len: u64) -> io::Result<Map> {
+        let len = usize::try_from(len).map_err(|_| io::Error::other("too large to map"))?;
+        let ptr = platform::map(file, len)?;
+        Ok(Map { ptr, len })
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        // The mapping is readable for `len` bytes until `self` is dropped.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Drop for Map {
+    fn drop(&mut self) {
+        platform::unmap(self.ptr, self.len);
+    }
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+mod platform {
+    use std::ffi::{c_int, c_void};
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    pub const SUPPORTED: bool = true;
+
+    const PROT_READ: c_int = 1;
+    const MAP_PRIVATE: c_int = 2;
+    const MADV_SEQUENTIAL: c_int = 2;
+
+    extern "C" {
+        fn mmap(
+            addr: *mut c_void,
+            len: usize,
+            prot: c_int,
+            flags: c_int,
+            fd: c_int,
+            offset: i64,
+        ) -> *mut c_void;
+        fn munmap(addr: *mut c_void, len: usize) -> c_int;
+        fn madvise(addr: *mut c_void, len: usize, advice: c_int) -> c_int;
+    }
+
+    pub fn map(file: &File, len: usize) -> io::Result<*mut u8> {
+        let ptr = unsafe {
+            mmap(std::ptr::null_mut(), len, PROT_READ, MAP_PRIVATE, file.as_raw_fd(), 0)
+        };
+        // MAP_FAILED is (void *)-1.
+        if ptr as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // Counting reads front to back: ask for read-ahead. It is only
+        // advice, so failing to give it changes nothing.
+        unsafe { madvise(ptr, len, MADV_SEQUENTIAL) };
+        Ok(ptr.cast())
+    }
+
+    pub fn unmap(ptr: *mut u8, len: usize) {
+        unsafe { munmap(ptr.cast(), len) };
+    }
+}
+
+#[cfg(not(all(unix, target_pointer_width = "64")))]
+mod platform {
+    use std::fs::File;
+    use std::io;
+
+    pub const SUPPORTED: bool = false;
+
+    pub fn map(_: &File, _: usize) -> io::Result<*mut u8> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "memory maps are not supported here"))
+    }
+
+    pub fn unmap(_: *mut u8, _: usize) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn modes() {
+        assert_eq!(Mode::default(), Mode::Never);
+        assert!(!Mode::Never.maps(1 << 30));
+        assert!(!Mode::Always.maps(0));
+        assert_eq!(Mode::Always.maps(1), platform::SUPPORTED);
+    }
+
+    #[cfg(all(unix, target_pointer_width = "64"))]
+    #[test]
+    fn maps_a_file() {
+        let path = std::env::temp_dir().join(format!("rwc-mmap-{}", std::process::id()));
+        fs::write(&path, "mapped text\n").unwrap();
+        let file = File::open(&path).unwrap();
+        let map = Map::new(&file, 12).unwrap();
+        drop(file);
+        assert_eq!(map.bytes(), b"mapped text\n");
+        let short = Map::new(&File::open(&path).unwrap(), 6).unwrap();
+        assert_eq!(short.bytes(), b"mapped");
+        fs::remove_file(path).unwrap();
+        // Still mapped after the file is gone.
+        assert_eq!(map.bytes(), b"mapped text\n");
+
+        // A directory cannot be mapped.
+        let dir = File::open(std::env::temp_dir()).unwrap();
+        assert!(Map::new(&dir, 1).is_err());
+    }
+}
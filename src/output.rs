@@ -0,0 +1,85 @@
+//! Serialized output, so concurrent workers never interleave mid-line.
+//!
+//! Results are only printed by the thread that owns the listing, one whole
+//! record at a time. Warnings raised while a worker counts a file are
+//! captured per file and replayed just before that file's result, so they
+//! come out next to it and in operand order however the work was scheduled.
+//!
+//! With `--output` records go to a file instead, and standard output is
+//! left to warnings and progress.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// Held for every write, so a record and a warning cannot cut into each
+/// other even when they go to different streams on the same terminal.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// The `--output` file, when records go there rather than to stdout.
+// Unbuffered, so nothing is lost when rwc exits early.
+static DESTINATION: Mutex<Option<File>> = Mutex::new(None);
+
+thread_local! {
+    /// Warnings held back on this thread by `capture`.
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Prints `record` (one or more lines, without the final newline) to
+/// standard output in a single write.
+pub fn print(record: &str) {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(file) = DESTINATION.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        let _ = file.write_all(format!("{}\n", record).as_bytes());
+        return;
+    }
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(stdout, "{}", record);
+    let _ = stdout.flush();
+}
+
+/// Clears the terminal and moves the cursor home before a redraw, in order
+/// with the records around it. With `--output` there is no screen to clear.
+pub fn clear_screen() {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if DESTINATION.lock().unwrap_or_else(|e| e.into_inner()).is_some() {
+        return;
+    }
+    let mut stdout = io::stdout().lock();
+    let _ = write!(stdout, "\x1b[2J\x1b[H");
+    let _ = stdout.flush();
+}
+
+/// Sends every record printed from now on to a new file at `path`.
+pub fn redirect(path: &str) -> io::Result<()> {
+    let file = File::create(path)?;
+    *DESTINATION.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+    Ok(())
+}
+
+/// Prints a warning line to standard error, or holds it back if the current
+/// thread is inside `capture`.
+pub fn warn(message: String) {
+    let held = CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(warnings) => {
+            warnings.push(message.clone());
+            true
+        }
+        None => false,
+    });
+    if !held {
+        let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = io::stdout().flush();
+        eprintln!("{}", message);
+    }
+}
+
+/// Runs `f`, returning its result with the warnings it raised instead of
+/// printing them; replay them with `warn` when the result is printed.
+pub fn capture<R>(f: impl FnOnce() -> R) -> (R, Vec<String>) {
+    let outer = CAPTURED.with(|captured| captured.replace(Some(Vec::new())));
+    let result = f();
+    let warnings = CAPTURED.with(|captured| captured.replace(outer)).unwrap_or_default();
+    (result, warnings)
+}
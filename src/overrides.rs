@@ -0,0 +1,229 @@
+//! Counting options kept beside the files they apply to, read during
+//! recursive scans (`-r`). A `.rwc.toml` in a directory sets options for
+//! every file beneath it:
+//!
+//! ```toml
+//! # docs/.rwc.toml
+//! options = ["--type", "html", "--exclude-quotes"]
+//! ```
+//!
+//! and an `rwc` key in a Markdown file's front matter (`rwc: --lossy` in
+//! YAML, `rwc = "--lossy"` in TOML) sets them for that file.
+//!
+//! Options nearer the file win: the command line's come first, then those
+//! of the scanned directory and of each directory below it, then the front
+//! matter's. Only options that change how a file's text is read and split
+//! into words can be set this way (`OPTIONS`); what is shown, and how, is
+//! for the command line to say.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::filetype::FileType;
+use crate::json::Value;
+use crate::transform::{self, Transform};
+use crate::{batch, encoding, frontmatter, tokenizer, toml, Config};
+
+/// The name of the file holding a directory's options.
+pub const FILE_NAME: &str = ".rwc.toml";
+
+/// The options a `.rwc.toml` or front matter may set.
+const OPTIONS: &[&str] = &[
+    "--type",
+    "--auto",
+    "--pre",
+    "--exclude-quotes",
+    "--academic",
+    "--encoding",
+    "--lossy",
+    "--whitespace",
+    "--tokenizer",
+    "--unicode-words",
+    "--ignore-format-chars",
+    "--no-decompress",
+];
+
+/// How much of a Markdown file is searched for front matter.
+const FRONT_MATTER_LEN: u64 = 16 * 1024;
+
+/// The configs of the files found by a recursive scan.
+pub struct Overrides<'a> {
+    base: &'a Config,
+    /// The options of each directory's `.rwc.toml` (none if it has none),
+    /// read once.
+    directories: HashMap<PathBuf, Vec<String>>,
+    /// The config for each list of options met so far.
+    configs: Vec<(Vec<String>, Config)>,
+}
+
+impl<'a> Overrides<'a> {
+    pub fn new(base: &'a Config) -> Self {
+        Overrides {
+            base,
+            directories: HashMap::new(),
+            configs: Vec::new(),
+        }
+    }
+
+    /// Which config `path`, found by scanning `root`, is counted with: an
+    /// index for `config`, or `None` when nothing changes the base config.
+    pub fn find(&mut self, root: &Path, path: &Path) -> Result<Option<usize>, String> {
+        let mut options = Vec::new();
+        let directories: Vec<&Path> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|directory| directory.starts_with(root))
+            .collect();
+        for directory in directories.into_iter().rev() {
+            options.extend(self.directory(directory)?.iter().cloned());
+        }
+        if is_markdown(path) {
+            options.extend(front_matter(path)?);
+        }
+        if options.is_empty() {
+            return Ok(None);
+        }
+        if let Some(index) = self.configs.iter().position(|(known, _)| *known == options) {
+            return Ok(Some(index));
+        }
+        let mut config = self.base.clone();
+        apply(&mut config, &options)?;
+        self.configs.push((options, config));
+        Ok(Some(self.configs.len() - 1))
+    }
+
+    pub fn config(&self, index: usize) -> &Config {
+        &self.configs[index].1
+    }
+
+    fn directory(&mut self, directory: &Path) -> Result<&[String], String> {
+        if !self.directories.contains_key(directory) {
+            let path = directory.join(FILE_NAME);
+            let options = match fs::read_to_string(&path) {
+                Ok(text) => parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(format!("{}: {}", path.display(), e)),
+            };
+            self.directories.insert(directory.to_path_buf(), options);
+        }
+        Ok(&self.directories[directory])
+    }
+}
+
+/// The options in a `.rwc.toml`.
+fn parse(text: &str) -> Result<Vec<String>, String> {
+    let Value::Object(members) = toml::parse(text)? else {
+        unreachable!("a TOML document is a table");
+    };
+    let mut options = Vec::new();
+    for (key, value) in &members {
+        match key.as_str() {
+            "options" => options = batch::strings(value, "options")?,
+            key => return Err(format!("unknown key '{}' (expected options)", key)),
+        }
+    }
+    check(&options)?;
+    Ok(options)
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            matches!(extension.to_ascii_lowercase().as_str(), "md" | "markdown")
+        })
+}
+
+/// The options under `rwc` in the front matter of the Markdown file at
+/// `path`, separated by spaces.
+fn front_matter(path: &Path) -> Result<Vec<String>, String> {
+    let with_name = |e: String| format!("{}: {}", path.display(), e);
+    let mut head = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(FRONT_MATTER_LEN).read_to_end(&mut head))
+        .map_err(|e| with_name(e.to_string()))?;
+    let Some(value) = frontmatter::value(&String::from_utf8_lossy(&head), "rwc") else {
+        return Ok(Vec::new());
+    };
+    let options: Vec<String> = value.split_whitespace().map(str::to_string).collect();
+    check(&options).map_err(|e| with_name(format!("front matter: {}", e)))?;
+    Ok(options)
+}
+
+/// Fails on options that cannot be set beside files.
+fn check(options: &[String]) -> Result<(), String> {
+    match options
+        .iter()
+        .find(|option| option.starts_with('-') && !OPTIONS.contains(&option.as_str()))
+    {
+        Some(option) => Err(format!(
+            "option '{}' cannot be set per directory (expected {})",
+            option,
+            OPTIONS.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Sets `options` in `config`, each overriding what came before it.
+fn apply(config: &mut Config, options: &[String]) -> Result<(), String> {
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let mut value = || {
+            options
+                .next()
+                .ok_or_else(|| format!("option '{}' requires an argument", option))
+        };
+        match option.as_str() {
+            "--type" => {
+                let value = value()?;
+                let kind =
+                    FileType::parse(value).ok_or_else(|| format!("invalid type '{}'", value))?;
+                config
+                    .pipeline
+                    .retain(|step| !matches!(step, Transform::Auto(_)));
+                config.pipeline.insert(0, Transform::Auto(Some(kind)));
+            }
+            "--auto" => {
+                if !config
+                    .pipeline
+                    .iter()
+                    .any(|step| matches!(step, Transform::Auto(_)))
+                {
+                    config.pipeline.insert(0, Transform::Auto(None));
+                }
+            }
+            "--pre" => config.pipeline.extend(transform::parse_list(value()?)?),
+            "--exclude-quotes" => config.pipeline.push(Transform::ExcludeQuotes),
+            "--academic" => config.pipeline.push(Transform::Academic),
+            "--encoding" => {
+                let value = value()?;
+                config.encoding = encoding::Encoding::parse(value)
+                    .ok_or_else(|| format!("invalid encoding '{}'", value))?;
+            }
+            "--lossy" => config.lossy = true,
+            "--whitespace" => {
+                config.whitespace = tokenizer::Whitespace::parse(value()?)?;
+                config.tokenizer = None;
+                config.unicode_words = false;
+            }
+            "--tokenizer" => {
+                let value = value()?;
+                config.tokenizer = Some((value.clone(), Arc::from(tokenizer::builtin(value)?)));
+                config.whitespace = tokenizer::Whitespace::default();
+                config.unicode_words = false;
+            }
+            "--unicode-words" => {
+                config.unicode_words = true;
+                config.tokenizer = None;
+            }
+            "--ignore-format-chars" => config.ignore_format = true,
+            "--no-decompress" => config.no_decompress = true,
+            other => return Err(format!("unexpected argument '{}'", other)),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! `--patch`: change size of a unified diff.
+//!
+//! Only lines inside hunks are counted, using the hunk headers' line counts to
+//! tell where each hunk ends, so a removed line that happens to start with
+//! `--` is not mistaken for a file header. Context lines are ignored.
+
+use std::io::{self, Read};
+
+use crate::json::{self, Value};
+use crate::{bidi, output, Config};
+
+#[derive(Debug, Default, Clone)]
+struct Change {
+    lines_added: usize,
+    lines_removed: usize,
+    words_added: usize,
+    words_removed: usize,
+}
+
+impl Change {
+    fn add(&mut self, other: &Change) {
+        self.lines_added += other.lines_added;
+        self.lines_removed += other.lines_removed;
+        self.words_added += other.words_added;
+        self.words_removed += other.words_removed;
+    }
+
+    fn to_json(&self, file: Option<&str>) -> Value {
+        let mut members = Vec::new();
+        if let Some(file) = file {
+            members.push(("file".to_string(), Value::from(file)));
+        }
+        for (key, n) in [
+            ("lines_added", self.lines_added),
+            ("lines_removed", self.lines_removed),
+            ("words_added", self.words_added),
+            ("words_removed", self.words_removed),
+        ] {
+            members.push((key.to_string(), n.into()));
+        }
+        Value::Object(members)
+    }
+}
+
+/// Parses `@@ -a,b +c,d @@` into the old and new line counts.
+fn hunk_lengths(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.strip_prefix("@@ ")?.split(' ');
+    let length = |range: &str| -> Option<usize> {
+        match range.split_once(',') {
+            Some((_, n)) => n.parse().ok(),
+            None => Some(1),
+        }
+    };
+    let old = length(ranges.next()?.strip_prefix('-')?)?;
+    let new = length(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+/// The file a `+++ b/path` header names, or the `---` side for deletions.
+fn header_path(line: &str) -> &str {
+    let path = line[4..].split('\t').next().unwrap_or("").trim_end();
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+}
+
+/// Splits `diff` into per-file changes, in the order files appear.
+fn parse(diff: &str) -> Vec<(String, Change)> {
+    let mut files: Vec<(String, Change)> = Vec::new();
+    let mut old_path = String::new();
+    let (mut old_left, mut new_left) = (0usize, 0usize);
+
+    for line in diff.lines() {
+        if old_left > 0 || new_left > 0 {
+            let Some(current) = files.last_mut().map(|(_, change)| change) else {
+                break;
+            };
+            match line.as_bytes().first() {
+                Some(b'+') => {
+                    current.lines_added += 1;
+                    current.words_added += line[1..].split_whitespace().count();
+                    new_left = new_left.saturating_sub(1);
+                }
+                Some(b'-') => {
+                    current.lines_removed += 1;
+                    current.words_removed += line[1..].split_whitespace().count();
+                    old_left = old_left.saturating_sub(1);
+                }
+                Some(b'\\') => {}
+                // Context, including blank context lines some tools emit
+                // without the leading space.
+                _ => {
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                }
+            }
+            continue;
+        }
+
+        if line.starts_with("--- ") {
+            old_path = header_path(line).to_string();
+        } else if line.starts_with("+++ ") {
+            let path = match header_path(line) {
+                "/dev/null" => old_path.clone(),
+                path => path.to_string(),
+            };
+            files.push((path, Change::default()));
+        } else if let Some((old, new)) = hunk_lengths(line) {
+            if files.is_empty() {
+                files.push((String::new(), Change::default()));
+            }
+            old_left = old;
+            new_left = new;
+        }
+    }
+    files
+}
+
+/// Reads a unified diff from the operands or stdin and prints its size.
+pub fn run(config: &Config) -> io::Result<()> {
+    let mut diff = String::new();
+    if config.files.is_empty() {
+        io::stdin().lock().read_to_string(&mut diff)?;
+    } else {
+        for file in &config.files {
+            let text = std::fs::read_to_string(file)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", file, e)))?;
+            diff.push_str(&text);
+            diff.push('\n');
+        }
+    }
+
+    let files = parse(&diff);
+    let mut total = Change::default();
+    for (_, change) in &files {
+        total.add(change);
+    }
+
+    if config.show_json {
+        let per_file = files
+            .iter()
+            .map(|(file, change)| change.to_json(Some(file)))
+            .collect();
+        let document = json::object([
+            ("files", Value::Array(per_file)),
+            ("total", total.to_json(None)),
+        ]);
+        output::print(&document.to_string());
+        return Ok(());
+    }
+
+    let row = |change: &Change, label: &str| {
+        output::print(&format!(
+            "{:>8}{:>8}{:>8}{:>8} {}",
+            format!("+{}", config.format_count(change.lines_added)),
+            format!("-{}", config.format_count(change.lines_removed)),
+            format!("+{}", config.format_count(change.words_added)),
+            format!("-{}", config.format_count(change.words_removed)),
+            label
+        ));
+    };
+    output::print(&format!(
+        "{:>8}{:>8}{:>8}{:>8} FILE",
+        "+LINES", "-LINES", "+WORDS", "-WORDS"
+    ));
+    for (file, change) in &files {
+        row(change, &bidi::display_name(file));
+    }
+    if files.len() != 1 {
+        row(&total, config.total_label());
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! A small worker pool for `--jobs`: items are processed concurrently and the
+//! results handed back in the original order, or, for `--unordered`, as each
+//! one is done.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, Scope};
+
+use crate::interrupt;
+
+/// Results of `ordered`, yielded in item order as soon as each one and all
+/// before it are done.
+pub struct Ordered<R> {
+    receiver: Receiver<(usize, R)>,
+    /// Results that finished ahead of an earlier item.
+    early: BTreeMap<usize, R>,
+    next: usize,
+    len: usize,
+}
+
+impl<R> Iterator for Ordered<R> {
+    type Item = R;
+
+    fn next(&mut self) -> Option<R> {
+        if self.next == self.len {
+            return None;
+        }
+        let result = loop {
+            if let Some(result) = self.early.remove(&self.next) {
+                break result;
+            }
+            let (index, result) = self.receiver.recv().ok()?;
+            if index == self.next {
+                break result;
+            }
+            self.early.insert(index, result);
+        };
+        self.next += 1;
+        Some(result)
+    }
+}
+
+/// Applies `f` to every item on `jobs` threads of `scope`. Workers take the
+/// next unclaimed item, so a slow item does not hold up the others.
+pub fn ordered<'scope, 'env, T, R, F>(
+    scope: &'scope Scope<'scope, 'env>,
+    items: &'env [T],
+    jobs: usize,
+    f: &'env F,
+) -> Ordered<R>
+where
+    T: Sync,
+    R: Send + 'scope,
+    F: Fn(&T) -> R + Sync,
+{
+    Ordered {
+        receiver: spawn(scope, items, jobs, f),
+        early: BTreeMap::new(),
+        next: 0,
+        len: items.len(),
+    }
+}
+
+/// Like `ordered`, but yields each result with the index of its item as soon
+/// as it is done.
+pub fn unordered<'scope, 'env, T, R, F>(
+    scope: &'scope Scope<'scope, 'env>,
+    items: &'env [T],
+    jobs: usize,
+    f: &'env F,
+) -> impl Iterator<Item = (usize, R)>
+where
+    T: Sync,
+    R: Send + 'scope,
+    F: Fn(&T) -> R + Sync,
+{
+    spawn(scope, items, jobs, f).into_iter()
+}
+
+fn spawn<'scope, 'env, T, R, F>(
+    scope: &'scope Scope<'scope, 'env>,
+    items: &'env [T],
+    jobs: usize,
+    f: &'env F,
+) -> Receiver<(usize, R)>
+where
+    T: Sync,
+    R: Send + 'scope,
+    F: Fn(&T) -> R + Sync,
+{
+    let (sender, receiver) = mpsc::channel();
+    let claimed = Arc::new(AtomicUsize::new(0));
+    for _ in 0..jobs.min(items.len()) {
+        let sender = sender.clone();
+        let claimed = Arc::clone(&claimed);
+        scope.spawn(move || loop {
+            // After Ctrl-C no new item is started.
+            if interrupt::requested() {
+                break;
+            }
+            let index = claimed.fetch_add(1, Ordering::Relaxed);
+            let Some(item) = items.get(index) else {
+                break;
+            };
+            // The receiver is gone only if the caller stopped early.
+            if sender.send((index, f(item))).is_err() {
+                break;
+            }
+        });
+    }
+    receiver
+}
+
+/// The number of jobs `--jobs 0` stands for: one per available CPU.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
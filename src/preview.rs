@@ -0,0 +1,44 @@
+//! `--preview N`: sample lines of the counted content, printed with the counts.
+
+use crate::sample::Rng;
+
+/// Longest preview line shown, in characters; longer lines are cut with `…`.
+const MAX_WIDTH: usize = 120;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Preview {
+    pub lines: usize,
+    /// Reservoir-sample the lines instead of taking the first ones.
+    pub random: bool,
+}
+
+impl Preview {
+    /// Picks the preview lines from `text`, in their original order.
+    pub fn pick(&self, text: &str, seed: u64) -> Vec<String> {
+        let mut chosen: Vec<(usize, &str)> = Vec::with_capacity(self.lines);
+        if self.random {
+            let mut rng = Rng::new(seed);
+            for (index, line) in text.lines().enumerate() {
+                if chosen.len() < self.lines {
+                    chosen.push((index, line));
+                } else {
+                    let slot = rng.below(index as u64 + 1) as usize;
+                    if slot < self.lines {
+                        chosen[slot] = (index, line);
+                    }
+                }
+            }
+            chosen.sort_by_key(|&(index, _)| index);
+        } else {
+            chosen.extend(text.lines().take(self.lines).enumerate());
+        }
+        chosen.into_iter().map(|(_, line)| truncate(line)).collect()
+    }
+}
+
+fn truncate(line: &str) -> String {
+    match line.char_indices().nth(MAX_WIDTH) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line.to_string(),
+    }
+}
@@ -0,0 +1,114 @@
+//! `--file-threads`: one large file counted in ranges on several threads.
+//!
+//! The file is cut into ranges of about the same size, each ending just
+//! after a newline, so no line, word or UTF-8 sequence is split between two
+//! of them. Every range is read and scanned on a thread of its own and the
+//! counts are joined in file order, which puts back together the paragraphs
+//! that run over a cut (`engine::RangeCounts::join`). A file with few
+//! newlines gets fewer ranges, since a line is never split: one long line is
+//! counted on one thread.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::thread;
+
+use rwc::engine::{self, Metrics, RangeCounts, Scanner};
+
+use crate::interrupt;
+
+/// Ranges are no smaller than this; a thread is not worth less.
+pub const MIN_RANGE: u64 = 4 << 20;
+
+const BUFFER_LEN: usize = 64 * 1024;
+
+/// Counts the `len` bytes of the UTF-8 file at `path` in up to `threads`
+/// ranges at once.
+pub fn count(
+    path: &Path,
+    len: u64,
+    metrics: &Metrics,
+    threads: usize,
+) -> io::Result<engine::Counts> {
+    let ranges = threads.min((len / MIN_RANGE) as usize).max(1);
+    let cuts = cuts(path, len, ranges)?;
+    let results: Vec<io::Result<RangeCounts>> = thread::scope(|scope| {
+        let workers: Vec<_> = cuts
+            .windows(2)
+            .map(|range| {
+                let (start, end) = (range[0], range[1]);
+                scope.spawn(move || count_range(path, start, end, metrics))
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("range worker panicked"))
+            .collect()
+    });
+    let mut results = results.into_iter();
+    let mut counts = results.next().expect("at least one range")?;
+    for range in results {
+        counts.join(range?);
+    }
+    Ok(counts.counts)
+}
+
+/// Where the ranges start, then `len`: the first position at or after each
+/// even share of the file that follows a newline.
+fn cuts(path: &Path, len: u64, ranges: usize) -> io::Result<Vec<u64>> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0; BUFFER_LEN];
+    let mut cuts = vec![0];
+    for share in 1..ranges as u64 {
+        let target = len * share / ranges as u64;
+        if target <= cuts[cuts.len() - 1] {
+            // The last cut came after a line longer than a share.
+            continue;
+        }
+        match line_start(&mut file, target, &mut buffer)? {
+            Some(cut) if cut < len => cuts.push(cut),
+            _ => break,
+        }
+    }
+    cuts.push(len);
+    Ok(cuts)
+}
+
+/// The first position at or after `target` that follows a newline, if the
+/// file has one.
+fn line_start(file: &mut File, target: u64, buffer: &mut [u8]) -> io::Result<Option<u64>> {
+    let mut position = file.seek(SeekFrom::Start(target - 1))?;
+    loop {
+        let n = match file.read(buffer) {
+            Ok(0) => return Ok(None),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if let Some(newline) = buffer[..n].iter().position(|&b| b == b'\n') {
+            return Ok(Some(position + newline as u64 + 1));
+        }
+        position += n as u64;
+    }
+}
+
+fn count_range(path: &Path, start: u64, end: u64, metrics: &Metrics) -> io::Result<RangeCounts> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut reader = file.take(end - start);
+    let mut scanner = Scanner::new(metrics);
+    let mut buffer = vec![0; BUFFER_LEN];
+    loop {
+        if interrupt::requested() {
+            return Err(interrupt::error());
+        }
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        scanner.feed(&buffer[..n])?;
+    }
+    scanner.finish_range()
+}
@@ -0,0 +1,51 @@
+//! Reading-time estimates and how they are written out.
+//!
+//! The estimate is words over a words-per-minute rate, rounded to whole
+//! minutes (up, by default, so a short note still takes a minute to read).
+//! `--time-format` picks the notation: the default text (`4 min`,
+//! `1h 5m`), a bare number of minutes, `h:mm:ss`, or an ISO 8601 duration
+//! (`PT1H5M`) as front matter and schema.org `timeRequired` fields expect.
+
+use crate::Rounding;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TimeFormat {
+    #[default]
+    Text,
+    Minutes,
+    Hms,
+    Iso8601,
+}
+
+impl TimeFormat {
+    pub fn parse(text: &str) -> Option<TimeFormat> {
+        match text {
+            "text" => Some(TimeFormat::Text),
+            "minutes" => Some(TimeFormat::Minutes),
+            "hms" => Some(TimeFormat::Hms),
+            "iso8601" => Some(TimeFormat::Iso8601),
+            _ => None,
+        }
+    }
+}
+
+/// Whole minutes to read `words` at `wpm` words per minute.
+pub fn minutes(words: usize, wpm: usize, rounding: Rounding) -> usize {
+    rounding.divide(words as u128, wpm.max(1) as u128) as usize
+}
+
+/// `minutes` written in `format`.
+pub fn format(minutes: usize, format: TimeFormat) -> String {
+    let (hours, rest) = (minutes / 60, minutes % 60);
+    match format {
+        TimeFormat::Text if minutes < 60 => format!("{} min", minutes),
+        TimeFormat::Text => format!("{}h {}m", hours, rest),
+        TimeFormat::Minutes => minutes.to_string(),
+        TimeFormat::Hms => format!("{}:{:02}:00", hours, rest),
+        TimeFormat::Iso8601 => match (hours, rest) {
+            (0, rest) => format!("PT{}M", rest),
+            (hours, 0) => format!("PT{}H", hours),
+            (hours, rest) => format!("PT{}H{}M", hours, rest),
+        },
+    }
+}
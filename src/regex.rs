@@ -0,0 +1,700 @@
+//! A small regular-expression engine.
+//!
+//! Supports literals, `.`, classes (`[a-z]`, `[^...]`), the escapes `\d \w \s
+//! \D \W \S \b \B \n \t \r`, anchors `^ $`, groups with alternation, the
+//! quantifiers `* + ? {m} {m,} {m,n}` (optionally lazy with a trailing `?`) and
+//! a leading `(?i)` for case-insensitive matching. Anything else that looks
+//! like syntax, such as `[[:alpha:]]`, `\p{L}` or `(?=...)`, is an error
+//! rather than a literal. Patterns are matched against single lines, so `^`
+//! and `$` are line anchors.
+//!
+//! Patterns are compiled to a program for a Pike VM, which runs every
+//! alternative in step over the line: matching takes time proportional to the
+//! line times the pattern and constant stack, however the pattern nests or repeats, and finds the
+//! match a backtracking engine would (leftmost, then by the priority of
+//! alternatives and greediness).
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Set(fn(char) -> bool, bool),
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    WordBoundary(bool),
+    Alt(Vec<Vec<Node>>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+/// The most instructions a pattern may compile to, so counted repetitions
+/// such as `(a{1000}){1000}` are refused instead of filling memory.
+const MAX_PROGRAM: usize = 100_000;
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<Inst>,
+    ignore_case: bool,
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let (pattern, ignore_case) = match pattern.strip_prefix("(?i)") {
+            Some(rest) => (rest, true),
+            None => (pattern, false),
+        };
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+        };
+        let nodes = parser.alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err(format!("unmatched ')' at position {}", parser.pos));
+        }
+        let mut program = Vec::new();
+        compile(&nodes, &mut program)?;
+        program.push(Inst::Match);
+        Ok(Regex {
+            program,
+            ignore_case,
+        })
+    }
+
+    /// The leftmost match starting at or after `from`, as char positions.
+    fn search(&self, text: &[char], from: usize) -> Option<(usize, usize)> {
+        let vm = Vm {
+            program: &self.program,
+            text,
+            ignore_case: self.ignore_case,
+        };
+        vm.search(from)
+    }
+
+    /// Byte ranges of the non-overlapping, non-empty matches in `line`.
+    pub fn find_iter(&self, line: &str) -> Vec<(usize, usize)> {
+        let text: Vec<char> = line.chars().collect();
+        let offsets: Vec<usize> = line
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([line.len()])
+            .collect();
+        let mut found = Vec::new();
+        let mut from = 0;
+        while from <= text.len() {
+            match self.search(&text, from) {
+                Some((start, end)) if end > start => {
+                    found.push((offsets[start], offsets[end]));
+                    from = end;
+                }
+                Some((start, _)) => from = start + 1,
+                None => break,
+            }
+        }
+        found
+    }
+
+    /// Whether the pattern matches anywhere in `line`.
+    pub fn is_match(&self, line: &str) -> bool {
+        let text: Vec<char> = line.chars().collect();
+        self.search(&text, 0).is_some()
+    }
+
+    /// Counts non-overlapping, non-empty matches in `line`.
+    pub fn count_matches(&self, line: &str) -> usize {
+        self.find_iter(line).len()
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn alternation(&mut self) -> Result<Vec<Node>, String> {
+        let mut branches = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            branches.push(self.sequence()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap_or_default()
+        } else {
+            vec![Node::Alt(branches)]
+        })
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantifier(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let at = self.pos;
+        match self.next() {
+            Some('(') => {
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                } else if self.peek() == Some('?') {
+                    let syntax: String = self.chars[at..].iter().take(3).collect();
+                    return Err(format!(
+                        "unsupported group syntax '{}' at position {}",
+                        syntax, at
+                    ));
+                }
+                let inner = self.alternation()?;
+                if self.next() != Some(')') {
+                    return Err(format!("unclosed group at position {}", at));
+                }
+                Ok(Node::Alt(vec![inner]))
+            }
+            Some('[') => self.class(at),
+            Some('.') => Ok(Node::Any),
+            Some('^') => Ok(Node::Start),
+            Some('$') => Ok(Node::End),
+            Some('\\') => self.escape(),
+            Some(c @ ('*' | '+' | '?' | '{')) => Err(format!(
+                "nothing to repeat before '{}' at position {}",
+                c, at
+            )),
+            Some(c) => Ok(Node::Char(c)),
+            None => Err("unexpected end of pattern".to_string()),
+        }
+    }
+
+    fn shorthand(c: char) -> Option<ClassItem> {
+        Some(match c {
+            'd' => ClassItem::Set(char::is_numeric, false),
+            'D' => ClassItem::Set(char::is_numeric, true),
+            'w' => ClassItem::Set(is_word, false),
+            'W' => ClassItem::Set(is_word, true),
+            's' => ClassItem::Set(char::is_whitespace, false),
+            'S' => ClassItem::Set(char::is_whitespace, true),
+            _ => return None,
+        })
+    }
+
+    /// The character `\c` stands for: a control escape, or punctuation as
+    /// itself. Other letters and digits are escapes this engine does not
+    /// know, such as `\p` or a backreference.
+    fn literal_escape(c: char) -> Result<char, String> {
+        match c {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            c if c.is_alphanumeric() => Err(format!("unsupported escape '\\{}'", c)),
+            c => Ok(c),
+        }
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let c = self
+            .next()
+            .ok_or_else(|| "trailing backslash in pattern".to_string())?;
+        if let Some(item) = Self::shorthand(c) {
+            return Ok(Node::Class(vec![item], false));
+        }
+        Ok(match c {
+            'b' => Node::WordBoundary(true),
+            'B' => Node::WordBoundary(false),
+            c => Node::Char(Self::literal_escape(c)?),
+        })
+    }
+
+    fn class(&mut self, at: usize) -> Result<Node, String> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self
+                .next()
+                .ok_or_else(|| format!("unclosed character class at position {}", at))?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let low = if c == '\\' {
+                let e = self
+                    .next()
+                    .ok_or_else(|| "trailing backslash in pattern".to_string())?;
+                if let Some(item) = Self::shorthand(e) {
+                    items.push(item);
+                    continue;
+                }
+                Self::literal_escape(e)?
+            } else if c == '[' && matches!(self.peek(), Some(':' | '=' | '.')) {
+                return Err(format!(
+                    "unsupported class syntax '[{}' at position {}",
+                    self.chars[self.pos],
+                    self.pos - 1
+                ));
+            } else {
+                c
+            };
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&n| n != ']') {
+                self.pos += 1;
+                let mut high = self.next().unwrap_or(low);
+                if high == '\\' {
+                    high = Self::literal_escape(self.next().unwrap_or('\\'))?;
+                }
+                if high < low {
+                    return Err(format!("invalid class range {}-{}", low, high));
+                }
+                items.push(ClassItem::Range(low, high));
+            } else {
+                items.push(ClassItem::Range(low, low));
+            }
+        }
+        Ok(Node::Class(items, negated))
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn quantifier(&mut self, node: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let save = self.pos;
+                self.pos += 1;
+                let Some(min) = self.number() else {
+                    // Not a repetition: treat '{' literally.
+                    self.pos = save;
+                    return Ok(node);
+                };
+                let max = if self.peek() == Some(',') {
+                    self.pos += 1;
+                    self.number()
+                } else {
+                    Some(min)
+                };
+                if self.peek() != Some('}') {
+                    self.pos = save;
+                    return Ok(node);
+                }
+                if max.is_some_and(|max| max < min) {
+                    return Err(format!("invalid repetition {{{},{:?}}}", min, max));
+                }
+                (min, max)
+            }
+            _ => return Ok(node),
+        };
+        self.pos += 1;
+        let greedy = self.peek() != Some('?');
+        if !greedy {
+            self.pos += 1;
+        }
+        if matches!(node, Node::Start | Node::End | Node::WordBoundary(_)) {
+            return Err("cannot repeat an anchor".to_string());
+        }
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+}
+
+/// An instruction of a compiled pattern.
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    WordBoundary(bool),
+    /// Continue at both targets, preferring the first.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// Appends the instructions for `nodes` to `program`.
+fn compile(nodes: &[Node], program: &mut Vec<Inst>) -> Result<(), String> {
+    for node in nodes {
+        if program.len() > MAX_PROGRAM {
+            return Err("pattern is too large (repetition counts multiply)".to_string());
+        }
+        match node {
+            Node::Char(c) => program.push(Inst::Char(*c)),
+            Node::Any => program.push(Inst::Any),
+            Node::Class(items, negated) => program.push(Inst::Class(items.clone(), *negated)),
+            Node::Start => program.push(Inst::Start),
+            Node::End => program.push(Inst::End),
+            Node::WordBoundary(expected) => program.push(Inst::WordBoundary(*expected)),
+            Node::Alt(branches) => {
+                // Split to each branch in turn; every branch jumps past the
+                // rest when it is done.
+                let mut jumps = Vec::new();
+                for (n, branch) in branches.iter().enumerate() {
+                    if n + 1 == branches.len() {
+                        compile(branch, program)?;
+                        break;
+                    }
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(branch, program)?;
+                    jumps.push(program.len());
+                    program.push(Inst::Jump(0));
+                    let next = program.len();
+                    program[split] = Inst::Split(split + 1, next);
+                }
+                let end = program.len();
+                for jump in jumps {
+                    program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                let body = std::slice::from_ref(&**node);
+                for _ in 0..*min {
+                    compile(body, program)?;
+                }
+                let split = |program: &mut Vec<Inst>, at: usize, out: usize| {
+                    program[at] = if *greedy {
+                        Inst::Split(at + 1, out)
+                    } else {
+                        Inst::Split(out, at + 1)
+                    };
+                };
+                match max {
+                    None => {
+                        let at = program.len();
+                        program.push(Inst::Match);
+                        compile(body, program)?;
+                        program.push(Inst::Jump(at));
+                        let out = program.len();
+                        split(program, at, out);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            if program.len() > MAX_PROGRAM {
+                                return Err(
+                                    "pattern is too large (repetition counts multiply)".to_string()
+                                );
+                            }
+                            splits.push(program.len());
+                            program.push(Inst::Match);
+                            compile(body, program)?;
+                        }
+                        let out = program.len();
+                        for at in splits {
+                            split(program, at, out);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if program.len() > MAX_PROGRAM {
+        return Err("pattern is too large (repetition counts multiply)".to_string());
+    }
+    Ok(())
+}
+
+/// Runs a program over a line. Threads are kept in priority order, so the
+/// first to reach `Match` is the one a backtracking engine would accept,
+/// and each instruction is run at most once per position.
+struct Vm<'a> {
+    program: &'a [Inst],
+    text: &'a [char],
+    ignore_case: bool,
+}
+
+/// Threads at one position: the instruction each waits at with where its
+/// match started, and which instructions were already reached there.
+struct Threads {
+    list: Vec<(usize, usize)>,
+    seen: Vec<bool>,
+    reached: Vec<usize>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Threads {
+            list: Vec::new(),
+            seen: vec![false; len],
+            reached: Vec::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        for pc in self.reached.drain(..) {
+            self.seen[pc] = false;
+        }
+    }
+}
+
+impl Vm<'_> {
+    fn char_eq(&self, pattern: char, c: char) -> bool {
+        pattern == c || (self.ignore_case && pattern.to_lowercase().eq(c.to_lowercase()))
+    }
+
+    fn class_has(&self, items: &[ClassItem], c: char) -> bool {
+        let test = |c: char| {
+            items.iter().any(|item| match *item {
+                ClassItem::Range(low, high) => low <= c && c <= high,
+                ClassItem::Set(f, negated) => f(c) != negated,
+            })
+        };
+        test(c) || (self.ignore_case && (c.to_lowercase().any(test) || c.to_uppercase().any(test)))
+    }
+
+    fn is_boundary(&self, i: usize) -> bool {
+        let before = i > 0 && is_word(self.text[i - 1]);
+        let after = self.text.get(i).is_some_and(|&c| is_word(c));
+        before != after
+    }
+
+    /// Adds the thread at `pc`, started at `start`, to `threads` at position
+    /// `i`, following jumps, splits and assertions to the instructions that
+    /// consume a character or match.
+    fn add(
+        &self,
+        threads: &mut Threads,
+        stack: &mut Vec<usize>,
+        pc: usize,
+        start: usize,
+        i: usize,
+    ) {
+        stack.push(pc);
+        while let Some(pc) = stack.pop() {
+            if threads.seen[pc] {
+                continue;
+            }
+            threads.seen[pc] = true;
+            threads.reached.push(pc);
+            match self.program[pc] {
+                Inst::Jump(to) => stack.push(to),
+                Inst::Split(first, second) => {
+                    stack.push(second);
+                    stack.push(first);
+                }
+                Inst::Start if i == 0 => stack.push(pc + 1),
+                Inst::End if i == self.text.len() => stack.push(pc + 1),
+                Inst::WordBoundary(expected) if self.is_boundary(i) == expected => {
+                    stack.push(pc + 1)
+                }
+                Inst::Start | Inst::End | Inst::WordBoundary(_) => {}
+                _ => threads.list.push((pc, start)),
+            }
+        }
+    }
+
+    fn search(&self, from: usize) -> Option<(usize, usize)> {
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut stack = Vec::new();
+        let mut found = None;
+        for i in from..=self.text.len() {
+            // A match starting here ranks below every thread already
+            // running, and is not tried once a match has been found.
+            if found.is_none() {
+                self.add(&mut current, &mut stack, 0, i, i);
+            }
+            if current.list.is_empty() && found.is_some() {
+                break;
+            }
+            let c = self.text.get(i).copied();
+            for &(pc, start) in &current.list {
+                let consumed = match (&self.program[pc], c) {
+                    (Inst::Match, _) => {
+                        found = Some((start, i));
+                        // Threads after this one rank below it.
+                        break;
+                    }
+                    (Inst::Char(p), Some(c)) => self.char_eq(*p, c),
+                    (Inst::Any, Some(_)) => true,
+                    (Inst::Class(items, negated), Some(c)) => self.class_has(items, c) != *negated,
+                    _ => false,
+                };
+                if consumed {
+                    self.add(&mut next, &mut stack, pc + 1, start, i + 1);
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Regex;
+    use std::time::{Duration, Instant};
+
+    fn matches(pattern: &str, line: &str) -> Vec<String> {
+        let regex = Regex::new(pattern).unwrap();
+        regex
+            .find_iter(line)
+            .into_iter()
+            .map(|(start, end)| line[start..end].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn literals_classes_and_escapes() {
+        assert_eq!(matches("ab", "xabyab"), ["ab", "ab"]);
+        assert_eq!(matches("a.c", "abc a\u{e9}c ac"), ["abc", "a\u{e9}c"]);
+        assert_eq!(matches("[a-c]+", "xaabcz cab"), ["aabc", "cab"]);
+        assert_eq!(matches("[^a-c ]+", "abxyc def"), ["xy", "def"]);
+        assert_eq!(matches(r"\d+", "a12b345"), ["12", "345"]);
+        assert_eq!(matches(r"\w+", "foo_1, bar"), ["foo_1", "bar"]);
+        assert_eq!(matches(r"[\d.]+", "v1.20 x"), ["1.20"]);
+        assert_eq!(matches(r"\[\^[^\]]+\]", "see[^note] here"), ["[^note]"]);
+        assert_eq!(matches(r"a\tb", "a\tb"), ["a\tb"]);
+        assert_eq!(matches("[]a]+", "x]a]"), ["]a]"]);
+        assert_eq!(matches("[a-]+", "-a-b"), ["-a-"]);
+    }
+
+    #[test]
+    fn anchors_and_boundaries() {
+        assert_eq!(matches("^a", "aaa"), ["a"]);
+        assert_eq!(matches("a$", "aaa"), ["a"]);
+        assert_eq!(matches(r"\bcat\b", "cat concat cat."), ["cat", "cat"]);
+        assert_eq!(matches(r"\Bcat", "cat concat"), ["cat"]);
+        assert!(Regex::new("^$").unwrap().is_match(""));
+        assert!(!Regex::new("^$").unwrap().is_match("a"));
+    }
+
+    #[test]
+    fn alternation_and_repetition_priority() {
+        // Leftmost, then the first alternative that matches.
+        assert_eq!(matches("a|ab", "ab"), ["a"]);
+        assert_eq!(matches("ab|a", "ab"), ["ab"]);
+        assert_eq!(matches("a+", "aaa"), ["aaa"]);
+        assert_eq!(matches("a+?", "aaa"), ["a", "a", "a"]);
+        assert_eq!(matches("<.*>", "<a><b>"), ["<a><b>"]);
+        assert_eq!(matches("<.*?>", "<a><b>"), ["<a>", "<b>"]);
+        assert_eq!(matches("a{2}", "aaaaa"), ["aa", "aa"]);
+        assert_eq!(matches("a{2,}", "a aaaa"), ["aaaa"]);
+        assert_eq!(matches("a{1,2}", "aaa"), ["aa", "a"]);
+        assert_eq!(matches("a{1,2}?", "aa"), ["a", "a"]);
+        assert_eq!(matches("(?:ab)+c", "ababc"), ["ababc"]);
+        assert_eq!(matches("(a|b)*c", "abac"), ["abac"]);
+        assert_eq!(matches("colou?r", "color colour"), ["color", "colour"]);
+    }
+
+    #[test]
+    fn empty_matches_are_skipped() {
+        assert_eq!(matches("a*", "baab"), ["aa"]);
+        assert_eq!(matches("(a*)*", "aa"), ["aa"]);
+        assert_eq!(matches("x?", "abc"), Vec::<String>::new());
+        assert_eq!(Regex::new("").unwrap().count_matches("abc"), 0);
+        assert!(Regex::new("").unwrap().is_match("abc"));
+    }
+
+    #[test]
+    fn ignore_case() {
+        assert_eq!(matches("(?i)abc", "ABC aBc"), ["ABC", "aBc"]);
+        assert_eq!(matches("(?i)[a-c]+", "CAB"), ["CAB"]);
+        assert_eq!(matches("(?i)straße", "STRASSE Straße"), ["Straße"]);
+    }
+
+    #[test]
+    fn byte_offsets_of_multibyte_text() {
+        let regex = Regex::new("界+").unwrap();
+        assert_eq!(regex.find_iter("世界界!"), [(3, 9)]);
+    }
+
+    #[test]
+    fn long_lines_do_not_overflow_the_stack() {
+        let line = "a".repeat(200_000) + "b";
+        assert!(Regex::new("(a)*b").unwrap().is_match(&line));
+        assert_eq!(Regex::new("(?:a|b)+").unwrap().count_matches(&line), 1);
+        assert!(!Regex::new("(a)*c").unwrap().is_match(&line));
+    }
+
+    #[test]
+    fn ambiguous_patterns_run_in_linear_time() {
+        let started = Instant::now();
+        let line = "a".repeat(10_000);
+        assert!(!Regex::new("(a|a)*b").unwrap().is_match(&line));
+        assert!(!Regex::new("(a*)*b").unwrap().is_match(&line));
+        assert!(!Regex::new("(a+)+b").unwrap().is_match(&"a".repeat(28)));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn rejects_unsupported_syntax() {
+        for pattern in [
+            "[[:alpha:]]",
+            "[[=a=]]",
+            "[[.a.]]",
+            r"\p{L}",
+            r"(a)\1",
+            r"\x41",
+            r"[\p]",
+            "(?=a)",
+            "(?P<name>a)",
+            "a(?i)b",
+        ] {
+            assert!(Regex::new(pattern).is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_patterns() {
+        for pattern in [
+            "(a", "a)", "[a", "*a", "a**", "+", "a{3,1}", "a{x}", "[z-a]", "\\", "^*",
+        ] {
+            assert!(Regex::new(pattern).is_err(), "{}", pattern);
+        }
+        assert!(Regex::new("(a{1000}){1000}").is_err());
+        assert!(Regex::new("a{1000}").is_ok());
+    }
+}
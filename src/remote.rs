@@ -0,0 +1,60 @@
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::{count_stored, Config, Counts};
+
+/// Counts a file on a remote host by streaming it over SSH, with the same
+/// options as a local file.
+///
+/// `spec` has the form `[user@]host:path`; the file is piped through
+/// `ssh host cat` so nothing is copied to local disk.
+pub fn count_remote(spec: &str, config: &Config) -> io::Result<Counts> {
+    let (host, path) = match spec.split_once(':') {
+        // A host starting with `-` would be read by ssh as an option.
+        Some((host, path)) if !host.is_empty() && !host.starts_with('-') && !path.is_empty() => {
+            (host, path)
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("rwc: {}: expected [user@]host:path", spec),
+            ))
+        }
+    };
+
+    let mut child = Command::new("ssh")
+        .arg("-o")
+        .arg("BatchMode=yes")
+        .arg("--")
+        .arg(host)
+        .arg(format!("cat -- {}", shell_quote(path)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("rwc: {}: ssh: {}", spec, e)))?;
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    // The path names the file for decompression and type detection.
+    let counted = count_stored(BufReader::new(stdout), Some(Path::new(path)), config);
+    if counted.is_err() {
+        // Nothing reads the rest; do not wait for ssh to send it.
+        let _ = child.kill();
+    }
+    let status = child.wait()?;
+    let counts = counted.map_err(|e| io::Error::new(e.kind(), format!("rwc: {}: {}", spec, e)))?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "rwc: {}: ssh exited with {}",
+            spec, status
+        )));
+    }
+
+    Ok(counts)
+}
+
+/// Quotes `s` for the remote POSIX shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
@@ -0,0 +1,327 @@
+//! `rwc report-diff`: compare two saved `--json` reports file by file.
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::cli;
+use crate::json::{self, Value};
+
+/// Numeric metrics per file, in report order.
+type Metrics = Vec<(String, f64)>;
+
+/// Entry point for `rwc report-diff`.
+pub fn run(args: &[String]) -> i32 {
+    let mut json = false;
+    let mut total_label = "total".to_string();
+    let mut reports = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--total-label" if i + 1 >= args.len() => {
+                eprintln!("rwc report-diff: option '--total-label' requires an argument");
+                return 1;
+            }
+            "--total-label" => {
+                i += 1;
+                total_label = args[i].clone();
+            }
+            "--json" => json = true,
+            "--help" => {
+                print_help();
+                return 0;
+            }
+            a if a.starts_with('-') && a != "-" => {
+                cli::unknown_option("rwc report-diff", a, &["--total-label", "--json", "--help"]);
+                return 1;
+            }
+            a => reports.push(a.to_string()),
+        }
+        i += 1;
+    }
+    let [old, new] = reports.as_slice() else {
+        eprintln!("rwc report-diff: expected OLD.json and NEW.json");
+        eprintln!("Try 'rwc report-diff --help' for more information.");
+        return 1;
+    };
+
+    let (old, new) = match (load(old, &total_label), load(new, &total_label)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("rwc report-diff: {}", e);
+            return 1;
+        }
+    };
+    let diff = Diff::between(&old, &new);
+    if json {
+        println!("{}", diff.to_json());
+    } else {
+        diff.print();
+    }
+    0
+}
+
+fn print_help() {
+    println!("Usage: rwc report-diff [--total-label STR] [--json] OLD.json NEW.json");
+    println!();
+    println!("Compares two reports saved with 'rwc --json' and lists added, removed and");
+    println!("changed files with per-metric deltas, followed by the change in totals.");
+    println!();
+    println!("OPTIONS:");
+    println!("    --total-label STR");
+    println!("                    Label of the totals row in the reports (default \"total\")");
+    println!("    --json          Output in JSON format");
+}
+
+/// Reads a report into per-file metrics keyed by file name.
+///
+/// Accepts the concatenated objects `rwc --json` prints, NDJSON, a JSON array
+/// of file objects, or an object with a `files` array. Rows labelled
+/// `total_label` are ignored; totals are recomputed from the files.
+fn load(path: &str, total_label: &str) -> Result<BTreeMap<String, Metrics>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let documents = json::parse_stream(&text).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut records = Vec::new();
+    for document in &documents {
+        match document {
+            Value::Array(items) => records.extend(items),
+            object => match object.get("files").and_then(Value::as_array) {
+                Some(items) => records.extend(items),
+                None => records.push(object),
+            },
+        }
+    }
+
+    let mut files = BTreeMap::new();
+    for record in records {
+        let Some(name) = record.get("file").and_then(Value::as_str) else {
+            continue;
+        };
+        if name == total_label {
+            continue;
+        }
+        let Value::Object(members) = record else {
+            continue;
+        };
+        let metrics = members
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), value.as_f64()?)))
+            .collect();
+        files.insert(name.to_string(), metrics);
+    }
+    Ok(files)
+}
+
+struct Change {
+    metric: String,
+    old: f64,
+    new: f64,
+}
+
+struct Diff {
+    added: Vec<(String, Metrics)>,
+    removed: Vec<(String, Metrics)>,
+    changed: Vec<(String, Vec<Change>)>,
+    unchanged: usize,
+    totals: Vec<Change>,
+}
+
+impl Diff {
+    fn between(old: &BTreeMap<String, Metrics>, new: &BTreeMap<String, Metrics>) -> Self {
+        let mut diff = Diff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+            unchanged: 0,
+            totals: Vec::new(),
+        };
+
+        for (file, metrics) in old {
+            if !new.contains_key(file) {
+                diff.removed.push((file.clone(), metrics.clone()));
+            }
+        }
+        for (file, metrics) in new {
+            let Some(before) = old.get(file) else {
+                diff.added.push((file.clone(), metrics.clone()));
+                continue;
+            };
+            let changes: Vec<Change> = metric_names(before, metrics)
+                .into_iter()
+                .filter_map(|metric| {
+                    let old = lookup(before, &metric);
+                    let new = lookup(metrics, &metric);
+                    (old != new).then_some(Change { metric, old, new })
+                })
+                .collect();
+            if changes.is_empty() {
+                diff.unchanged += 1;
+            } else {
+                diff.changed.push((file.clone(), changes));
+            }
+        }
+
+        let old_total = sum(old.values());
+        let new_total = sum(new.values());
+        diff.totals = metric_names(&old_total, &new_total)
+            .into_iter()
+            .map(|metric| Change {
+                old: lookup(&old_total, &metric),
+                new: lookup(&new_total, &metric),
+                metric,
+            })
+            .collect();
+        diff
+    }
+
+    fn print(&self) {
+        let width = self
+            .added
+            .iter()
+            .map(|(f, _)| f)
+            .chain(self.removed.iter().map(|(f, _)| f))
+            .chain(self.changed.iter().map(|(f, _)| f))
+            .map(|f| f.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        for (file, metrics) in &self.added {
+            println!("added    {:<width$}  {}", file, format_metrics(metrics));
+        }
+        for (file, metrics) in &self.removed {
+            println!("removed  {:<width$}  {}", file, format_metrics(metrics));
+        }
+        for (file, changes) in &self.changed {
+            println!("changed  {:<width$}  {}", file, format_changes(changes));
+        }
+        if !self.added.is_empty() || !self.removed.is_empty() || !self.changed.is_empty() {
+            println!();
+        }
+        println!(
+            "{} added, {} removed, {} changed, {} unchanged",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.unchanged
+        );
+        if !self.totals.is_empty() {
+            println!("total: {}", format_changes(&self.totals));
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let files = |list: &[(String, Metrics)]| {
+            Value::Array(
+                list.iter()
+                    .map(|(file, metrics)| {
+                        let mut members = vec![("file".to_string(), Value::from(file.as_str()))];
+                        members.extend(
+                            metrics.iter().map(|(k, v)| (k.clone(), Value::Number(*v))),
+                        );
+                        Value::Object(members)
+                    })
+                    .collect(),
+            )
+        };
+        let changes = |changes: &[Change]| {
+            Value::Object(
+                changes
+                    .iter()
+                    .map(|c| {
+                        let delta = json::object([
+                            ("old", Value::Number(c.old)),
+                            ("new", Value::Number(c.new)),
+                            ("delta", Value::Number(c.new - c.old)),
+                        ]);
+                        (c.metric.clone(), delta)
+                    })
+                    .collect(),
+            )
+        };
+
+        json::object([
+            ("added", files(&self.added)),
+            ("removed", files(&self.removed)),
+            (
+                "changed",
+                Value::Array(
+                    self.changed
+                        .iter()
+                        .map(|(file, c)| {
+                            json::object([("file", file.as_str().into()), ("metrics", changes(c))])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("unchanged", self.unchanged.into()),
+            ("totals", changes(&self.totals)),
+        ])
+    }
+}
+
+/// Metric names present in either side, in first-seen order.
+fn metric_names(a: &Metrics, b: &Metrics) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (name, _) in a.iter().chain(b) {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    names
+}
+
+fn lookup(metrics: &Metrics, name: &str) -> f64 {
+    metrics
+        .iter()
+        .find(|(k, _)| k == name)
+        .map_or(0.0, |(_, v)| *v)
+}
+
+fn sum<'a>(all: impl Iterator<Item = &'a Metrics>) -> Metrics {
+    let mut total: Metrics = Vec::new();
+    for metrics in all {
+        for (name, value) in metrics {
+            match total.iter_mut().find(|(k, _)| k == name) {
+                Some((_, sum)) => *sum += value,
+                None => total.push((name.clone(), *value)),
+            }
+        }
+    }
+    total
+}
+
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+fn format_metrics(metrics: &Metrics) -> String {
+    metrics
+        .iter()
+        .map(|(k, v)| format!("{} {}", k, format_value(*v)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_changes(changes: &[Change]) -> String {
+    changes
+        .iter()
+        .map(|c| {
+            let delta = c.new - c.old;
+            let sign = if delta >= 0.0 { "+" } else { "" };
+            format!(
+                "{} {} → {} ({}{})",
+                c.metric,
+                format_value(c.old),
+                format_value(c.new),
+                sign,
+                format_value(delta)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
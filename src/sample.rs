@@ -0,0 +1,497 @@
+//! Seeded line sampling with extrapolated totals.
+//!
+//! Sampled lines are related to total bytes (which are always known exactly),
+//! so every estimate carries a confidence interval derived from the variance
+//! of the sampled lines. A file is sampled at random byte offsets, which draw
+//! each line in proportion to its length, and every draw is weighted by the
+//! inverse of that; a stream keeps lines with equal chance and uses a ratio
+//! estimator.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+
+use crate::{bidi, count_text, json, output, print_json_fields, Config, Counts};
+
+/// z-score for the reported 95% confidence intervals.
+const Z_95: f64 = 1.96;
+
+#[derive(Clone, Copy)]
+pub enum Sampling {
+    /// Sample roughly this fraction of the input.
+    Fraction(f64),
+    /// Sample this many lines.
+    Lines(usize),
+}
+
+impl Sampling {
+    /// Parses `--sample` values such as `1%` or `0.01`.
+    pub fn parse_fraction(text: &str) -> Option<Sampling> {
+        let fraction = match text.strip_suffix('%') {
+            Some(percent) => percent.parse::<f64>().ok()? / 100.0,
+            None => text.parse::<f64>().ok()?,
+        };
+        (fraction > 0.0 && fraction <= 1.0).then_some(Sampling::Fraction(fraction))
+    }
+}
+
+/// SplitMix64, a small seedable generator with good statistical quality.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform value in `[0, n)`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+}
+
+/// An extrapolated total and the variance of the estimate.
+#[derive(Default, Clone, Copy)]
+struct Metric {
+    value: f64,
+    variance: f64,
+}
+
+impl Metric {
+    fn exact(value: usize) -> Self {
+        Metric {
+            value: value as f64,
+            variance: 0.0,
+        }
+    }
+
+    fn add(&mut self, other: &Metric) {
+        self.value += other.value;
+        self.variance += other.variance;
+    }
+
+    fn margin(&self) -> f64 {
+        Z_95 * self.variance.sqrt()
+    }
+}
+
+#[derive(Default)]
+struct Estimate {
+    sampled_lines: usize,
+    bytes: usize,
+    lines: Metric,
+    words: Metric,
+    chars: Metric,
+}
+
+impl Estimate {
+    fn exact(counts: &Counts, sampled_lines: usize) -> Self {
+        Estimate {
+            sampled_lines,
+            bytes: counts.bytes,
+            lines: Metric::exact(counts.lines),
+            words: Metric::exact(counts.words),
+            chars: Metric::exact(counts.chars),
+        }
+    }
+
+    fn add(&mut self, other: &Estimate) {
+        self.sampled_lines += other.sampled_lines;
+        self.bytes += other.bytes;
+        self.lines.add(&other.lines);
+        self.words.add(&other.words);
+        self.chars.add(&other.chars);
+    }
+}
+
+/// Running sums for a ratio estimate of `y` per byte `x`.
+#[derive(Default)]
+struct RatioSums {
+    y: f64,
+    yy: f64,
+    xy: f64,
+}
+
+#[derive(Default)]
+struct Sample {
+    n: usize,
+    x: f64,
+    xx: f64,
+    lines: RatioSums,
+    words: RatioSums,
+    chars: RatioSums,
+}
+
+impl Sample {
+    fn push(&mut self, line: &[u8]) {
+        let counts = count_text(&String::from_utf8_lossy(line));
+        let x = line.len() as f64;
+        self.n += 1;
+        self.x += x;
+        self.xx += x * x;
+        for (sums, y) in [
+            (&mut self.lines, 1.0),
+            (&mut self.words, counts.words as f64),
+            (&mut self.chars, counts.chars as f64),
+        ] {
+            sums.y += y;
+            sums.yy += y * y;
+            sums.xy += x * y;
+        }
+    }
+
+    /// Extrapolates `sums` to `total_bytes` with a finite-population-corrected
+    /// delta-method variance, for lines kept without replacement.
+    fn extrapolate(&self, sums: &RatioSums, total_bytes: f64) -> Metric {
+        if self.n == 0 || self.x == 0.0 {
+            return Metric::default();
+        }
+        let n = self.n as f64;
+        let ratio = sums.y / self.x;
+        let value = total_bytes * ratio;
+        if self.n < 2 {
+            return Metric {
+                value,
+                variance: 0.0,
+            };
+        }
+        let residual = sums.yy - 2.0 * ratio * sums.xy + ratio * ratio * self.xx;
+        let s2 = (residual / (n - 1.0)).max(0.0);
+        let mean_x = self.x / n;
+        let fpc = (1.0 - self.x / total_bytes).max(0.0);
+        let variance = total_bytes * total_bytes * fpc * s2 / (n * mean_x * mean_x);
+        Metric { value, variance }
+    }
+
+    fn estimate(&self, total_bytes: usize) -> Estimate {
+        let b = total_bytes as f64;
+        Estimate {
+            sampled_lines: self.n,
+            bytes: total_bytes,
+            lines: self.extrapolate(&self.lines, b),
+            words: self.extrapolate(&self.words, b),
+            chars: self.extrapolate(&self.chars, b),
+        }
+    }
+}
+
+/// Running sums of `y` per byte over lines drawn in proportion to their
+/// length.
+#[derive(Default)]
+struct DrawSums {
+    r: f64,
+    rr: f64,
+}
+
+/// Lines drawn with replacement, each with probability `len / total_bytes`
+/// (the chance a random byte offset falls inside it), for a Hansen-Hurwitz
+/// estimate: every draw of `y` in a line of `x` bytes stands for
+/// `total_bytes * y / x`.
+#[derive(Default)]
+struct Draws {
+    n: usize,
+    bytes: u64,
+    lines: DrawSums,
+    words: DrawSums,
+    chars: DrawSums,
+}
+
+impl Draws {
+    fn push(&mut self, line: &[u8]) {
+        let counts = count_text(&String::from_utf8_lossy(line));
+        let x = line.len() as f64;
+        self.n += 1;
+        self.bytes += line.len() as u64;
+        for (sums, y) in [
+            (&mut self.lines, 1.0),
+            (&mut self.words, counts.words as f64),
+            (&mut self.chars, counts.chars as f64),
+        ] {
+            let r = y / x;
+            sums.r += r;
+            sums.rr += r * r;
+        }
+    }
+
+    /// The mean of the draws scaled to `total_bytes`, with the variance of
+    /// a mean of independent draws.
+    fn extrapolate(&self, sums: &DrawSums, total_bytes: f64) -> Metric {
+        if self.n == 0 {
+            return Metric::default();
+        }
+        let n = self.n as f64;
+        let mean = sums.r / n;
+        let value = total_bytes * mean;
+        if self.n < 2 {
+            return Metric {
+                value,
+                variance: 0.0,
+            };
+        }
+        let s2 = ((sums.rr - n * mean * mean) / (n - 1.0)).max(0.0);
+        let variance = total_bytes * total_bytes * s2 / n;
+        Metric { value, variance }
+    }
+
+    fn estimate(&self, total_bytes: usize) -> Estimate {
+        let b = total_bytes as f64;
+        Estimate {
+            sampled_lines: self.n,
+            bytes: total_bytes,
+            lines: self.extrapolate(&self.lines, b),
+            words: self.extrapolate(&self.words, b),
+            chars: self.extrapolate(&self.chars, b),
+        }
+    }
+}
+
+/// Samples a seekable file by reading the lines that random byte offsets
+/// fall in, so only the sampled portion of the file is ever read.
+fn sample_file(path: &str, sampling: Sampling, rng: &mut Rng) -> io::Result<Estimate> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let target_bytes = match sampling {
+        Sampling::Fraction(p) => (size as f64 * p) as u64,
+        Sampling::Lines(_) => 0,
+    };
+
+    // Small inputs are cheaper (and more accurate) to count outright.
+    let whole_file = match sampling {
+        Sampling::Fraction(p) => p >= 0.5,
+        Sampling::Lines(n) => (n as u64).saturating_mul(256) >= size,
+    };
+    if whole_file || size == 0 {
+        return sample_stream(BufReader::new(file), Sampling::Fraction(1.0), rng);
+    }
+
+    let mut reader = BufReader::new(file);
+    let mut draws = Draws::default();
+    let mut line = Vec::new();
+    loop {
+        let done = match sampling {
+            Sampling::Fraction(_) => draws.bytes >= target_bytes.max(1),
+            Sampling::Lines(n) => draws.n >= n,
+        };
+        if done {
+            break;
+        }
+        let start = line_start(&mut reader, rng.below(size))?;
+        reader.seek(SeekFrom::Start(start))?;
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        draws.push(&line);
+    }
+
+    Ok(draws.estimate(size as usize))
+}
+
+/// The offset of the start of the line holding the byte at `offset`: just
+/// after the last newline before it, found by reading backwards.
+fn line_start<R: Read + Seek>(reader: &mut R, offset: u64) -> io::Result<u64> {
+    let mut buffer = [0; 4096];
+    let mut end = offset;
+    while end > 0 {
+        let start = end.saturating_sub(buffer.len() as u64);
+        let chunk = &mut buffer[..(end - start) as usize];
+        reader.seek(SeekFrom::Start(start))?;
+        reader.read_exact(chunk)?;
+        if let Some(newline) = chunk.iter().rposition(|&b| b == b'\n') {
+            return Ok(start + newline as u64 + 1);
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+/// Samples a non-seekable stream. Lines and bytes are counted exactly on the
+/// way through; only words and chars are extrapolated.
+fn sample_stream<R: BufRead>(mut reader: R, sampling: Sampling, rng: &mut Rng) -> io::Result<Estimate> {
+    let mut sample = Sample::default();
+    let mut reservoir: Vec<Vec<u8>> = Vec::new();
+    let mut total = Counts::default();
+    let mut seen = 0usize;
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        total.bytes += line.len();
+        total.lines += usize::from(line.last() == Some(&b'\n'));
+        seen += 1;
+        match sampling {
+            Sampling::Fraction(p) if p >= 1.0 => {
+                let counts = count_text(&String::from_utf8_lossy(&line));
+                total.words += counts.words;
+                total.chars += counts.chars;
+            }
+            Sampling::Fraction(p) => {
+                if rng.next_f64() < p {
+                    sample.push(&line);
+                }
+            }
+            Sampling::Lines(n) => {
+                if reservoir.len() < n {
+                    reservoir.push(line.clone());
+                } else {
+                    let slot = rng.below(seen as u64) as usize;
+                    if slot < n {
+                        reservoir[slot] = line.clone();
+                    }
+                }
+            }
+        }
+    }
+    // An unterminated last line still counts, matching `count_text`.
+    if !line.is_empty() && line.last() != Some(&b'\n') {
+        total.lines += 1;
+    }
+
+    if let Sampling::Fraction(p) = sampling {
+        if p >= 1.0 {
+            return Ok(Estimate::exact(&total, seen));
+        }
+    }
+    for line in &reservoir {
+        sample.push(line);
+    }
+    if reservoir.len() == seen && seen > 0 {
+        // The reservoir held the entire input.
+        let mut exact = Counts::default();
+        for line in &reservoir {
+            exact.add(&count_text(&String::from_utf8_lossy(line)));
+        }
+        return Ok(Estimate::exact(&exact, seen));
+    }
+
+    let mut estimate = sample.estimate(total.bytes);
+    estimate.lines = Metric::exact(total.lines);
+    Ok(estimate)
+}
+
+fn format_metric(metric: &Metric, config: &Config) -> String {
+    let value = config.format_count(metric.value.round() as usize);
+    let margin = metric.margin().round() as usize;
+    if margin == 0 {
+        value
+    } else {
+        format!("{}±{}", value, config.format_count(margin))
+    }
+}
+
+fn print_estimate(estimate: &Estimate, config: &Config, name: Option<&str>) {
+    if config.show_json {
+        let metric = |key: &str, m: &Metric| {
+            format!(
+                "  \"{}\": {{\"estimate\": {:.0}, \"low\": {:.0}, \"high\": {:.0}}}",
+                key,
+                m.value,
+                (m.value - m.margin()).max(0.0),
+                m.value + m.margin()
+            )
+        };
+        let mut fields = Vec::new();
+        if let Some(name) = name {
+            fields.push(format!("  \"file\": {}", json::quote(name)));
+        }
+        fields.push(format!("  \"sampled_lines\": {}", estimate.sampled_lines));
+        fields.push("  \"confidence\": 0.95".to_string());
+        if config.show_lines {
+            fields.push(metric("lines", &estimate.lines));
+        }
+        if config.show_words {
+            fields.push(metric("words", &estimate.words));
+        }
+        if config.show_chars {
+            fields.push(metric("chars", &estimate.chars));
+        }
+        if config.show_bytes {
+            fields.push(format!("  \"bytes\": {}", estimate.bytes));
+        }
+        print_json_fields(&fields, config);
+        return;
+    }
+
+    let mut output = Vec::new();
+    if config.show_lines {
+        output.push(format_metric(&estimate.lines, config));
+    }
+    if config.show_words {
+        output.push(format_metric(&estimate.words, config));
+    }
+    if config.show_chars {
+        output.push(format_metric(&estimate.chars, config));
+    }
+    if config.show_bytes {
+        output.push(config.format_count(estimate.bytes));
+    }
+    let columns: String = output.iter().map(|c| format!("{:>16}", c)).collect();
+    match name {
+        Some(name) => output::print(&format!("{} {}", columns, bidi::display_name(name))),
+        None => output::print(&columns),
+    }
+}
+
+/// Runs sampled counting over the configured files (or stdin).
+pub fn run(config: &Config, sampling: Sampling) -> i32 {
+    let mut rng = Rng::new(config.seed);
+
+    if config.files.is_empty() {
+        return match sample_stream(io::stdin().lock(), sampling, &mut rng) {
+            Ok(estimate) => {
+                print_estimate(&estimate, config, None);
+                note(&estimate);
+                0
+            }
+            Err(e) => {
+                eprintln!("rwc: {}", e);
+                1
+            }
+        };
+    }
+
+    let mut total = Estimate::default();
+    for filename in &config.files {
+        let result = if filename == "-" {
+            sample_stream(io::stdin().lock(), sampling, &mut rng)
+        } else {
+            sample_file(filename, sampling, &mut rng)
+        };
+        match result {
+            Ok(estimate) => {
+                print_estimate(&estimate, config, Some(filename));
+                total.add(&estimate);
+            }
+            Err(e) => {
+                eprintln!("rwc: {}: {}", filename, e);
+                return 1;
+            }
+        }
+    }
+    if config.files.len() > 1 {
+        print_estimate(&total, config, Some(config.total_label()));
+    }
+    note(&total);
+    0
+}
+
+fn note(estimate: &Estimate) {
+    eprintln!(
+        "rwc: estimated from {} sampled lines (±95% confidence interval)",
+        estimate.sampled_lines
+    );
+}
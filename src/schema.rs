@@ -0,0 +1,151 @@
+//! The version and JSON Schema of the `--json` report.
+//!
+//! Every report starts with `"schema": VERSION`. The version goes up only
+//! when a change could break a consumer: a member removed or renamed, or
+//! its type changed. New optional members leave it as it is, so consumers
+//! should ignore members they do not know. `rwc --print-schema` prints the
+//! schema below, which CI can validate reports against.
+
+use crate::json::{self, Value};
+
+/// The version of the report format.
+pub const VERSION: usize = 1;
+
+/// A schema for `type`, described.
+fn typed(kind: &str, description: &str) -> Value {
+    json::object([("type", kind.into()), ("description", description.into())])
+}
+
+fn count(description: &str) -> Value {
+    json::object([
+        ("type", "integer".into()),
+        ("minimum", 0usize.into()),
+        ("description", description.into()),
+    ])
+}
+
+fn object(properties: Vec<(&str, Value)>, required: &[&str]) -> Value {
+    let properties = properties.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    let required = required.iter().map(|&key| Value::from(key)).collect();
+    json::object([
+        ("type", "object".into()),
+        ("properties", Value::Object(properties)),
+        ("required", Value::Array(required)),
+    ])
+}
+
+fn array_of(items: Value) -> Value {
+    json::object([("type", "array".into()), ("items", items)])
+}
+
+fn reference(name: &str) -> Value {
+    json::object([("$ref", format!("#/$defs/{}", name).into())])
+}
+
+/// The counts of one input, a group or the totals. Only the counts asked for
+/// are present.
+fn counts() -> Value {
+    object(
+        vec![
+            ("file", typed("string", "The input; absent for totals and groups")),
+            ("lines", count("Newline characters")),
+            ("words", count("Words")),
+            ("chars", count("Characters")),
+            ("bytes", count("Bytes")),
+            ("max_line_length", count("Display width of the longest line")),
+            ("max_display_width", count("The same, with tabs at --tab-size")),
+            ("paragraphs", count("Blocks of lines separated by blank lines")),
+            ("blank_lines", count("Whitespace-only lines")),
+            ("sentences", count("Sentences")),
+            ("unique_lines", count("Distinct lines")),
+            ("unique_lines_error", typed("number", "Relative error of an estimate")),
+            ("unique_words", count("Distinct words")),
+            ("unique_words_error", typed("number", "Relative error of an estimate")),
+            ("score", typed("number", "--weights score")),
+            ("minified", typed("boolean", "Whether the input looks minified")),
+            ("minified_score", typed("number", "Minified score, 0 to 1")),
+            ("language", typed("string", "ISO 639-1 code of the language, or und")),
+            ("indent_max", count("Deepest indentation")),
+            ("indent_avg", typed("number", "Average indentation depth")),
+            ("indent_tab_ratio", typed("number", "Share of tab-indented lines")),
+            ("visual_lines", count("Lines when soft-wrapped at --wrap-at")),
+            ("records", count("Records started by --record-start")),
+            (
+                "levels",
+                json::object([
+                    ("type", "object".into()),
+                    ("additionalProperties", count("Lines at a severity")),
+                ]),
+            ),
+            ("reading_minutes", count("--reading-time estimate in whole minutes")),
+            ("reading_time", typed("string", "The estimate written as --time-format asks")),
+            ("speaking_minutes", count("--speaking-time estimate in whole minutes")),
+            ("speaking_time", typed("string", "The estimate written as --time-format asks")),
+            ("pages", count("--pages estimate in whole pages")),
+            (
+                "status",
+                json::object([
+                    ("enum", Value::Array(vec!["ok".into(), "warn".into(), "error".into()])),
+                    ("description", "--warn-above/--error-above status".into()),
+                ]),
+            ),
+            ("structure", typed("object", "--structure report of the document")),
+            ("preview", array_of(typed("string", "A line of the input"))),
+        ],
+        &[],
+    )
+}
+
+/// The JSON Schema (draft 2020-12) of the report.
+pub fn document() -> Value {
+    let group = object(
+        vec![
+            ("group", typed("string", "The --group-by key")),
+            ("files", count("Files in the group")),
+        ],
+        &["group", "files"],
+    );
+    let group = json::object([("allOf", Value::Array(vec![reference("counts"), group]))]);
+    let summary = object(
+        ["min", "max", "mean", "median"]
+            .into_iter()
+            .map(|statistic| {
+                let values = json::object([
+                    ("type", "object".into()),
+                    ("additionalProperties", typed("number", statistic)),
+                ]);
+                (statistic, values)
+            })
+            .collect(),
+        &["min", "max", "mean", "median"],
+    );
+    let Value::Object(mut members) = object(
+        vec![
+            (
+                "schema",
+                json::object([
+                    ("const", VERSION.into()),
+                    ("description", "Version of this report format".into()),
+                ]),
+            ),
+            ("metadata", typed("object", "--metadata provenance record")),
+            ("partial", typed("boolean", "True when Ctrl-C stopped the count early")),
+            ("files", array_of(reference("counts"))),
+            ("groups", array_of(group)),
+            ("total", reference("counts")),
+            ("summary", summary),
+        ],
+        &["schema", "total"],
+    ) else {
+        unreachable!("a schema is an object");
+    };
+    let header = [
+        ("$schema", "https://json-schema.org/draft/2020-12/schema".into()),
+        ("title", "rwc report".into()),
+        ("$defs", Value::Object(vec![("counts".to_string(), counts())])),
+    ];
+    for (index, (key, value)) in header.into_iter().enumerate() {
+        members.insert(index, (key.to_string(), value));
+    }
+    Value::Object(members)
+}
@@ -0,0 +1,62 @@
+//! `--sidecar`: per-file `FILE.rwc.json` metadata for build systems.
+
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::json::Value;
+use crate::{sha256, Counts};
+
+/// Where the sidecar for `file` goes: next to it, or mirrored under `dir`.
+fn sidecar_path(file: &Path, dir: Option<&Path>) -> PathBuf {
+    let mut name = file.as_os_str().to_os_string();
+    name.push(".rwc.json");
+    let beside = PathBuf::from(name);
+    match dir {
+        None => beside,
+        Some(dir) => {
+            // Keep the input's relative layout so equal basenames don't collide.
+            let relative: PathBuf = beside
+                .components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .collect();
+            dir.join(relative)
+        }
+    }
+}
+
+/// Writes the sidecar for `file` and returns its path.
+pub fn write(file: &str, counts: &Counts, dir: Option<&Path>) -> io::Result<PathBuf> {
+    let path = Path::new(file);
+    let digest = match &counts.sha256 {
+        Some(digest) => digest.clone(),
+        None => sha256::hex_digest(File::open(path)?)?,
+    };
+
+    let mut members = vec![
+        ("file".to_string(), Value::from(file)),
+        ("sha256".to_string(), digest.into()),
+        ("lines".to_string(), counts.lines.into()),
+        ("words".to_string(), counts.words.into()),
+        ("chars".to_string(), counts.chars.into()),
+        ("bytes".to_string(), counts.bytes.into()),
+    ];
+    if let Some(uniques) = &counts.uniques {
+        for (key, tracker) in [("unique_lines", &uniques.lines), ("unique_words", &uniques.words)] {
+            if let Some(tracker) = tracker {
+                members.push((key.to_string(), tracker.count().into()));
+            }
+        }
+    }
+
+    if let Some(score) = counts.score {
+        members.push(("score".to_string(), Value::Number(score)));
+    }
+
+    let target = sidecar_path(path, dir);
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&target, format!("{}\n", Value::Object(members)))?;
+    Ok(target)
+}
@@ -0,0 +1,118 @@
+//! `--split-on REGEX`: counts per section of a plain-text manuscript.
+//!
+//! A line matching the pattern (such as `^CHAPTER \d+`) starts a section,
+//! which runs to the next such line; the line itself belongs to the section
+//! it starts and names it. Text before the first match is the preamble.
+//! Each input gets a row per section and its own total.
+
+use std::fs;
+use std::io::{self, Read};
+
+use crate::json::Value;
+use crate::regex::Regex;
+use crate::{
+    counts_json, encoding, engine, formats, print_counts, print_json, transform, Config, Counts,
+};
+
+/// The name of the text before the first marker.
+const PREAMBLE: &str = "(preamble)";
+
+/// Entry point for `--split-on`.
+pub fn run(config: &Config, marker: &Regex) -> i32 {
+    let stdin = ["-".to_string()];
+    let operands = if config.files.is_empty() {
+        &stdin[..]
+    } else {
+        &config.files[..]
+    };
+    let formats = formats::formats(config);
+    let mut files = Vec::new();
+    let mut total = Counts::default();
+    for operand in operands {
+        let label = if operand == "-" {
+            config.stdin_label()
+        } else {
+            operand.as_str()
+        };
+        let sections = match read(operand, config) {
+            Ok(text) => sections(&text, marker, config),
+            Err(e) => {
+                eprintln!("rwc: {}: {}", label, e);
+                return 1;
+            }
+        };
+        let mut file_total = Counts::default();
+        for (_, counts) in &sections {
+            file_total.add(counts);
+        }
+        total.add(&file_total);
+        if config.show_json {
+            let sections = sections
+                .iter()
+                .map(|(title, counts)| {
+                    let Value::Object(mut members) = counts_json(counts, config, None) else {
+                        unreachable!("counts are an object");
+                    };
+                    members.insert(0, ("section".to_string(), title.as_str().into()));
+                    Value::Object(members)
+                })
+                .collect();
+            files.push(Value::Object(vec![
+                ("file".to_string(), label.into()),
+                ("sections".to_string(), Value::Array(sections)),
+                ("total".to_string(), counts_json(&file_total, config, None)),
+            ]));
+            continue;
+        }
+        for (title, counts) in &sections {
+            print_counts(&formats, counts, config, Some(&format!("{}: {}", label, title)));
+        }
+        print_counts(&formats, &file_total, config, Some(label));
+    }
+
+    if config.show_json {
+        let document = Value::Object(vec![
+            ("files".to_string(), Value::Array(files)),
+            ("total".to_string(), counts_json(&total, config, None)),
+        ]);
+        print_json(&document, config);
+    } else if operands.len() > 1 {
+        print_counts(&formats, &total, config, Some(config.total_label()));
+    }
+    0
+}
+
+fn read(operand: &str, config: &Config) -> io::Result<String> {
+    let data = if operand == "-" {
+        let mut data = Vec::new();
+        io::stdin().lock().read_to_end(&mut data)?;
+        data
+    } else {
+        fs::read(operand)?
+    };
+    transform::to_text(encoding::decode(data, config.encoding), config.lossy)
+}
+
+/// The sections of `text`, named by their marker lines, with their counts.
+/// The preamble is left out when there is nothing in it.
+fn sections(text: &str, marker: &Regex, config: &Config) -> Vec<(String, Counts)> {
+    let mut starts = vec![(PREAMBLE.to_string(), 0)];
+    let mut at = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        if marker.is_match(content) {
+            starts.push((content.trim().to_string(), at));
+        }
+        at += line.len();
+    }
+    let metrics = config.metrics();
+    let ends: Vec<usize> =
+        starts.iter().skip(1).map(|&(_, start)| start).chain([text.len()]).collect();
+    starts
+        .into_iter()
+        .zip(ends)
+        .enumerate()
+        .filter(|(index, ((_, start), end))| *index > 0 || !text[*start..*end].trim().is_empty())
+        .map(|(_, ((title, start), end))| (title, engine::scan(&text[start..end], &metrics).into()))
+        .collect()
+}
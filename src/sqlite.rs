@@ -0,0 +1,212 @@
+//! `rwc sqlite FILE.db`: rows per table and the size of the text stored in
+//! a SQLite database.
+//!
+//! Like `rwc sql`, this runs the `sqlite3` client rather than linking
+//! SQLite. Each table is streamed once; a cell counts as text when SQLite
+//! stores it as text, whatever its column's declared type, so the words and
+//! characters are those of the strings in the database.
+
+use std::io::{self, BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+use crate::engine::{Metrics, Scanner};
+use crate::json::{self, Value};
+use crate::{cli, format_number};
+
+const FIELD_SEP: u8 = 0x1f;
+const RECORD_SEP: u8 = 0x1e;
+
+#[derive(Default)]
+struct TableCounts {
+    rows: usize,
+    words: usize,
+    chars: usize,
+}
+
+impl TableCounts {
+    fn add(&mut self, other: &TableCounts) {
+        self.rows += other.rows;
+        self.words += other.words;
+        self.chars += other.chars;
+    }
+
+    fn to_json(&self) -> [(&'static str, Value); 3] {
+        [
+            ("rows", self.rows.into()),
+            ("words", self.words.into()),
+            ("chars", self.chars.into()),
+        ]
+    }
+}
+
+/// Entry point for `rwc sqlite FILE.db`.
+pub fn run(args: &[String]) -> i32 {
+    let mut file = None;
+    let mut json = false;
+    let mut human = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--human" => human = true,
+            "--help" => {
+                println!("Usage: rwc sqlite [--json] [-h] FILE.db");
+                println!();
+                println!("Counts the rows of every table in a SQLite database, and the words");
+                println!("and characters in its text cells. Requires sqlite3 on PATH.");
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option("rwc sqlite", a, &["--json", "--human", "--help"]);
+                return 1;
+            }
+            a if file.is_none() => file = Some(a.to_string()),
+            a => {
+                eprintln!("rwc sqlite: unexpected argument '{}'", a);
+                return 1;
+            }
+        }
+    }
+    let Some(file) = file else {
+        eprintln!("rwc sqlite: no database file");
+        eprintln!("Try 'rwc sqlite --help' for more information.");
+        return 1;
+    };
+
+    let tables = match count_tables(&file) {
+        Ok(tables) => tables,
+        Err(e) => {
+            eprintln!("rwc sqlite: {}: {}", file, e);
+            return 1;
+        }
+    };
+    let mut total = TableCounts::default();
+    for (_, counts) in &tables {
+        total.add(counts);
+    }
+
+    if json {
+        let tables = tables
+            .iter()
+            .map(|(name, counts)| {
+                let mut members = vec![("table".to_string(), Value::from(name.as_str()))];
+                members.extend(counts.to_json().map(|(key, value)| (key.to_string(), value)));
+                Value::Object(members)
+            })
+            .collect();
+        let document = json::object([
+            ("file", file.as_str().into()),
+            ("tables", Value::Array(tables)),
+            ("total", json::object(total.to_json())),
+        ]);
+        println!("{}", document.pretty());
+        return 0;
+    }
+    let row = |counts: &TableCounts, label: &str| {
+        println!(
+            "{:>8}{:>10}{:>12} {}",
+            format_number(counts.rows, human),
+            format_number(counts.words, human),
+            format_number(counts.chars, human),
+            label
+        );
+    };
+    for (name, counts) in &tables {
+        row(counts, name);
+    }
+    row(&total, &file);
+    0
+}
+
+fn count_tables(file: &str) -> io::Result<Vec<(String, TableCounts)>> {
+    let mut names = Vec::new();
+    query(
+        file,
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' \
+         ORDER BY name",
+        |fields| names.push(String::from_utf8_lossy(fields[0]).into_owned()),
+    )?;
+    names
+        .into_iter()
+        .map(|name| {
+            let counts = count_table(file, &name)?;
+            Ok((name, counts))
+        })
+        .collect()
+}
+
+fn quote(text: &str, mark: char) -> String {
+    let doubled = format!("{}{}", mark, mark);
+    format!("{}{}{}", mark, text.replace(mark, &doubled), mark)
+}
+
+fn count_table(file: &str, table: &str) -> io::Result<TableCounts> {
+    let mut columns = Vec::new();
+    let sql = format!("SELECT name FROM pragma_table_info({})", quote(table, '\''));
+    query(file, &sql, |fields| {
+        columns.push(String::from_utf8_lossy(fields[0]).into_owned())
+    })?;
+    // Cells that are not text come back empty, and count nothing.
+    let selected: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            let column = quote(column, '"');
+            format!("CASE WHEN typeof({0}) = 'text' THEN {0} END", column)
+        })
+        .collect();
+    let sql = format!("SELECT {} FROM {}", selected.join(", "), quote(table, '"'));
+
+    let metrics = Metrics::default();
+    let mut scanner = Scanner::new(&metrics);
+    let mut counts = TableCounts::default();
+    let mut result = Ok(());
+    query(file, &sql, |fields| {
+        counts.rows += 1;
+        for field in fields {
+            // Text stored in another encoding is counted lossily.
+            let text = String::from_utf8_lossy(field);
+            counts.chars += text.chars().count();
+            // A space keeps one cell's last word apart from the next's first.
+            if result.is_ok() {
+                result = scanner.feed(text.as_bytes()).and_then(|()| scanner.feed(b" "));
+            }
+        }
+    })?;
+    result?;
+    counts.words = scanner.finish()?.words;
+    Ok(counts)
+}
+
+/// Runs `sql` on the database read-only and passes each result row, split
+/// into fields, to `row`.
+fn query(file: &str, sql: &str, mut row: impl FnMut(&[&[u8]])) -> io::Result<()> {
+    let mut child = Command::new("sqlite3")
+        .args(["-readonly", "-batch", "-noheader", "-bail"])
+        .args(["-separator", "\x1f", "-newline", "\x1e"])
+        .arg(file)
+        .arg(sql)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("sqlite3: {}", e)))?;
+
+    let mut reader = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut record = Vec::new();
+    loop {
+        record.clear();
+        if reader.read_until(RECORD_SEP, &mut record)? == 0 {
+            break;
+        }
+        if record.last() == Some(&RECORD_SEP) {
+            record.pop();
+        }
+        let fields: Vec<&[u8]> = record.split(|&b| b == FIELD_SEP).collect();
+        row(&fields);
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("sqlite3 exited with {}", status)));
+    }
+    Ok(())
+}
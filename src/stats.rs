@@ -0,0 +1,647 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::cli;
+use crate::engine::{self, Metrics};
+use crate::format::csv_field;
+use crate::distinct::HyperLogLog;
+use crate::json::{self, Value};
+use crate::width;
+
+/// Target false-positive rate for the overlap Bloom filters.
+const FALSE_POSITIVE_RATE: f64 = 0.01;
+/// Salts Bloom hashes so positions are independent of the HyperLogLog hashes.
+const BLOOM_SALT: u64 = 0x9E37_79B9_7F4A_7C15;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+/// Entry point for `rwc stats`.
+pub fn run(args: &[String]) -> i32 {
+    let mut overlap = false;
+    let mut distribution = false;
+    let mut readability = false;
+    let mut format = Format::Table;
+    let mut files = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--overlap" => overlap = true,
+            "--length-distribution" => distribution = true,
+            "--readability" => readability = true,
+            "--json" => format = Format::Json,
+            "--format" => {
+                let value = args.get(i + 1).map(String::as_str);
+                format = match value {
+                    Some("table") => Format::Table,
+                    Some("csv") => Format::Csv,
+                    Some("json") => Format::Json,
+                    Some(other) => {
+                        eprintln!(
+                            "rwc stats: unknown format '{}' (expected table, csv, json)",
+                            other
+                        );
+                        return 1;
+                    }
+                    None => {
+                        eprintln!("rwc stats: option '--format' requires an argument");
+                        return 1;
+                    }
+                };
+                i += 1;
+            }
+            "--help" => {
+                print_help();
+                return 0;
+            }
+            a if a.starts_with('-') && a != "-" => {
+                cli::unknown_option(
+                    "rwc stats",
+                    a,
+                    &[
+                        "--overlap",
+                        "--length-distribution",
+                        "--readability",
+                        "--format",
+                        "--json",
+                        "--help",
+                    ],
+                );
+                return 1;
+            }
+            a => files.push(a.to_string()),
+        }
+        i += 1;
+    }
+
+    if [overlap, distribution, readability].iter().filter(|&&on| on).count() != 1 {
+        eprintln!(
+            "rwc stats: select one statistic: --overlap, --length-distribution or --readability"
+        );
+        eprintln!("Try 'rwc stats --help' for more information.");
+        return 1;
+    }
+
+    if distribution {
+        if files.is_empty() {
+            files.push("-".to_string());
+        }
+        return match length_distribution(&files) {
+            Ok(lengths) => {
+                print_distribution(&lengths, format);
+                0
+            }
+            Err(e) => {
+                eprintln!("rwc stats: {}", e);
+                1
+            }
+        };
+    }
+
+    if readability {
+        if files.is_empty() {
+            files.push("-".to_string());
+        }
+        let mut rows = Vec::new();
+        for file in &files {
+            match Readability::of_file(file) {
+                Ok(row) => rows.push((file.as_str(), row)),
+                Err(e) => {
+                    eprintln!("rwc stats: {}", e);
+                    return 1;
+                }
+            }
+        }
+        print_readability(&rows, format);
+        return 0;
+    }
+
+    if format == Format::Csv {
+        eprintln!("rwc stats: --overlap supports table and json output");
+        return 1;
+    }
+    let [a, b] = files.as_slice() else {
+        eprintln!("rwc stats: --overlap needs exactly two files");
+        return 1;
+    };
+
+    match estimate_overlap(a, b) {
+        Ok(report) => {
+            print_overlap(&report, format == Format::Json);
+            0
+        }
+        Err(e) => {
+            eprintln!("rwc stats: {}", e);
+            1
+        }
+    }
+}
+
+fn print_help() {
+    println!("Usage: rwc stats --overlap [--json] FILE_A FILE_B");
+    println!("       rwc stats --length-distribution [--format FORMAT] [FILE...]");
+    println!("       rwc stats --readability [--format FORMAT] [FILE...]");
+    println!();
+    println!("OPTIONS:");
+    println!("    --overlap       Estimate distinct lines and words shared by two files");
+    println!("    --length-distribution");
+    println!("                    Count the lines of each length (display columns) across");
+    println!("                    the files, or standard input");
+    println!("    --readability   Sentences, words per sentence, estimated syllables and the");
+    println!("                    Flesch reading ease and Flesch-Kincaid grade of each file");
+    println!("    --format FORMAT table (default), csv or json; csv suits plotting tools");
+    println!("    --json          Same as --format json");
+    println!();
+    println!("Overlap is estimated with Bloom filters and HyperLogLog sketches, so memory");
+    println!("stays small even for very large files; results are approximate.");
+    println!();
+    println!("Syllables are estimated from vowel groups, so readability scores are meant");
+    println!("for English prose. Higher reading ease is easier; the grade is a US school");
+    println!("grade level.");
+}
+
+/// Number of lines of each display width, over all of `files`.
+fn length_distribution(files: &[String]) -> io::Result<BTreeMap<usize, usize>> {
+    let mut lengths = BTreeMap::new();
+    for file in files {
+        for_each_line(file, |line| *lengths.entry(width::str_width(line)).or_insert(0) += 1)?;
+    }
+    Ok(lengths)
+}
+
+/// Prints every length that occurs, shortest first, with its line count.
+fn print_distribution(lengths: &BTreeMap<usize, usize>, format: Format) {
+    match format {
+        Format::Table => {
+            println!("{:>8} {:>10}", "LENGTH", "LINES");
+            for (length, lines) in lengths {
+                println!("{:>8} {:>10}", length, lines);
+            }
+        }
+        Format::Csv => {
+            println!("length,lines");
+            for (length, lines) in lengths {
+                println!("{},{}", length, lines);
+            }
+        }
+        Format::Json => {
+            let rows = lengths
+                .iter()
+                .map(|(&length, &lines)| {
+                    json::object([("length", length.into()), ("lines", lines.into())])
+                })
+                .collect();
+            println!("{}", json::object([("lengths", Value::Array(rows))]));
+        }
+    }
+}
+
+/// Counts behind the readability formulas.
+#[derive(Default)]
+struct Readability {
+    sentences: usize,
+    words: usize,
+    syllables: usize,
+}
+
+impl Readability {
+    /// Reads `path` (`-` for standard input), decoding invalid UTF-8
+    /// lossily.
+    fn of_file(path: &str) -> io::Result<Readability> {
+        let mut data = Vec::new();
+        if path == "-" {
+            io::stdin().lock().read_to_end(&mut data)?;
+        } else {
+            File::open(path)
+                .and_then(|mut file| file.read_to_end(&mut data))
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+        }
+        let text = String::from_utf8_lossy(&data);
+        let counts = engine::scan(&text, &Metrics::default());
+        Ok(Readability {
+            // Text without a sentence terminator is still one sentence.
+            sentences: counts.sentences.max(usize::from(counts.words > 0)),
+            words: counts.words,
+            syllables: text.split_whitespace().map(syllables).sum(),
+        })
+    }
+
+    fn add(&mut self, other: &Readability) {
+        self.sentences += other.sentences;
+        self.words += other.words;
+        self.syllables += other.syllables;
+    }
+
+    fn words_per_sentence(&self) -> f64 {
+        self.words as f64 / self.sentences.max(1) as f64
+    }
+
+    fn syllables_per_word(&self) -> f64 {
+        self.syllables as f64 / self.words.max(1) as f64
+    }
+
+    /// Flesch reading ease: about 0-100, higher is easier.
+    fn reading_ease(&self) -> f64 {
+        206.835 - 1.015 * self.words_per_sentence() - 84.6 * self.syllables_per_word()
+    }
+
+    /// Flesch-Kincaid grade level.
+    fn grade(&self) -> f64 {
+        0.39 * self.words_per_sentence() + 11.8 * self.syllables_per_word() - 15.59
+    }
+
+    fn to_json(&self, file: &str) -> Value {
+        let rounded = |value: f64| Value::Number((value * 100.0).round() / 100.0);
+        json::object([
+            ("file", file.into()),
+            ("sentences", self.sentences.into()),
+            ("words", self.words.into()),
+            ("words_per_sentence", rounded(self.words_per_sentence())),
+            ("syllables", self.syllables.into()),
+            ("reading_ease", rounded(self.reading_ease())),
+            ("grade", rounded(self.grade())),
+        ])
+    }
+}
+
+/// Estimated English syllables in `word`: groups of vowels (counting `y`),
+/// less a silent final `e` or `-ed`, and at least one for any word with a
+/// letter.
+fn syllables(word: &str) -> usize {
+    let letters: Vec<char> = word
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if letters.is_empty() {
+        return 0;
+    }
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut groups = 0;
+    let mut previous_vowel = false;
+    for &c in &letters {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            groups += 1;
+        }
+        previous_vowel = vowel;
+    }
+    // "make", "loved": the final e is not sounded, except in "-le" and
+    // "-ted"/"-ded".
+    let silent_e = match letters.as_slice() {
+        [.., c, 'e'] => !is_vowel(*c) && *c != 'l',
+        [.., c, 'e', 'd'] => !is_vowel(*c) && !matches!(c, 't' | 'd'),
+        _ => false,
+    };
+    if silent_e && groups > 1 {
+        groups -= 1;
+    }
+    groups.max(1)
+}
+
+/// Prints one row per file, and a total row when there are several.
+fn print_readability(rows: &[(&str, Readability)], format: Format) {
+    let mut total = Readability::default();
+    rows.iter().for_each(|(_, row)| total.add(row));
+    let total = (rows.len() > 1).then_some(("total", total));
+    let all = rows
+        .iter()
+        .map(|(file, row)| (*file, row))
+        .chain(total.as_ref().map(|(file, row)| (*file, row)));
+    match format {
+        Format::Table => {
+            println!(
+                "{:>10} {:>10} {:>10} {:>10} {:>8} {:>6}  FILE",
+                "SENTENCES", "WORDS", "WORDS/SENT", "SYLLABLES", "EASE", "GRADE"
+            );
+            for (file, row) in all {
+                println!(
+                    "{:>10} {:>10} {:>10.1} {:>10} {:>8.1} {:>6.1}  {}",
+                    row.sentences,
+                    row.words,
+                    row.words_per_sentence(),
+                    row.syllables,
+                    row.reading_ease(),
+                    row.grade(),
+                    file
+                );
+            }
+        }
+        Format::Csv => {
+            println!("file,sentences,words,words_per_sentence,syllables,reading_ease,grade");
+            for (file, row) in all {
+                println!(
+                    "{},{},{},{:.2},{},{:.2},{:.2}",
+                    csv_field(file),
+                    row.sentences,
+                    row.words,
+                    row.words_per_sentence(),
+                    row.syllables,
+                    row.reading_ease(),
+                    row.grade()
+                );
+            }
+        }
+        Format::Json => {
+            let files = rows.iter().map(|(file, row)| row.to_json(file)).collect();
+            let mut members = vec![("files".to_string(), Value::Array(files))];
+            if let Some((_, total)) = &total {
+                members.push(("total".to_string(), total.to_json("total")));
+            }
+            println!("{}", Value::Object(members));
+        }
+    }
+}
+
+/// Fixed-size Bloom filter using double hashing.
+struct Bloom {
+    bits: Vec<u64>,
+    len: u64,
+    hashes: u32,
+}
+
+impl Bloom {
+    fn with_capacity(items: usize, false_positive_rate: f64) -> Self {
+        let n = items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let len = (-(n * false_positive_rate.ln()) / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let hashes = ((len as f64 / n) * ln2).round().clamp(1.0, 16.0) as u32;
+        Bloom {
+            bits: vec![0; len.div_ceil(64) as usize],
+            len,
+            hashes,
+        }
+    }
+
+    fn positions(&self, item: &str) -> impl Iterator<Item = u64> {
+        let mut hasher = DefaultHasher::new();
+        (BLOOM_SALT, item).hash(&mut hasher);
+        let hash = hasher.finish();
+        let (h1, h2) = (hash & 0xFFFF_FFFF, (hash >> 32) | 1);
+        let len = self.len;
+        (0..u64::from(self.hashes)).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % len)
+    }
+
+    fn insert(&mut self, item: &str) {
+        for bit in self.positions(item).collect::<Vec<_>>() {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    fn contains(&self, item: &str) -> bool {
+        self.positions(item)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    /// False-positive probability given how full the filter is.
+    fn false_positive_rate(&self) -> f64 {
+        let set: u32 = self.bits.iter().map(|w| w.count_ones()).sum();
+        (f64::from(set) / self.len as f64).powi(self.hashes as i32)
+    }
+}
+
+/// Calls `f` for every line of `path` (`-` for standard input), decoding
+/// invalid UTF-8 lossily.
+fn for_each_line(path: &str, mut f: impl FnMut(&str)) -> io::Result<()> {
+    let mut reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        let file =
+            File::open(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+        Box::new(BufReader::new(file))
+    };
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            return Ok(());
+        }
+        let line = String::from_utf8_lossy(&buf);
+        f(line.trim_end_matches(['\n', '\r']));
+    }
+}
+
+#[derive(Default)]
+struct Overlap {
+    a: usize,
+    b: usize,
+    shared: usize,
+}
+
+struct OverlapReport {
+    lines: Overlap,
+    words: Overlap,
+}
+
+fn estimate_overlap(a: &str, b: &str) -> io::Result<OverlapReport> {
+    // Pass 1: size the filters from the distinct counts of A.
+    let mut a_lines = HyperLogLog::new();
+    let mut a_words = HyperLogLog::new();
+    for_each_line(a, |line| {
+        a_lines.insert(line);
+        line.split_whitespace().for_each(|w| a_words.insert(w));
+    })?;
+
+    // Pass 2: remember A's lines and words.
+    let mut line_filter = Bloom::with_capacity(a_lines.estimate(), FALSE_POSITIVE_RATE);
+    let mut word_filter = Bloom::with_capacity(a_words.estimate(), FALSE_POSITIVE_RATE);
+    for_each_line(a, |line| {
+        line_filter.insert(line);
+        line.split_whitespace().for_each(|w| word_filter.insert(w));
+    })?;
+
+    // Pass 3: stream B, tracking distinct items and those also seen in A.
+    let mut b_lines = HyperLogLog::new();
+    let mut b_words = HyperLogLog::new();
+    let mut shared_lines = HyperLogLog::new();
+    let mut shared_words = HyperLogLog::new();
+    for_each_line(b, |line| {
+        b_lines.insert(line);
+        if line_filter.contains(line) {
+            shared_lines.insert(line);
+        }
+        for word in line.split_whitespace() {
+            b_words.insert(word);
+            if word_filter.contains(word) {
+                shared_words.insert(word);
+            }
+        }
+    })?;
+
+    let corrected = |shared: &HyperLogLog, b: &HyperLogLog, filter: &Bloom| {
+        // Remove the expected false positives among B's distinct items.
+        let fp = filter.false_positive_rate();
+        let observed = shared.estimate() as f64;
+        let estimate = (observed - fp * b.estimate() as f64) / (1.0 - fp);
+        estimate.clamp(0.0, observed).round() as usize
+    };
+
+    Ok(OverlapReport {
+        lines: Overlap {
+            a: a_lines.estimate(),
+            b: b_lines.estimate(),
+            shared: corrected(&shared_lines, &b_lines, &line_filter),
+        },
+        words: Overlap {
+            a: a_words.estimate(),
+            b: b_words.estimate(),
+            shared: corrected(&shared_words, &b_words, &word_filter),
+        },
+    })
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        (part as f64 / whole as f64 * 100.0).min(100.0)
+    }
+}
+
+fn print_overlap(report: &OverlapReport, json: bool) {
+    let rows = [("lines", &report.lines), ("words", &report.words)];
+    if json {
+        let fields: Vec<String> = rows
+            .iter()
+            .map(|(name, o)| {
+                format!(
+                    "  \"{}\": {{\"a\": {}, \"b\": {}, \"shared\": {}, \"shared_pct_a\": {:.1}, \"shared_pct_b\": {:.1}}}",
+                    name,
+                    o.a,
+                    o.b,
+                    o.shared,
+                    percent(o.shared, o.a),
+                    percent(o.shared, o.b)
+                )
+            })
+            .collect();
+        println!("{{\n{}\n}}", fields.join(",\n"));
+        return;
+    }
+
+    println!(
+        "{:<8}{:>12}{:>12}{:>12}{:>9}{:>9}",
+        "", "A", "B", "SHARED", "%A", "%B"
+    );
+    for (name, o) in rows {
+        println!(
+            "{:<8}{:>12}{:>12}{:>12}{:>8.1}%{:>8.1}%",
+            name,
+            o.a,
+            o.b,
+            o.shared,
+            percent(o.shared, o.a),
+            percent(o.shared, o.b)
+        );
+    }
+    println!("(distinct counts; estimated with Bloom filters and HyperLogLog)");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn bloom_has_no_false_negatives_and_few_false_positives() {
+        let mut bloom = Bloom::with_capacity(10_000, FALSE_POSITIVE_RATE);
+        assert_eq!(bloom.false_positive_rate(), 0.0);
+        assert!(!bloom.contains("anything"));
+        (0..10_000).for_each(|n| bloom.insert(&n.to_string()));
+        assert!((0..10_000).all(|n| bloom.contains(&n.to_string())));
+        let false_positives = (10_000..30_000)
+            .filter(|n| bloom.contains(&n.to_string()))
+            .count();
+        assert!(false_positives < 20_000 * 3 / 100, "{}", false_positives);
+        let rate = bloom.false_positive_rate();
+        assert!(rate > 0.005 && rate < 0.02, "{}", rate);
+    }
+
+    #[test]
+    fn bloom_sizes_stay_sane() {
+        // Nothing to hold still gets a word of bits, and no more than 16
+        // hashes.
+        let empty = Bloom::with_capacity(0, FALSE_POSITIVE_RATE);
+        assert_eq!((empty.len, empty.hashes, empty.bits.len()), (64, 16, 1));
+        let strict = Bloom::with_capacity(10, 1e-12);
+        assert_eq!(strict.hashes, 16);
+        let loose = Bloom::with_capacity(1_000, 0.9);
+        assert_eq!(loose.hashes, 1);
+        let mut full = Bloom::with_capacity(1, FALSE_POSITIVE_RATE);
+        (0..1_000).for_each(|n| full.insert(&n.to_string()));
+        assert!(full.false_positive_rate() > 0.99);
+        assert!(full.contains("not inserted"));
+    }
+
+    #[test]
+    fn overlap_of_two_files() {
+        let dir = std::env::temp_dir().join(format!("rwc-stats-overlap-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (a, b, empty) = (dir.join("a.txt"), dir.join("b.txt"), dir.join("empty.txt"));
+        let lines = |range: std::ops::Range<usize>| -> String {
+            range.map(|n| format!("line{} w{}\n", n, n % 100)).collect()
+        };
+        fs::write(&a, lines(0..2_000)).unwrap();
+        fs::write(&b, lines(1_000..4_000)).unwrap();
+        fs::write(&empty, "").unwrap();
+        let path = |path: &std::path::Path| path.to_str().unwrap().to_string();
+
+        let report = estimate_overlap(&path(&a), &path(&b)).unwrap();
+        let near = |estimate: usize, exact: usize| estimate.abs_diff(exact) * 20 <= exact;
+        assert!(near(report.lines.a, 2_000), "{}", report.lines.a);
+        assert!(near(report.lines.b, 3_000), "{}", report.lines.b);
+        assert!(near(report.lines.shared, 1_000), "{}", report.lines.shared);
+        // The lines' first words and the 100 shared second words.
+        assert!(near(report.words.a, 2_100), "{}", report.words.a);
+        assert!(near(report.words.shared, 1_100), "{}", report.words.shared);
+
+        let report = estimate_overlap(&path(&empty), &path(&b)).unwrap();
+        assert_eq!((report.lines.a, report.lines.shared), (0, 0));
+        assert_eq!(percent(report.lines.shared, report.lines.a), 0.0);
+        let missing = dir.join("missing.txt");
+        assert!(estimate_overlap(&path(&missing), &path(&b)).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn small_overlaps_are_exact() {
+        let dir = std::env::temp_dir().join(format!("rwc-stats-small-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let overlap = |a: &str, b: &str| {
+            fs::write(dir.join("a.txt"), a).unwrap();
+            fs::write(dir.join("b.txt"), b).unwrap();
+            let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+            estimate_overlap(&path("a.txt"), &path("b.txt")).unwrap()
+        };
+
+        let report = overlap("a,b\n", "b,c\n");
+        assert_eq!(
+            (report.lines.a, report.lines.b, report.lines.shared),
+            (1, 1, 0)
+        );
+        let report = overlap("a\nb\n", "b\nc\n");
+        assert_eq!(
+            (report.lines.a, report.lines.b, report.lines.shared),
+            (2, 2, 1)
+        );
+        assert_eq!(report.words.shared, 1);
+        let report = overlap("w x\nx\ny\nz\n", "x\ny\nz\nq r\n");
+        assert_eq!(
+            (report.lines.a, report.lines.b, report.lines.shared),
+            (4, 4, 3)
+        );
+        assert_eq!(
+            (report.words.a, report.words.b, report.words.shared),
+            (4, 5, 3)
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+//! `--status-line`: a compact one-line summary for shell prompts and status bars.
+//!
+//! Per-file counts are cached by path, size and modification time so that a
+//! prompt re-rendering on every keystroke only recounts files that changed.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    count_input, count_named, is_generated, output, remote, walk, Config, Counts,
+};
+
+pub const DEFAULT_TEMPLATE: &str = "{words} words · {reading_time} read";
+
+struct CacheEntry {
+    modified: u128,
+    len: u64,
+    counts: Counts,
+}
+
+fn cache_path() -> PathBuf {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "user".to_string());
+    std::env::temp_dir().join(format!("rwc-status-{}.tsv", user))
+}
+
+/// Loads the cache; a missing or malformed file is treated as empty.
+fn load_cache(path: &Path) -> HashMap<PathBuf, CacheEntry> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let path = PathBuf::from(fields.next()?);
+            let mut numbers = fields.map(|f| f.parse::<u128>().ok());
+            let mut next = || numbers.next().flatten();
+            let entry = CacheEntry {
+                modified: next()?,
+                len: next()? as u64,
+                counts: Counts {
+                    bytes: next()? as usize,
+                    chars: next()? as usize,
+                    words: next()? as usize,
+                    lines: next()? as usize,
+                    ..Counts::default()
+                },
+            };
+            Some((path, entry))
+        })
+        .collect()
+}
+
+fn save_cache(path: &Path, cache: &HashMap<PathBuf, CacheEntry>) -> io::Result<()> {
+    let mut text = String::new();
+    for (file, entry) in cache {
+        let Some(name) = file.to_str() else { continue };
+        if name.contains(['\t', '\n']) {
+            continue;
+        }
+        let c = &entry.counts;
+        text.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            name, entry.modified, entry.len, c.bytes, c.chars, c.words, c.lines
+        ));
+    }
+    // Write then rename so a concurrent prompt never reads a partial cache.
+    let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&partial, text)?;
+    fs::rename(&partial, path)
+}
+
+/// Formats a count compactly: `950`, `12.3k`, `4.1M`.
+pub fn compact(num: usize) -> String {
+    let n = num as f64;
+    if num >= 1_000_000_000 {
+        format!("{:.1}G", n / 1e9)
+    } else if num >= 1_000_000 {
+        format!("{:.1}M", n / 1e6)
+    } else if num >= 1_000 {
+        format!("{:.1}k", n / 1e3)
+    } else {
+        num.to_string()
+    }
+}
+
+/// Expands `{lines}`, `{words}`, `{chars}`, `{bytes}`, `{files}` and
+/// `{reading_time}` in `template`. Unknown placeholders are left untouched.
+pub fn render(template: &str, counts: &Counts, files: usize, config: &Config) -> String {
+    template
+        .replace("{lines}", &compact(counts.lines))
+        .replace("{words}", &compact(counts.words))
+        .replace("{chars}", &compact(counts.chars))
+        .replace("{bytes}", &compact(counts.bytes))
+        .replace("{files}", &compact(files))
+        .replace("{reading_time}", &config.reading_time(counts.words))
+}
+
+fn modified_nanos(metadata: &fs::Metadata) -> u128 {
+    metadata
+        .modified()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos())
+}
+
+/// Prints the status line for the configured inputs. Returns the exit code.
+pub fn run(config: &Config, template: &str) -> i32 {
+    let mut total = Counts::default();
+    let mut files = 0;
+
+    if config.files.is_empty() && config.remotes.is_empty() {
+        match count_input(io::stdin().lock(), config) {
+            Ok(counts) => total.add(&counts),
+            Err(e) => {
+                eprintln!("rwc: {}", e);
+                return 1;
+            }
+        }
+        output::print(&render(template, &total, 1, config));
+        return 0;
+    }
+
+    let cache_file = cache_path();
+    let mut cache = load_cache(&cache_file);
+    let mut dirty = false;
+
+    let mut budget = walk::Budget::new(config.limits);
+    for operand in &config.files {
+        if budget.is_exhausted() {
+            break;
+        }
+        let paths = match walk::files_within(Path::new(operand), &mut budget) {
+            Ok(paths) => paths,
+            Err(e) => {
+                eprintln!("rwc: {}: {}", operand, e);
+                return 1;
+            }
+        };
+        for path in paths {
+            if config.no_generated && is_generated(&path) {
+                continue;
+            }
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let key = fs::canonicalize(&path).unwrap_or(path);
+            let modified = modified_nanos(&metadata);
+            let len = metadata.len();
+            match cache.get(&key) {
+                Some(entry) if entry.modified == modified && entry.len == len => {
+                    total.add(&entry.counts);
+                }
+                _ => {
+                    // Binary and non-UTF-8 files are skipped rather than
+                    // failing a prompt render.
+                    let Ok(counts) = fs::File::open(&key).and_then(|f| count_named(f, Some(&key), config))
+                    else {
+                        continue;
+                    };
+                    total.add(&counts);
+                    cache.insert(key, CacheEntry { modified, len, counts });
+                    dirty = true;
+                }
+            }
+            files += 1;
+        }
+    }
+
+    for spec in &config.remotes {
+        match remote::count_remote(spec, config) {
+            Ok(counts) => {
+                total.add(&counts);
+                files += 1;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        }
+    }
+
+    // A prompt has no room for the partial-scan warning; the limit just
+    // keeps a status line pointed at a huge tree from stalling the shell.
+    if dirty {
+        // The cache is only an accelerator; failing to write it is not fatal.
+        let _ = save_cache(&cache_file, &cache);
+    }
+    output::print(&render(template, &total, files, config));
+    0
+}
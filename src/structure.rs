@@ -0,0 +1,216 @@
+//! `--structure`: how an extracted document is put together, reported next
+//! to the counts of its text.
+//!
+//! PDFs report their pages and the paragraphs on them, as laid out by
+//! `pdftotext` (a form feed after every page, blank lines between
+//! paragraphs). HTML reports its text nodes and how deeply elements nest.
+//! Other types have no structure to report.
+
+use std::io;
+use std::path::Path;
+
+use crate::filetype::{self, FileType};
+use crate::json::{self, Value};
+use crate::transform::{self, Transform};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Structure {
+    Pdf { pages: usize, paragraphs: usize },
+    Html { text_nodes: usize, max_depth: usize },
+}
+
+/// Elements that never have content or an end tag.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param",
+    "source", "track", "wbr",
+];
+
+/// Elements whose end tag may be left out before a sibling of the same kind
+/// (`<li>one<li>two`).
+const IMPLIED_END: [&str; 8] = ["dd", "dt", "li", "option", "p", "td", "th", "tr"];
+
+/// Elements whose content is raw text rather than markup.
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["script", "style", "textarea", "title"];
+
+impl Structure {
+    pub fn paragraphs_per_page(&self) -> Option<f64> {
+        match *self {
+            Structure::Pdf { pages: 0, .. } => Some(0.0),
+            Structure::Pdf { pages, paragraphs } => Some(paragraphs as f64 / pages as f64),
+            Structure::Html { .. } => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match *self {
+            Structure::Pdf { pages, paragraphs } => {
+                let per_page = self.paragraphs_per_page().unwrap_or(0.0);
+                json::object([
+                    ("type", "pdf".into()),
+                    ("pages", pages.into()),
+                    ("paragraphs", paragraphs.into()),
+                    ("paragraphs_per_page", Value::Number((per_page * 100.0).round() / 100.0)),
+                ])
+            }
+            Structure::Html { text_nodes, max_depth } => json::object([
+                ("type", "html".into()),
+                ("text_nodes", text_nodes.into()),
+                ("max_depth", max_depth.into()),
+            ]),
+        }
+    }
+
+    /// Labelled values, for the `--plain` record.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        match *self {
+            Structure::Pdf { pages, paragraphs } => vec![
+                ("pages", pages.to_string()),
+                ("paragraphs", paragraphs.to_string()),
+                (
+                    "paragraphs per page",
+                    format!("{:.1}", self.paragraphs_per_page().unwrap_or(0.0)),
+                ),
+            ],
+            Structure::Html { text_nodes, max_depth } => vec![
+                ("text nodes", text_nodes.to_string()),
+                ("deepest element", max_depth.to_string()),
+            ],
+        }
+    }
+
+    /// One-line summary, shown under the file's row in the table.
+    pub fn summary(&self) -> String {
+        match *self {
+            Structure::Pdf { pages, paragraphs } => format!(
+                "{} pages, {} paragraphs ({:.1} per page)",
+                pages,
+                paragraphs,
+                self.paragraphs_per_page().unwrap_or(0.0)
+            ),
+            Structure::Html { text_nodes, max_depth } => {
+                format!("{} text nodes, elements nested {} deep", text_nodes, max_depth)
+            }
+        }
+    }
+}
+
+/// Like `transform::apply`, also reporting the structure of the document
+/// the leading `--auto`/`--type` step extracts from.
+pub fn apply(
+    steps: &[Transform],
+    path: Option<&Path>,
+    data: Vec<u8>,
+    lossy: bool,
+) -> io::Result<(String, Option<Structure>)> {
+    let Some((&Transform::Auto(forced), rest)) = steps.split_first() else {
+        return Ok((transform::apply(steps, path, data, lossy)?, None));
+    };
+    let (kind, data) = filetype::peel(path, data, forced)?;
+    let mut structure = match kind {
+        FileType::Html => Some(html(&String::from_utf8_lossy(&data))),
+        _ => None,
+    };
+    let text = filetype::extract_as(kind, data)?;
+    if kind == FileType::Pdf {
+        structure = Some(pdf(&String::from_utf8_lossy(&text)));
+    }
+    Ok((transform::apply(rest, path, text, lossy)?, structure))
+}
+
+/// Pages and paragraphs of `pdftotext` output.
+fn pdf(text: &str) -> Structure {
+    let mut pages = text.split('\x0c').collect::<Vec<_>>();
+    // Every page ends with a form feed, leaving an empty piece at the end.
+    if pages.last().is_some_and(|page| page.trim().is_empty()) {
+        pages.pop();
+    }
+    let paragraphs = pages
+        .iter()
+        .map(|page| {
+            let mut count = 0;
+            let mut in_paragraph = false;
+            for line in page.lines() {
+                let blank = line.trim().is_empty();
+                if !blank && !in_paragraph {
+                    count += 1;
+                }
+                in_paragraph = !blank;
+            }
+            count
+        })
+        .sum();
+    Structure::Pdf { pages: pages.len(), paragraphs }
+}
+
+/// Text nodes and element nesting of an HTML document. Text nodes that are
+/// only whitespace, and the content of script and style elements, do not
+/// count; unclosed elements are closed by the end tag of an ancestor.
+fn html(html: &str) -> Structure {
+    let lower = html.to_ascii_lowercase();
+    let mut open: Vec<String> = Vec::new();
+    let mut text_nodes = 0;
+    let mut max_depth = 0;
+    let mut pos = 0;
+    while pos < html.len() {
+        let Some(lt) = html[pos..].find('<').map(|at| pos + at) else {
+            if !html[pos..].trim().is_empty() {
+                text_nodes += 1;
+            }
+            break;
+        };
+        if !html[pos..lt].trim().is_empty() {
+            text_nodes += 1;
+        }
+        let tag = &html[lt..];
+        if tag.starts_with("<!--") {
+            pos = tag.find("-->").map_or(html.len(), |end| lt + end + 3);
+            continue;
+        }
+        let Some(gt) = tag.find('>') else {
+            break;
+        };
+        pos = lt + gt + 1;
+        let inner = &tag[1..gt];
+        if inner.starts_with('!') || inner.starts_with('?') {
+            continue;
+        }
+        let closing = inner.starts_with('/');
+        let name = inner
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if name.is_empty() {
+            continue;
+        }
+        if closing {
+            if let Some(at) = open.iter().rposition(|element| *element == name) {
+                open.truncate(at);
+            }
+            continue;
+        }
+        if IMPLIED_END.contains(&name.as_str()) && open.last() == Some(&name) {
+            open.pop();
+        }
+        max_depth = max_depth.max(open.len() + 1);
+        if VOID_ELEMENTS.contains(&name.as_str()) || inner.ends_with('/') {
+            continue;
+        }
+        if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            // Skip to the end tag; the text of a title or textarea is a
+            // text node, scripts and styles are not.
+            let end = lower[pos..]
+                .find(&format!("</{}", name))
+                .map_or(html.len(), |at| pos + at);
+            let text = &html[pos..end];
+            if matches!(name.as_str(), "title" | "textarea") && !text.trim().is_empty() {
+                text_nodes += 1;
+            }
+            pos = html[end..].find('>').map_or(html.len(), |gt| end + gt + 1);
+            continue;
+        }
+        open.push(name);
+    }
+    Structure::Html { text_nodes, max_depth }
+}
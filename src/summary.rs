@@ -0,0 +1,123 @@
+//! `--summary`: min, max, mean and median of each count across the files,
+//! printed after the totals.
+//!
+//! In the table the mean and median are rounded to whole numbers so they
+//! line up with the other rows; JSON keeps two decimals.
+
+use crate::json::Value;
+use crate::{Config, Counts};
+
+/// The per-file values of each basic count, in table column order.
+#[derive(Default)]
+pub struct Summary {
+    files: Vec<[usize; 9]>,
+}
+
+/// What a summary row reports, and its label.
+const STATISTICS: [&str; 4] = ["min", "max", "mean", "median"];
+
+fn values(counts: &Counts) -> [usize; 9] {
+    [
+        counts.lines,
+        counts.words,
+        counts.chars,
+        counts.bytes,
+        counts.max_line_length,
+        counts.max_display_width,
+        counts.paragraphs,
+        counts.blank_lines,
+        counts.sentences,
+    ]
+}
+
+fn counts_from(values: [usize; 9]) -> Counts {
+    let [lines, words, chars, bytes, max_line_length, max_display_width, paragraphs, blank_lines, sentences] =
+        values;
+    Counts {
+        lines,
+        words,
+        chars,
+        bytes,
+        max_line_length,
+        max_display_width,
+        paragraphs,
+        blank_lines,
+        sentences,
+        ..Counts::default()
+    }
+}
+
+impl Summary {
+    pub fn push(&mut self, counts: &Counts) {
+        self.files.push(values(counts));
+    }
+
+    /// `statistic` of the `column`th count across the files.
+    fn statistic(&self, statistic: &str, column: usize) -> f64 {
+        let mut sorted: Vec<usize> = self.files.iter().map(|values| values[column]).collect();
+        sorted.sort_unstable();
+        let Some(&max) = sorted.last() else {
+            return 0.0;
+        };
+        match statistic {
+            "min" => sorted[0] as f64,
+            "max" => max as f64,
+            "mean" => sorted.iter().sum::<usize>() as f64 / sorted.len() as f64,
+            _ => {
+                let middle = sorted.len() / 2;
+                if sorted.len() % 2 == 1 {
+                    sorted[middle] as f64
+                } else {
+                    (sorted[middle - 1] + sorted[middle]) as f64 / 2.0
+                }
+            }
+        }
+    }
+
+    /// The summary rows, labelled, as counts for the table.
+    pub fn rows(&self) -> Vec<(&'static str, Counts)> {
+        STATISTICS
+            .iter()
+            .map(|&statistic| {
+                let values = std::array::from_fn(|column| {
+                    self.statistic(statistic, column).round() as usize
+                });
+                (statistic, counts_from(values))
+            })
+            .collect()
+    }
+
+    /// `{"min": {"lines": ..}, ...}` with the counts `config` shows.
+    pub fn to_json(&self, config: &Config) -> Value {
+        let shown = [
+            (config.show_lines, "lines"),
+            (config.show_words, "words"),
+            (config.show_chars, "chars"),
+            (config.show_bytes, "bytes"),
+            (config.show_max_line_length, "max_line_length"),
+            (config.show_max_display_width, "max_display_width"),
+            (config.show_paragraphs, "paragraphs"),
+            (config.show_blank_lines, "blank_lines"),
+            (config.show_all, "sentences"),
+        ];
+        let statistics = STATISTICS
+            .iter()
+            .map(|&statistic| {
+                let members = shown
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (shown, _))| *shown)
+                    .map(|(column, &(_, key))| {
+                        let value = self.statistic(statistic, column);
+                        (
+                            key.to_string(),
+                            Value::Number((value * 100.0).round() / 100.0),
+                        )
+                    })
+                    .collect();
+                (statistic.to_string(), Value::Object(members))
+            })
+            .collect();
+        Value::Object(statistics)
+    }
+}
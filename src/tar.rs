@@ -0,0 +1,323 @@
+//! Streaming reader for ustar/GNU/pax tar archives.
+
+use std::io::{self, Read};
+
+const BLOCK: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Directory,
+    Other,
+}
+
+#[derive(Debug)]
+pub struct Header {
+    pub path: String,
+    pub size: u64,
+    pub kind: EntryKind,
+}
+
+/// Returns true when `block` looks like the first header of a tar archive.
+pub fn is_tar_header(block: &[u8]) -> bool {
+    block.len() >= BLOCK && &block[257..262] == b"ustar"
+}
+
+/// Calls `f` for every entry in the archive with a reader over its data.
+///
+/// Whatever `f` leaves unread is skipped, so callers may inspect only a prefix
+/// of each entry. GNU long names and pax `path`/`size` records are applied to
+/// the entry that follows them.
+pub fn for_each_entry<R, F>(mut reader: R, mut f: F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(&Header, &mut dyn Read) -> io::Result<()>,
+{
+    let mut block = [0u8; BLOCK];
+    let mut long_name: Option<String> = None;
+    let mut pax_size: Option<u64> = None;
+
+    while read_block(&mut reader, &mut block)? {
+        if block.iter().all(|&b| b == 0) {
+            break;
+        }
+
+        let mut size = parse_size(&block[124..136])?;
+        let typeflag = block[156];
+
+        if matches!(typeflag, b'L' | b'x' | b'g') {
+            let mut data = Vec::new();
+            (&mut reader).take(size).read_to_end(&mut data)?;
+            skip(&mut reader, padding(size))?;
+            match typeflag {
+                b'L' => long_name = Some(cstr(&data)),
+                b'x' => {
+                    for (key, value) in pax_records(&data) {
+                        match key.as_str() {
+                            "path" => long_name = Some(value),
+                            "size" => pax_size = value.parse().ok(),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if let Some(s) = pax_size.take() {
+            size = s;
+        }
+        let path = long_name.take().unwrap_or_else(|| {
+            let name = cstr(&block[0..100]);
+            let prefix = if is_tar_header(&block) {
+                cstr(&block[345..500])
+            } else {
+                String::new()
+            };
+            if prefix.is_empty() {
+                name
+            } else {
+                format!("{}/{}", prefix, name)
+            }
+        });
+        let kind = match typeflag {
+            b'0' | b'\0' | b'7' => EntryKind::File,
+            b'5' => EntryKind::Directory,
+            _ => EntryKind::Other,
+        };
+        // Only regular files carry data; links and devices report a size of 0.
+        let data_size = if kind == EntryKind::Other { 0 } else { size };
+
+        let header = Header {
+            path,
+            size: data_size,
+            kind,
+        };
+        let mut data = (&mut reader).take(data_size);
+        f(&header, &mut data)?;
+        io::copy(&mut data, &mut io::sink())?;
+        if data.limit() > 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated tar entry"));
+        }
+        skip(&mut reader, padding(data_size))?;
+    }
+
+    Ok(())
+}
+
+/// Reads one header block, returning false on a clean end of stream.
+fn read_block<R: Read>(reader: &mut R, block: &mut [u8; BLOCK]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < BLOCK {
+        match reader.read(&mut block[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated tar header")),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+fn skip<R: Read>(reader: &mut R, n: u64) -> io::Result<()> {
+    io::copy(&mut reader.take(n), &mut io::sink()).map(|_| ())
+}
+
+fn padding(size: u64) -> u64 {
+    (BLOCK as u64 - size % BLOCK as u64) % BLOCK as u64
+}
+
+fn cstr(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn parse_size(field: &[u8]) -> io::Result<u64> {
+    // GNU base-256 encoding for sizes that do not fit in 11 octal digits.
+    if field[0] & 0x80 != 0 {
+        let mut size = u64::from(field[0] & 0x7f);
+        for &b in &field[1..] {
+            size = (size << 8) | u64::from(b);
+        }
+        return Ok(size);
+    }
+    let text = cstr(field);
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid size in tar header"))
+}
+
+/// Splits pax extended header data into `key=value` records.
+fn pax_records(data: &[u8]) -> Vec<(String, String)> {
+    let mut records = Vec::new();
+    let mut rest = data;
+    while let Some(space) = rest.iter().position(|&b| b == b' ') {
+        let len: usize = match std::str::from_utf8(&rest[..space]).ok().and_then(|l| l.parse().ok()) {
+            Some(len) if len > space && len <= rest.len() => len,
+            _ => break,
+        };
+        let record = String::from_utf8_lossy(&rest[space + 1..len]);
+        if let Some((key, value)) = record.trim_end_matches('\n').split_once('=') {
+            records.push((key.to_string(), value.to_string()));
+        }
+        rest = &rest[len..];
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(name: &str, size: &[u8], typeflag: u8) -> Vec<u8> {
+        let mut block = vec![0u8; BLOCK];
+        block[..name.len()].copy_from_slice(name.as_bytes());
+        block[124..124 + size.len()].copy_from_slice(size);
+        block[156] = typeflag;
+        block[257..263].copy_from_slice(b"ustar\0");
+        block
+    }
+
+    fn octal(size: usize) -> Vec<u8> {
+        format!("{:011o}\0", size).into_bytes()
+    }
+
+    /// An entry's header followed by its data padded to whole blocks.
+    fn entry(name: &str, typeflag: u8, data: &[u8]) -> Vec<u8> {
+        let mut out = header(name, &octal(data.len()), typeflag);
+        out.extend_from_slice(data);
+        out.resize(out.len() + padding(data.len() as u64) as usize, 0);
+        out
+    }
+
+    fn entries(archive: &[u8]) -> io::Result<Vec<(String, EntryKind, String)>> {
+        let mut seen = Vec::new();
+        for_each_entry(archive, |header, data| {
+            let mut text = String::new();
+            data.read_to_string(&mut text)?;
+            seen.push((header.path.clone(), header.kind, text));
+            Ok(())
+        })?;
+        Ok(seen)
+    }
+
+    #[test]
+    fn files_directories_and_links() {
+        let mut archive = entry("docs/", b'5', b"");
+        archive.extend(entry("docs/a.txt", b'0', b"one\n"));
+        archive.extend(header("docs/link", &octal(0), b'2'));
+        archive.extend(entry("b.txt", b'\0', &b"x".repeat(BLOCK + 1)));
+        archive.extend([0; 2 * BLOCK]);
+        assert!(is_tar_header(&archive));
+        let seen = entries(&archive).unwrap();
+        assert_eq!(seen.len(), 4);
+        assert_eq!(
+            seen[0],
+            ("docs/".to_string(), EntryKind::Directory, String::new())
+        );
+        assert_eq!(
+            seen[1],
+            (
+                "docs/a.txt".to_string(),
+                EntryKind::File,
+                "one\n".to_string()
+            )
+        );
+        assert_eq!(seen[2].1, EntryKind::Other);
+        assert_eq!(seen[3].2.len(), BLOCK + 1);
+    }
+
+    #[test]
+    fn unread_data_is_skipped() {
+        let mut archive = entry("a.txt", b'0', &b"a".repeat(1000));
+        archive.extend(entry("b.txt", b'0', b"b"));
+        // No end-of-archive blocks: the end of the stream ends it too.
+        let mut names = Vec::new();
+        for_each_entry(&archive[..], |header, data| {
+            let mut first = [0u8; 1];
+            data.read_exact(&mut first)?;
+            names.push((header.path.clone(), header.size, first[0]));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            names,
+            [
+                ("a.txt".to_string(), 1000, b'a'),
+                ("b.txt".to_string(), 1, b'b')
+            ]
+        );
+    }
+
+    #[test]
+    fn long_names_pax_records_and_prefixes() {
+        let long = "d/".repeat(80) + "long.txt";
+        let mut archive = entry("././@LongLink", b'L', format!("{}\0", long).as_bytes());
+        archive.extend(entry("truncated", b'0', b"1"));
+        let record = "path=pax/name.txt\n";
+        let record = format!("{} {}", record.len() + 3, record);
+        archive.extend(entry(
+            "PaxHeader",
+            b'x',
+            format!("{}10 size=2\n", record).as_bytes(),
+        ));
+        // The pax size overrides the header's.
+        let mut pax = header("ignored", &octal(0), b'0');
+        pax.extend(b"hi");
+        pax.resize(2 * BLOCK, 0);
+        archive.extend(pax);
+        let mut prefixed = entry("c.txt", b'0', b"");
+        prefixed[345..351].copy_from_slice(b"prefix");
+        archive.extend(prefixed);
+
+        let seen = entries(&archive).unwrap();
+        let paths: Vec<&str> = seen.iter().map(|(path, _, _)| path.as_str()).collect();
+        assert_eq!(paths, [long.as_str(), "pax/name.txt", "prefix/c.txt"]);
+        assert_eq!(seen[1].2, "hi");
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size(b"00000000017 ").unwrap(), 15);
+        assert_eq!(parse_size(b"     17\0    ").unwrap(), 15);
+        assert_eq!(parse_size(&[0; 12]).unwrap(), 0);
+        let mut base256 = [0u8; 12];
+        base256[0] = 0x80;
+        base256[7] = 0x01;
+        assert_eq!(parse_size(&base256).unwrap(), 1 << 32);
+        assert_eq!(parse_size(b"77777777777\0").unwrap(), 0o77777777777);
+        let error = parse_size(b"0000000009x\0").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn malformed_archives_are_errors() {
+        let archive = entry("a.txt", b'0', b"some text");
+        let error = entries(&archive[..100]).unwrap_err();
+        assert_eq!(error.to_string(), "truncated tar header");
+        let error = entries(&archive[..BLOCK + 4]).unwrap_err();
+        assert_eq!(error.to_string(), "truncated tar entry");
+        let archive = header("a.txt", b"nonsense\0", b'0');
+        assert_eq!(
+            entries(&archive).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert!(!is_tar_header(&archive[..300]));
+        assert!(!is_tar_header(&[0; BLOCK]));
+        // Records whose lengths do not add up are ignored.
+        assert!(pax_records(b"99 path=x\n").is_empty());
+        assert!(pax_records(b"x path=x\n").is_empty());
+        assert_eq!(
+            pax_records(b"6 a=b\n8 k=v=w\n"),
+            [
+                ("a".to_string(), "b".to_string()),
+                ("k".to_string(), "v=w".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! `--warn-above` and `--error-above`: count budgets that are reported, not
+//! enforced.
+//!
+//! Each file gets a status, the worst severity any of its counts reaches:
+//! a table cell over a limit is colored (yellow for a warning, red for an
+//! error) when stdout is a terminal and `NO_COLOR` is unset, and a status
+//! column says `ok`, `warn` or `error` in every output format. The exit code
+//! is not affected. Totals are not checked, as budgets are per file.
+
+use std::io::{self, IsTerminal};
+
+use crate::metric::Metric;
+use crate::Counts;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    #[default]
+    Ok,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Ok => "ok",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Thresholds {
+    /// Limits in the order given; a count over one is at its severity.
+    limits: Vec<(Metric, Severity, usize)>,
+    /// Color cells over a limit.
+    color: bool,
+}
+
+/// Parses a `METRIC=N` limit, such as `words=5000`.
+fn parse_limit(text: &str) -> Result<(Metric, usize), String> {
+    let (metric, limit) = text
+        .split_once('=')
+        .ok_or_else(|| format!("invalid limit '{}' (expected METRIC=N, e.g. words=5000)", text))?;
+    let metric = Metric::parse(metric.trim()).ok_or_else(|| {
+        format!("invalid metric '{}' (expected lines, words, chars or bytes)", metric)
+    })?;
+    let limit = limit
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid limit '{}' for {}", limit, metric.name()))?;
+    Ok((metric, limit))
+}
+
+impl Thresholds {
+    /// Adds a `METRIC=N` limit at `severity`.
+    pub fn add(&mut self, severity: Severity, text: &str) -> Result<(), String> {
+        let (metric, limit) = parse_limit(text)?;
+        self.limits.push((metric, severity, limit));
+        self.color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    /// The severity `value` of `metric` reaches.
+    pub fn severity(&self, metric: Metric, value: usize) -> Severity {
+        self.limits
+            .iter()
+            .filter(|&&(m, _, limit)| m == metric && value > limit)
+            .map(|&(_, severity, _)| severity)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The worst severity any count in `counts` reaches.
+    pub fn status(&self, counts: &Counts) -> Severity {
+        [Metric::Lines, Metric::Words, Metric::Chars, Metric::Bytes]
+            .into_iter()
+            .map(|metric| self.severity(metric, metric.of(counts)))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// `cell` colored for the severity of `value`, when coloring.
+    pub fn paint(&self, cell: &str, metric: Metric, value: usize) -> String {
+        let code = match self.severity(metric, value) {
+            _ if !self.color => return cell.to_string(),
+            Severity::Ok => return cell.to_string(),
+            Severity::Warn => "33",
+            Severity::Error => "31",
+        };
+        format!("\x1b[{}m{}\x1b[0m", code, cell)
+    }
+}
@@ -0,0 +1,278 @@
+//! Timestamp formatting and duration parsing without external crates.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Parses durations such as `500ms`, `60s`, `5m`, `1h` or `1d`; a bare
+/// number is taken as seconds.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let value: f64 = number.parse().ok()?;
+    let seconds = match unit {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        "d" => value * 86400.0,
+        _ => return None,
+    };
+    if seconds > 0.0 {
+        Duration::try_from_secs_f64(seconds).ok()
+    } else {
+        None
+    }
+}
+
+/// Formats `time` as an RFC 3339 UTC timestamp with millisecond precision.
+pub fn format_rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs() as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+    let rem = secs.rem_euclid(86400);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// The UTC calendar year `time` falls in.
+pub fn year_of(time: SystemTime) -> i64 {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+    civil_from_days(secs.div_euclid(86400)).0
+}
+
+/// Converts days since 1970-01-01 to a (year, month, day) civil date.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Parses `--since`/`--until` style timestamps.
+///
+/// Accepts `now`, `today`, `yesterday`, relative offsets (`-2h`, `2h ago`),
+/// `YYYY-MM-DD`, `YYYY-MM-DD HH:MM[:SS]` and RFC 3339. Times without an
+/// explicit offset are interpreted as UTC.
+pub fn parse_timestamp(text: &str) -> Option<SystemTime> {
+    let text = text.trim();
+    let now = SystemTime::now();
+    let today = || {
+        let secs = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        Some(UNIX_EPOCH + Duration::from_secs(secs - secs % 86400))
+    };
+    match text {
+        "now" => return Some(now),
+        "today" => return today(),
+        "yesterday" => return today()?.checked_sub(Duration::from_secs(86400)),
+        _ => {}
+    }
+    if let Some(ago) = text.strip_prefix('-').or_else(|| text.strip_suffix(" ago")) {
+        return now.checked_sub(parse_duration(ago.trim())?);
+    }
+
+    let date_len = text.len().min(10);
+    if !text.is_char_boundary(date_len) {
+        return None;
+    }
+    let (date, rest) = text.split_at(date_len);
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    let mut secs = days_from_civil(year, month, day) * 86400;
+
+    let rest = rest.trim_start_matches(['T', 't', ' ']);
+    if !rest.is_empty() {
+        let offset_at = rest
+            .find(['Z', 'z', '+', '-'])
+            .unwrap_or(rest.len());
+        let (clock, offset) = rest.split_at(offset_at);
+        let mut fields = clock.split(':');
+        let hour: i64 = fields.next()?.parse().ok()?;
+        let minute: i64 = fields.next()?.parse().ok()?;
+        let second: f64 = fields.next().map_or(Some(0.0), |s| s.parse().ok())?;
+        // 60 for a leap second.
+        if !(0..24).contains(&hour)
+            || !(0..60).contains(&minute)
+            || !(0.0..61.0).contains(&second)
+        {
+            return None;
+        }
+        secs += hour * 3600 + minute * 60 + second as i64;
+        if let Some(sign) = offset.chars().next().filter(|c| *c == '+' || *c == '-') {
+            let (h, m) = offset[1..].split_once(':').unwrap_or((&offset[1..], "0"));
+            let delta = h.parse::<i64>().ok()? * 3600 + m.parse::<i64>().ok()? * 60;
+            secs -= if sign == '+' { delta } else { -delta };
+        }
+    }
+
+    if secs >= 0 {
+        Some(UNIX_EPOCH + Duration::from_secs(secs as u64))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(secs.unsigned_abs()))
+    }
+}
+
+/// The number of days in `month` (1-12) of `year`.
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Converts a civil date to days since 1970-01-01.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1.5h"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("2d"), Some(Duration::from_secs(172_800)));
+        for text in [
+            "",
+            "0",
+            "0s",
+            "-5s",
+            "5x",
+            "m",
+            "1.2.3s",
+            "5 m",
+            "99999999999999999999d",
+        ] {
+            assert_eq!(parse_duration(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn civil_dates_round_trip() {
+        for (days, date) in [
+            (0, (1970, 1, 1)),
+            (-1, (1969, 12, 31)),
+            (11_016, (2000, 2, 29)),
+            (19_782, (2024, 2, 29)),
+            (-719_468, (0, 3, 1)),
+        ] {
+            assert_eq!(civil_from_days(days), date, "{}", days);
+            assert_eq!(days_from_civil(date.0, date.1, date.2), days, "{:?}", date);
+        }
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(2023, 4), 30);
+    }
+
+    #[test]
+    fn rfc3339_output() {
+        assert_eq!(format_rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        let time = at(951_782_400) + Duration::from_millis(1_999);
+        assert_eq!(format_rfc3339(time), "2000-02-29T00:00:01.999Z");
+        assert_eq!(year_of(time), 2000);
+        // Before the epoch is clamped to it.
+        assert_eq!(
+            format_rfc3339(UNIX_EPOCH - Duration::from_secs(1)),
+            "1970-01-01T00:00:00.000Z"
+        );
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(parse_timestamp("2000-02-29"), Some(at(951_782_400)));
+        assert_eq!(
+            parse_timestamp(" 2000-02-29 01:02 "),
+            Some(at(951_782_400 + 3720))
+        );
+        assert_eq!(
+            parse_timestamp("2000-02-29T01:02:03Z"),
+            Some(at(951_782_400 + 3723))
+        );
+        assert_eq!(
+            parse_timestamp("2000-02-29t01:02:03.9z"),
+            Some(at(951_782_400 + 3723))
+        );
+        assert_eq!(
+            parse_timestamp("2000-02-29T05:30:00+05:30"),
+            Some(at(951_782_400))
+        );
+        assert_eq!(
+            parse_timestamp("2000-02-28T23:00:00-01"),
+            Some(at(951_782_400))
+        );
+        assert_eq!(
+            parse_timestamp("1969-12-31"),
+            Some(UNIX_EPOCH - Duration::from_secs(86400))
+        );
+        let hour_ago = parse_timestamp("1h ago").unwrap();
+        let now = SystemTime::now();
+        assert!(hour_ago <= now - Duration::from_secs(3600));
+        assert!(hour_ago > now - Duration::from_secs(3660));
+        assert_eq!(parse_timestamp("-1h").map(|t| t <= now), Some(true));
+        let today = parse_timestamp("today").unwrap();
+        let yesterday = parse_timestamp("yesterday").unwrap();
+        assert_eq!(
+            today.duration_since(yesterday).ok(),
+            Some(Duration::from_secs(86400))
+        );
+    }
+
+    #[test]
+    fn malformed_timestamps() {
+        for text in [
+            "",
+            "soon",
+            "2023-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-00-10",
+            "2024-01-01T24:00",
+            "2024-01-01T12:60",
+            "2024-01-01T12:00:61",
+            "2024-01-01T12",
+            "2024-01-01T12:00+xx",
+            "2024-01-01 noon",
+            "aaaaaaaaaé",
+            "2024-01-0é",
+            "-forever",
+            "10 parsecs ago",
+        ] {
+            assert_eq!(parse_timestamp(text), None, "{:?}", text);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! `rwc toc`: a Markdown heading outline annotated with word counts, and
+//! `--outline`, the same for every input as a tree.
+
+use std::fs;
+use std::io::{self, Read};
+
+use crate::filter::markdown_heading;
+use crate::json::{self, Value};
+use crate::{cli, output, print_json, print_plain, Config};
+
+struct Section {
+    level: usize,
+    title: String,
+    /// Words directly under this heading, before the next heading.
+    own: usize,
+    /// Words including all subsections.
+    total: usize,
+}
+
+/// Entry point for `rwc toc`.
+pub fn run(args: &[String]) -> i32 {
+    let mut json = false;
+    let mut depth = 6;
+    let mut files = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--depth" if i + 1 >= args.len() => {
+                eprintln!("rwc toc: option '--depth' requires an argument");
+                return 1;
+            }
+            "--depth" => {
+                i += 1;
+                match args[i].parse() {
+                    Ok(n) if (1..=6).contains(&n) => depth = n,
+                    _ => {
+                        eprintln!("rwc toc: invalid depth '{}' (expected 1-6)", args[i]);
+                        return 1;
+                    }
+                }
+            }
+            "--json" => json = true,
+            "--help" => {
+                println!("Usage: rwc toc [--depth N] [--json] FILE.md");
+                println!();
+                println!("Prints the heading outline of a Markdown document with the words in");
+                println!("each section (including its subsections) and its share of the whole.");
+                println!();
+                println!("OPTIONS:");
+                println!("    --depth N       Only list headings up to level N (default 6)");
+                println!("    --json          Output in JSON format");
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option("rwc toc", a, &["--depth", "--json", "--help"]);
+                return 1;
+            }
+            a => files.push(a.to_string()),
+        }
+        i += 1;
+    }
+    let [file] = files.as_slice() else {
+        eprintln!("rwc toc: expected exactly one Markdown file");
+        return 1;
+    };
+
+    let text = match fs::read_to_string(file) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("rwc toc: {}: {}", file, e);
+            return 1;
+        }
+    };
+    let (preamble, sections) = outline(&text);
+    let document = preamble + sections.iter().map(|s| s.own).sum::<usize>();
+    let percent = |words: usize| {
+        if document == 0 {
+            0.0
+        } else {
+            words as f64 * 100.0 / document as f64
+        }
+    };
+    let shown = sections.iter().filter(|s| s.level <= depth);
+
+    if json {
+        let items = shown
+            .map(|s| {
+                json::object([
+                    ("level", s.level.into()),
+                    ("title", s.title.as_str().into()),
+                    ("words", s.total.into()),
+                    ("own_words", s.own.into()),
+                    ("percent", Value::Number((percent(s.total) * 10.0).round() / 10.0)),
+                ])
+            })
+            .collect();
+        let doc = json::object([
+            ("file", file.as_str().into()),
+            ("words", document.into()),
+            ("preamble_words", preamble.into()),
+            ("sections", Value::Array(items)),
+        ]);
+        println!("{}", doc);
+        return 0;
+    }
+
+    let top = sections.iter().map(|s| s.level).min().unwrap_or(1);
+    if preamble > 0 {
+        println!("- (preamble) ({} words, {:.1}%)", preamble, percent(preamble));
+    }
+    for section in shown {
+        println!(
+            "{}- {} ({} words, {:.1}%)",
+            "  ".repeat(section.level - top.min(section.level)),
+            section.title,
+            section.total,
+            percent(section.total)
+        );
+    }
+    0
+}
+
+/// Entry point for `--outline`: each input's sections as a tree indented by
+/// heading level, or nested in JSON, with the words under each including its
+/// subsections.
+pub fn run_outline(config: &Config) -> i32 {
+    let stdin = ["-".to_string()];
+    let operands = if config.files.is_empty() {
+        &stdin[..]
+    } else {
+        &config.files[..]
+    };
+    let mut files = Vec::new();
+    for operand in operands {
+        let label = if operand == "-" {
+            config.stdin_label()
+        } else {
+            operand.as_str()
+        };
+        let text = if operand == "-" {
+            let mut text = String::new();
+            io::stdin().lock().read_to_string(&mut text).map(|_| text)
+        } else {
+            fs::read_to_string(operand)
+        };
+        match text {
+            Ok(text) => {
+                let (preamble, sections) = outline(&text);
+                if config.show_json {
+                    files.push(outline_json(label, preamble, &sections));
+                } else {
+                    print_outline(label, preamble, &sections, config);
+                }
+            }
+            Err(e) => {
+                eprintln!("rwc: {}: {}", label, e);
+                return 1;
+            }
+        }
+    }
+    if config.show_json {
+        print_json(&json::object([("files", Value::Array(files))]), config);
+    }
+    0
+}
+
+fn print_outline(label: &str, preamble: usize, sections: &[Section], config: &Config) {
+    let words = preamble + sections.iter().map(|s| s.own).sum::<usize>();
+    if config.plain {
+        let mut fields = vec![("file", label.to_string()), ("words", config.format_count(words))];
+        if preamble > 0 {
+            fields.push(("before the first heading", config.format_count(preamble)));
+        }
+        for section in sections {
+            let heading = format!("{} {}", "#".repeat(section.level), section.title);
+            fields.push(("section", format!("{}, {} words", heading, section.total)));
+        }
+        print_plain(&fields);
+        return;
+    }
+    let top = sections.iter().map(|s| s.level).min().unwrap_or(1);
+    let mut lines = vec![format!("{:>8} {}", config.format_count(words), label)];
+    if preamble > 0 {
+        lines.push(format!("{:>8}   (preamble)", config.format_count(preamble)));
+    }
+    for section in sections {
+        lines.push(format!(
+            "{:>8}   {}{}",
+            config.format_count(section.total),
+            "  ".repeat(section.level - top.min(section.level)),
+            section.title
+        ));
+    }
+    output::print(&lines.join("\n"));
+}
+
+/// `{"file", "words", "preamble_words", "sections"}`, each section holding
+/// the sections under it.
+fn outline_json(label: &str, preamble: usize, sections: &[Section]) -> Value {
+    // The sections from `at` up to the next heading at or above `level`,
+    // and where that is.
+    fn nested(sections: &[Section], mut at: usize, level: usize) -> (Vec<Value>, usize) {
+        let mut items = Vec::new();
+        while at < sections.len() && sections[at].level > level {
+            let section = &sections[at];
+            let (children, next) = nested(sections, at + 1, section.level);
+            items.push(json::object([
+                ("level", section.level.into()),
+                ("title", section.title.as_str().into()),
+                ("words", section.total.into()),
+                ("own_words", section.own.into()),
+                ("sections", Value::Array(children)),
+            ]));
+            at = next;
+        }
+        (items, at)
+    }
+    let words = preamble + sections.iter().map(|s| s.own).sum::<usize>();
+    json::object([
+        ("file", label.into()),
+        ("words", words.into()),
+        ("preamble_words", preamble.into()),
+        ("sections", Value::Array(nested(sections, 0, 0).0)),
+    ])
+}
+
+/// Splits `text` into sections at ATX headings outside code fences. Returns
+/// the words before the first heading and the sections in document order.
+fn outline(text: &str) -> (usize, Vec<Section>) {
+    let mut preamble = 0;
+    let mut sections: Vec<Section> = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some(m),
+            (Some(open), Some(m)) if open == m => fence = None,
+            _ => {}
+        }
+        if marker.is_some() {
+            continue;
+        }
+        if fence.is_none() {
+            if let Some((level, title)) = markdown_heading(line) {
+                sections.push(Section {
+                    level,
+                    title: title.to_string(),
+                    own: 0,
+                    total: 0,
+                });
+                continue;
+            }
+        }
+        let words = line.split_whitespace().count();
+        match sections.last_mut() {
+            Some(section) => section.own += words,
+            None => preamble += words,
+        }
+    }
+
+    // A section's total runs until the next heading at the same or a higher level.
+    for i in 0..sections.len() {
+        let level = sections[i].level;
+        let total = sections[i..]
+            .iter()
+            .enumerate()
+            .take_while(|&(j, s)| j == 0 || s.level > level)
+            .map(|(_, s)| s.own)
+            .sum();
+        sections[i].total = total;
+    }
+    (preamble, sections)
+}
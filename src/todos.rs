@@ -0,0 +1,235 @@
+//! `rwc todos`: TODO/FIXME/HACK comment counts, grouped by owner.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::json::{self, Value};
+use crate::{cli, walk};
+
+const TAGS: [&str; 3] = ["TODO", "FIXME", "HACK"];
+
+/// Owner key for markers without a `(name)` tag.
+const UNASSIGNED: &str = "(unassigned)";
+
+/// Strings that start a comment in the languages rwc is commonly run over.
+const COMMENT_LEADERS: &[&str] = &["//", "#", "/*", "--", ";", "<!--", "%", "\"\"\""];
+
+/// Entry point for `rwc todos`.
+pub fn run(args: &[String]) -> i32 {
+    let mut json = false;
+    let mut recursive = false;
+    let mut paths = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "-r" | "--recursive" => recursive = true,
+            "--json" => json = true,
+            "--help" => {
+                print_help();
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option("rwc todos", a, &["--recursive", "--json", "--help"]);
+                return 1;
+            }
+            a => paths.push(a.to_string()),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("rwc todos: no PATH given");
+        eprintln!("Try 'rwc todos --help' for more information.");
+        return 1;
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    for path in &paths {
+        let path = Path::new(path);
+        if path.is_dir() && !recursive {
+            eprintln!("rwc todos: {}: is a directory (use -r)", path.display());
+            return 1;
+        }
+        match walk::files(path) {
+            Ok(found) => files.extend(found),
+            Err(e) => {
+                eprintln!("rwc todos: {}: {}", path.display(), e);
+                return 1;
+            }
+        }
+    }
+
+    let mut report = Report::default();
+    for file in files {
+        match fs::read_to_string(&file) {
+            Ok(text) => report.add(file, &text),
+            // Binary and non-UTF-8 files hold no comments worth counting.
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {}
+            Err(e) => {
+                eprintln!("rwc todos: {}: {}", file.display(), e);
+                return 1;
+            }
+        }
+    }
+
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        report.print();
+    }
+    0
+}
+
+fn print_help() {
+    println!("Usage: rwc todos [-r] [--json] PATH...");
+    println!();
+    println!("Counts TODO, FIXME and HACK comments per file and groups them by owner.");
+    println!("An owner is the name in parentheses right after the tag, as in");
+    println!("'TODO(alice): ...'; other markers are reported as {}.", UNASSIGNED);
+    println!();
+    println!("Only tags inside comments are counted (after //, #, /*, --, ;, <!-- or a");
+    println!("leading *). Files with no markers are not listed.");
+    println!();
+    println!("OPTIONS:");
+    println!("    -r, --recursive Walk directories");
+    println!("    --json          Output in JSON format");
+}
+
+/// One TODO-style marker found in a comment.
+struct Marker<'a> {
+    tag: usize,
+    owner: Option<&'a str>,
+}
+
+/// Finds the markers on `line`. A tag must be a whole word and appear after a
+/// comment leader, so identifiers like `TODO_LIST` or strings in code are
+/// skipped.
+fn markers(line: &str) -> Vec<Marker<'_>> {
+    let comment_start = COMMENT_LEADERS
+        .iter()
+        .filter_map(|leader| line.find(leader))
+        .min()
+        .or_else(|| line.trim_start().starts_with('*').then(|| line.find('*').unwrap_or(0)));
+    let Some(comment_start) = comment_start else {
+        return Vec::new();
+    };
+
+    let comment = &line[comment_start..];
+    let mut found = Vec::new();
+    for (tag, name) in TAGS.iter().enumerate() {
+        for (at, _) in comment.match_indices(name) {
+            let before = comment[..at].chars().next_back();
+            let rest = &comment[at + name.len()..];
+            let after = rest.chars().next();
+            if before.is_some_and(|c| c.is_alphanumeric() || c == '_')
+                || after.is_some_and(|c| c.is_alphanumeric() || c == '_')
+            {
+                continue;
+            }
+            let owner = rest
+                .strip_prefix('(')
+                .and_then(|r| r.split_once(')'))
+                .map(|(owner, _)| owner.trim())
+                .filter(|owner| !owner.is_empty());
+            found.push(Marker { tag, owner });
+        }
+    }
+    found
+}
+
+#[derive(Default)]
+struct Report {
+    /// Per-file counts in TAGS order, for files with at least one marker.
+    files: Vec<(PathBuf, [usize; 3])>,
+    owners: BTreeMap<String, usize>,
+}
+
+impl Report {
+    fn add(&mut self, path: PathBuf, text: &str) {
+        let mut counts = [0; 3];
+        for line in text.lines() {
+            for marker in markers(line) {
+                counts[marker.tag] += 1;
+                *self
+                    .owners
+                    .entry(marker.owner.unwrap_or(UNASSIGNED).to_string())
+                    .or_insert(0) += 1;
+            }
+        }
+        if counts.iter().any(|&n| n > 0) {
+            self.files.push((path, counts));
+        }
+    }
+
+    fn totals(&self) -> [usize; 3] {
+        let mut totals = [0; 3];
+        for (_, counts) in &self.files {
+            for (total, n) in totals.iter_mut().zip(counts) {
+                *total += n;
+            }
+        }
+        totals
+    }
+
+    /// Owners by descending count, then name.
+    fn ranked_owners(&self) -> Vec<(&str, usize)> {
+        let mut owners: Vec<(&str, usize)> =
+            self.owners.iter().map(|(k, &v)| (k.as_str(), v)).collect();
+        owners.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        owners
+    }
+
+    fn print(&self) {
+        println!("{:>8}{:>8}{:>8} FILE", TAGS[0], TAGS[1], TAGS[2]);
+        for (path, counts) in &self.files {
+            println!(
+                "{:>8}{:>8}{:>8} {}",
+                counts[0],
+                counts[1],
+                counts[2],
+                path.display()
+            );
+        }
+        let totals = self.totals();
+        if self.files.len() > 1 {
+            println!("{:>8}{:>8}{:>8} total", totals[0], totals[1], totals[2]);
+        }
+        let owners = self.ranked_owners();
+        if !owners.is_empty() {
+            let width = owners.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+            println!();
+            println!("{:<width$}  COUNT", "OWNER", width = width.max(5));
+            for (owner, count) in owners {
+                println!("{:<width$}  {:>5}", owner, count, width = width.max(5));
+            }
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let tags = |counts: &[usize; 3]| -> Vec<(String, Value)> {
+            TAGS.iter()
+                .zip(counts)
+                .map(|(tag, &n)| (tag.to_ascii_lowercase(), Value::from(n)))
+                .collect()
+        };
+        let files = self
+            .files
+            .iter()
+            .map(|(path, counts)| {
+                let mut members = vec![("file".to_string(), Value::from(path.display().to_string()))];
+                members.extend(tags(counts));
+                Value::Object(members)
+            })
+            .collect();
+        let owners = self
+            .ranked_owners()
+            .into_iter()
+            .map(|(owner, count)| json::object([("owner", owner.into()), ("count", count.into())]))
+            .collect();
+        json::object([
+            ("files", Value::Array(files)),
+            ("total", Value::Object(tags(&self.totals()))),
+            ("owners", Value::Array(owners)),
+        ])
+    }
+}
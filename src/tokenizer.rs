@@ -0,0 +1,286 @@
+//! What counts as a word: the `Tokenizer` trait and the tokenizers rwc ships.
+//!
+//! A tokenizer is handed one line at a time, without its line ending, and
+//! reports the words in it; words never span lines. `--tokenizer` selects a
+//! built-in one by name (see `builtin`), and a program using this library
+//! can implement the trait for its own definition of a word, such as
+//! hyphenated compounds kept whole or identifiers split at case changes.
+
+use crate::regex::Regex;
+
+/// Splits lines into words.
+pub trait Tokenizer: Send + Sync {
+    /// Calls `word` with each word of `line`, in order. `line` holds no
+    /// newline.
+    fn words<'t>(&self, line: &'t str, word: &mut dyn FnMut(&'t str));
+
+    /// The number of words in `line`.
+    fn count(&self, line: &str) -> usize {
+        let mut count = 0;
+        self.words(line, &mut |_| count += 1);
+        count
+    }
+}
+
+/// The number of words `tokenizer` finds in `text`, which may hold many
+/// lines ending in `\n` or `\r\n`.
+pub fn count_words(text: &str, tokenizer: &dyn Tokenizer) -> usize {
+    text.lines().map(|line| tokenizer.count(line)).sum()
+}
+
+/// The tokenizer `--tokenizer` names: `whitespace`, `uax29`, `cjk` or
+/// `regex:PATTERN`.
+pub fn builtin(name: &str) -> Result<Box<dyn Tokenizer>, String> {
+    match name {
+        "whitespace" => Ok(Box::new(Whitespace::Unicode)),
+        "uax29" => Ok(Box::new(Uax29)),
+        "cjk" => Ok(Box::new(Cjk)),
+        _ => match name.strip_prefix("regex:") {
+            Some(pattern) => Ok(Box::new(Pattern::new(pattern)?)),
+            None => Err(format!(
+                "unknown tokenizer '{}' (expected whitespace, uax29, cjk or regex:PATTERN)",
+                name
+            )),
+        },
+    }
+}
+
+/// Words are runs of characters between separators (`--whitespace`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Whitespace {
+    /// Unicode `White_Space`, which includes no-break and ideographic spaces.
+    #[default]
+    Unicode,
+    /// Space, tab, newline, carriage return, form feed and vertical tab.
+    Ascii,
+    /// Exactly the given characters.
+    Custom(Vec<char>),
+}
+
+impl Whitespace {
+    /// Parses `ascii`, `unicode` or `custom:CHARS`, where CHARS may use the
+    /// escapes `\t`, `\n`, `\r`, `\s` (space) and `\\`.
+    pub fn parse(text: &str) -> Result<Whitespace, String> {
+        match text {
+            "unicode" => return Ok(Whitespace::Unicode),
+            "ascii" => return Ok(Whitespace::Ascii),
+            _ => {}
+        }
+        let Some(chars) = text.strip_prefix("custom:") else {
+            return Err(format!(
+                "unknown whitespace '{}' (expected ascii, unicode or custom:CHARS)",
+                text
+            ));
+        };
+        let mut separators = Vec::new();
+        let mut chars = chars.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                separators.push(c);
+                continue;
+            }
+            separators.push(match chars.next() {
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('s') => ' ',
+                Some('\\') => '\\',
+                other => {
+                    return Err(format!(
+                        "invalid escape '\\{}' in --whitespace",
+                        other.map(String::from).unwrap_or_default()
+                    ))
+                }
+            });
+        }
+        if separators.is_empty() {
+            return Err("custom whitespace needs at least one character".to_string());
+        }
+        Ok(Whitespace::Custom(separators))
+    }
+
+    /// Whether `c` separates words.
+    pub fn separates(&self, c: char) -> bool {
+        match self {
+            Whitespace::Unicode => c.is_whitespace(),
+            Whitespace::Ascii => c.is_ascii_whitespace() || c == '\x0b',
+            Whitespace::Custom(separators) => separators.contains(&c),
+        }
+    }
+}
+
+impl Tokenizer for Whitespace {
+    fn words<'t>(&self, line: &'t str, word: &mut dyn FnMut(&'t str)) {
+        line.split(|c| self.separates(c))
+            .filter(|w| !w.is_empty())
+            .for_each(word);
+    }
+}
+
+/// Word segmentation as in Unicode UAX #29, reduced to what word counts
+/// need: runs of letters or digits, possibly joined by punctuation such as
+/// the apostrophe in `can't`; runs of katakana; and every Chinese or
+/// Japanese ideograph and hiragana on its own. Punctuation and symbols are
+/// not words.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Uax29;
+
+/// Word-segmentation classes for `Uax29`, a reduction of the UAX #29
+/// word-break properties.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WordClass {
+    /// Han ideographs and hiragana: every character is a word of its own.
+    Ideograph,
+    /// Runs of katakana form one word.
+    Katakana,
+    /// Letters, digits and `_`; runs form one word. Scripts written without
+    /// spaces that are not ideographic (Thai, Lao, Khmer) need a dictionary
+    /// to split, so a whole run counts as one word, as in UAX #29.
+    Letter,
+    /// Punctuation that joins letters or digits on both sides: `can't`,
+    /// `3.14`, `e.g`.
+    Mid,
+    /// Combining marks and joiners, which belong to the previous character.
+    Extend,
+    Other,
+}
+
+fn word_class(c: char) -> WordClass {
+    match c {
+        '\'' | '.' | ':' | ',' | ';' | '·' | '’' | '\u{2024}' | '\u{fe13}' | '\u{ff0e}' => {
+            WordClass::Mid
+        }
+        '\u{3005}'
+        | '\u{3007}'
+        | '\u{3021}'..='\u{3029}'
+        | '\u{3041}'..='\u{309f}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{20000}'..='\u{3fffd}' => WordClass::Ideograph,
+        '\u{30a0}'..='\u{30ff}' | '\u{31f0}'..='\u{31ff}' | '\u{ff66}'..='\u{ff9f}' => {
+            WordClass::Katakana
+        }
+        '\u{0300}'..='\u{036f}'
+        | '\u{1ab0}'..='\u{1aff}'
+        | '\u{1dc0}'..='\u{1dff}'
+        | '\u{200c}'..='\u{200d}'
+        | '\u{20d0}'..='\u{20ff}'
+        | '\u{fe00}'..='\u{fe0f}'
+        | '\u{fe20}'..='\u{fe2f}'
+        | '\u{e0100}'..='\u{e01ef}' => WordClass::Extend,
+        '_' => WordClass::Letter,
+        c if c.is_alphanumeric() => WordClass::Letter,
+        _ => WordClass::Other,
+    }
+}
+
+impl Tokenizer for Uax29 {
+    fn words<'t>(&self, line: &'t str, word: &mut dyn FnMut(&'t str)) {
+        // The word in progress: where it starts and the class continuing it.
+        let mut current: Option<(usize, WordClass)> = None;
+        // Punctuation only joins when a letter follows it.
+        let mut emit = |start: usize, end: usize| {
+            word(line[start..end].trim_end_matches(|c| word_class(c) == WordClass::Mid))
+        };
+        for (at, c) in line.char_indices() {
+            let class = word_class(c);
+            if let Some((start, previous)) = current {
+                let continues = match class {
+                    WordClass::Letter => matches!(previous, WordClass::Letter | WordClass::Mid),
+                    WordClass::Katakana => previous == WordClass::Katakana,
+                    WordClass::Mid => previous == WordClass::Letter,
+                    WordClass::Extend => previous != WordClass::Mid,
+                    WordClass::Ideograph | WordClass::Other => false,
+                };
+                if continues {
+                    if class != WordClass::Extend {
+                        current = Some((start, class));
+                    }
+                    continue;
+                }
+                emit(start, at);
+                current = None;
+            }
+            if matches!(class, WordClass::Letter | WordClass::Katakana | WordClass::Ideograph) {
+                current = Some((at, class));
+            }
+        }
+        if let Some((start, _)) = current {
+            emit(start, line.len());
+        }
+    }
+}
+
+/// Words as CJK word processors count them: every Chinese, Japanese or
+/// Korean character is a word of its own, and other text is split on
+/// whitespace and on CJK punctuation, so `hello, 世界` is three words.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cjk;
+
+fn is_cjk_character(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3005}'
+            | '\u{3007}'
+            | '\u{3021}'..='\u{3029}'
+            | '\u{3041}'..='\u{30ff}'
+            | '\u{3105}'..='\u{312f}'
+            | '\u{31f0}'..='\u{31ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{ac00}'..='\u{d7af}'
+            | '\u{f900}'..='\u{faff}'
+            | '\u{ff66}'..='\u{ff9f}'
+            | '\u{20000}'..='\u{3fffd}'
+    )
+}
+
+/// Ideographic spaces and punctuation, and the full-width forms of ASCII
+/// punctuation.
+fn is_cjk_punctuation(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303f}' | '\u{ff01}'..='\u{ff65}') && !c.is_alphanumeric()
+}
+
+impl Tokenizer for Cjk {
+    fn words<'t>(&self, line: &'t str, word: &mut dyn FnMut(&'t str)) {
+        let mut start = None;
+        for (at, c) in line.char_indices() {
+            let character = is_cjk_character(c);
+            if character || c.is_whitespace() || is_cjk_punctuation(c) {
+                if let Some(start) = start.take() {
+                    word(&line[start..at]);
+                }
+                if character {
+                    word(&line[at..at + c.len_utf8()]);
+                }
+            } else if start.is_none() {
+                start = Some(at);
+            }
+        }
+        if let Some(start) = start {
+            word(&line[start..]);
+        }
+    }
+}
+
+/// Every match of a regular expression is a word, as with
+/// `regex:[A-Za-z]+(-[A-Za-z]+)*` for hyphenated compounds.
+#[derive(Clone, Debug)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Pattern, String> {
+        Regex::new(pattern)
+            .map(Pattern)
+            .map_err(|e| format!("invalid tokenizer pattern: {}", e))
+    }
+}
+
+impl Tokenizer for Pattern {
+    fn words<'t>(&self, line: &'t str, word: &mut dyn FnMut(&'t str)) {
+        for (start, end) in self.0.find_iter(line) {
+            word(&line[start..end]);
+        }
+    }
+}
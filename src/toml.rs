@@ -0,0 +1,366 @@
+//! The subset of TOML that rwc's own files use, read into a JSON `Value`.
+//!
+//! Supported: `# comments`, `[table]` and `[dotted.table]` headers, bare and
+//! quoted keys, basic (`"..."`, with escapes) and literal (`'...'`) strings,
+//! integers, floats, booleans, and arrays of those, which may span lines.
+//! Inline tables, arrays of tables, multi-line strings and dates are not,
+//! and are reported as errors rather than misread.
+
+use crate::json::Value;
+
+/// Parses `text` into an object of its top-level keys and tables. Errors
+/// name the line they are on.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        at: 0,
+        line: 1,
+    };
+    let mut root = Value::Object(Vec::new());
+    let mut table: Vec<String> = Vec::new();
+    loop {
+        parser.skip_blank();
+        let Some(c) = parser.peek() else {
+            return Ok(root);
+        };
+        if c == '[' {
+            parser.at += 1;
+            if parser.peek() == Some('[') {
+                return Err(parser.error("arrays of tables are not supported"));
+            }
+            table = parser.key_path()?;
+            parser.skip_spaces();
+            parser.expect(']')?;
+            // Creating the table now makes an empty one appear.
+            table_at(&mut root, &table).map_err(|e| parser.error(&e))?;
+        } else {
+            let path = parser.key_path()?;
+            parser.skip_spaces();
+            parser.expect('=')?;
+            parser.skip_spaces();
+            let value = parser.value()?;
+            let (key, parents) = path.split_last().expect("a key path is never empty");
+            let full: Vec<String> = table.iter().chain(parents).cloned().collect();
+            let Value::Object(members) =
+                table_at(&mut root, &full).map_err(|e| parser.error(&e))?
+            else {
+                unreachable!("tables are objects");
+            };
+            if members.iter().any(|(k, _)| k == key) {
+                return Err(parser.error(&format!("duplicate key '{}'", key)));
+            }
+            members.push((key.clone(), value));
+        }
+        parser.skip_spaces();
+        parser.end_of_line()?;
+    }
+}
+
+/// The table at `path` under `root`, created if missing.
+fn table_at<'a>(root: &'a mut Value, path: &[String]) -> Result<&'a mut Value, String> {
+    let mut table = root;
+    for name in path {
+        let Value::Object(members) = table else {
+            unreachable!("tables are objects");
+        };
+        let index = match members.iter().position(|(k, _)| k == name) {
+            Some(index) => index,
+            None => {
+                members.push((name.clone(), Value::Object(Vec::new())));
+                members.len() - 1
+            }
+        };
+        table = &mut members[index].1;
+        if !matches!(table, Value::Object(_)) {
+            return Err(format!("'{}' is not a table", name));
+        }
+    }
+    Ok(table)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {}", self.line, message)
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.at += 1;
+        }
+    }
+
+    /// Skips whitespace, newlines and comments.
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r') => self.at += 1,
+                Some('\n') => {
+                    self.at += 1;
+                    self.line += 1;
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.at += 1;
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.at += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    /// Requires the rest of the line to be empty or a comment.
+    fn end_of_line(&mut self) -> Result<(), String> {
+        match self.peek() {
+            None | Some('\n' | '\r' | '#') => Ok(()),
+            Some(_) => Err(self.error("expected the end of the line")),
+        }
+    }
+
+    /// A dotted key: `name`, `"quoted name"`, `a.b`.
+    fn key_path(&mut self) -> Result<Vec<String>, String> {
+        let mut path = Vec::new();
+        loop {
+            self.skip_spaces();
+            let key = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.at;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || "_-".contains(c))
+                    {
+                        self.at += 1;
+                    }
+                    if self.at == start {
+                        return Err(self.error("expected a key"));
+                    }
+                    self.chars[start..self.at].iter().collect()
+                }
+            };
+            path.push(key);
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(path);
+            }
+            self.at += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') => {
+                if self.chars[self.at..].starts_with(&['"', '"', '"']) {
+                    return Err(self.error("multi-line strings are not supported"));
+                }
+                Ok(Value::String(self.basic_string()?))
+            }
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => Err(self.error("inline tables are not supported")),
+            _ => {
+                let start = self.at;
+                while self.peek().is_some_and(|c| !" \t\r\n,]#".contains(c)) {
+                    self.at += 1;
+                }
+                let word: String = self.chars[start..self.at].iter().collect();
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "" => Err(self.error("expected a value")),
+                    number => number
+                        .replace('_', "")
+                        .parse()
+                        .map(Value::Number)
+                        .map_err(|_| self.error(&format!("invalid value '{}'", number))),
+                }
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.at += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.at += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => self.at += 1,
+                Some(']') => {}
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.at += 1;
+        let mut out = String::new();
+        loop {
+            let c = match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some(c) => c,
+            };
+            self.at += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.at += 1;
+                    match escape {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        '"' => out.push('"'),
+                        '\\' => out.push('\\'),
+                        'u' | 'U' => {
+                            let digits = if escape == 'u' { 4 } else { 8 };
+                            let hex: String =
+                                self.chars.iter().skip(self.at).take(digits).collect();
+                            self.at += digits;
+                            let c = u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| {
+                                    self.error(&format!("invalid escape '\\{}{}'", escape, hex))
+                                })?;
+                            out.push(c);
+                        }
+                        other => return Err(self.error(&format!("invalid escape '\\{}'", other))),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.at += 1;
+        let start = self.at;
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('\'') => break,
+                Some(_) => self.at += 1,
+            }
+        }
+        let text = self.chars[start..self.at].iter().collect();
+        self.at += 1;
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_keys_and_values() {
+        let text = r#"# A manifest.
+name = "rwc" # trailing comment
+"quoted key" = 'C:\literal'
+size.max = 1_000
+
+[jobs.docs]
+paths = [
+    "docs", # the manual
+    'README.md',
+]
+ratio = -0.5
+enabled = true
+
+[empty]
+"#;
+        let value = parse(text).unwrap();
+        assert_eq!(
+            value.to_string(),
+            concat!(
+                r#"{"name":"rwc","quoted key":"C:\\literal","size":{"max":1000},"#,
+                r#""jobs":{"docs":{"paths":["docs","README.md"],"ratio":-0.5,"enabled":true}},"#,
+                r#""empty":{}}"#
+            )
+        );
+        assert_eq!(parse("").unwrap(), Value::Object(Vec::new()));
+        assert_eq!(
+            parse("a = []\r\nb = [[1], [2, 3]]\r\n")
+                .unwrap()
+                .to_string(),
+            r#"{"a":[],"b":[[1],[2,3]]}"#
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        let value = parse(r#"s = "tab\there \"q\" \\ \u00e9 \U0001F600""#).unwrap();
+        assert_eq!(
+            value.get("s").and_then(Value::as_str),
+            Some("tab\there \"q\" \\ é 😀")
+        );
+        assert_eq!(
+            parse(r#"s = "\q""#).unwrap_err(),
+            "line 1: invalid escape '\\q'"
+        );
+        assert_eq!(
+            parse(r#"s = "\ud800""#).unwrap_err(),
+            "line 1: invalid escape '\\ud800'"
+        );
+        assert_eq!(
+            parse(r#"s = "\u12""#).unwrap_err(),
+            "line 1: invalid escape '\\u12\"'"
+        );
+    }
+
+    #[test]
+    fn malformed_documents_name_the_line() {
+        for (text, error) in [
+            ("a = 1\nb", "line 2: expected '='"),
+            ("a = 1 2", "line 1: expected the end of the line"),
+            ("a =", "line 1: expected a value"),
+            ("= 1", "line 1: expected a key"),
+            ("a = 1\na = 2", "line 2: duplicate key 'a'"),
+            ("a = 1\n[a]", "line 2: 'a' is not a table"),
+            ("a.b = 1\na.b.c = 2", "line 2: 'b' is not a table"),
+            ("[table", "line 1: expected ']'"),
+            ("\n\n[[jobs]]", "line 3: arrays of tables are not supported"),
+            ("a = {b = 1}", "line 1: inline tables are not supported"),
+            (
+                "a = \"\"\"\nx\"\"\"",
+                "line 1: multi-line strings are not supported",
+            ),
+            ("a = \"open\nb = 1", "line 1: unterminated string"),
+            ("a = 'open", "line 1: unterminated string"),
+            ("a = [1,\n2", "line 2: expected ',' or ']' in array"),
+            ("a = [1,", "line 1: expected a value"),
+            ("a = [1 2]", "line 1: expected ',' or ']' in array"),
+            ("when = 1979-05-27", "line 1: invalid value '1979-05-27'"),
+            ("a = yes", "line 1: invalid value 'yes'"),
+        ] {
+            assert_eq!(parse(text).unwrap_err(), error, "{:?}", text);
+        }
+    }
+}
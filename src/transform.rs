@@ -0,0 +1,239 @@
+//! The `--pre` content transform pipeline.
+//!
+//! Steps run in the order given, each turning the input bytes into new
+//! bytes, so extraction and normalization compose: `--pre decompress,html-text`
+//! counts the visible text of a gzip'd HTML page. `--auto` inserts a step
+//! that picks the extraction from the detected file type.
+
+use std::io;
+use std::path::Path;
+
+use crate::engine::invalid_utf8;
+use crate::filetype::{self, FileType};
+use crate::{decompress, filter};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transform {
+    /// Remove ANSI escape sequences (colors, cursor movement).
+    StripAnsi,
+    /// Unpack gzip, bzip2, xz or zstd data; plain input passes through.
+    Decompress,
+    /// Reduce HTML to its visible text.
+    HtmlText,
+    /// Drop quoted material (`--exclude-quotes`).
+    ExcludeQuotes,
+    /// Drop citations, footnotes and references (`--academic`).
+    Academic,
+    /// Detect the input type and extract its text (`--auto`, `--type`).
+    Auto(Option<FileType>),
+}
+
+pub const NAMES: &[(&str, Transform)] = &[
+    ("strip-ansi", Transform::StripAnsi),
+    ("decompress", Transform::Decompress),
+    ("html-text", Transform::HtmlText),
+    ("exclude-quotes", Transform::ExcludeQuotes),
+    ("academic", Transform::Academic),
+];
+
+/// Parses a comma-separated list of step names.
+pub fn parse_list(text: &str) -> Result<Vec<Transform>, String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            NAMES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|&(_, step)| step)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = NAMES.iter().map(|(n, _)| *n).collect();
+                    format!("unknown transform '{}' (expected {})", name, names.join(", "))
+                })
+        })
+        .collect()
+}
+
+/// `data` as text. With `lossy`, invalid UTF-8 reads as U+FFFD instead of
+/// failing.
+pub fn to_text(data: Vec<u8>, lossy: bool) -> io::Result<String> {
+    match String::from_utf8(data) {
+        Ok(text) => Ok(text),
+        Err(e) if lossy => Ok(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+        Err(_) => Err(invalid_utf8()),
+    }
+}
+
+/// Applies a text-level step to UTF-8 data.
+fn map_text(data: Vec<u8>, lossy: bool, f: impl Fn(&str) -> String) -> io::Result<Vec<u8>> {
+    Ok(f(&to_text(data, lossy)?).into_bytes())
+}
+
+/// Runs `steps` over `data`, read from `path` if known, and returns the
+/// resulting text.
+pub fn apply(
+    steps: &[Transform],
+    path: Option<&Path>,
+    mut data: Vec<u8>,
+    lossy: bool,
+) -> io::Result<String> {
+    for step in steps {
+        data = match *step {
+            Transform::Auto(forced) => filetype::extract(path, data, forced)?,
+            Transform::StripAnsi => strip_ansi(&data),
+            Transform::Decompress => decompress::decompress_all(data)?,
+            Transform::HtmlText => map_text(data, lossy, html_text)?,
+            Transform::ExcludeQuotes => map_text(data, lossy, filter::strip_quotes)?,
+            Transform::Academic => map_text(data, lossy, filter::strip_academic)?,
+        };
+    }
+    to_text(data, lossy)
+}
+
+/// Removes CSI (`ESC [ ... final`), OSC (`ESC ] ... BEL` or `ESC \`) and
+/// two-byte escape sequences.
+fn strip_ansi(data: &[u8]) -> Vec<u8> {
+    const ESC: u8 = 0x1b;
+    const BEL: u8 = 0x07;
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] != ESC {
+            out.push(data[i]);
+            i += 1;
+            continue;
+        }
+        match data.get(i + 1) {
+            Some(b'[') => {
+                i += 2;
+                while i < data.len() && !(0x40..=0x7e).contains(&data[i]) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            Some(b']') => {
+                i += 2;
+                while i < data.len() {
+                    if data[i] == BEL {
+                        i += 1;
+                        break;
+                    }
+                    if data[i] == ESC && data.get(i + 1) == Some(&b'\\') {
+                        i += 2;
+                        break;
+                    }
+                    i += 1;
+                }
+            }
+            Some(_) => i += 2,
+            None => i += 1,
+        }
+    }
+    out
+}
+
+/// Elements whose content is never displayed.
+const HIDDEN_ELEMENTS: [&str; 4] = ["script", "style", "template", "noscript"];
+
+/// Elements that start a new line of text.
+const BLOCK_ELEMENTS: [&str; 24] = [
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "footer",
+    "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "p", "pre", "section", "table",
+    "tr",
+];
+
+/// Reduces HTML to its visible text: tags, comments and the content of
+/// script/style elements are dropped, block elements become line breaks and
+/// character references are decoded.
+pub fn html_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len() / 2);
+    let lower = html.to_ascii_lowercase();
+    let mut rest = html;
+    while let Some(open) = rest.find('<') {
+        out.push_str(&decode_entities(&rest[..open]));
+        let offset = html.len() - rest.len() + open;
+        let tag = &rest[open..];
+
+        if tag.starts_with("<!--") {
+            rest = match tag.find("-->") {
+                Some(end) => &tag[end + 3..],
+                None => "",
+            };
+            continue;
+        }
+        let Some(close) = tag.find('>') else {
+            // A stray '<' in text.
+            out.push('<');
+            rest = &tag[1..];
+            continue;
+        };
+        let name: String = tag[1..close]
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        rest = &tag[close + 1..];
+
+        if !tag.starts_with("</") && HIDDEN_ELEMENTS.contains(&name.as_str()) {
+            let end_tag = format!("</{}", name);
+            let from = offset + close + 1;
+            rest = match lower[from..].find(&end_tag) {
+                Some(end) => {
+                    let after = &html[from + end..];
+                    after.find('>').map_or("", |gt| &after[gt + 1..])
+                }
+                None => "",
+            };
+            continue;
+        }
+        if BLOCK_ELEMENTS.contains(&name.as_str()) {
+            out.push('\n');
+        }
+    }
+    out.push_str(&decode_entities(rest));
+    out
+}
+
+/// Decodes HTML and XML character references.
+pub fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        let candidate = &rest[amp..];
+        let decoded = candidate.find(';').filter(|&semi| semi <= 10).and_then(|semi| {
+            let entity = &candidate[1..semi];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, semi))
+        });
+        match decoded {
+            Some((c, semi)) => {
+                out.push(c);
+                rest = &candidate[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,528 @@
+//! `--po` and `--xliff`: translation files counted entry by entry.
+//!
+//! Each entry (a gettext message, an XLIFF 1.2 `trans-unit` or an XLIFF 2
+//! `segment`) is translated, fuzzy or untranslated, and the words of its
+//! source and target text are counted apart: these are the numbers
+//! translation work is quoted and paid by. The PO header, obsolete (`#~`)
+//! messages and XLIFF units marked `translate="no"` are left out. Inline
+//! markup in XLIFF text is dropped, and native code (`ph`, `bpt`, `ept`,
+//! `it`) does not count as words.
+
+use std::fs;
+use std::io::{self, Read};
+
+use crate::engine::Scanner;
+use crate::json::Value;
+use crate::xml::{attribute, Event, Xml};
+use crate::{encoding, output, print_json, print_plain, transform, Config};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Po,
+    Xliff,
+}
+
+#[derive(Default)]
+struct Report {
+    entries: usize,
+    translated: usize,
+    /// PO entries flagged `fuzzy`, or XLIFF targets waiting for review.
+    fuzzy: usize,
+    untranslated: usize,
+    source_words: usize,
+    target_words: usize,
+}
+
+impl Report {
+    fn add(&mut self, other: &Report) {
+        self.entries += other.entries;
+        self.translated += other.translated;
+        self.fuzzy += other.fuzzy;
+        self.untranslated += other.untranslated;
+        self.source_words += other.source_words;
+        self.target_words += other.target_words;
+    }
+
+    fn members(&self) -> [(&'static str, usize); 6] {
+        [
+            ("entries", self.entries),
+            ("translated", self.translated),
+            ("fuzzy", self.fuzzy),
+            ("untranslated", self.untranslated),
+            ("source_words", self.source_words),
+            ("target_words", self.target_words),
+        ]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Translated,
+    Fuzzy,
+    Untranslated,
+}
+
+/// Adds up entries and feeds their text to a scanner each for source and
+/// target.
+struct Tally<'m> {
+    report: Report,
+    source: Scanner<'m>,
+    target: Scanner<'m>,
+}
+
+impl Tally<'_> {
+    fn entry(&mut self, source: &[String], target: &[String], state: State) -> io::Result<()> {
+        self.report.entries += 1;
+        match state {
+            State::Translated => self.report.translated += 1,
+            State::Fuzzy => self.report.fuzzy += 1,
+            State::Untranslated => self.report.untranslated += 1,
+        }
+        // A line per text keeps the last word of one entry from running into
+        // the first of the next.
+        for text in source {
+            self.source.feed(text.as_bytes())?;
+            self.source.feed(b"\n")?;
+        }
+        for text in target {
+            self.target.feed(text.as_bytes())?;
+            self.target.feed(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<Report> {
+        self.report.source_words = self.source.finish()?.words;
+        self.report.target_words = self.target.finish()?.words;
+        Ok(self.report)
+    }
+}
+
+/// Counts each operand (standard input when there are none). Returns the
+/// exit code.
+pub fn run(config: &Config, format: Format) -> i32 {
+    let stdin = ["-".to_string()];
+    let operands = if config.files.is_empty() {
+        &stdin[..]
+    } else {
+        &config.files[..]
+    };
+    let mut reports = Vec::new();
+    for operand in operands {
+        let label = if operand == "-" {
+            config.stdin_label()
+        } else {
+            operand.as_str()
+        };
+        match count(operand, format, config) {
+            Ok(report) => reports.push((label, report)),
+            Err(e) => {
+                eprintln!("rwc: {}: {}", label, e);
+                return 1;
+            }
+        }
+    }
+    let mut total = Report::default();
+    for (_, report) in &reports {
+        total.add(report);
+    }
+
+    if config.show_json {
+        let object = |report: &Report, label: Option<&str>| {
+            let mut members: Vec<(String, Value)> =
+                label.map(|label| ("file".to_string(), label.into())).into_iter().collect();
+            members.extend(report.members().map(|(key, value)| (key.to_string(), value.into())));
+            Value::Object(members)
+        };
+        let files = reports.iter().map(|(label, report)| object(report, Some(label))).collect();
+        let document = Value::Object(vec![
+            ("files".to_string(), Value::Array(files)),
+            ("total".to_string(), object(&total, None)),
+        ]);
+        print_json(&document, config);
+        return 0;
+    }
+    let print = |report: &Report, label: &str| {
+        if config.plain {
+            let mut fields = vec![("file", label.to_string())];
+            fields.extend(report.members().map(|(key, value)| (key, config.format_count(value))));
+            print_plain(&fields);
+            return;
+        }
+        let columns: Vec<String> = report
+            .members()
+            .iter()
+            .map(|&(_, value)| format!("{:>8}", config.format_count(value)))
+            .collect();
+        output::print(&format!("{} {}", columns.join(""), label));
+    };
+    for (label, report) in &reports {
+        print(report, label);
+    }
+    if reports.len() > 1 {
+        print(&total, config.total_label());
+    }
+    0
+}
+
+fn count(operand: &str, format: Format, config: &Config) -> io::Result<Report> {
+    let data = if operand == "-" {
+        let mut data = Vec::new();
+        io::stdin().lock().read_to_end(&mut data)?;
+        data
+    } else {
+        fs::read(operand)?
+    };
+    let text = transform::to_text(encoding::decode(data, config.encoding), config.lossy)?;
+    let metrics = config.metrics();
+    let mut tally = Tally {
+        report: Report::default(),
+        source: Scanner::new(&metrics),
+        target: Scanner::new(&metrics),
+    };
+    match format {
+        Format::Po => po(&text, &mut tally)?,
+        Format::Xliff => xliff(&text, &mut tally)?,
+    }
+    tally.finish()
+}
+
+/// The gettext message being read.
+#[derive(Default)]
+struct Message {
+    context: bool,
+    source: Vec<String>,
+    target: Vec<String>,
+    fuzzy: bool,
+    obsolete: bool,
+}
+
+impl Message {
+    fn is_empty(&self) -> bool {
+        !self.context && self.source.is_empty() && self.target.is_empty()
+    }
+}
+
+/// Which string a continuation line (`"..."`) extends.
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    None,
+    Source,
+    Target,
+}
+
+fn po(text: &str, tally: &mut Tally) -> io::Result<()> {
+    let mut message = Message::default();
+    let mut field = Field::None;
+    let mut flush = |message: &mut Message| -> io::Result<()> {
+        let message = std::mem::take(message);
+        // The header is the message with an empty msgid and no context.
+        let header = !message.context && message.source.iter().all(String::is_empty);
+        if message.is_empty() || message.obsolete || header {
+            return Ok(());
+        }
+        let state = if message.target.is_empty() || message.target.iter().any(String::is_empty) {
+            State::Untranslated
+        } else if message.fuzzy {
+            State::Fuzzy
+        } else {
+            State::Translated
+        };
+        tally.entry(&message.source, &message.target, state)
+    };
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            flush(&mut message)?;
+            field = Field::None;
+            continue;
+        }
+        if let Some(flags) = line.strip_prefix("#,") {
+            if !message.target.is_empty() {
+                flush(&mut message)?;
+            }
+            message.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            continue;
+        }
+        let (obsolete, line) = match line.strip_prefix("#~") {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, line),
+        };
+        if line.starts_with('#') {
+            continue;
+        }
+        let (keyword, rest) = line.split_once(|c: char| c.is_ascii_whitespace()).unwrap_or((line, ""));
+        let quoted = if keyword.starts_with('"') { line } else { rest.trim() };
+        let value = unquote(quoted);
+        match keyword {
+            "msgctxt" | "msgid" => {
+                // A new message starts once the previous one has its msgstr.
+                if !message.target.is_empty() || (keyword == "msgctxt" && !message.is_empty()) {
+                    flush(&mut message)?;
+                }
+                message.obsolete |= obsolete;
+                if keyword == "msgctxt" {
+                    message.context = true;
+                    field = Field::None;
+                } else {
+                    message.source.push(value);
+                    field = Field::Source;
+                }
+            }
+            "msgid_plural" => {
+                message.source.push(value);
+                field = Field::Source;
+            }
+            k if k == "msgstr" || k.starts_with("msgstr[") => {
+                message.target.push(value);
+                field = Field::Target;
+            }
+            k if k.starts_with('"') => {
+                let current = match field {
+                    Field::Source => message.source.last_mut(),
+                    Field::Target => message.target.last_mut(),
+                    Field::None => None,
+                };
+                if let Some(current) = current {
+                    current.push_str(&value);
+                }
+            }
+            _ => {}
+        }
+    }
+    flush(&mut message)
+}
+
+/// The text of a PO string literal, with C escapes decoded.
+fn unquote(quoted: &str) -> String {
+    let inner = quoted.strip_prefix('"').unwrap_or(quoted);
+    let inner = inner.strip_suffix('"').unwrap_or(inner);
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text
+}
+
+fn xliff(text: &str, tally: &mut Tally) -> io::Result<()> {
+    // The open entry: its element name, whether it is skipped, its texts
+    // and its target's state.
+    let mut entry: Option<(&str, bool)> = None;
+    let (mut source, mut target) = (Vec::new(), Vec::new());
+    let mut target_state = None;
+    // Which text is being collected, and how deep in native code or
+    // alternative translations the reader is.
+    let mut field = Field::None;
+    let (mut native, mut alternative) = (0usize, 0usize);
+    let mut skipped_unit = false;
+    for event in Xml::new(text) {
+        match event {
+            Event::Start { name: "unit", attributes, .. } => {
+                skipped_unit = attribute(attributes, "translate").as_deref() == Some("no");
+            }
+            Event::Start { name: name @ ("trans-unit" | "segment"), attributes, empty } => {
+                let skipped = skipped_unit
+                    || attribute(attributes, "translate").as_deref() == Some("no");
+                if name == "segment" {
+                    target_state = attribute(attributes, "state");
+                }
+                if !empty {
+                    entry = Some((name, skipped));
+                    source.clear();
+                    target.clear();
+                }
+            }
+            Event::Start { name: "alt-trans", empty: false, .. } => alternative += 1,
+            Event::End("alt-trans") => alternative = alternative.saturating_sub(1),
+            Event::Start { name: "source", empty, .. } if entry.is_some() && alternative == 0 => {
+                source.push(String::new());
+                field = if empty { Field::None } else { Field::Source };
+            }
+            Event::Start { name: "target", attributes, empty }
+                if entry.is_some() && alternative == 0 =>
+            {
+                if let Some(state) = attribute(attributes, "state") {
+                    target_state = Some(state);
+                }
+                target.push(String::new());
+                field = if empty { Field::None } else { Field::Target };
+            }
+            Event::End("source" | "target") => field = Field::None,
+            Event::Start { name: "ph" | "bpt" | "ept" | "it", empty: false, .. } => native += 1,
+            Event::End("ph" | "bpt" | "ept" | "it") => native = native.saturating_sub(1),
+            Event::Text(text) if native == 0 && alternative == 0 => {
+                let current = match field {
+                    Field::Source => source.last_mut(),
+                    Field::Target => target.last_mut(),
+                    Field::None => None,
+                };
+                if let Some(current) = current {
+                    current.push_str(&text);
+                }
+            }
+            Event::End(name) if entry.is_some_and(|(open, _)| open == name) => {
+                let (_, skipped) = entry.take().expect("entry is open");
+                if !skipped {
+                    let state = xliff_state(&target, target_state.take().as_deref());
+                    tally.entry(&source, &target, state)?;
+                }
+                target_state = None;
+            }
+            Event::End("unit") => skipped_unit = false,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// An XLIFF entry's state, from its target text and the `state` attribute
+/// of the target (1.2) or segment (2.x).
+fn xliff_state(target: &[String], state: Option<&str>) -> State {
+    if target.iter().all(|text| text.trim().is_empty()) {
+        return State::Untranslated;
+    }
+    match state {
+        Some("new" | "needs-translation") => State::Untranslated,
+        Some(state) if state.starts_with("needs-") => State::Fuzzy,
+        _ => State::Translated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Metrics;
+
+    fn report(format: Format, text: &str) -> [(&'static str, usize); 6] {
+        let metrics = Metrics::default();
+        let mut tally = Tally {
+            report: Report::default(),
+            source: Scanner::new(&metrics),
+            target: Scanner::new(&metrics),
+        };
+        match format {
+            Format::Po => po(text, &mut tally).unwrap(),
+            Format::Xliff => xliff(text, &mut tally).unwrap(),
+        }
+        tally.finish().unwrap().members()
+    }
+
+    /// Entries, translated, fuzzy, untranslated, source and target words.
+    fn numbers(format: Format, text: &str) -> [usize; 6] {
+        report(format, text).map(|(_, n)| n)
+    }
+
+    #[test]
+    fn po_states_plurals_and_contexts() {
+        let text = r#"# Translator comment
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#: src/main.c:10
+msgid "Open the file"
+msgstr "Ouvrir le fichier"
+
+#, c-format, fuzzy
+msgid "Save %s"
+msgstr "Enregistrer %s"
+
+msgid "one file"
+msgid_plural "%d files"
+msgstr[0] "un fichier"
+msgstr[1] ""
+
+msgctxt "menu"
+msgid ""
+msgstr "vide"
+
+msgid "Long "
+"text"
+msgstr ""
+"Texte "
+"long"
+msgid "Not yet"
+msgstr ""
+
+#~ msgid "Gone"
+#~ msgstr "Parti"
+"#;
+        // Open, Save and Long are translated or fuzzy; the plural and "Not
+        // yet" are not, and the context makes an empty msgid an entry.
+        assert_eq!(
+            numbers(Format::Po, text),
+            [6, 3, 1, 2, 3 + 2 + 4 + 2 + 2, 3 + 2 + 2 + 1 + 2]
+        );
+    }
+
+    #[test]
+    fn po_edge_cases() {
+        assert_eq!(numbers(Format::Po, ""), [0; 6]);
+        // Only a header.
+        assert_eq!(numbers(Format::Po, "msgid \"\"\nmsgstr \"x\"\n"), [0; 6]);
+        // A msgid without a msgstr is untranslated; stray text is ignored.
+        assert_eq!(
+            numbers(Format::Po, "\"stray\"\nmsgid \"a b\"\nnonsense\n"),
+            [1, 0, 0, 1, 2, 0]
+        );
+        assert_eq!(unquote(r#""a\"b\\c\n\td\"#), "a\"b\\c\n\td");
+        assert_eq!(unquote("unquoted"), "unquoted");
+    }
+
+    #[test]
+    fn xliff_1_2_units() {
+        let text = r#"<?xml version="1.0"?>
+<xliff version="1.2"><file source-language="en" target-language="de"><body>
+  <trans-unit id="1"><source>Hello <g id="b">bold</g> world</source>
+    <target state="translated">Hallo <g id="b">fette</g> Welt</target></trans-unit>
+  <trans-unit id="2"><source>Press <ph id="p">&lt;Ctrl&gt;</ph> now</source>
+    <target state="needs-review-translation">Jetzt drücken</target>
+    <alt-trans><source>ignored words here</source><target>auch ignoriert</target></alt-trans>
+  </trans-unit>
+  <trans-unit id="3"><source>Not started</source><target state="new">Nicht</target></trans-unit>
+  <trans-unit id="4"><source>No target</source></trans-unit>
+  <trans-unit id="5" translate="no"><source>Brand Name</source></trans-unit>
+  <trans-unit id="6"><source>Empty</source><target/></trans-unit>
+</body></file></xliff>"#;
+        assert_eq!(
+            numbers(Format::Xliff, text),
+            [5, 1, 1, 3, 3 + 2 + 2 + 2 + 1, 3 + 2 + 1]
+        );
+    }
+
+    #[test]
+    fn xliff_2_segments() {
+        let text = r#"<xliff version="2.0" srcLang="en" trgLang="fr"><file id="f">
+  <unit id="u1">
+    <segment state="final"><source>First part.</source><target>Première partie.</target></segment>
+    <segment state="initial"><source>Second part.</source><target>Deuxième.</target></segment>
+  </unit>
+  <unit id="u2" translate="no"><segment><source>Skipped text</source></segment></unit>
+  <unit id="u3"><segment><source>Open</source>"#;
+        // The last unit never closes and is not counted.
+        assert_eq!(numbers(Format::Xliff, text), [2, 2, 0, 0, 4, 3]);
+        assert_eq!(numbers(Format::Xliff, "not xml at all"), [0; 6]);
+    }
+
+    #[test]
+    fn xliff_states() {
+        let text = |t: &str| vec![t.to_string()];
+        assert!(xliff_state(&[], Some("final")) == State::Untranslated);
+        assert!(xliff_state(&text("  "), None) == State::Untranslated);
+        assert!(xliff_state(&text("x"), Some("needs-translation")) == State::Untranslated);
+        assert!(xliff_state(&text("x"), Some("needs-l10n")) == State::Fuzzy);
+        assert!(xliff_state(&text("x"), Some("signed-off")) == State::Translated);
+        assert!(xliff_state(&text("x"), None) == State::Translated);
+    }
+}
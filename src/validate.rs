@@ -0,0 +1,132 @@
+//! `--validate-utf8`: report which files are not valid UTF-8, and where.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::json::{self, Value};
+use crate::{bidi, is_generated, output, walk, Config};
+
+/// Byte offset of the first invalid UTF-8 sequence `reader` yields, or `None`
+/// if it is all valid. A sequence cut short by the end of input is invalid.
+pub fn first_invalid<R: Read>(mut reader: R) -> io::Result<Option<u64>> {
+    let mut buffer = vec![0; 64 * 1024];
+    // Bytes at the front of `buffer` left over from an incomplete sequence
+    // at the end of the previous read, and the stream offset of `buffer[0]`.
+    let mut carry = 0;
+    let mut offset = 0u64;
+    loop {
+        let n = match reader.read(&mut buffer[carry..]) {
+            Ok(0) if carry == 0 => return Ok(None),
+            Ok(0) => return Ok(Some(offset)),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let filled = carry + n;
+        match std::str::from_utf8(&buffer[..filled]) {
+            Ok(_) => carry = 0,
+            Err(e) if e.error_len().is_some() => {
+                return Ok(Some(offset + e.valid_up_to() as u64));
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                buffer.copy_within(valid..filled, 0);
+                carry = filled - valid;
+            }
+        }
+        offset += (filled - carry) as u64;
+    }
+}
+
+/// Checks every file under the configured operands (standard input when
+/// there are none). Returns the exit code: 1 if any file is invalid.
+pub fn run(config: &Config) -> i32 {
+    let stdin = ["-".to_string()];
+    let operands = if config.files.is_empty() {
+        &stdin[..]
+    } else {
+        &config.files[..]
+    };
+
+    let mut budget = walk::Budget::new(config.limits);
+    let mut results: Vec<(String, Option<u64>)> = Vec::new();
+    for operand in operands {
+        if budget.is_exhausted() {
+            break;
+        }
+        if operand == "-" {
+            match first_invalid(io::stdin().lock()) {
+                Ok(invalid) => results.push((config.stdin_label().to_string(), invalid)),
+                Err(e) => {
+                    eprintln!("rwc: {}: {}", config.stdin_label(), e);
+                    return 2;
+                }
+            }
+            continue;
+        }
+        let paths = match walk::files_with(Path::new(operand), &mut budget, &config.walk) {
+            Ok(paths) => paths,
+            Err(e) => {
+                eprintln!("rwc: {}: {}", operand, e);
+                return 2;
+            }
+        };
+        for path in paths {
+            if config.no_generated && is_generated(&path) {
+                continue;
+            }
+            match File::open(&path).and_then(first_invalid) {
+                Ok(invalid) => results.push((path.display().to_string(), invalid)),
+                Err(e) => {
+                    eprintln!("rwc: {}: {}", path.display(), e);
+                    return 2;
+                }
+            }
+        }
+    }
+    budget.warn_if_exhausted();
+
+    let invalid = results.iter().filter(|(_, offset)| offset.is_some()).count();
+    if config.show_json {
+        let files = results
+            .iter()
+            .map(|(file, offset)| {
+                let mut members = vec![
+                    ("file".to_string(), Value::from(file.as_str())),
+                    ("valid".to_string(), Value::Bool(offset.is_none())),
+                ];
+                if let Some(offset) = offset {
+                    members.push(("offset".to_string(), Value::Number(*offset as f64)));
+                }
+                Value::Object(members)
+            })
+            .collect();
+        output::print(&format!(
+            "{}",
+            json::object([
+                ("files", Value::Array(files)),
+                ("checked", results.len().into()),
+                ("invalid", invalid.into()),
+            ])
+        ));
+    } else {
+        for (file, offset) in &results {
+            match offset {
+                None => output::print(&format!("ok       {}", bidi::display_name(file))),
+                Some(offset) => output::print(&format!(
+                    "invalid  {} (first invalid byte at offset {})",
+                    bidi::display_name(file),
+                    offset
+                )),
+            }
+        }
+        eprintln!("rwc: {} of {} files invalid UTF-8", invalid, results.len());
+    }
+
+    if invalid > 0 {
+        1
+    } else {
+        0
+    }
+}
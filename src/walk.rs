@@ -0,0 +1,300 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::glob::Glob;
+use crate::{interrupt, output};
+
+/// Work limits for scans: `--max-files` and `--max-total-bytes`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    pub max_files: Option<usize>,
+    pub max_total_bytes: Option<u64>,
+}
+
+/// What a directory walk visits: `--hidden`, `--follow-symlinks`, ignore
+/// files and `--include`/`--exclude`.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Include entries whose names start with `.`.
+    pub hidden: bool,
+    /// Follow symbolic links to files and directories. Each directory is
+    /// still visited once, so link cycles end the descent.
+    pub follow_symlinks: bool,
+    /// Skip what `.gitignore` and `.ignore` files in the walked tree exclude.
+    pub gitignore: bool,
+    /// When not empty, only files matching one of these are listed.
+    pub include: Vec<Pattern>,
+    /// Files and directories matching any of these are skipped.
+    pub exclude: Vec<Pattern>,
+}
+
+/// A glob matched against a file name, or, if it contains a `/`, against
+/// the path relative to where matching starts (a leading `/` is dropped).
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    glob: Glob,
+    anchored: bool,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Pattern {
+        let anchored = pattern.contains('/');
+        Pattern {
+            glob: Glob::new(pattern.strip_prefix('/').unwrap_or(pattern)),
+            anchored,
+        }
+    }
+
+    /// Whether the pattern matches `relative`, a `/`-separated path.
+    fn matches(&self, relative: &str) -> bool {
+        if self.anchored {
+            self.glob.matches(relative)
+        } else {
+            let name = relative.rsplit('/').next().unwrap_or(relative);
+            self.glob.matches(name)
+        }
+    }
+}
+
+/// One line of an ignore file.
+struct Rule {
+    pattern: Pattern,
+    /// `!PATTERN`: re-include what an earlier rule ignored.
+    negated: bool,
+    /// `PATTERN/`: only matches directories.
+    dir_only: bool,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Rule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        Some(Rule {
+            pattern: Pattern::new(line),
+            negated,
+            dir_only,
+        })
+    }
+}
+
+/// The ignore rules in effect in a directory: those of its own `.gitignore`
+/// and `.ignore` files, then through `parent` those of each directory above
+/// it, up to the walk root.
+struct Ignores {
+    dir: PathBuf,
+    rules: Vec<Rule>,
+    parent: Option<Rc<Ignores>>,
+}
+
+impl Ignores {
+    fn load(dir: &Path, parent: Option<Rc<Ignores>>) -> Option<Rc<Ignores>> {
+        let rules: Vec<Rule> = [".gitignore", ".ignore"]
+            .iter()
+            .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+            .flat_map(|text| text.lines().filter_map(Rule::parse).collect::<Vec<_>>())
+            .collect();
+        if rules.is_empty() {
+            return parent;
+        }
+        Some(Rc::new(Ignores {
+            dir: dir.to_path_buf(),
+            rules,
+            parent,
+        }))
+    }
+
+    /// As in git, the deepest ignore file with a matching rule decides, and
+    /// within a file the last matching rule wins.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut level = Some(self);
+        while let Some(ignores) = level {
+            if let Ok(relative) = path.strip_prefix(&ignores.dir) {
+                let relative = slash_path(relative);
+                let matched = ignores
+                    .rules
+                    .iter()
+                    .rev()
+                    .find(|rule| (is_dir || !rule.dir_only) && rule.pattern.matches(&relative));
+                if let Some(rule) = matched {
+                    return !rule.negated;
+                }
+            }
+            level = ignores.parent.as_deref();
+        }
+        false
+    }
+}
+
+/// `path` with `/` separators on every platform, for pattern matching.
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Tracks how much of the `Limits` a scan has used.
+#[derive(Debug, Default)]
+pub struct Budget {
+    limits: Limits,
+    files: usize,
+    bytes: u64,
+    /// The limit that stopped the scan, if any.
+    exhausted: Option<&'static str>,
+}
+
+impl Budget {
+    pub fn new(limits: Limits) -> Self {
+        Budget {
+            limits,
+            ..Budget::default()
+        }
+    }
+
+    /// Accounts for a file of `len` bytes. Returns false, and stays
+    /// exhausted, once the file would exceed a limit.
+    pub fn admit(&mut self, len: u64) -> bool {
+        if self.exhausted.is_some() {
+            return false;
+        }
+        if self.limits.max_files.is_some_and(|max| self.files >= max) {
+            self.exhausted = Some("--max-files");
+            return false;
+        }
+        if self
+            .limits
+            .max_total_bytes
+            .is_some_and(|max| self.bytes + len > max)
+        {
+            self.exhausted = Some("--max-total-bytes");
+            return false;
+        }
+        self.files += 1;
+        self.bytes += len;
+        true
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted.is_some()
+    }
+
+    /// Prints a warning to stderr if a limit cut the scan short.
+    pub fn warn_if_exhausted(&self) {
+        if let Some(limit) = self.exhausted {
+            output::warn(format!(
+                "rwc: warning: {} reached after {} files ({} bytes); results are partial",
+                limit, self.files, self.bytes
+            ));
+        }
+    }
+}
+
+/// Lists the regular files under `root` in sorted order.
+///
+/// Hidden entries (names starting with `.`) are skipped and symlinks are not
+/// followed. A `root` that is itself a file is returned as-is.
+pub fn files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    files_within(root, &mut Budget::default())
+}
+
+/// Like `files`, but stops walking as soon as `budget` is exhausted, so a
+/// scan accidentally pointed at `/` ends quickly. The files admitted before
+/// that point are returned, sorted.
+pub fn files_within(root: &Path, budget: &mut Budget) -> io::Result<Vec<PathBuf>> {
+    files_with(root, budget, &Options::default())
+}
+
+/// Like `files_within`, with hidden entries, symlinks, ignore files and
+/// glob filters handled as `options` says.
+pub fn files_with(root: &Path, budget: &mut Budget, options: &Options) -> io::Result<Vec<PathBuf>> {
+    let metadata = fs::metadata(root)?;
+    if !metadata.is_dir() {
+        return Ok(if budget.admit(metadata.len()) {
+            vec![root.to_path_buf()]
+        } else {
+            Vec::new()
+        });
+    }
+
+    let mut files = Vec::new();
+    // Canonical paths of the directories seen, to stop symlink cycles.
+    let mut visited = HashSet::new();
+    let mut pending = vec![(root.to_path_buf(), None)];
+    'walk: while let Some((dir, parent_ignores)) = pending.pop() {
+        if interrupt::requested() {
+            break;
+        }
+        if options.follow_symlinks && !visited.insert(fs::canonicalize(&dir)?) {
+            continue;
+        }
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                output::warn(format!("rwc: {}: {}", dir.display(), e));
+                continue;
+            }
+        };
+        let ignores = if options.gitignore {
+            Ignores::load(&dir, parent_ignores)
+        } else {
+            None
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            if !options.hidden && name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // Git's own data is never counted when ignore files are honoured.
+            if options.gitignore && name == ".git" {
+                continue;
+            }
+            let Ok(mut file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_symlink() && options.follow_symlinks {
+                // A dangling link has no target to count.
+                let Ok(target) = fs::metadata(entry.path()) else {
+                    continue;
+                };
+                file_type = target.file_type();
+            }
+            let path = entry.path();
+            let is_dir = file_type.is_dir();
+            if ignores.as_ref().is_some_and(|i| i.is_ignored(&path, is_dir)) {
+                continue;
+            }
+            let relative = slash_path(path.strip_prefix(root).unwrap_or(&path));
+            if options.exclude.iter().any(|p| p.matches(&relative)) {
+                continue;
+            }
+            if is_dir {
+                pending.push((path, ignores.clone()));
+            } else if file_type.is_file() {
+                if !options.include.is_empty() && !options.include.iter().any(|p| p.matches(&relative))
+                {
+                    continue;
+                }
+                let len = fs::metadata(&path).map_or(0, |m| m.len());
+                if !budget.admit(len) {
+                    break 'walk;
+                }
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,149 @@
+//! WebAssembly exports for `count(text, options)`, built with the `wasm`
+//! feature; `wasm/rwc.mjs` wraps them for browsers, Node and VS Code web
+//! extensions.
+//!
+//! Strings cross the boundary as UTF-8 in the module's memory. The host
+//! allocates room with `rwc_alloc`, writes the text and a JSON object of
+//! options into it and calls `rwc_count`, which answers with JSON: the
+//! counts under the names `rwc --json` uses, or `{"error": MESSAGE}`. The
+//! answer stays valid until the next call.
+//!
+//! Options: `unique_lines`, `unique_words`, `approx`,
+//! `ignore_format_chars` and `max_display_width` (booleans), `wrap_at` and
+//! `tab_size` (columns), `whitespace` and `tokenizer` (as on the command
+//! line).
+
+use std::slice;
+use std::sync::Mutex;
+
+use crate::engine::{self, Counts, Metrics};
+use crate::json::{self, Value};
+use crate::tokenizer::{self, Whitespace};
+
+/// The answer of the last `rwc_count`.
+static ANSWER: Mutex<String> = Mutex::new(String::new());
+
+/// Allocates `len` bytes for the host to write into.
+#[no_mangle]
+pub extern "C" fn rwc_alloc(len: usize) -> *mut u8 {
+    let mut buffer = Vec::<u8>::with_capacity(len);
+    let ptr = buffer.as_mut_ptr();
+    std::mem::forget(buffer);
+    ptr
+}
+
+/// Frees what `rwc_alloc` returned.
+///
+/// # Safety
+///
+/// `ptr` and `len` must be the result and argument of one `rwc_alloc` call.
+#[no_mangle]
+pub unsafe extern "C" fn rwc_free(ptr: *mut u8, len: usize) {
+    drop(Vec::from_raw_parts(ptr, 0, len));
+}
+
+/// Counts the UTF-8 `text` with the JSON `options` (which may be empty) and
+/// returns a pointer to the answer; `rwc_answer_len` is its length.
+///
+/// # Safety
+///
+/// Both ranges must be readable memory, such as buffers from `rwc_alloc`.
+#[no_mangle]
+pub unsafe extern "C" fn rwc_count(
+    text: *const u8,
+    text_len: usize,
+    options: *const u8,
+    options_len: usize,
+) -> *const u8 {
+    let text = slice::from_raw_parts(text, text_len);
+    let options = slice::from_raw_parts(options, options_len);
+    let answer = match count(text, options) {
+        Ok(counts) => counts,
+        Err(e) => json::object([("error", e.into())]),
+    };
+    let mut kept = ANSWER.lock().unwrap_or_else(|e| e.into_inner());
+    *kept = answer.to_string();
+    kept.as_ptr()
+}
+
+/// The length of the answer of the last `rwc_count`.
+#[no_mangle]
+pub extern "C" fn rwc_answer_len() -> usize {
+    ANSWER.lock().unwrap_or_else(|e| e.into_inner()).len()
+}
+
+fn count(text: &[u8], options: &[u8]) -> Result<Value, String> {
+    let text = std::str::from_utf8(text).map_err(|_| engine::invalid_utf8().to_string())?;
+    let options = std::str::from_utf8(options).map_err(|e| e.to_string())?;
+    let options = if options.trim().is_empty() {
+        Value::Object(Vec::new())
+    } else {
+        json::parse(options)?
+    };
+    let flag = |key: &str| match options.get(key) {
+        None | Some(Value::Null) => Ok(false),
+        Some(Value::Bool(value)) => Ok(*value),
+        Some(_) => Err(format!("option '{}' must be true or false", key)),
+    };
+    let text_option = |key: &str| match options.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.as_str())),
+        Some(_) => Err(format!("option '{}' must be a string", key)),
+    };
+    let columns = |key: &str| match options.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => match value.as_f64() {
+            Some(columns) if columns >= 1.0 && columns.fract() == 0.0 => Ok(Some(columns as usize)),
+            _ => Err(format!("option '{}' must be a positive whole number", key)),
+        },
+    };
+    let tokenizer = text_option("tokenizer")?.map(tokenizer::builtin).transpose()?;
+    let metrics = Metrics {
+        unique_lines: flag("unique_lines")?,
+        unique_words: flag("unique_words")?,
+        approx: flag("approx")?,
+        ignore_format: flag("ignore_format_chars")?,
+        wrap_at: columns("wrap_at")?,
+        display_width: flag("max_display_width")?,
+        tab_size: columns("tab_size")?,
+        whitespace: text_option("whitespace")?
+            .map(Whitespace::parse)
+            .transpose()?
+            .unwrap_or_default(),
+        tokenizer: tokenizer.as_deref(),
+        ..Metrics::default()
+    };
+    Ok(to_json(&engine::scan(text, &metrics)))
+}
+
+fn to_json(counts: &Counts) -> Value {
+    let mut members = vec![
+        ("lines", counts.lines.into()),
+        ("words", counts.words.into()),
+        ("chars", counts.chars.into()),
+        ("bytes", counts.bytes.into()),
+        ("max_line_length", counts.max_line_length.into()),
+        ("paragraphs", counts.paragraphs.into()),
+        ("blank_lines", counts.blank_lines.into()),
+        ("sentences", counts.sentences.into()),
+    ];
+    let uniques = counts.uniques.as_ref();
+    for (key, error_key, tracker) in [
+        ("unique_lines", "unique_lines_error", uniques.and_then(|u| u.lines.as_ref())),
+        ("unique_words", "unique_words_error", uniques.and_then(|u| u.words.as_ref())),
+    ] {
+        if let Some(tracker) = tracker {
+            members.push((key, tracker.count().into()));
+            if let Some(error) = tracker.error() {
+                members.push((error_key, Value::Number((error * 1e4).round() / 1e4)));
+            }
+        }
+    }
+    if let Some(width) = counts.max_display_width {
+        members.push(("max_display_width", width.into()));
+    }
+    if let Some(visual) = counts.visual_lines {
+        members.push(("visual_lines", visual.into()));
+    }
+    Value::Object(members.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+}
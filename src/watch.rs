@@ -0,0 +1,116 @@
+//! `--watch`: recount and reprint whenever a watched file changes.
+//!
+//! Files are polled rather than subscribed to, which needs no platform
+//! support: every `POLL_INTERVAL` each file's size and modification time are
+//! compared with the last round, and any difference (including a file
+//! appearing or disappearing) reprints the counts of every file. With `-r`,
+//! directory operands are walked again each round so new files are picked up.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::format::Formats;
+use crate::json::Value;
+use crate::{
+    counts_json, formats, is_generated, output, print_counts, print_json, process_file, walk,
+    Config, Counts,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What is compared between rounds: each file with its size and
+/// modification time, or `None` if it could not be read.
+type Snapshot = Vec<(String, Option<(u64, SystemTime)>)>;
+
+/// Watches the configured files until interrupted. Returns the exit code.
+pub fn run(config: &Config) -> i32 {
+    if config.files.is_empty() || config.files.iter().any(|file| file == "-") {
+        eprintln!("rwc: --watch needs FILE operands; standard input cannot be watched");
+        return 1;
+    }
+    let formats = formats::formats(config);
+    let mut last: Option<Snapshot> = None;
+    loop {
+        let snapshot = match snapshot(config) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                eprintln!("rwc: {}", e);
+                return 1;
+            }
+        };
+        if last.as_ref() != Some(&snapshot) {
+            if config.clear_screen {
+                output::clear_screen();
+            } else if last.is_some() && !config.show_json {
+                output::print("");
+            }
+            report(&snapshot, &formats, config);
+            last = Some(snapshot);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Lists the files to watch this round, walking directories with `-r`.
+fn snapshot(config: &Config) -> io::Result<Snapshot> {
+    let mut files = Vec::new();
+    let mut budget = walk::Budget::new(config.limits);
+    for operand in &config.files {
+        if config.recursive && Path::new(operand).is_dir() {
+            let paths = walk::files_with(Path::new(operand), &mut budget, &config.walk)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", operand, e)))?;
+            files.extend(
+                paths
+                    .into_iter()
+                    .filter(|path| !(config.no_generated && is_generated(path)))
+                    .map(|path| path.display().to_string()),
+            );
+        } else {
+            files.push(operand.clone());
+        }
+    }
+    Ok(files
+        .into_iter()
+        .map(|file| {
+            let stamp = fs::metadata(&file)
+                .and_then(|m| Ok((m.len(), m.modified()?)))
+                .ok();
+            (file, stamp)
+        })
+        .collect())
+}
+
+/// Counts and prints every readable file in `snapshot`, with a total row
+/// when there are several. A file that fails to count (say, caught halfway
+/// through a write) is reported and left out until it next changes.
+fn report(snapshot: &Snapshot, formats: &Formats<'_, Counts>, config: &Config) {
+    let mut total = Counts::default();
+    let mut counted = 0;
+    let mut json_files = Vec::new();
+    for (file, _) in snapshot {
+        match process_file(file, config) {
+            Ok(counts) => {
+                if config.show_json {
+                    json_files.push(counts_json(&counts, config, Some(file)));
+                } else {
+                    print_counts(formats, &counts, config, Some(file));
+                }
+                total.add(&counts);
+                counted += 1;
+            }
+            Err(e) => output::warn(e.to_string()),
+        }
+    }
+    if config.show_json {
+        let document = Value::Object(vec![
+            ("files".to_string(), Value::Array(json_files)),
+            ("total".to_string(), counts_json(&total, config, None)),
+        ]);
+        print_json(&document, config);
+    } else if counted > 1 {
+        print_counts(formats, &total, config, Some(config.total_label()));
+    }
+}
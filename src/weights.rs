@@ -0,0 +1,52 @@
+//! `--weights FILE`: a per-file score from weighted pattern matches.
+//!
+//! The file maps regular expressions to weights, one `PATTERN=WEIGHT` per
+//! line (the last `=` separates the two, so patterns may contain `=`). Blank
+//! lines and lines starting with `#` are ignored:
+//!
+//! ```text
+//! # complexity score
+//! TODO=5
+//! \bunsafe\b=10
+//! ```
+
+use std::fs;
+
+use crate::regex::Regex;
+
+#[derive(Clone)]
+pub struct Weights {
+    rules: Vec<(Regex, f64)>,
+}
+
+impl Weights {
+    pub fn load(path: &str) -> Result<Weights, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut rules = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let at = |msg: String| format!("{}:{}: {}", path, number + 1, msg);
+            let (pattern, weight) = line
+                .rsplit_once('=')
+                .ok_or_else(|| at("expected PATTERN=WEIGHT".to_string()))?;
+            let weight: f64 = weight
+                .trim()
+                .parse()
+                .map_err(|_| at(format!("invalid weight '{}'", weight.trim())))?;
+            let regex = Regex::new(pattern.trim()).map_err(at)?;
+            rules.push((regex, weight));
+        }
+        Ok(Weights { rules })
+    }
+
+    /// Sums `weight × matches` over every pattern for one line.
+    pub fn score_line(&self, line: &str) -> f64 {
+        self.rules
+            .iter()
+            .map(|(regex, weight)| regex.count_matches(line) as f64 * weight)
+            .sum()
+    }
+}
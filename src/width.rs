@@ -0,0 +1,134 @@
+//! Terminal display width of characters, without external crates.
+//!
+//! East Asian wide and fullwidth characters and most emoji take two columns;
+//! combining marks, zero-width characters and controls take none. Everything
+//! else takes one. Tables cover the common blocks rather than all of Unicode.
+
+/// Tab stops are every 8 columns, as in terminals and `wc -L`, unless
+/// `--tab-size` says otherwise.
+pub const TAB_WIDTH: usize = 8;
+
+/// Columns `c` occupies on a terminal. Tabs are not handled here: their
+/// width depends on the column they start at (see `advance`).
+pub fn char_width(c: char) -> usize {
+    let cp = c as u32;
+    if cp < 0x7f {
+        return usize::from(cp >= 0x20);
+    }
+    if cp < 0xa0 || is_zero_width(cp) {
+        0
+    } else if is_wide(cp) {
+        2
+    } else {
+        1
+    }
+}
+
+/// The column after `c` is written at column `column`.
+pub fn advance(column: usize, c: char) -> usize {
+    advance_tabbed(column, c, TAB_WIDTH)
+}
+
+/// Like `advance`, with tab stops every `tab_size` columns.
+pub fn advance_tabbed(column: usize, c: char, tab_size: usize) -> usize {
+    match c {
+        '\t' => column + tab_size - column % tab_size,
+        c => column + char_width(c),
+    }
+}
+
+/// Display width of `text`, expanding tabs.
+pub fn str_width(text: &str) -> usize {
+    text.chars().fold(0, advance)
+}
+
+fn is_zero_width(cp: u32) -> bool {
+    matches!(
+        cp,
+        0x00ad
+            | 0x0300..=0x036f
+            | 0x0483..=0x0489
+            | 0x0591..=0x05bd
+            | 0x0610..=0x061a
+            | 0x064b..=0x065f
+            | 0x0670
+            | 0x06d6..=0x06dc
+            | 0x0900..=0x0903
+            | 0x093a..=0x094f
+            | 0x0e31
+            | 0x0e34..=0x0e3a
+            | 0x0e47..=0x0e4e
+            | 0x1160..=0x11ff
+            | 0x1ab0..=0x1aff
+            | 0x1dc0..=0x1dff
+            | 0x200b..=0x200f
+            | 0x2028..=0x202e
+            | 0x2060..=0x2064
+            | 0x20d0..=0x20ff
+            | 0xfe00..=0xfe0f
+            | 0xfe20..=0xfe2f
+            | 0xfeff
+            | 0x1f3fb..=0x1f3ff
+            | 0xe0000..=0xe0fff
+    )
+}
+
+fn is_wide(cp: u32) -> bool {
+    matches!(
+        cp,
+        0x1100..=0x115f
+            | 0x231a..=0x231b
+            | 0x2329..=0x232a
+            | 0x23e9..=0x23ec
+            | 0x25fd..=0x25fe
+            | 0x2614..=0x2615
+            | 0x2648..=0x2653
+            | 0x26a1
+            | 0x26aa..=0x26ab
+            | 0x26bd..=0x26be
+            | 0x26c4..=0x26c5
+            | 0x26d4
+            | 0x26ea
+            | 0x26f2..=0x26f5
+            | 0x26fa
+            | 0x26fd
+            | 0x2705
+            | 0x270a..=0x270b
+            | 0x2728
+            | 0x274c
+            | 0x2753..=0x2755
+            | 0x2757
+            | 0x2795..=0x2797
+            | 0x27b0
+            | 0x27bf
+            | 0x2b1b..=0x2b1c
+            | 0x2b50
+            | 0x2b55
+            | 0x2e80..=0x303e
+            | 0x3041..=0x33ff
+            | 0x3400..=0x4dbf
+            | 0x4e00..=0x9fff
+            | 0xa000..=0xa4cf
+            | 0xa960..=0xa97f
+            | 0xac00..=0xd7a3
+            | 0xf900..=0xfaff
+            | 0xfe10..=0xfe19
+            | 0xfe30..=0xfe6f
+            | 0xff00..=0xff60
+            | 0xffe0..=0xffe6
+            | 0x16fe0..=0x16fe4
+            | 0x17000..=0x18cff
+            | 0x1b000..=0x1b2ff
+            | 0x1f004
+            | 0x1f0cf
+            | 0x1f18e
+            | 0x1f191..=0x1f19a
+            | 0x1f200..=0x1f251
+            | 0x1f300..=0x1f64f
+            | 0x1f680..=0x1f6ff
+            | 0x1f7e0..=0x1f7eb
+            | 0x1f90c..=0x1f9ff
+            | 0x1fa70..=0x1faff
+            | 0x20000..=0x3fffd
+    )
+}
@@ -0,0 +1,81 @@
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::time::format_rfc3339;
+use crate::{count_text, output, Config, Counts};
+
+/// Counts standard input in consecutive time windows, printing one JSON
+/// record per window as it closes instead of a single final total.
+pub fn run(window: Duration, config: &Config) -> io::Result<()> {
+    let (tx, rx) = mpsc::channel::<io::Result<Vec<u8>>>();
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        loop {
+            let mut line = Vec::new();
+            match stdin.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {
+                    if tx.send(Ok(line)).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut start = SystemTime::now();
+    let mut started = Instant::now();
+    let mut deadline = started + window;
+    let mut counts = Counts::default();
+
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(line)) => counts.add(&count_text(&String::from_utf8_lossy(&line))),
+            Ok(Err(e)) => return Err(e),
+            Err(RecvTimeoutError::Timeout) => {
+                let end = SystemTime::now();
+                print_window(start, end, &counts, config);
+                if let Some(alert) = &config.alert {
+                    alert.check(counts.lines, started.elapsed());
+                }
+                counts = Counts::default();
+                start = end;
+                started = Instant::now();
+                deadline += window;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                print_window(start, SystemTime::now(), &counts, config);
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn print_window(start: SystemTime, end: SystemTime, counts: &Counts, config: &Config) {
+    let mut record = format!(
+        "{{\"start\": \"{}\", \"end\": \"{}\"",
+        format_rfc3339(start),
+        format_rfc3339(end)
+    );
+    if config.show_lines {
+        record.push_str(&format!(", \"lines\": {}", counts.lines));
+    }
+    if config.show_words {
+        record.push_str(&format!(", \"words\": {}", counts.words));
+    }
+    if config.show_chars {
+        record.push_str(&format!(", \"chars\": {}", counts.chars));
+    }
+    if config.show_bytes {
+        record.push_str(&format!(", \"bytes\": {}", counts.bytes));
+    }
+    record.push('}');
+    output::print(&record);
+}
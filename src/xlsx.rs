@@ -0,0 +1,335 @@
+//! `rwc xlsx`: sheets, non-empty cells and words of Excel workbooks.
+//!
+//! An `.xlsx` file is a zip archive of XML parts: `xl/workbook.xml` names the
+//! sheets, its relationships say which part holds each one, and text cells
+//! usually refer to `xl/sharedStrings.xml` by index rather than holding
+//! their text. Only the text of string cells (shared, inline or a formula's
+//! string result) counts towards words; numbers, dates and booleans count
+//! as cells only. `--auto` uses the same reading to count a workbook's text.
+
+use std::fs;
+use std::io;
+
+use crate::engine::{Metrics, Scanner};
+use crate::json::{self, Value};
+use crate::xml::{attribute, Event, Xml};
+use crate::{cli, format_number, zip};
+
+#[derive(Default)]
+struct SheetCounts {
+    /// Cells with a value.
+    cells: usize,
+    /// Cells holding a string.
+    text_cells: usize,
+    words: usize,
+}
+
+impl SheetCounts {
+    fn add(&mut self, other: &SheetCounts) {
+        self.cells += other.cells;
+        self.text_cells += other.text_cells;
+        self.words += other.words;
+    }
+
+    fn members(&self) -> [(&'static str, Value); 3] {
+        [
+            ("cells", self.cells.into()),
+            ("text_cells", self.text_cells.into()),
+            ("words", self.words.into()),
+        ]
+    }
+}
+
+/// A workbook's sheets in order, each with the text of its string cells.
+struct Workbook {
+    sheets: Vec<(String, SheetCounts, Vec<String>)>,
+}
+
+/// Entry point for `rwc xlsx`.
+pub fn run(args: &[String]) -> i32 {
+    let mut json = false;
+    let mut human = false;
+    let mut files = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--human" => human = true,
+            "--help" => {
+                println!("Usage: rwc xlsx [--json] [-h] FILE.xlsx...");
+                println!();
+                println!("Counts the non-empty cells, the cells holding text and the words in");
+                println!("them for every sheet of an Excel workbook, with a total per file.");
+                return 0;
+            }
+            a if a.starts_with('-') => {
+                cli::unknown_option("rwc xlsx", a, &["--json", "--human", "--help"]);
+                return 1;
+            }
+            file => files.push(file.to_string()),
+        }
+    }
+    if files.is_empty() {
+        eprintln!("rwc xlsx: no input files");
+        eprintln!("Try 'rwc xlsx --help' for more information.");
+        return 1;
+    }
+
+    let mut documents = Vec::new();
+    for file in &files {
+        let workbook = match fs::read(file).and_then(|data| read(&data)) {
+            Ok(workbook) => workbook,
+            Err(e) => {
+                eprintln!("rwc xlsx: {}: {}", file, e);
+                return 1;
+            }
+        };
+        let mut total = SheetCounts::default();
+        for (_, counts, _) in &workbook.sheets {
+            total.add(counts);
+        }
+        if json {
+            let sheets = workbook
+                .sheets
+                .iter()
+                .map(|(name, counts, _)| {
+                    let mut members = vec![("sheet".to_string(), Value::from(name.as_str()))];
+                    members.extend(counts.members().map(|(key, value)| (key.to_string(), value)));
+                    Value::Object(members)
+                })
+                .collect();
+            let mut totals = vec![("sheets".to_string(), workbook.sheets.len().into())];
+            totals.extend(total.members().map(|(key, value)| (key.to_string(), value)));
+            documents.push(json::object([
+                ("file", file.as_str().into()),
+                ("sheets", Value::Array(sheets)),
+                ("total", Value::Object(totals)),
+            ]));
+            continue;
+        }
+        let row = |counts: &SheetCounts, label: &str| {
+            println!(
+                "{:>8}{:>8}{:>10} {}",
+                format_number(counts.cells, human),
+                format_number(counts.text_cells, human),
+                format_number(counts.words, human),
+                label
+            );
+        };
+        for (name, counts, _) in &workbook.sheets {
+            row(counts, &format!("{}:{}", file, name));
+        }
+        let sheets = workbook.sheets.len();
+        let plural = if sheets == 1 { "" } else { "s" };
+        row(&total, &format!("{} ({} sheet{})", file, sheets, plural));
+    }
+    if json {
+        println!("{}", Value::Array(documents).pretty());
+    }
+    0
+}
+
+/// The text of every string cell, a line per cell and sheet after sheet,
+/// for `--auto`.
+pub fn text(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = String::new();
+    for (_, _, cells) in read(data)?.sheets {
+        for cell in cells {
+            out.push_str(&cell);
+            out.push('\n');
+        }
+    }
+    Ok(out.into_bytes())
+}
+
+fn read(data: &[u8]) -> io::Result<Workbook> {
+    let archive = zip::Archive::open(data)?;
+    let part = |name: &str| -> io::Result<Option<String>> {
+        match archive.entries().iter().find(|entry| entry.name == name) {
+            Some(entry) => Ok(Some(String::from_utf8_lossy(&archive.read(entry)?).into_owned())),
+            None => Ok(None),
+        }
+    };
+    let workbook = part("xl/workbook.xml")?.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "not an xlsx workbook (no xl/workbook.xml)")
+    })?;
+    let relationships = part("xl/_rels/workbook.xml.rels")?.unwrap_or_default();
+    let shared = part("xl/sharedStrings.xml")?.map_or_else(Vec::new, |xml| shared_strings(&xml));
+
+    // Relationship ids to the parts they name.
+    let mut targets = Vec::new();
+    for event in Xml::new(&relationships) {
+        if let Event::Start { name: "Relationship", attributes, .. } = event {
+            if let (Some(id), Some(target)) =
+                (attribute(attributes, "Id"), attribute(attributes, "Target"))
+            {
+                // Targets are relative to `xl/` unless absolute.
+                let target = match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("xl/{}", target),
+                };
+                targets.push((id, target));
+            }
+        }
+    }
+
+    let mut sheets = Vec::new();
+    for event in Xml::new(&workbook) {
+        let Event::Start { name: "sheet", attributes, .. } = event else {
+            continue;
+        };
+        let name = attribute(attributes, "name").unwrap_or_default();
+        let Some(id) = attribute(attributes, "r:id") else {
+            continue;
+        };
+        let Some((_, target)) = targets.iter().find(|(candidate, _)| *candidate == id) else {
+            continue;
+        };
+        let xml = part(target)?.unwrap_or_default();
+        let (counts, cells) = sheet(&xml, &shared)?;
+        sheets.push((name, counts, cells));
+    }
+    Ok(Workbook { sheets })
+}
+
+/// The strings of `sharedStrings.xml`; rich text runs are joined and
+/// phonetic guides (`rPh`) left out.
+fn shared_strings(xml: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let (mut in_text, mut in_phonetic) = (false, false);
+    for event in Xml::new(xml) {
+        match event {
+            Event::Start { name: "si", .. } => current.clear(),
+            Event::Start { name: "t", empty: false, .. } => in_text = true,
+            Event::Start { name: "rPh", empty: false, .. } => in_phonetic = true,
+            Event::End("t") => in_text = false,
+            Event::End("rPh") => in_phonetic = false,
+            Event::End("si") => strings.push(std::mem::take(&mut current)),
+            Event::Text(text) if in_text && !in_phonetic => current.push_str(&text),
+            _ => {}
+        }
+    }
+    strings
+}
+
+/// Counts the cells of one worksheet and collects the text of its string
+/// cells.
+fn sheet(xml: &str, shared: &[String]) -> io::Result<(SheetCounts, Vec<String>)> {
+    let metrics = Metrics::default();
+    let mut scanner = Scanner::new(&metrics);
+    let mut counts = SheetCounts::default();
+    let mut cells = Vec::new();
+    let mut kind = String::new();
+    let mut value = String::new();
+    let mut inline = String::new();
+    // Which element's text is being collected.
+    let (mut in_value, mut in_inline) = (false, false);
+    for event in Xml::new(xml) {
+        match event {
+            Event::Start { name: "c", attributes, empty } => {
+                kind = attribute(attributes, "t").unwrap_or_default();
+                value.clear();
+                inline.clear();
+                if empty {
+                    // Styled but empty.
+                    kind.clear();
+                }
+            }
+            Event::Start { name: "v", empty: false, .. } => in_value = true,
+            Event::Start { name: "t", empty: false, .. } => in_inline = true,
+            Event::End("v") => in_value = false,
+            Event::End("t") => in_inline = false,
+            Event::Text(text) if in_value => value.push_str(&text),
+            Event::Text(text) if in_inline => inline.push_str(&text),
+            Event::End("c") => {
+                let text = match kind.as_str() {
+                    "s" => value.trim().parse().ok().and_then(|i: usize| shared.get(i)).cloned(),
+                    "inlineStr" => Some(std::mem::take(&mut inline)),
+                    "str" => Some(std::mem::take(&mut value)),
+                    _ => None,
+                };
+                match text {
+                    Some(text) if !text.is_empty() => {
+                        counts.cells += 1;
+                        counts.text_cells += 1;
+                        scanner.feed(text.as_bytes())?;
+                        scanner.feed(b"\n")?;
+                        cells.push(text);
+                    }
+                    Some(_) => {}
+                    None if !value.is_empty() => counts.cells += 1,
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    counts.words = scanner.finish()?.words;
+    Ok((counts, cells))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_strings_join_runs_without_phonetics() {
+        let xml = r#"<sst count="3">
+            <si><t>plain text</t></si>
+            <si><r><rPr><b/></rPr><t>bold</t></r><r><t xml:space="preserve"> and not</t></r></si>
+            <si><t>漢字</t><rPh sb="0" eb="2"><t>かんじ</t></rPh></si>
+            <si><t/></si>
+        </sst>"#;
+        assert_eq!(
+            shared_strings(xml),
+            ["plain text", "bold and not", "漢字", ""]
+        );
+        assert!(shared_strings("").is_empty());
+        assert!(shared_strings("<sst><si><t>unclosed").is_empty());
+    }
+
+    #[test]
+    fn cells_by_type() {
+        let shared = ["first second".to_string(), String::new()];
+        let xml = r#"<worksheet><sheetData><row r="1">
+            <c r="A1" t="s"><v>0</v></c>
+            <c r="B1"><v>42</v></c>
+            <c r="C1" t="inlineStr"><is><t>one two three</t></is></c>
+            <c r="D1" t="str"><f>A1&amp;"!"</f><v>first second!</v></c>
+            <c r="E1" t="b"><v>1</v></c>
+            <c r="F1" s="3"/>
+            <c r="G1" t="s"><v>1</v></c>
+            <c r="H1" t="s"><v>7</v></c>
+            <c r="I1" t="s"><v>x</v></c>
+        </row></sheetData></worksheet>"#;
+        let (counts, cells) = sheet(xml, &shared).unwrap();
+        assert_eq!(cells, ["first second", "one two three", "first second!"]);
+        assert_eq!(counts.text_cells, 3);
+        // Numbers, booleans and string indexes that name no string count as
+        // cells without text; styled empty cells and empty strings do not.
+        assert_eq!(counts.cells, 3 + 4);
+        assert_eq!(counts.words, 7);
+    }
+
+    #[test]
+    fn empty_and_malformed_sheets() {
+        let (counts, cells) = sheet("", &[]).unwrap();
+        assert_eq!((counts.cells, counts.words), (0, 0));
+        assert!(cells.is_empty());
+        // A cell that never closes is not counted.
+        let (counts, _) = sheet(r#"<c t="inlineStr"><is><t>lost"#, &[]).unwrap();
+        assert_eq!(counts.cells, 0);
+    }
+
+    #[test]
+    fn workbooks_need_a_workbook_part() {
+        let error = read(b"plain text").err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let mut empty_zip = b"PK\x05\x06".to_vec();
+        empty_zip.resize(22, 0);
+        assert_eq!(
+            read(&empty_zip).err().unwrap().to_string(),
+            "not an xlsx workbook (no xl/workbook.xml)"
+        );
+    }
+}
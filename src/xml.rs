@@ -0,0 +1,107 @@
+//! A minimal pull reader for the XML that `rwc xlsx` and `--xliff` read.
+//!
+//! There is no validation and no DTD support: well-formed documents are read
+//! element by element, entity and character references in text and attribute
+//! values are decoded, and anything malformed ends the document early.
+
+use crate::transform::decode_entities;
+
+/// A step through an XML document; names have any namespace prefix removed.
+pub enum Event<'a> {
+    Start {
+        name: &'a str,
+        attributes: &'a str,
+        /// `<name/>`, which has no end tag.
+        empty: bool,
+    },
+    End(&'a str),
+    /// Character data, with references decoded.
+    Text(String),
+}
+
+/// Just enough of an XML reader for workbook parts and translation files:
+/// comments, processing instructions and declarations are skipped.
+pub struct Xml<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Xml<'a> {
+    pub fn new(xml: &'a str) -> Xml<'a> {
+        Xml { rest: xml }
+    }
+}
+
+pub fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
+
+impl<'a> Iterator for Xml<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Event<'a>> {
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+            let Some(lt) = self.rest.find('<') else {
+                let text = std::mem::take(&mut self.rest);
+                return Some(Event::Text(decode_entities(text)));
+            };
+            if lt > 0 {
+                let text = &self.rest[..lt];
+                self.rest = &self.rest[lt..];
+                return Some(Event::Text(decode_entities(text)));
+            }
+            if let Some(cdata) = self.rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>").unwrap_or(cdata.len());
+                self.rest = cdata.get(end + 3..).unwrap_or("");
+                return Some(Event::Text(cdata[..end].to_string()));
+            }
+            let (terminator, skipped) = if self.rest.starts_with("<!--") {
+                ("-->", true)
+            } else {
+                (">", self.rest.starts_with("<?") || self.rest.starts_with("<!"))
+            };
+            let Some(end) = self.rest.find(terminator) else {
+                self.rest = "";
+                return None;
+            };
+            let tag = &self.rest[1..end];
+            self.rest = &self.rest[end + terminator.len()..];
+            if skipped {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                return Some(Event::End(local_name(name.trim())));
+            }
+            let empty = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let (name, attributes) = tag
+                .split_once(|c: char| c.is_ascii_whitespace())
+                .unwrap_or((tag, ""));
+            return Some(Event::Start {
+                name: local_name(name),
+                attributes,
+                empty,
+            });
+        }
+    }
+}
+
+/// The decoded value of attribute `name`. A prefixed name such as `r:id`
+/// also matches the same attribute under another prefix.
+pub fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        let after = rest[eq + 1..].trim_start();
+        let quote = after.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let close = after[1..].find(quote)? + 1;
+        let prefixed = key.contains(':') && name.contains(':');
+        if key == name || (prefixed && local_name(key) == local_name(name)) {
+            return Some(decode_entities(&after[1..close]));
+        }
+        rest = &after[close + 1..];
+    }
+    None
+}
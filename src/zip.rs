@@ -0,0 +1,310 @@
+//! Reader for zip archives held in memory.
+//!
+//! Members are listed from the central directory at the end of the archive.
+//! Stored and deflated members can be read; zip64 archives and encrypted
+//! members are reported as unsupported.
+
+use std::io;
+
+use crate::inflate;
+
+/// A member of the archive, as listed in the central directory.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub name: String,
+    /// Uncompressed size.
+    size: u64,
+    method: u16,
+    encrypted: bool,
+    compressed_size: usize,
+    /// Offset of the member's local header.
+    offset: usize,
+}
+
+impl Entry {
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+pub struct Archive<'a> {
+    data: &'a [u8],
+    entries: Vec<Entry>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid zip archive: {}", message))
+}
+
+fn unsupported(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, message)
+}
+
+fn u16_at(data: &[u8], at: usize) -> io::Result<u16> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid("truncated"))
+}
+
+fn u32_at(data: &[u8], at: usize) -> io::Result<u32> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("truncated"))
+}
+
+impl<'a> Archive<'a> {
+    pub fn open(data: &'a [u8]) -> io::Result<Archive<'a>> {
+        // The end-of-central-directory record is 22 bytes plus a comment of
+        // up to 64 KiB. The comment may hold the record's signature too, so
+        // the record is the one whose comment runs to the end.
+        let earliest = data.len().saturating_sub(22 + 0xffff);
+        let end = (earliest..data.len().saturating_sub(21))
+            .rev()
+            .find(|&at| {
+                data[at..].starts_with(b"PK\x05\x06")
+                    && u16_at(data, at + 20).ok().map(|len| at + 22 + usize::from(len))
+                        == Some(data.len())
+            })
+            .ok_or_else(|| invalid("no central directory"))?;
+        let count = usize::from(u16_at(data, end + 10)?);
+        let mut at = u32_at(data, end + 16)? as usize;
+        if count == 0xffff || at == 0xffff_ffff {
+            return Err(unsupported("zip64 archives are not supported".to_string()));
+        }
+        if at + u32_at(data, end + 12)? as usize > end {
+            return Err(invalid("truncated"));
+        }
+
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if u32_at(data, at)? != 0x0201_4b50 {
+                return Err(invalid("bad central directory entry"));
+            }
+            let name_len = usize::from(u16_at(data, at + 28)?);
+            let extra_len = usize::from(u16_at(data, at + 30)?);
+            let comment_len = usize::from(u16_at(data, at + 32)?);
+            let name = data
+                .get(at + 46..at + 46 + name_len)
+                .ok_or_else(|| invalid("truncated"))?;
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                size: u64::from(u32_at(data, at + 24)?),
+                method: u16_at(data, at + 10)?,
+                encrypted: u16_at(data, at + 8)? & 1 != 0,
+                compressed_size: u32_at(data, at + 20)? as usize,
+                offset: u32_at(data, at + 42)? as usize,
+            });
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(Archive { data, entries })
+    }
+
+    /// The members in central directory order, directories included.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The uncompressed content of `entry`.
+    pub fn read(&self, entry: &Entry) -> io::Result<Vec<u8>> {
+        if entry.encrypted {
+            return Err(unsupported(format!("{}: encrypted members are not supported", entry.name)));
+        }
+        let at = entry.offset;
+        if u32_at(self.data, at)? != 0x0403_4b50 {
+            return Err(invalid("bad local header"));
+        }
+        // The local header's name and extra field may differ in length from
+        // the central directory's.
+        let start = at + 30 + usize::from(u16_at(self.data, at + 26)?)
+            + usize::from(u16_at(self.data, at + 28)?);
+        let compressed = self
+            .data
+            .get(start..start + entry.compressed_size)
+            .ok_or_else(|| invalid("truncated"))?;
+        let content = match entry.method {
+            0 => compressed.to_vec(),
+            8 => inflate::inflate(compressed)?,
+            method => {
+                return Err(unsupported(format!(
+                    "{}: compression method {} is not supported",
+                    entry.name, method
+                )))
+            }
+        };
+        if content.len() as u64 != entry.size {
+            return Err(invalid("member size does not match the central directory"));
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Member<'a> {
+        name: &'a str,
+        method: u16,
+        flags: u16,
+        data: &'a [u8],
+        size: usize,
+    }
+
+    fn stored<'a>(name: &'a str, data: &'a [u8]) -> Member<'a> {
+        Member {
+            name,
+            method: 0,
+            flags: 0,
+            data,
+            size: data.len(),
+        }
+    }
+
+    /// Lays out local headers, the central directory and its end record,
+    /// followed by `comment`.
+    fn archive(members: &[Member], comment: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for member in members {
+            let offset = out.len() as u32;
+            out.extend(b"PK\x03\x04");
+            out.extend([0; 22]);
+            out.extend((member.name.len() as u16).to_le_bytes());
+            out.extend(0u16.to_le_bytes());
+            out.extend(member.name.as_bytes());
+            out.extend(member.data);
+
+            central.extend(b"PK\x01\x02");
+            central.extend([0; 4]);
+            central.extend(member.flags.to_le_bytes());
+            central.extend(member.method.to_le_bytes());
+            central.extend([0; 8]);
+            central.extend((member.data.len() as u32).to_le_bytes());
+            central.extend((member.size as u32).to_le_bytes());
+            central.extend((member.name.len() as u16).to_le_bytes());
+            central.extend([0; 12]);
+            central.extend(offset.to_le_bytes());
+            central.extend(member.name.as_bytes());
+        }
+        let start = out.len() as u32;
+        out.extend(&central);
+        out.extend(b"PK\x05\x06");
+        out.extend([0; 4]);
+        out.extend((members.len() as u16).to_le_bytes());
+        out.extend((members.len() as u16).to_le_bytes());
+        out.extend((central.len() as u32).to_le_bytes());
+        out.extend(start.to_le_bytes());
+        out.extend((comment.len() as u16).to_le_bytes());
+        out.extend(comment);
+        out
+    }
+
+    fn error<T>(result: io::Result<T>) -> String {
+        match result {
+            Ok(_) => panic!("expected an error"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn stored_and_deflated_members() {
+        // A single stored deflate block holding "hello".
+        let deflated = [0x01, 0x05, 0x00, 0xfa, 0xff, b'h', b'e', b'l', b'l', b'o'];
+        let data = archive(
+            &[
+                stored("docs/", b""),
+                stored("docs/a.txt", b"one two\n"),
+                Member {
+                    name: "b.txt",
+                    method: 8,
+                    flags: 0,
+                    data: &deflated,
+                    size: 5,
+                },
+            ],
+            b"PK\x05\x06 in the comment is not the record",
+        );
+        let zip = Archive::open(&data).unwrap();
+        let names: Vec<&str> = zip.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["docs/", "docs/a.txt", "b.txt"]);
+        assert!(zip.entries()[0].is_dir());
+        assert!(!zip.entries()[1].is_dir());
+        assert_eq!(zip.read(&zip.entries()[0]).unwrap(), b"");
+        assert_eq!(zip.read(&zip.entries()[1]).unwrap(), b"one two\n");
+        assert_eq!(zip.read(&zip.entries()[2]).unwrap(), b"hello");
+        assert!(Archive::open(&archive(&[], b""))
+            .unwrap()
+            .entries()
+            .is_empty());
+    }
+
+    #[test]
+    fn malformed_archives_are_errors() {
+        assert_eq!(
+            error(Archive::open(b"")),
+            "invalid zip archive: no central directory"
+        );
+        assert_eq!(
+            error(Archive::open(b"PK\x03\x04 not really")),
+            "invalid zip archive: no central directory"
+        );
+
+        let data = archive(&[stored("a.txt", b"text")], b"");
+        for cut in [data.len() - 22 - 10, 40] {
+            let mut short = data[..cut].to_vec();
+            short.extend(&data[data.len() - 22..]);
+            assert!(Archive::open(&short).is_err(), "{}", cut);
+        }
+        let mut bad_offset = data.clone();
+        let end = data.len() - 22;
+        bad_offset[end + 16] -= 1;
+        assert_eq!(
+            error(Archive::open(&bad_offset)),
+            "invalid zip archive: bad central directory entry"
+        );
+
+        let mut zip64 = data.clone();
+        zip64[end + 10..end + 12].copy_from_slice(&[0xff, 0xff]);
+        assert_eq!(
+            error(Archive::open(&zip64)),
+            "zip64 archives are not supported"
+        );
+    }
+
+    #[test]
+    fn unreadable_members_are_errors() {
+        let member = |method, flags, data, size| Member {
+            name: "m",
+            method,
+            flags,
+            data,
+            size,
+        };
+        let read = |member: Member| {
+            let data = archive(&[member], b"");
+            let zip = Archive::open(&data).unwrap();
+            zip.read(&zip.entries()[0]).map(|_| ())
+        };
+        assert_eq!(
+            error(read(member(0, 1, b"x", 1))),
+            "m: encrypted members are not supported"
+        );
+        assert_eq!(
+            error(read(member(12, 0, b"x", 1))),
+            "m: compression method 12 is not supported"
+        );
+        assert_eq!(
+            error(read(member(0, 0, b"x", 2))),
+            "invalid zip archive: member size does not match the central directory"
+        );
+        assert!(read(member(8, 0, &[0xff, 0xff], 1)).is_err());
+
+        let mut data = archive(&[stored("a.txt", b"text")], b"");
+        data[0] = b'X';
+        let zip = Archive::open(&data).unwrap();
+        assert_eq!(
+            error(zip.read(&zip.entries()[0])),
+            "invalid zip archive: bad local header"
+        );
+    }
+}
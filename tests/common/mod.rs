@@ -0,0 +1,62 @@
+//! What the integration tests share: running the `rwc` binary and naming
+//! scratch files. Each test uses some of it.
+#![allow(dead_code)]
+
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Runs `rwc` with `args`.
+pub fn rwc<S: AsRef<OsStr>>(args: impl IntoIterator<Item = S>) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rwc"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Runs `rwc` with `args` in `dir`.
+pub fn rwc_in<S: AsRef<OsStr>>(dir: &Path, args: impl IntoIterator<Item = S>) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rwc"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Runs `rwc` with `args`, writing `input` to its standard input.
+pub fn rwc_with_input<S: AsRef<OsStr>>(
+    args: impl IntoIterator<Item = S>,
+    input: impl AsRef<[u8]>,
+) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rwc"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.as_ref().to_vec();
+    // Written from another thread so a large input cannot fill both pipes.
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap().unwrap();
+    output
+}
+
+/// The standard output of a run that must have succeeded.
+pub fn stdout(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// A path in the temporary directory for this process's `name`:
+/// `rwc-NAME-PID`.
+pub fn scratch(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rwc-{}-{}", name, std::process::id()))
+}
@@ -0,0 +1,97 @@
+//! `rwc compare-dirs` pairs a source tree with its translation by relative
+//! path.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::{rwc_in, scratch};
+
+fn trees(name: &str) -> PathBuf {
+    let dir = scratch(name);
+    for (file, text) in [
+        (
+            "en/guide/intro.md",
+            "# Intro\n\nThis is the guide to everything.\n",
+        ),
+        ("fr/guide/intro.md", "# Intro\n\nVoici le guide de tout.\n"),
+        ("en/page.html", "<p>Hello <b>world</b></p>\n"),
+        ("fr/page.html", "<p>Bonjour le <b>monde</b></p>\n"),
+        (
+            "en/setup.txt",
+            "one two three four five six seven eight nine ten\n",
+        ),
+        ("fr/setup.txt", "un deux\n"),
+        ("en/faq.txt", "only in English\n"),
+        ("fr/notes.txt", "seulement\n"),
+    ] {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn pairs_files_and_flags_gaps() {
+    let dir = trees("compare-dirs");
+    let output = rwc_in(&dir, ["compare-dirs", "en", "fr"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ["missing", "3", "-", "-", "faq.txt"],
+            ["ok", "8", "7", "0.88", "guide/intro.md"],
+            ["extra", "-", "1", "-", "notes.txt"],
+            ["ok", "2", "3", "1.50", "page.html"],
+            ["short", "10", "2", "0.20", "setup.txt"],
+        ]
+    );
+    assert!(
+        stdout.contains("3 paired (1 short, 0 long), 1 missing, 1 extra"),
+        "{}",
+        stdout
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn limits_and_json() {
+    let dir = trees("compare-dirs-json");
+    let output = rwc_in(
+        &dir,
+        ["compare-dirs", "--json", "--max-ratio", "1.2", "en", "fr"],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains(
+            "{\"file\":\"page.html\",\"status\":\"long\",\"source_words\":2,\"target_words\":3,\"ratio\":1.5}"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("{\"file\":\"faq.txt\",\"status\":\"missing\",\"source_words\":3,\"target_words\":null,\"ratio\":null}"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("\"source_words\":20,\"target_words\":12,\"ratio\":0.6}"),
+        "{}",
+        stdout
+    );
+
+    let output = rwc_in(&dir, ["compare-dirs", "en/setup.txt", "fr"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not a directory"));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,75 @@
+//! Compressed input is counted as the text inside it, and only input that
+//! really is compressed.
+
+mod common;
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use common::{scratch, stdout};
+
+/// `printf 'hello hello hello hello\n' | gzip -n`
+const HELLO_GZ: [u8; 29] = [
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57,
+    0xc8, 0x40, 0x27, 0xb9, 0x00, 0x00, 0x88, 0x59, 0x0b, 0x18, 0x00, 0x00, 0x00,
+];
+
+fn write(name: &str, data: &[u8]) -> PathBuf {
+    let path = scratch(name);
+    fs::write(&path, data).unwrap();
+    path
+}
+
+fn rwc(args: &[&str], path: &Path) -> Output {
+    common::rwc(args.iter().map(OsStr::new).chain([path.as_os_str()]))
+}
+
+fn counts(args: &[&str], path: &Path) -> String {
+    stdout(rwc(args, path))
+}
+
+#[test]
+fn gzip_is_counted_without_a_tool() {
+    let path = write("hello.txt.gz", &[HELLO_GZ, HELLO_GZ].concat());
+    // No PATH, so no gzip to run.
+    let output = Command::new(env!("CARGO_BIN_EXE_rwc"))
+        .args(["--json-compact", "-l", "-w"])
+        .arg(&path)
+        .env("PATH", "")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\"lines\":2,\"words\":8"), "{}", stdout);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn no_decompress_explains_binary_input() {
+    let path = write("raw.gz", &HELLO_GZ);
+    let output = rwc(&["--no-decompress"], &path);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--no-decompress"), "{}", stderr);
+    assert_eq!(
+        counts(&["--no-decompress", "-c"], &path)
+            .split_whitespace()
+            .next(),
+        Some("29")
+    );
+    assert!(counts(&["--no-decompress", "--lossy"], &path).contains("29"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn text_starting_like_bzip2_is_text() {
+    let path = write("bzh.txt", b"BZh is how this line starts\n");
+    assert_eq!(counts(&["-w"], &path).split_whitespace().next(), Some("6"));
+    fs::remove_file(path).unwrap();
+}
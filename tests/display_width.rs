@@ -0,0 +1,50 @@
+//! `--max-display-width`: the widest line in terminal columns, with tabs
+//! expanded to `--tab-size`; `-L` keeps `wc`'s tab stops every 8.
+
+mod common;
+
+use common::{rwc_with_input, stdout};
+
+fn widest(args: &[&str], text: &str) -> String {
+    stdout(rwc_with_input(args, text)).trim().to_string()
+}
+
+#[test]
+fn counts_terminal_columns() {
+    // 4 wide characters take 8 columns, though they are 4 characters and
+    // 12 bytes.
+    let text = "世界世界\nabcdef\n";
+    assert_eq!(widest(&["-L"], text), "8");
+    assert_eq!(widest(&["--max-display-width"], text), "8");
+    assert_eq!(widest(&["--max-display-width"], "e\u{301}\n"), "1");
+}
+
+#[test]
+fn expands_tabs_to_tab_size() {
+    let text = "\tab\nx\ty\n";
+    assert_eq!(widest(&["--max-display-width"], text), "10");
+    assert_eq!(widest(&["-L", "--tab-size", "4"], text), "10");
+    let both = widest(&["-L", "--max-display-width", "--tab-size", "4"], text);
+    assert_eq!(both.split_whitespace().collect::<Vec<_>>(), ["10", "6"]);
+    assert!(widest(
+        &["--max-display-width", "--tab-size", "2", "--json-compact"],
+        text
+    )
+    .contains("\"max_display_width\":4"));
+    assert_eq!(
+        widest(&["--max-display-width", "--tab-size", "4"], text),
+        "6"
+    );
+    assert_eq!(
+        widest(&["--max-display-width", "--tab-size", "1"], text),
+        "3"
+    );
+    assert!(widest(
+        &["--wrap-at", "5", "--tab-size", "4", "--json-compact"],
+        "\tab\n"
+    )
+    .contains("\"visual_lines\":2"));
+
+    let output = rwc_with_input(["--tab-size", "0"], "");
+    assert!(!output.status.success());
+}
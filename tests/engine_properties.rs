@@ -0,0 +1,265 @@
+//! Invariants of the counting engine, checked on generated inputs: the
+//! counts do not depend on how the input is split into chunks, agree with a
+//! naive reference and with GNU wc, and never report fewer bytes than
+//! characters. `fuzz/` checks the same on inputs found by coverage.
+//!
+//! Inputs come from a small seeded generator, so a failure names the seed
+//! that reproduces it.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use rwc::engine::{self, Counts, Metrics, Scanner};
+use rwc::tokenizer::{self, Tokenizer};
+
+const CASES: u64 = 500;
+
+/// xorshift64*: enough to spread inputs over the alphabet, and repeatable.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Pieces text is made of: words, every kind of separator the engine
+/// treats specially, and characters of one to four bytes, wide, combining
+/// and invisible ones included.
+const PIECES: &[&str] = &[
+    "word",
+    "a",
+    "can't",
+    "3.14",
+    "e.g.",
+    "end.",
+    "Yes!",
+    "\"Quoted.\"",
+    " ",
+    "  ",
+    "\t",
+    "\n",
+    "\n\n",
+    "\r\n",
+    "\r",
+    "\x0b",
+    "\x0c",
+    "\u{a0}",
+    "\u{3000}",
+    "é",
+    "e\u{301}",
+    "世界",
+    "カタカナ",
+    "こんにちは",
+    "。",
+    "😀",
+    "\u{200b}",
+    "\u{ad}",
+    "\u{200d}",
+    "ไทย",
+    "한국어",
+];
+
+/// Printable ASCII separated by spaces, tabs and newlines only, on which
+/// rwc and GNU wc must agree exactly.
+const ASCII_PIECES: &[&str] = &[
+    "word", "x", "it's", "--", "3.14", "(a)", " ", "  ", "\t", "\n", "\n\n",
+];
+
+fn text(rng: &mut Rng, pieces: &[&str]) -> String {
+    let len = rng.below(60);
+    (0..len).map(|_| pieces[rng.below(pieces.len())]).collect()
+}
+
+/// `text` as bytes with, now and then, a byte that is not valid UTF-8.
+fn bytes(rng: &mut Rng) -> Vec<u8> {
+    let mut data = text(rng, PIECES).into_bytes();
+    for _ in 0..rng.below(3) {
+        let at = rng.below(data.len() + 1);
+        data.insert(at, [0xff, 0xc3, 0xe4, 0x80][rng.below(4)]);
+    }
+    data
+}
+
+/// Everything a scan reports that can be compared.
+fn summary(counts: &Counts) -> Vec<usize> {
+    let uniques = counts.uniques.as_ref();
+    vec![
+        counts.lines,
+        counts.words,
+        counts.chars,
+        counts.bytes,
+        counts.max_line_length,
+        counts.paragraphs,
+        counts.blank_lines,
+        counts.sentences,
+        uniques
+            .and_then(|u| u.lines.as_ref())
+            .map_or(0, |t| t.count()),
+        uniques
+            .and_then(|u| u.words.as_ref())
+            .map_or(0, |t| t.count()),
+        counts.visual_lines.unwrap_or(0),
+    ]
+}
+
+fn chunked(data: &[u8], metrics: &Metrics, rng: &mut Rng) -> std::io::Result<Counts> {
+    let mut scanner = Scanner::new(metrics);
+    let mut rest = data;
+    while !rest.is_empty() {
+        let (chunk, tail) = rest.split_at(1 + rng.below(rest.len().min(8)));
+        scanner.feed(chunk)?;
+        rest = tail;
+    }
+    scanner.finish()
+}
+
+fn all_metrics(tokenizer: Option<&dyn Tokenizer>) -> Metrics<'_> {
+    Metrics {
+        unique_lines: true,
+        unique_words: true,
+        wrap_at: Some(7),
+        tokenizer,
+        lossy: true,
+        ..Metrics::default()
+    }
+}
+
+#[test]
+fn chunking_does_not_change_counts() {
+    let uax29 = tokenizer::Uax29;
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let data = bytes(&mut rng);
+        for metrics in [
+            Metrics::default(),
+            all_metrics(None),
+            all_metrics(Some(&uax29)),
+        ] {
+            let mut whole = Scanner::new(&metrics);
+            let whole = whole.feed(&data).and_then(|()| whole.finish());
+            let split = chunked(&data, &metrics, &mut rng);
+            match (whole, split) {
+                (Ok(whole), Ok(split)) => {
+                    assert_eq!(summary(&whole), summary(&split), "seed {}", seed)
+                }
+                (Err(_), Err(_)) => {}
+                (whole, split) => panic!("seed {}: {:?} but chunked {:?}", seed, whole, split),
+            }
+        }
+    }
+}
+
+#[test]
+fn bytes_are_at_least_chars() {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let data = bytes(&mut rng);
+        let metrics = Metrics {
+            lossy: true,
+            ..Metrics::default()
+        };
+        let counts = chunked(&data, &metrics, &mut rng).unwrap();
+        // A replacement character stands for at least one byte.
+        assert!(counts.bytes >= counts.chars, "seed {}", seed);
+        assert_eq!(counts.bytes, data.len(), "seed {}", seed);
+    }
+}
+
+#[test]
+fn agrees_with_a_naive_reference() {
+    for seed in 0..CASES {
+        let text = text(&mut Rng::new(seed), PIECES);
+        let counts = engine::scan(&text, &Metrics::default());
+        let lines =
+            text.matches('\n').count() + usize::from(!text.is_empty() && !text.ends_with('\n'));
+        assert_eq!(counts.lines, lines, "seed {}", seed);
+        assert_eq!(
+            counts.words,
+            text.split_whitespace().count(),
+            "seed {}",
+            seed
+        );
+        assert_eq!(counts.chars, text.chars().count(), "seed {}", seed);
+        assert_eq!(counts.bytes, text.len(), "seed {}", seed);
+        let blank = text
+            .split_terminator('\n')
+            .filter(|line| line.trim().is_empty())
+            .count();
+        assert_eq!(counts.blank_lines, blank, "seed {}", seed);
+    }
+}
+
+#[test]
+fn tokenizers_find_words_in_order() {
+    let tokenizers: [Box<dyn Tokenizer>; 4] = [
+        tokenizer::builtin("whitespace").unwrap(),
+        tokenizer::builtin("uax29").unwrap(),
+        tokenizer::builtin("cjk").unwrap(),
+        tokenizer::builtin("regex:[a-z]+").unwrap(),
+    ];
+    for seed in 0..CASES {
+        let text = text(&mut Rng::new(seed), PIECES);
+        for line in text.lines() {
+            for tokenizer in &tokenizers {
+                // Each word is a non-empty part of the line after the last.
+                let mut rest = line;
+                tokenizer.words(line, &mut |word| {
+                    assert!(!word.is_empty(), "seed {}", seed);
+                    let at = rest.find(word).unwrap_or_else(|| panic!("seed {}", seed));
+                    rest = &rest[at + word.len()..];
+                });
+            }
+        }
+        let whitespace = tokenizer::count_words(&text, tokenizers[0].as_ref());
+        let counts = engine::scan(&text, &Metrics::default());
+        assert_eq!(whitespace, counts.words, "seed {}", seed);
+    }
+}
+
+#[test]
+fn agrees_with_gnu_wc_on_ascii() {
+    let Ok(wc) = Command::new("wc").arg("--version").output() else {
+        return;
+    };
+    if !String::from_utf8_lossy(&wc.stdout).contains("GNU") {
+        return;
+    }
+    for seed in 0..50 {
+        // wc leaves out a last line without a newline; rwc counts it.
+        let text = text(&mut Rng::new(seed), ASCII_PIECES) + "\n";
+        let run = |program: &str| {
+            let mut child = Command::new(program)
+                .args(["-l", "-w", "-c"])
+                .env("LC_ALL", "C")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(text.as_bytes())
+                .unwrap();
+            let output = child.wait_with_output().unwrap();
+            let output = String::from_utf8(output.stdout).unwrap();
+            output
+                .split_whitespace()
+                .map(|n| n.parse().unwrap())
+                .collect::<Vec<u64>>()
+        };
+        assert_eq!(run(env!("CARGO_BIN_EXE_rwc")), run("wc"), "seed {}", seed);
+    }
+}
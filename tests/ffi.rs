@@ -0,0 +1,76 @@
+//! The C API: a C program built against the generated `rwc.h` and linked
+//! with the shared library gets the counts the command line gives.
+#![cfg(all(feature = "cdylib", unix))]
+
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::fs;
+use std::process::Command;
+
+const PROGRAM: &str = r#"
+#include <stdio.h>
+#include "rwc.h"
+
+int main(int argc, char **argv) {
+    const char *text = "h\xc3\xa9llo w\xc3\xb6rld\n\nagain\n";
+    rwc_counts counts;
+    int status = rwc_count_buffer((const uint8_t *)text, 19, &counts);
+    printf("%d %llu %llu %llu %llu %llu\n", status,
+           (unsigned long long)counts.lines, (unsigned long long)counts.words,
+           (unsigned long long)counts.chars, (unsigned long long)counts.bytes,
+           (unsigned long long)counts.paragraphs);
+    printf("%d\n", rwc_count_file(argv[1], &counts));
+    printf("%d\n", rwc_count_buffer(NULL, 1, &counts) == RWC_NULL_ARGUMENT);
+    printf("%d\n", rwc_count_buffer((const uint8_t *)"\xff", 1, &counts) == RWC_INVALID_UTF8);
+    return argc == 2 ? 0 : 1;
+}
+"#;
+
+#[test]
+fn counts_from_c() {
+    // Cargo builds the shared library for this run next to the test.
+    let exe = std::env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap();
+    let library = lib_dir.join(format!("{}rwc{}", DLL_PREFIX, DLL_SUFFIX));
+    assert!(library.exists(), "{} was not built", library.display());
+
+    let dir = std::env::temp_dir().join(format!("rwc-ffi-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("main.c");
+    fs::write(&source, PROGRAM).unwrap();
+    let program = dir.join("main");
+    let compiled = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg(&source)
+        .arg("-I")
+        .arg(env!("OUT_DIR"))
+        .arg("-L")
+        .arg(lib_dir)
+        .args(["-lrwc", "-o"])
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert!(
+        compiled.status.success(),
+        "{}",
+        String::from_utf8_lossy(&compiled.stderr)
+    );
+
+    // Cargo's LD_LIBRARY_PATH may hold a library built with other features.
+    let output = Command::new(&program)
+        .arg(dir.join("missing.txt"))
+        .env("LD_LIBRARY_PATH", lib_dir)
+        .env("DYLD_LIBRARY_PATH", lib_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{:?}: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        // ENOENT for the missing file.
+        "0 3 3 17 19 2\n2\n1\n1\n"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
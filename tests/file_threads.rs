@@ -0,0 +1,71 @@
+//! `--file-threads` counts a large file in ranges on several threads, with
+//! the same numbers as a single pass.
+
+mod common;
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use common::{rwc, scratch, stdout};
+
+/// Over 8 MiB, so the file is split: paragraphs of several lines, blank
+/// lines, sentences, tabs and multibyte text, so every cut falls somewhere
+/// that needs joining.
+fn large_text() -> String {
+    let lines = [
+        "The first line of a paragraph. It has two sentences!",
+        "\tnaïve café, 世界 and 😀 on the second line",
+        "and a third, ending without a stop",
+        "",
+        "   ",
+        "One-line paragraph? Yes.\r",
+        "",
+    ];
+    let mut text = String::new();
+    let mut n = 0;
+    while text.len() < 9 << 20 {
+        text.push_str(lines[n % lines.len()]);
+        text.push('\n');
+        n += 1;
+    }
+    text
+}
+
+fn write(name: &str, text: &str) -> PathBuf {
+    let path = scratch(name).with_extension("txt");
+    fs::write(&path, text).unwrap();
+    path
+}
+
+fn counts(path: &Path, args: &[&str]) -> String {
+    let metrics = ["--json-compact", "-l", "-w", "-m", "-c", "-L"];
+    let more = ["--paragraphs", "--blank-lines", "--show-all"];
+    let args = args.iter().chain(&metrics).chain(&more).map(OsStr::new);
+    stdout(rwc(args.chain([path.as_os_str()])))
+}
+
+#[test]
+fn ranges_join_to_a_single_pass() {
+    let path = write("file-threads", &large_text());
+    let single = counts(&path, &[]);
+    for threads in ["2", "3", "7"] {
+        assert_eq!(counts(&path, &["--file-threads", threads]), single);
+    }
+    let wrapped = ["--wrap-width", "20"];
+    assert_eq!(
+        counts(&path, &[&wrapped[..], &["--file-threads", "4"]].concat()),
+        counts(&path, &wrapped)
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn lines_are_never_split() {
+    // Too few newlines to cut anywhere but near the end.
+    let mut text = "word ".repeat(2 << 20);
+    text.push_str("\n\nlast paragraph\nno newline");
+    let path = write("file-threads-long-line", &text);
+    assert_eq!(counts(&path, &["--file-threads", "4"]), counts(&path, &[]));
+    fs::remove_file(path).unwrap();
+}
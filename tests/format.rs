@@ -0,0 +1,85 @@
+//! The library's output formats, and formats a program registers beside
+//! them.
+
+use rwc::engine::{self, Counts, Metrics};
+use rwc::format::{self, Column, Formats, OutputFormatter};
+
+fn counts() -> Counts {
+    engine::scan("one two\nthree\n", &Metrics::default())
+}
+
+#[test]
+fn builtin_formats() {
+    let columns = [Column::Lines, Column::Words, Column::Bytes];
+    let formats = format::builtin(&columns);
+    assert_eq!(
+        formats.names().collect::<Vec<_>>(),
+        ["table", "csv", "ndjson"]
+    );
+
+    let table = formats.get("table").unwrap();
+    assert_eq!(table.header(), None);
+    assert_eq!(
+        table.format(&counts(), Some("a.txt")),
+        "2        3        14 a.txt"
+    );
+    assert_eq!(
+        table.format(&engine::scan("", &Metrics::default()), None),
+        "0        0        0"
+    );
+    let cells = ["\x1b[31m7\x1b[0m".to_string()];
+    assert_eq!(format::table_row(&cells), "       \x1b[31m7\x1b[0m");
+
+    let csv = formats.get("csv").unwrap();
+    assert_eq!(csv.header().as_deref(), Some("file,lines,words,bytes"));
+    assert_eq!(
+        csv.format(&counts(), Some("a, b.txt")),
+        "\"a, b.txt\",2,3,14"
+    );
+
+    let ndjson = formats.get("ndjson").unwrap();
+    assert_eq!(
+        ndjson.format(&counts(), None),
+        "{\"lines\":2,\"words\":3,\"bytes\":14}"
+    );
+    assert!(formats.get("xml").is_none());
+}
+
+/// Words per line, as a program might add.
+struct Density;
+
+impl OutputFormatter<Counts> for Density {
+    fn format(&self, counts: &Counts, name: Option<&str>) -> String {
+        let density = counts.words as f64 / counts.lines as f64;
+        format!("{}: {:.1}", name.unwrap_or("-"), density)
+    }
+}
+
+#[test]
+fn registered_formats() {
+    let mut formats = format::builtin(&Column::ALL);
+    formats.register("density", Density);
+    assert_eq!(
+        formats.get("density").unwrap().format(&counts(), None),
+        "-: 1.5"
+    );
+
+    // A name already taken is replaced in place.
+    formats.register("table", Density);
+    assert_eq!(
+        formats.names().collect::<Vec<_>>(),
+        ["table", "csv", "ndjson", "density"]
+    );
+    assert_eq!(
+        formats
+            .get("table")
+            .unwrap()
+            .format(&counts(), Some("a.txt")),
+        "a.txt: 1.5"
+    );
+
+    let mut empty: Formats<Counts> = Formats::default();
+    assert!(empty.get("table").is_none());
+    empty.register("table", Density);
+    assert!(empty.get("table").is_some());
+}
@@ -0,0 +1,199 @@
+//! rwc against GNU wc on files that trip word counters up: no trailing
+//! newline, CRLF, UTF-16, multibyte text and lines longer than a read
+//! buffer.
+//!
+//! Each file carries the numbers GNU wc 9.1 reports for it in a UTF-8
+//! locale, so the suite catches drift anywhere; where GNU wc is installed,
+//! its live output is checked as well. rwc differs from wc in two ways, on
+//! purpose: a last line without a newline is still a line, and UTF-16 is
+//! decoded before characters and words are counted.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// The columns compared, in the order both tools print them.
+const FLAGS: [&str; 5] = ["-l", "-w", "-m", "-c", "-L"];
+
+struct Case {
+    name: &'static str,
+    data: Vec<u8>,
+    /// What GNU wc prints for `FLAGS`.
+    wc: [u64; 5],
+    /// What rwc prints instead, where it differs on purpose.
+    rwc: Option<[u64; 5]>,
+}
+
+fn utf16(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+    let bom = if bom { "\u{feff}" } else { "" };
+    (bom.to_string() + text)
+        .encode_utf16()
+        .flat_map(|unit| {
+            if big_endian {
+                unit.to_be_bytes()
+            } else {
+                unit.to_le_bytes()
+            }
+        })
+        .collect()
+}
+
+fn cases() -> Vec<Case> {
+    let case = |name, data: &[u8], wc| Case {
+        name,
+        data: data.to_vec(),
+        wc,
+        rwc: None,
+    };
+    let mixed = "A line of text, été 世界.\r\nSecond line\n\ttabbed\n";
+    vec![
+        case("empty", b"", [0, 0, 0, 0, 0]),
+        case("blank-lines", b"\n\n\n", [3, 0, 3, 3, 0]),
+        Case {
+            rwc: Some([1, 4, 17, 17, 17]),
+            ..case(
+                "no-trailing-newline",
+                b"no newline at end",
+                [0, 4, 17, 17, 17],
+            )
+        },
+        case("crlf", b"one two\r\nthree\r\n\r\n", [3, 3, 18, 18, 7]),
+        case(
+            "multibyte",
+            "café naïve 世界 😀\nこん\n".as_bytes(),
+            [2, 5, 19, 32, 18],
+        ),
+        case("tabs", b"a\tb\tc\n\t\tx\n", [2, 4, 10, 10, 17]),
+        case(
+            "odd-spaces",
+            " \x0b \x0c x\u{a0}y\n".as_bytes(),
+            [1, 2, 9, 10, 4],
+        ),
+        case(
+            "huge-line",
+            format!("{}\n", ["word"; 40_000].join(" ")).as_bytes(),
+            [1, 40_000, 200_000, 200_000, 199_999],
+        ),
+        // Multibyte characters straddle every read-buffer boundary.
+        case(
+            "huge-multibyte-lines",
+            format!("{}\n{}\n", "é世 ".repeat(100_000), "x".repeat(70_000)).as_bytes(),
+            [2, 100_001, 370_002, 670_002, 400_000],
+        ),
+        case("utf8-reference", mixed.as_bytes(), [3, 9, 45, 51, 25]),
+        // wc sees UTF-16 as bytes; rwc counts the text, as in the UTF-8
+        // reference above, and keeps wc's byte count.
+        Case {
+            rwc: Some([3, 9, 45, 92, 25]),
+            ..case(
+                "utf16le-bom",
+                &utf16(mixed, false, true),
+                [3, 9, 88, 92, 22],
+            )
+        },
+        Case {
+            rwc: Some([3, 9, 45, 90, 25]),
+            ..case("utf16be", &utf16(mixed, true, false), [3, 9, 88, 90, 22])
+        },
+    ]
+}
+
+fn numbers(output: &[u8]) -> Vec<u64> {
+    String::from_utf8_lossy(output)
+        .split_whitespace()
+        .filter_map(|n| n.parse().ok())
+        .collect()
+}
+
+fn run_on_stdin(program: &str, data: &[u8]) -> Vec<u64> {
+    let mut child = Command::new(program)
+        .args(FLAGS)
+        .env("LC_ALL", "C.UTF-8")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let data = data.to_vec();
+    // Written from another thread so a large file cannot fill both pipes.
+    let writer = std::thread::spawn(move || stdin.write_all(&data).unwrap());
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    assert!(output.status.success(), "{} failed", program);
+    numbers(&output.stdout)
+}
+
+/// Whether GNU wc is installed and counts characters as UTF-8, which the
+/// recorded numbers assume.
+fn gnu_wc() -> bool {
+    let Ok(version) = Command::new("wc").arg("--version").output() else {
+        return false;
+    };
+    String::from_utf8_lossy(&version.stdout).contains("GNU")
+        && run_on_stdin("wc", "é\n".as_bytes())[2] == 2
+}
+
+fn corpus(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rwc-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for case in cases() {
+        fs::write(dir.join(case.name), &case.data).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn matches_recorded_wc_numbers() {
+    for case in cases() {
+        let expected = case.rwc.unwrap_or(case.wc);
+        assert_eq!(
+            run_on_stdin(env!("CARGO_BIN_EXE_rwc"), &case.data),
+            expected,
+            "{}",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn recorded_numbers_match_installed_wc() {
+    if !gnu_wc() {
+        return;
+    }
+    for case in cases() {
+        assert_eq!(run_on_stdin("wc", &case.data), case.wc, "{}", case.name);
+    }
+}
+
+#[test]
+fn totals_match_wc() {
+    if !gnu_wc() {
+        return;
+    }
+    let dir = corpus("gnu-wc");
+    let names: Vec<&str> = cases().iter().map(|case| case.name).collect();
+    let total = |program: &str, dir: &Path| {
+        let output = Command::new(program)
+            .current_dir(dir)
+            .args(FLAGS)
+            .args(&names)
+            .env("LC_ALL", "C.UTF-8")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        numbers(stdout.lines().next_back().unwrap().as_bytes())
+    };
+    let mut expected = total("wc", &dir);
+    // Per-file differences carry into the total; the longest line does not
+    // change.
+    for case in cases() {
+        if let Some(rwc) = case.rwc {
+            for (column, total) in expected.iter_mut().enumerate().take(4) {
+                *total = *total + rwc[column] - case.wc[column];
+            }
+        }
+    }
+    assert_eq!(total(env!("CARGO_BIN_EXE_rwc"), &dir), expected);
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,72 @@
+//! Ctrl-C during a recursive count: the files counted so far are reported
+//! as partial, and rwc exits with 130.
+#![cfg(unix)]
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+
+#[test]
+fn sigint_reports_partial_counts() {
+    let dir = std::env::temp_dir().join(format!("rwc-interrupt-{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.txt"), "one\n").unwrap();
+    fs::write(dir.join("sub/b.txt"), "two\nthree\n").unwrap();
+
+    // Standard input comes last and waits on a pipe, so the signal lands
+    // while the directory's files are counted and the pipe is not.
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rwc"))
+        .arg("-l")
+        .arg("-r")
+        .arg(&dir)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"four\n").unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut rows = String::new();
+    for _ in 0..2 {
+        stdout.read_line(&mut rows).unwrap();
+    }
+
+    let killed = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    // The read the signal interrupted carries on; more input ends it. If
+    // the signal came before standard input was started, rwc has already
+    // gone and the pipe is closed.
+    let _ = stdin.write_all(b"five\n");
+
+    stdout.read_to_string(&mut rows).unwrap();
+    let mut stderr = String::new();
+    child
+        .stderr
+        .take()
+        .unwrap()
+        .read_to_string(&mut stderr)
+        .unwrap();
+    let status = child.wait().unwrap();
+    drop(stdin);
+
+    assert_eq!(status.code(), Some(130), "{}", stderr);
+    assert!(
+        stderr.contains("interrupted after 2 files; results are partial"),
+        "{}",
+        stderr
+    );
+    let rows: Vec<&str> = rows.lines().collect();
+    assert_eq!(rows.len(), 3, "{:?}", rows);
+    assert!(rows[0].ends_with("a.txt"), "{:?}", rows);
+    assert!(rows[1].ends_with("b.txt"), "{:?}", rows);
+    assert_eq!(
+        rows[2].split_whitespace().collect::<Vec<_>>(),
+        ["3", "total", "(partial)"]
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
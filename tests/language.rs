@@ -0,0 +1,132 @@
+//! `--detect-language` and `--group-by language`, built with the `language`
+//! feature.
+
+#![cfg(feature = "language")]
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::{rwc_in, scratch, stdout};
+
+const SAMPLES: &[(&str, &str)] = &[
+    (
+        "en",
+        "The weather was fine, so we went out to the park with the children and it was a good day.",
+    ),
+    (
+        "de",
+        "Der Hund ist nicht in dem Haus, und die Katze ist auch nicht bei uns gewesen.",
+    ),
+    (
+        "fr",
+        "Le chat est dans la maison et il ne veut pas sortir avec nous pour le moment.",
+    ),
+    (
+        "es",
+        "El perro está en la casa y no quiere salir con nosotros porque hace mucho frío.",
+    ),
+    (
+        "ru",
+        "Это был очень хороший день, и мы не знали, что он так быстро закончится.",
+    ),
+    (
+        "uk",
+        "Це був дуже гарний день, і ми не знали, що він так швидко закінчиться.",
+    ),
+    ("ja", "今日はとても良い天気ですね。私は公園に行きました。"),
+    (
+        "zh",
+        "今天天气很好，我们一起去公园散步，然后在湖边吃了午饭。",
+    ),
+    (
+        "ko",
+        "오늘은 날씨가 아주 좋아서 우리는 공원에 산책하러 갔습니다.",
+    ),
+    (
+        "el",
+        "Σήμερα ο καιρός είναι πολύ καλός και πήγαμε στο πάρκο.",
+    ),
+    ("ar", "الطقس اليوم جميل جدا وذهبنا إلى الحديقة مع الأطفال."),
+];
+
+fn samples(name: &str) -> PathBuf {
+    let dir = scratch(name);
+    fs::create_dir_all(&dir).unwrap();
+    for (code, text) in SAMPLES {
+        fs::write(dir.join(format!("{}.txt", code)), format!("{}\n", text)).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn detects_each_sample() {
+    let dir = samples("language");
+    for (code, _) in SAMPLES {
+        let file = format!("{}.txt", code);
+        let stdout = stdout(rwc_in(&dir, ["--detect-language", "--json-compact", &file]));
+        assert!(
+            stdout.contains(&format!("\"language\":\"{}\"", code)),
+            "{}: {}",
+            code,
+            stdout
+        );
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn too_little_text_is_undetermined() {
+    let dir = samples("language-short");
+    fs::write(dir.join("short.txt"), "ok\n").unwrap();
+    fs::write(dir.join("numbers.txt"), "1 2 3 4 5\n").unwrap();
+    let stdout = stdout(rwc_in(
+        &dir,
+        [
+            "--detect-language",
+            "--json-compact",
+            "short.txt",
+            "numbers.txt",
+        ],
+    ));
+    assert_eq!(
+        stdout.matches("\"language\":\"und\"").count(),
+        2,
+        "{}",
+        stdout
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn groups_words_by_language() {
+    let dir = samples("language-groups");
+    fs::write(
+        dir.join("en2.txt"),
+        "This is the second file and it is written in English too.\n",
+    )
+    .unwrap();
+    let stdout = stdout(rwc_in(
+        &dir,
+        [
+            "--group-by",
+            "language",
+            "-w",
+            "en.txt",
+            "en2.txt",
+            "de.txt",
+        ],
+    ));
+    let words = |group: &str| -> u64 {
+        stdout
+            .lines()
+            .find(|line| line.split_whitespace().last() == Some(group))
+            .and_then(|line| line.split_whitespace().next())
+            .and_then(|words| words.parse().ok())
+            .unwrap_or_else(|| panic!("no group {}: {}", group, stdout))
+    };
+    assert_eq!(words("en"), 20 + 12);
+    assert_eq!(words("de"), 16);
+    fs::remove_dir_all(dir).unwrap();
+}
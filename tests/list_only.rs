@@ -0,0 +1,107 @@
+//! `--list-only` prints the files a recursive count would read, after every
+//! filter, without counting them.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Output;
+
+use common::{rwc_in, scratch};
+
+/// A tree with an ignored file, a hidden directory and two kinds of file.
+fn tree(name: &str) -> PathBuf {
+    let dir = scratch(name);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::create_dir_all(dir.join(".cache")).unwrap();
+    fs::write(dir.join(".gitignore"), "build.log\n").unwrap();
+    fs::write(dir.join("build.log"), "ignored\n").unwrap();
+    fs::write(dir.join(".cache/entry.txt"), "hidden\n").unwrap();
+    fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.join("src/notes.txt"), "some notes\n").unwrap();
+    fs::write(dir.join("README.md"), "# Title\n").unwrap();
+    dir
+}
+
+fn listed(output: &Output) -> Vec<String> {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut files: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.replace('\\', "/"))
+        .collect();
+    files.sort_unstable();
+    files
+}
+
+#[test]
+fn lists_what_the_filters_leave() {
+    let dir = tree("list");
+    assert_eq!(
+        listed(&rwc_in(&dir, ["-r", "--list-only", "."])),
+        ["./README.md", "./src/main.rs", "./src/notes.txt"]
+    );
+    assert_eq!(
+        listed(&rwc_in(
+            &dir,
+            ["-r", "--list-only", "--exclude", "*.md", "."]
+        )),
+        ["./src/main.rs", "./src/notes.txt"]
+    );
+    assert_eq!(
+        listed(&rwc_in(
+            &dir,
+            [
+                "-r",
+                "--list-only",
+                "--hidden",
+                "--no-ignore",
+                "--include",
+                "*.txt",
+                "."
+            ]
+        )),
+        ["./.cache/entry.txt", "./src/notes.txt"]
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn limits_mark_the_list_partial() {
+    let dir = tree("list-limits");
+    let output = rwc_in(
+        &dir,
+        [
+            "-r",
+            "--list-only",
+            "--max-files",
+            "2",
+            "--json-compact",
+            ".",
+        ],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("{\"files\":[\""), "{}", stdout);
+    assert!(
+        stdout.trim_end().ends_with("],\"partial\":true}"),
+        "{}",
+        stdout
+    );
+    assert_eq!(stdout.matches("\",").count() + 1, 2, "{}", stdout);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--max-files reached"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn nothing_is_read() {
+    let dir = tree("list-unreadable");
+    // Not UTF-8, so counting it would fail.
+    fs::write(dir.join("src/data.bin"), b"caf\xe9\n").unwrap();
+    let files = listed(&rwc_in(&dir, ["--list-only", "src/data.bin", "README.md"]));
+    assert_eq!(files, ["README.md", "src/data.bin"]);
+    fs::remove_dir_all(dir).unwrap();
+}
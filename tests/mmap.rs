@@ -0,0 +1,75 @@
+//! `--mmap` counts a file over a memory map of it, with the counts reading
+//! it gives.
+
+mod common;
+
+use std::fs;
+
+use common::{rwc, scratch, stdout};
+
+/// `printf 'hello hello hello hello\n' | gzip -n`
+const HELLO_GZ: [u8; 29] = [
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57,
+    0xc8, 0x40, 0x27, 0xb9, 0x00, 0x00, 0x88, 0x59, 0x0b, 0x18, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn mapped_counts_match_read_counts() {
+    let dir = scratch("mmap");
+    fs::create_dir_all(&dir).unwrap();
+    let text = dir.join("text.txt");
+    // Pieces of 2 MiB and more cross the chunks the map is scanned in.
+    let paragraph = "Zwölf Boxkämpfer jagen Viktor quer über den Sylter Deich.\n\n";
+    fs::write(&text, paragraph.repeat(40_000) + "no newline at the end").unwrap();
+    let empty = dir.join("empty.txt");
+    fs::write(&empty, "").unwrap();
+    let compressed = dir.join("hello.txt.gz");
+    fs::write(&compressed, HELLO_GZ).unwrap();
+    // UTF-16 is decoded a piece of the map at a time.
+    let utf16 = dir.join("utf16.txt");
+    let mut bytes = vec![0xff, 0xfe];
+    for unit in paragraph.repeat(20_000).encode_utf16() {
+        bytes.extend(unit.to_le_bytes());
+    }
+    fs::write(&utf16, bytes).unwrap();
+
+    for file in [&text, &empty, &compressed, &utf16] {
+        let args = |mode: &'static str| {
+            [
+                mode,
+                "--encoding",
+                "auto",
+                "--json-compact",
+                "-l",
+                "-w",
+                "-m",
+                "-c",
+                "-L",
+                "--paragraphs",
+            ]
+            .map(std::ffi::OsStr::new)
+            .into_iter()
+            .chain([file.as_os_str()])
+        };
+        let mapped = stdout(rwc(args("--mmap")));
+        assert_eq!(mapped, stdout(rwc(args("--no-mmap"))), "{}", file.display());
+    }
+    let counts = stdout(rwc([
+        "--mmap".as_ref(),
+        "-w".as_ref(),
+        compressed.as_os_str(),
+    ]));
+    assert_eq!(counts.split_whitespace().next(), Some("4"));
+
+    // The sidecar digest is of the mapped bytes.
+    let digest = |mode: &str| {
+        stdout(rwc([mode.as_ref(), "--sidecar".as_ref(), text.as_os_str()]));
+        let sidecar = fs::read_to_string(dir.join("text.txt.rwc.json")).unwrap();
+        let rest = sidecar.split("\"sha256\"").nth(1).unwrap().to_string();
+        rest.split('"').nth(1).unwrap().to_string()
+    };
+    let mapped = digest("--mmap");
+    assert_eq!(mapped.len(), 64, "{}", mapped);
+    assert_eq!(mapped, digest("--no-mmap"));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,54 @@
+//! `--format ndjson`: the `--json` object of each input and of the totals,
+//! one to a line.
+
+mod common;
+
+use std::fs;
+
+use common::{rwc, scratch, stdout};
+
+#[test]
+fn one_object_per_line() {
+    let dir = scratch("ndjson");
+    fs::create_dir_all(&dir).unwrap();
+    let a = dir.join("a.txt");
+    let b = dir.join("b.txt");
+    fs::write(&a, "one two\n").unwrap();
+    fs::write(&b, "three\n").unwrap();
+
+    let stdout = stdout(rwc([
+        "--format".as_ref(),
+        "ndjson".as_ref(),
+        "-l".as_ref(),
+        "-w".as_ref(),
+        a.as_os_str(),
+        b.as_os_str(),
+    ]));
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", stdout);
+    assert!(
+        lines[0].ends_with("a.txt\",\"lines\":1,\"words\":2}"),
+        "{}",
+        stdout
+    );
+    assert!(
+        lines[1].ends_with("b.txt\",\"lines\":1,\"words\":1}"),
+        "{}",
+        stdout
+    );
+    assert_eq!(lines[2], "{\"file\":\"total\",\"lines\":2,\"words\":3}");
+
+    // A .ndjson --output picks the format too.
+    let report = dir.join("report.ndjson");
+    let output = rwc([
+        "-l".as_ref(),
+        a.as_os_str(),
+        "--output".as_ref(),
+        report.as_os_str(),
+    ]);
+    assert!(output.status.success());
+    assert!(fs::read_to_string(&report)
+        .unwrap()
+        .ends_with("a.txt\",\"lines\":1}\n"));
+    fs::remove_dir_all(dir).unwrap();
+}
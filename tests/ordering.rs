@@ -0,0 +1,99 @@
+//! With `--jobs`, files are listed in operand order and the totals match a
+//! single-threaded run; `--unordered` gives up only the listing order.
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use common::{rwc_in, scratch, stdout};
+
+/// A directory of files whose sizes vary widely, so that with several jobs
+/// they finish out of order.
+fn corpus(name: &str) -> (PathBuf, Vec<String>) {
+    let dir = scratch(name);
+    fs::create_dir_all(&dir).unwrap();
+    let mut files = Vec::new();
+    for index in 0..40 {
+        let file = format!("{:02}.txt", index);
+        let repeat = if index % 7 == 0 {
+            20_000
+        } else {
+            1 + index % 5
+        };
+        fs::write(dir.join(&file), "one two three\nfour five\n".repeat(repeat)).unwrap();
+        files.push(file);
+    }
+    (dir, files)
+}
+
+fn rwc(dir: &Path, args: &[&str], files: &[String]) -> String {
+    let files = files.iter().map(String::as_str);
+    stdout(rwc_in(dir, args.iter().copied().chain(files)))
+}
+
+fn sorted_lines(text: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.lines().collect();
+    lines.sort_unstable();
+    lines
+}
+
+#[test]
+fn jobs_keep_operand_order() {
+    let (dir, files) = corpus("order");
+    let expected = rwc(&dir, &["-j", "1"], &files);
+    for _ in 0..5 {
+        assert_eq!(rwc(&dir, &["-j", "8"], &files), expected);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn jobs_give_the_same_json() {
+    let (dir, files) = corpus("json");
+    let args = ["--json", "--unique-words", "--summary"];
+    let expected = rwc(&dir, &args, &files);
+    assert_eq!(
+        rwc(&dir, &[&args[..], &["-j", "8"]].concat(), &files),
+        expected
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unordered_lists_every_file_and_the_same_total() {
+    let (dir, files) = corpus("unordered");
+    let expected = rwc(&dir, &["-j", "1"], &files);
+    let unordered = rwc(&dir, &["-j", "8", "--unordered"], &files);
+    assert_eq!(sorted_lines(&unordered), sorted_lines(&expected));
+    assert_eq!(unordered.lines().last(), expected.lines().last());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unordered_keeps_rankings_and_summaries() {
+    let (dir, files) = corpus("ranked");
+    let args = ["--top", "10", "--by", "words"];
+    let expected = rwc(&dir, &args, &files);
+    assert_eq!(
+        rwc(
+            &dir,
+            &[&args[..], &["-j", "8", "--unordered"]].concat(),
+            &files
+        ),
+        expected
+    );
+
+    // The total is followed by min, max, mean and median.
+    let expected = rwc(&dir, &["--summary"], &files);
+    let unordered = rwc(&dir, &["--summary", "-j", "8", "--unordered"], &files);
+    let tail = |text: &str| {
+        text.lines()
+            .rev()
+            .take(5)
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(tail(&unordered), tail(&expected));
+    fs::remove_dir_all(dir).unwrap();
+}
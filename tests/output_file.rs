@@ -0,0 +1,74 @@
+//! `--output` sends the records of every mode to the file, not stdout.
+
+mod common;
+
+use std::fs;
+
+use common::{rwc, scratch};
+
+#[test]
+fn modes_write_records_to_output() {
+    let dir = scratch("output-file");
+    fs::create_dir_all(&dir).unwrap();
+    let diff = dir.join("change.diff");
+    fs::write(
+        &diff,
+        "diff --git a/x b/x\n--- a/x\n+++ b/x\n@@ -1 +1 @@\n-old line\n+new words here\n",
+    )
+    .unwrap();
+    let text = dir.join("text.txt");
+    fs::write(&text, "a line of words\n".repeat(100)).unwrap();
+
+    let report = dir.join("report.txt");
+    for args in [
+        vec!["--patch".as_ref(), diff.as_os_str()],
+        vec!["--sample".as_ref(), "50%".as_ref(), text.as_os_str()],
+    ] {
+        let output = rwc(args
+            .iter()
+            .copied()
+            .chain(["--output".as_ref(), report.as_os_str()]));
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "", "{:?}", args);
+        assert!(
+            !fs::read_to_string(&report).unwrap().is_empty(),
+            "{:?}",
+            args
+        );
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn invalid_command_lines_leave_output_alone() {
+    let dir = scratch("output-invalid");
+    fs::create_dir_all(&dir).unwrap();
+    let text = dir.join("text.txt");
+    fs::write(&text, "words\n").unwrap();
+    let report = dir.join("report.txt");
+    fs::write(&report, "last week's report\n").unwrap();
+
+    for invalid in [
+        &["--by", "words"][..],
+        &["--json", "--status-line"],
+        &["--clear"],
+    ] {
+        let output = rwc(invalid.iter().map(AsRef::as_ref).chain([
+            text.as_os_str(),
+            "--output".as_ref(),
+            report.as_os_str(),
+        ]));
+        assert!(!output.status.success(), "{:?}", invalid);
+        assert_eq!(
+            fs::read_to_string(&report).unwrap(),
+            "last week's report\n",
+            "{:?}",
+            invalid
+        );
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,118 @@
+//! With `-r`, a `.rwc.toml` sets counting options for the files beneath its
+//! directory, and an `rwc` key in front matter for one Markdown file.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Output;
+
+use common::{rwc_in, scratch};
+
+fn tree(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = scratch(name);
+    for (path, text) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+    dir
+}
+
+/// Each file's word count, by name.
+fn words(output: &Output) -> Vec<(String, usize)> {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut words: Vec<(String, usize)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (count, name) = line.trim().split_once(' ')?;
+            Some((name.trim().replace('\\', "/"), count.parse().ok()?))
+        })
+        .filter(|(name, _)| name != "total")
+        .collect();
+    words.sort();
+    words
+}
+
+const HTML: &str = "<p>one</p><p>two</p>\n";
+
+#[test]
+fn directory_options_apply_beneath_it() {
+    let dir = tree(
+        "overrides",
+        &[
+            ("docs/.rwc.toml", "options = [\"--type\", \"html\"]\n"),
+            ("docs/page.txt", HTML),
+            ("docs/guide/page.txt", HTML),
+            ("src/page.txt", HTML),
+        ],
+    );
+    assert_eq!(
+        words(&rwc_in(&dir, ["-r", "-w", "."])),
+        [
+            ("./docs/guide/page.txt".to_string(), 2),
+            ("./docs/page.txt".to_string(), 2),
+            ("./src/page.txt".to_string(), 1),
+        ]
+    );
+    // Named on their own, files are counted as the command line says.
+    assert_eq!(
+        words(&rwc_in(&dir, ["-w", "docs/page.txt", "src/page.txt"])),
+        [
+            ("docs/page.txt".to_string(), 1),
+            ("src/page.txt".to_string(), 1),
+        ]
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn nearer_options_win() {
+    let dir = tree(
+        "overrides-nearer",
+        &[
+            (".rwc.toml", "options = [\"--tokenizer\", \"cjk\"]\n"),
+            ("a/.rwc.toml", "options = \"--unicode-words\"\n"),
+            ("a/text.txt", "x-y 世界\n"),
+            ("b/text.txt", "x-y 世界\n"),
+            (
+                "b/notes.md",
+                "---\nrwc: --whitespace ascii\n---\nx-y 世界\n",
+            ),
+        ],
+    );
+    assert_eq!(
+        words(&rwc_in(&dir, ["-r", "-w", "."])),
+        [
+            ("./a/text.txt".to_string(), 4),
+            // The front matter's five words, then two.
+            ("./b/notes.md".to_string(), 7),
+            ("./b/text.txt".to_string(), 3),
+        ]
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn display_options_are_refused() {
+    let dir = tree(
+        "overrides-refused",
+        &[
+            ("docs/.rwc.toml", "options = [\"--json\"]\n"),
+            ("docs/page.txt", HTML),
+        ],
+    );
+    let output = rwc_in(&dir, ["-r", "."]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(".rwc.toml: option '--json' cannot be set per directory"),
+        "{}",
+        stderr
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
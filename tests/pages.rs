@@ -0,0 +1,40 @@
+//! `--pages`: estimated page counts next to the word count.
+
+mod common;
+
+use common::{rwc_with_input, stdout};
+
+fn pages(args: &[&str], words: usize) -> String {
+    let mut args = args.to_vec();
+    args.extend(["-w", "--format", "csv"]);
+    let stdout = stdout(rwc_with_input(&args, "word ".repeat(words)));
+    assert_eq!(stdout.lines().next(), Some("file,words,pages"));
+    stdout
+        .lines()
+        .nth(1)
+        .unwrap()
+        .rsplit(',')
+        .next()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn rounds_part_pages_up() {
+    assert_eq!(pages(&["--pages"], 0), "0");
+    assert_eq!(pages(&["--pages"], 250), "1");
+    assert_eq!(pages(&["--pages"], 251), "2");
+    assert_eq!(pages(&["--pages", "100"], 1000), "10");
+    assert_eq!(pages(&["--pages=400"], 1000), "3");
+}
+
+#[test]
+fn presets() {
+    assert_eq!(pages(&["--pages", "manuscript"], 1000), "4");
+    assert_eq!(pages(&["--pages", "novel"], 1000), "4");
+    assert_eq!(pages(&["--pages=academic"], 1000), "2");
+
+    let output = rwc_with_input(["--pages=screenplay"], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("manuscript, novel, academic"));
+}
@@ -0,0 +1,66 @@
+//! `--remote` streams a file over ssh and counts it like a local one. A
+//! stand-in `ssh` on `PATH` runs the remote command here.
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::process::{Command, Output};
+
+mod common;
+
+use common::{scratch, stdout};
+
+/// `printf 'hello hello hello hello\n' | gzip -n`
+const HELLO_GZ: [u8; 29] = [
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57,
+    0xc8, 0x40, 0x27, 0xb9, 0x00, 0x00, 0x88, 0x59, 0x0b, 0x18, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn remote_files_are_counted_with_local_options() {
+    let dir = scratch("remote");
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    // Records its arguments, then runs the last one, the remote command.
+    let ssh = bin.join("ssh");
+    let script = format!(
+        "#!/bin/sh\nprintf '%s\\n' \"$@\" > '{}'\nfor last; do :; done\nexec sh -c \"$last\"\n",
+        dir.join("args").display()
+    );
+    fs::write(&ssh, script).unwrap();
+    fs::set_permissions(&ssh, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(dir.join("notes.txt"), "a long line here\nshort\n").unwrap();
+    fs::write(dir.join("hello.txt.gz"), HELLO_GZ).unwrap();
+
+    let run = |args: &[&str]| -> Output {
+        let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap());
+        Command::new(env!("CARGO_BIN_EXE_rwc"))
+            .env("PATH", path)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let notes = format!("host:{}", dir.join("notes.txt").display());
+    let counts = stdout(run(&["-l", "-L", "--remote", &notes]));
+    assert_eq!(
+        counts.split_whitespace().take(2).collect::<Vec<_>>(),
+        ["2", "16"]
+    );
+    let args = fs::read_to_string(dir.join("args")).unwrap();
+    assert_eq!(
+        args.lines().take(4).collect::<Vec<_>>(),
+        ["-o", "BatchMode=yes", "--", "host"]
+    );
+
+    let hello = format!("host:{}", dir.join("hello.txt.gz").display());
+    let counts = stdout(run(&["-w", "--remote", &hello]));
+    assert_eq!(counts.split_whitespace().next(), Some("4"));
+
+    // A host that ssh would read as an option never reaches it.
+    fs::remove_file(dir.join("args")).unwrap();
+    let output = run(&["--remote", "-oProxyCommand=touch pwned:notes.txt"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("expected [user@]host:path"));
+    assert!(!dir.join("args").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,55 @@
+//! `--sample` estimates from a file: unbiased, with intervals that hold the
+//! true count about as often as they claim to.
+
+mod common;
+
+use std::fs;
+
+use common::{rwc, scratch, stdout};
+
+/// Lines of 2 to 7 bytes, like `seq 1 200000`: a line's length says nothing
+/// of the line before it, which is what offset sampling used to lean on.
+const LINES: usize = 200_000;
+
+fn estimate(path: &std::path::Path, seed: u64) -> (f64, f64) {
+    let seed = seed.to_string();
+    let stdout = stdout(rwc([
+        "--sample".as_ref(),
+        "5%".as_ref(),
+        "--seed".as_ref(),
+        seed.as_ref(),
+        "-l".as_ref(),
+        path.as_os_str(),
+    ]));
+    let (value, margin) = stdout
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .split_once('±')
+        .unwrap();
+    (value.parse().unwrap(), margin.parse().unwrap())
+}
+
+#[test]
+fn file_estimates_are_unbiased() {
+    let path = scratch("sample").with_extension("txt");
+    let text: String = (1..=LINES).map(|n| format!("{}\n", n)).collect();
+    fs::write(&path, text).unwrap();
+
+    let seeds = 20;
+    let mut sum = 0.0;
+    let mut covered = 0;
+    for seed in 0..seeds {
+        let (value, margin) = estimate(&path, seed);
+        sum += value;
+        covered += usize::from((value - LINES as f64).abs() <= margin);
+    }
+    let mean = sum / seeds as f64;
+    assert!(
+        (mean - LINES as f64).abs() < 0.002 * LINES as f64,
+        "{}",
+        mean
+    );
+    assert!(covered >= 16, "{} of {} intervals", covered, seeds);
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,27 @@
+//! `--wrap-at`/`--wrap-width`: the lines text takes when soft-wrapped.
+
+mod common;
+
+use common::{rwc_with_input, stdout};
+
+fn wrapped(option: &str, width: &str, text: &str) -> String {
+    let stdout = stdout(rwc_with_input([option, width, "--json-compact"], text));
+    let start = stdout.find("\"visual_lines\":").expect("no visual_lines") + 15;
+    stdout[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect()
+}
+
+#[test]
+fn counts_rows_in_display_columns() {
+    // 25 columns, an empty line, then 10 wide characters (20 columns).
+    let text = format!("{}\n\n{}\n", "x".repeat(25), "世".repeat(10));
+    assert_eq!(wrapped("--wrap-at", "10", &text), "6");
+    assert_eq!(wrapped("--wrap-width", "10", &text), "6");
+    // A wide character never straddles the edge.
+    assert_eq!(wrapped("--wrap-width", "3", "世世世\n"), "3");
+    // Tabs stop every 8 columns.
+    assert_eq!(wrapped("--wrap-width", "8", "\tab\n"), "2");
+    assert_eq!(wrapped("--wrap-width", "80", &text), "3");
+}
@@ -0,0 +1,47 @@
+// JavaScript bindings for the WebAssembly build of rwc; see docs/wasm.md.
+//
+//   import { load } from "./rwc.mjs";
+//   const rwc = await load(bytesOrUrlOfRwcWasm);
+//   rwc.count("Hello, world", { unique_words: true });
+//   // { lines: 1, words: 2, chars: 12, bytes: 12, ... }
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+export async function load(source) {
+  const module =
+    source instanceof URL || typeof source === "string"
+      ? await WebAssembly.instantiateStreaming(fetch(source))
+      : await WebAssembly.instantiate(source);
+  const rwc = module.instance.exports;
+
+  // Copies `bytes` into a buffer of the module's own.
+  function place(bytes) {
+    const ptr = rwc.rwc_alloc(bytes.length);
+    new Uint8Array(rwc.memory.buffer, ptr, bytes.length).set(bytes);
+    return ptr;
+  }
+
+  return {
+    // The counts of `text`, as `rwc --json` names them. Throws on invalid
+    // options.
+    count(text, options = {}) {
+      const input = encoder.encode(text);
+      const settings = encoder.encode(JSON.stringify(options));
+      const inputPtr = place(input);
+      const settingsPtr = place(settings);
+      try {
+        const answerPtr = rwc.rwc_count(inputPtr, input.length, settingsPtr, settings.length);
+        const answer = new Uint8Array(rwc.memory.buffer, answerPtr, rwc.rwc_answer_len());
+        const counts = JSON.parse(decoder.decode(answer));
+        if (counts.error !== undefined) {
+          throw new Error(counts.error);
+        }
+        return counts;
+      } finally {
+        rwc.rwc_free(inputPtr, input.length);
+        rwc.rwc_free(settingsPtr, settings.length);
+      }
+    },
+  };
+}